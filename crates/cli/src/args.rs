@@ -0,0 +1,169 @@
+use clap::{Parser, Subcommand, ValueEnum};
+use logic::service::MediaKind;
+use std::path::PathBuf;
+
+/// Track movies and TV shows from the terminal
+#[derive(Debug, Parser)]
+#[command(name = "tv", version, about)]
+pub struct Cli {
+    /// Print JSON instead of a table
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Kind {
+    Movie,
+    Tv,
+}
+
+impl From<Kind> for MediaKind {
+    fn from(kind: Kind) -> Self {
+        match kind {
+            Kind::Movie => MediaKind::Movie,
+            Kind::Tv => MediaKind::Tv,
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Search TMDB for movies or TV shows
+    Search {
+        kind: Kind,
+        #[arg(required = true, num_args = 1..)]
+        query: Vec<String>,
+    },
+    /// List today's trending movies or TV shows
+    Trending { kind: Kind },
+    /// Show the TMDB details of a title
+    Info { kind: Kind, id: u32 },
+    /// Manage the watchlist
+    #[command(subcommand)]
+    Watchlist(WatchlistCommand),
+    /// Manage watched titles and their ratings
+    #[command(subcommand)]
+    Watched(WatchedCommand),
+    /// Manage notes
+    #[command(subcommand)]
+    Notes(NotesCommand),
+    /// Export the whole library as JSON
+    Export {
+        /// Write to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Import a library written by `tv export`
+    Import { file: PathBuf },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum WatchlistCommand {
+    /// Add a title to the watchlist
+    Add { kind: Kind, id: u32 },
+    /// Remove a title from the watchlist
+    Rm { kind: Kind, id: u32 },
+    /// List the watchlist
+    Ls { kind: Option<Kind> },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum WatchedCommand {
+    /// Log a title as watched
+    Add {
+        kind: Kind,
+        id: u32,
+        /// Rating between 0.5 and 5.0 in 0.5 steps
+        #[arg(short, long)]
+        rating: f32,
+        /// When it was watched, as YYYY-MM-DD or RFC 3339 (defaults to now)
+        #[arg(short, long)]
+        date: Option<String>,
+    },
+    /// Change the rating of a watched title
+    Rate { kind: Kind, id: u32, rating: f32 },
+    /// List watched titles
+    Ls { kind: Option<Kind> },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum NotesCommand {
+    /// Edit the notes of a title in $EDITOR
+    Edit { kind: Kind, id: u32 },
+    /// Show the notes of a title, formatted when printing to a terminal
+    Show { kind: Kind, id: u32 },
+    /// List the task list items in the notes of a title, with the offsets `toggle` takes
+    Tasks { kind: Kind, id: u32 },
+    /// Check or uncheck the task list item at an offset listed by `tasks`
+    Toggle { kind: Kind, id: u32, offset: usize },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_search_joins_query_words() {
+        let cli = Cli::try_parse_from(["tv", "search", "movie", "the", "matrix"]).unwrap();
+
+        match cli.command {
+            Command::Search { kind, query } => {
+                assert_eq!(kind, Kind::Movie);
+                assert_eq!(query, vec!["the", "matrix"]);
+            }
+            other => panic!("Expected search command, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_notes_show() {
+        let cli = Cli::try_parse_from(["tv", "notes", "show", "tv", "1399"]).unwrap();
+
+        assert!(matches!(cli.command, Command::Notes(NotesCommand::Show { kind: Kind::Tv, id: 1399 })));
+    }
+
+    #[test]
+    fn test_parse_notes_toggle() {
+        let cli = Cli::try_parse_from(["tv", "notes", "toggle", "movie", "603", "17"]).unwrap();
+
+        assert!(matches!(cli.command, Command::Notes(NotesCommand::Toggle { kind: Kind::Movie, id: 603, offset: 17 })));
+    }
+
+    #[test]
+    fn test_parse_global_json_flag() {
+        let cli = Cli::try_parse_from(["tv", "watchlist", "ls", "--json"]).unwrap();
+
+        assert!(cli.json);
+        assert!(matches!(cli.command, Command::Watchlist(WatchlistCommand::Ls { kind: None })));
+    }
+
+    #[test]
+    fn test_parse_watched_add() {
+        let cli = Cli::try_parse_from(["tv", "watched", "add", "tv", "1399", "--rating", "4.5", "--date", "2025-01-01"])
+            .unwrap();
+
+        match cli.command {
+            Command::Watched(WatchedCommand::Add { kind, id, rating, date }) => {
+                assert_eq!(kind, Kind::Tv);
+                assert_eq!(id, 1399);
+                assert_eq!(rating, 4.5);
+                assert_eq!(date.as_deref(), Some("2025-01-01"));
+            }
+            other => panic!("Expected watched add command, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_rejects_unknown_kind() {
+        assert!(Cli::try_parse_from(["tv", "info", "book", "1"]).is_err());
+    }
+
+    #[test]
+    fn test_verify_cli() {
+        use clap::CommandFactory;
+        Cli::command().debug_assert();
+    }
+}
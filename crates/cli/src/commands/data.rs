@@ -0,0 +1,75 @@
+use anyhow::{Context, Result};
+use logic::database::entities::LibraryExport;
+use logic::service::Library;
+use std::fs;
+use std::path::Path;
+
+pub fn export(library: &Library, output: Option<&Path>) -> Result<()> {
+    let json = serde_json::to_string_pretty(&library.export()?)?;
+
+    match output {
+        Some(path) => {
+            fs::write(path, json).with_context(|| format!("Failed to write {}", path.display()))?;
+            eprintln!("Exported library to {}", path.display());
+        }
+        None => println!("{}", json),
+    }
+    Ok(())
+}
+
+pub fn import(library: &Library, file: &Path) -> Result<()> {
+    let content = fs::read_to_string(file).with_context(|| format!("Failed to read {}", file.display()))?;
+    let export = parse_export(&content)?;
+
+    library.import(&export)?;
+    eprintln!(
+        "Imported {} watchlist and {} watched titles",
+        export.movies_to_watch.len() + export.tv_shows_to_watch.len(),
+        export.watched_movies.len() + export.watched_tv_shows.len()
+    );
+    Ok(())
+}
+
+fn parse_export(content: &str) -> Result<LibraryExport> {
+    serde_json::from_str(content).context("Not a library export")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_export() {
+        let content = r#"{
+            "version": 1,
+            "movies_to_watch": [{ "id": 603, "title": "The Matrix", "poster_path": "", "notes": "" }],
+            "tv_shows_to_watch": [],
+            "watched_movies": [],
+            "watched_tv_shows": []
+        }"#;
+
+        let library = parse_export(content).unwrap();
+        assert_eq!(library.movies_to_watch.len(), 1);
+        assert_eq!(library.movies_to_watch[0].title, "The Matrix");
+    }
+
+    #[test]
+    fn test_import_rejects_newer_version() {
+        let content = r#"{
+            "version": 99,
+            "movies_to_watch": [],
+            "tv_shows_to_watch": [],
+            "watched_movies": [],
+            "watched_tv_shows": []
+        }"#;
+
+        let export = parse_export(content).unwrap();
+
+        assert!(Library::open_in_memory().unwrap().import(&export).is_err());
+    }
+
+    #[test]
+    fn test_parse_export_rejects_other_json() {
+        assert!(parse_export(r#"{ "movies": [] }"#).is_err());
+    }
+}
@@ -0,0 +1,46 @@
+mod data;
+mod notes;
+mod tmdb;
+mod watched;
+mod watchlist;
+
+use anyhow::Result;
+use logic::service::{Library, LibraryResult, ValidationErrors};
+
+use crate::args::{Cli, Command};
+use crate::output::OutputFormat;
+
+pub async fn run(cli: Cli) -> Result<()> {
+    let format = if cli.json { OutputFormat::Json } else { OutputFormat::Table };
+    // The same database and TMDB key the desktop app uses
+    let library = Library::open()?;
+
+    match cli.command {
+        Command::Search { kind, query } => tmdb::search(&library, kind, &query.join(" "), format).await,
+        Command::Trending { kind } => tmdb::trending(&library, kind, format).await,
+        Command::Info { kind, id } => tmdb::info(&library, kind, id, format).await,
+        Command::Watchlist(command) => watchlist::run(&library, command, format).await,
+        Command::Watched(command) => watched::run(&library, command, format).await,
+        Command::Notes(command) => notes::run(&library, command, format).await,
+        Command::Export { output } => data::export(&library, output.as_deref()),
+        Command::Import { file } => data::import(&library, &file),
+    }
+}
+
+/// TMDB ids are unsigned but the database stores them as `i32`, so reject the ones that would wrap
+fn entry_id(id: u32) -> LibraryResult<i32> {
+    i32::try_from(id).map_err(|_| ValidationErrors::single("id", format!("{} is not a valid TMDB id", id)).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use logic::service::LibraryError;
+
+    #[test]
+    fn test_entry_id_rejects_ids_that_do_not_fit() {
+        assert_eq!(entry_id(1399).unwrap(), 1399);
+        assert!(matches!(entry_id(u32::MAX), Err(LibraryError::Invalid(_))));
+        assert!(entry_id(i32::MAX as u32 + 1).is_err());
+    }
+}
@@ -0,0 +1,114 @@
+use anyhow::{bail, Context, Result};
+use logic::service::Library;
+use markdown_renderer::{parse_markdown, tasks, AnsiOptions, AnsiRenderer, Renderer};
+use std::fs;
+use std::io::IsTerminal;
+use std::process::Command;
+use std::sync::Arc;
+
+use super::entry_id;
+use crate::args::{Kind, NotesCommand};
+use crate::output::{print_rows, OutputFormat};
+
+pub async fn run(library: &Library, command: NotesCommand, format: OutputFormat) -> Result<()> {
+    match command {
+        NotesCommand::Edit { kind, id } => edit(library, kind, entry_id(id)?),
+        NotesCommand::Show { kind, id } => show(library, kind, entry_id(id)?).await,
+        NotesCommand::Tasks { kind, id } => list_tasks(library, kind, entry_id(id)?, format),
+        NotesCommand::Toggle { kind, id, offset } => toggle(library, kind, entry_id(id)?, offset),
+    }
+}
+
+/// Print the notes, styled for the terminal unless the output is piped somewhere
+async fn show(library: &Library, kind: Kind, id: i32) -> Result<()> {
+    let Some((_, notes)) = library.find_notes(kind.into(), id)? else {
+        bail!("Title {} is neither on the watchlist nor watched", id);
+    };
+    if !std::io::stdout().is_terminal() {
+        print!("{}", notes);
+        return Ok(());
+    }
+
+    let options = AnsiOptions {
+        width: Some(terminal_width()),
+        media_resolver: Some(Arc::new(library.resolve_titles(&notes).await?)),
+        ..Default::default()
+    };
+    let ast = parse_markdown(&notes).context("Failed to parse notes")?;
+    match AnsiRenderer::with_options(options).render(&ast) {
+        Ok(rendered) => print!("{}", rendered),
+        Err(never) => match never {},
+    }
+    Ok(())
+}
+
+fn list_tasks(library: &Library, kind: Kind, id: i32, format: OutputFormat) -> Result<()> {
+    let Some((_, notes)) = library.find_notes(kind.into(), id)? else {
+        bail!("Title {} is neither on the watchlist nor watched", id);
+    };
+    let ast = parse_markdown(&notes).context("Failed to parse notes")?;
+    print_rows(&tasks(&ast), format)
+}
+
+fn toggle(library: &Library, kind: Kind, id: i32, offset: usize) -> Result<()> {
+    let Some((location, notes)) = library.find_notes(kind.into(), id)? else {
+        bail!("Title {} is neither on the watchlist nor watched", id);
+    };
+
+    if library.toggle_notes_task(location, kind.into(), id, offset)? == notes {
+        bail!("No task starts at offset {}, see `tv notes tasks`", offset);
+    }
+    eprintln!("Task toggled");
+    Ok(())
+}
+
+/// Width to wrap notes at, from `$COLUMNS` when the shell exports it
+fn terminal_width() -> usize {
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.parse().ok())
+        .filter(|&columns| columns > 0)
+        .map_or(80, |columns: usize| columns.min(100))
+}
+
+fn edit(library: &Library, kind: Kind, id: i32) -> Result<()> {
+    let Some((location, notes)) = library.find_notes(kind.into(), id)? else {
+        bail!("Title {} is neither on the watchlist nor watched, add it first", id);
+    };
+
+    let edited = edit_in_editor(&notes, &format!("tv-notes-{:?}-{}.md", kind, id).to_lowercase())?;
+    if edited == notes {
+        eprintln!("Notes unchanged");
+        return Ok(());
+    }
+
+    library.update_notes(location, kind.into(), id, &edited)?;
+    eprintln!("Notes saved");
+    Ok(())
+}
+
+/// Open `$VISUAL` or `$EDITOR` (falling back to `vi`) on a temporary copy of `text`
+fn edit_in_editor(text: &str, file_name: &str) -> Result<String> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().context("$EDITOR is empty")?;
+
+    let path = std::env::temp_dir().join(file_name);
+    fs::write(&path, text).context("Failed to write temporary notes file")?;
+
+    let status = Command::new(program)
+        .args(parts)
+        .arg(&path)
+        .status()
+        .with_context(|| format!("Failed to start editor '{}'", editor));
+
+    let edited = fs::read_to_string(&path).context("Failed to read temporary notes file");
+    let _ = fs::remove_file(&path);
+
+    if !status?.success() {
+        bail!("Editor exited with an error, notes were not saved");
+    }
+    edited
+}
@@ -0,0 +1,26 @@
+use anyhow::Result;
+use logic::service::Library;
+
+use crate::args::Kind;
+use crate::output::{print_record, print_rows, OutputFormat};
+
+pub async fn search(library: &Library, kind: Kind, query: &str, format: OutputFormat) -> Result<()> {
+    match kind {
+        Kind::Movie => print_rows(&library.search_movies(query).await?, format),
+        Kind::Tv => print_rows(&library.search_tv(query).await?, format),
+    }
+}
+
+pub async fn trending(library: &Library, kind: Kind, format: OutputFormat) -> Result<()> {
+    match kind {
+        Kind::Movie => print_rows(&library.trending_movies().await?, format),
+        Kind::Tv => print_rows(&library.trending_tv().await?, format),
+    }
+}
+
+pub async fn info(library: &Library, kind: Kind, id: u32, format: OutputFormat) -> Result<()> {
+    match kind {
+        Kind::Movie => print_record(&library.movie_details(id).await?, format),
+        Kind::Tv => print_record(&library.tv_details(id).await?, format),
+    }
+}
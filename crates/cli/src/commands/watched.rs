@@ -0,0 +1,163 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use logic::service::{Library, MediaKind, RateMovie, RateTvShow};
+
+use super::entry_id;
+use crate::args::{Kind, WatchedCommand};
+use crate::output::{print_library, print_rows, OutputFormat};
+
+pub async fn run(library: &Library, command: WatchedCommand, format: OutputFormat) -> Result<()> {
+    match command {
+        WatchedCommand::Add { kind, id, rating, date } => {
+            let watched_at = Some(parse_watched_at(date.as_deref())?);
+
+            match kind {
+                Kind::Movie => {
+                    let movie = library.movie_details(id).await?;
+                    let movie = library.rate_movie(RateMovie {
+                        id: entry_id(movie.id)?,
+                        rating,
+                        watched_at,
+                        title: movie.title,
+                        poster_path: movie.poster_path,
+                    })?;
+                    eprintln!("Logged {} with {:.1} stars", movie.title, rating);
+                }
+                Kind::Tv => {
+                    let show = library.tv_details(id).await?;
+                    let show = library.rate_tv_show(RateTvShow {
+                        id: entry_id(show.id)?,
+                        rating,
+                        watched_at,
+                        name: show.name,
+                        poster_path: show.poster_path,
+                        first_air_date: show.first_air_date,
+                        vote_average: show.vote_average,
+                        overview: show.overview,
+                    })?;
+                    eprintln!("Logged {} with {:.1} stars", show.name, rating);
+                }
+            }
+            Ok(())
+        }
+        WatchedCommand::Rate { kind, id, rating } => rate(library, kind, entry_id(id)?, rating),
+        WatchedCommand::Ls { kind } => list(library, kind, format),
+    }
+}
+
+/// Accept a plain date (watched at 20:00 local time, like the desktop app) or a full RFC 3339 timestamp
+fn parse_watched_at(date: Option<&str>) -> Result<String> {
+    let Some(date) = date else {
+        return Ok(Utc::now().to_rfc3339());
+    };
+
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(date) {
+        return Ok(timestamp.with_timezone(&Utc).to_rfc3339());
+    }
+
+    let day = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .with_context(|| format!("Invalid date '{}', expected YYYY-MM-DD or RFC 3339", date))?;
+    let evening = day
+        .and_hms_opt(20, 0, 0)
+        .and_then(|time| time.and_local_timezone(Local).earliest())
+        .with_context(|| format!("Invalid local time on {}", date))?;
+
+    Ok(evening.with_timezone(&Utc).to_rfc3339())
+}
+
+/// Change the rating of a title that is already on the watched list
+fn rate(library: &Library, kind: Kind, id: i32, rating: f32) -> Result<()> {
+    if library.rating(kind.into(), id)?.is_none() {
+        let kind = match kind {
+            Kind::Movie => "Movie",
+            Kind::Tv => "TV show",
+        };
+        bail!("{} {} is not in the watched list, log it with `tv watched add` first", kind, id);
+    }
+
+    match MediaKind::from(kind) {
+        MediaKind::Movie => {
+            let input = RateMovie { id, rating, watched_at: None, title: String::new(), poster_path: String::new() };
+            library.rate_movie(input)?;
+        }
+        MediaKind::Tv => {
+            let input = RateTvShow {
+                id,
+                rating,
+                watched_at: None,
+                name: String::new(),
+                poster_path: String::new(),
+                first_air_date: String::new(),
+                vote_average: 0.0,
+                overview: String::new(),
+            };
+            library.rate_tv_show(input)?;
+        }
+    }
+    Ok(())
+}
+
+fn list(library: &Library, kind: Option<Kind>, format: OutputFormat) -> Result<()> {
+    match kind {
+        Some(Kind::Movie) => print_rows(&library.watched_movies()?, format),
+        Some(Kind::Tv) => print_rows(&library.watched_tv_shows()?, format),
+        None => print_library(&library.watched_movies()?, &library.watched_tv_shows()?, format),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use logic::service::NotesLocation;
+
+    #[test]
+    fn test_parse_watched_at_rfc3339() {
+        let watched_at = parse_watched_at(Some("2025-01-01T20:00:00+02:00")).unwrap();
+
+        assert_eq!(watched_at, "2025-01-01T18:00:00+00:00");
+    }
+
+    #[test]
+    fn test_parse_watched_at_date() {
+        let watched_at = parse_watched_at(Some("2025-01-01")).unwrap();
+
+        assert!(DateTime::parse_from_rfc3339(&watched_at).is_ok());
+    }
+
+    #[test]
+    fn test_parse_watched_at_invalid() {
+        assert!(parse_watched_at(Some("yesterday")).is_err());
+        assert!(parse_watched_at(Some("2025-13-01")).is_err());
+    }
+
+    #[test]
+    fn test_rate_updates_existing_entry() {
+        let library = Library::open_in_memory().unwrap();
+        library
+            .rate_movie(RateMovie {
+                id: 603,
+                rating: 3.0,
+                watched_at: Some("2025-01-01T20:00:00+00:00".to_string()),
+                title: "The Matrix".to_string(),
+                poster_path: "/matrix.jpg".to_string(),
+            })
+            .unwrap();
+        library.update_notes(NotesLocation::Watched, MediaKind::Movie, 603, "Better the second time").unwrap();
+
+        rate(&library, Kind::Movie, 603, 4.5).unwrap();
+
+        let movie = library.watched_movie(603).unwrap().unwrap();
+        assert_eq!(movie.rating, 4.5);
+        assert_eq!(movie.title, "The Matrix");
+        assert_eq!(movie.watched_at, "2025-01-01T20:00:00+00:00");
+        assert_eq!(movie.notes, "Better the second time");
+    }
+
+    #[test]
+    fn test_rate_requires_watched_entry() {
+        let library = Library::open_in_memory().unwrap();
+
+        let error = rate(&library, Kind::Tv, 1399, 4.0).unwrap_err();
+        assert!(error.to_string().contains("tv watched add"));
+    }
+}
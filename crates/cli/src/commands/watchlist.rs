@@ -0,0 +1,111 @@
+use anyhow::Result;
+use logic::api::{MovieDetail, TvDetail};
+use logic::service::{Library, LibraryResult, NewMovie, NewTvShow};
+
+use super::entry_id;
+use crate::args::{Kind, WatchlistCommand};
+use crate::output::{print_library, print_rows, OutputFormat};
+
+pub async fn run(library: &Library, command: WatchlistCommand, format: OutputFormat) -> Result<()> {
+    match command {
+        WatchlistCommand::Add { kind: Kind::Movie, id } => {
+            let movie = library.movie_details(id).await?;
+            let movie = library.add_movie_to_watchlist(new_movie(&movie)?)?;
+            eprintln!("Added {} to the watchlist", movie.title);
+            Ok(())
+        }
+        WatchlistCommand::Add { kind: Kind::Tv, id } => {
+            let show = library.tv_details(id).await?;
+            let show = library.add_tv_show_to_watchlist(new_tv_show(&show)?)?;
+            eprintln!("Added {} to the watchlist", show.name);
+            Ok(())
+        }
+        WatchlistCommand::Rm { kind, id } => Ok(library.remove_from_watchlist(kind.into(), entry_id(id)?)?),
+        WatchlistCommand::Ls { kind } => list(library, kind, format),
+    }
+}
+
+fn new_movie(movie: &MovieDetail) -> LibraryResult<NewMovie> {
+    Ok(NewMovie {
+        id: entry_id(movie.id)?,
+        title: movie.title.clone(),
+        poster_path: movie.poster_path.clone(),
+    })
+}
+
+fn new_tv_show(show: &TvDetail) -> LibraryResult<NewTvShow> {
+    Ok(NewTvShow {
+        id: entry_id(show.id)?,
+        name: show.name.clone(),
+        poster_path: show.poster_path.clone(),
+        first_air_date: show.first_air_date.clone(),
+        vote_average: show.vote_average,
+        overview: show.overview.clone(),
+    })
+}
+
+fn list(library: &Library, kind: Option<Kind>, format: OutputFormat) -> Result<()> {
+    match kind {
+        Some(Kind::Movie) => print_rows(&library.watchlist_movies()?, format),
+        Some(Kind::Tv) => print_rows(&library.watchlist_tv_shows()?, format),
+        None => print_library(&library.watchlist_movies()?, &library.watchlist_tv_shows()?, format),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use logic::service::MediaKind;
+
+    fn matrix() -> MovieDetail {
+        MovieDetail {
+            id: 603,
+            title: "The Matrix".to_string(),
+            poster_path: "/matrix.jpg".to_string(),
+            runtime: 136,
+            overview: String::new(),
+            genres: vec!["Action".to_string()],
+            vote_average: 8.2,
+            release_date: "1999-03-31".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_add_and_remove_movie() {
+        let library = Library::open_in_memory().unwrap();
+
+        library.add_movie_to_watchlist(new_movie(&matrix()).unwrap()).unwrap();
+        let movies = library.watchlist_movies().unwrap();
+        assert_eq!(movies.len(), 1);
+        assert_eq!(movies[0].title, "The Matrix");
+        assert_eq!(movies[0].poster_path, "/matrix.jpg");
+
+        library.remove_from_watchlist(Kind::Movie.into(), 603).unwrap();
+        assert!(library.watchlist_movies().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_add_tv_show() {
+        let library = Library::open_in_memory().unwrap();
+        let show = TvDetail {
+            id: 1399,
+            name: "Game of Thrones".to_string(),
+            poster_path: "/got.jpg".to_string(),
+            episode_run_time: vec![60],
+            overview: "Winter is coming".to_string(),
+            genres: vec![],
+            vote_average: 8.4,
+            first_air_date: "2011-04-17".to_string(),
+            number_of_seasons: 8,
+            number_of_episodes: 73,
+            status: "Ended".to_string(),
+        };
+
+        library.add_tv_show_to_watchlist(new_tv_show(&show).unwrap()).unwrap();
+
+        let shows = library.watchlist_tv_shows().unwrap();
+        assert_eq!(shows.len(), 1);
+        assert_eq!(shows[0].first_air_date, "2011-04-17");
+        assert_eq!(library.rating(MediaKind::Tv, 1399).unwrap(), None);
+    }
+}
@@ -0,0 +1,13 @@
+mod args;
+mod commands;
+mod output;
+
+use anyhow::Result;
+use clap::Parser;
+
+use crate::args::Cli;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    commands::run(Cli::parse()).await
+}
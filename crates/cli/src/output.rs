@@ -0,0 +1,272 @@
+use anyhow::Result;
+use logic::api::{Movie, MovieDetail, Tv, TvDetail};
+use logic::database::entities::{MovieToWatch, TvShowToWatch, WatchedMovie, WatchedTvShow};
+use markdown_renderer::TaskEntry;
+use serde::Serialize;
+use serde_json::json;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Table,
+    Json,
+}
+
+/// A value that can be shown as one line of a table
+pub trait Row {
+    const HEADERS: &'static [&'static str];
+
+    fn cells(&self) -> Vec<String>;
+}
+
+/// A value that is shown as a list of labelled fields
+pub trait Record {
+    fn fields(&self) -> Vec<(&'static str, String)>;
+}
+
+pub fn print_rows<T: Row + Serialize>(rows: &[T], format: OutputFormat) -> Result<()> {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(rows)?),
+        OutputFormat::Table => print!("{}", render_table(T::HEADERS, rows.iter().map(Row::cells))),
+    }
+    Ok(())
+}
+
+pub fn print_record<T: Record + Serialize>(record: &T, format: OutputFormat) -> Result<()> {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(record)?),
+        OutputFormat::Table => print!("{}", render_record(&record.fields())),
+    }
+    Ok(())
+}
+
+/// Print movies and TV shows together, as two tables or one JSON object
+pub fn print_library<M, T>(movies: &[M], tv_shows: &[T], format: OutputFormat) -> Result<()>
+where
+    M: Row + Serialize,
+    T: Row + Serialize,
+{
+    match format {
+        OutputFormat::Json => {
+            let library = json!({ "movies": movies, "tv_shows": tv_shows });
+            println!("{}", serde_json::to_string_pretty(&library)?);
+        }
+        OutputFormat::Table => {
+            println!("Movies");
+            print_rows(movies, format)?;
+            println!();
+            println!("TV shows");
+            print_rows(tv_shows, format)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn render_table(headers: &[&str], rows: impl Iterator<Item = Vec<String>>) -> String {
+    let rows: Vec<Vec<String>> = rows.collect();
+    let mut widths: Vec<usize> = headers.iter().map(|header| header.chars().count()).collect();
+
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut output = String::new();
+    let header_cells: Vec<String> = headers.iter().map(|header| header.to_string()).collect();
+    push_line(&mut output, &header_cells, &widths);
+    for row in &rows {
+        push_line(&mut output, row, &widths);
+    }
+    output
+}
+
+pub fn render_record(fields: &[(&str, String)]) -> String {
+    let width = fields.iter().map(|(label, _)| label.chars().count()).max().unwrap_or(0);
+
+    let mut output = String::new();
+    for (label, value) in fields {
+        output.push_str(&format!("{:<width$}  {}\n", label, value, width = width));
+    }
+    output
+}
+
+fn push_line(output: &mut String, cells: &[String], widths: &[usize]) {
+    let line: Vec<String> = cells
+        .iter()
+        .zip(widths)
+        .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+        .collect();
+    output.push_str(line.join("  ").trim_end());
+    output.push('\n');
+}
+
+fn format_rating(rating: f32) -> String {
+    format!("{:.1}", rating)
+}
+
+fn format_date(timestamp: &str) -> String {
+    timestamp.chars().take(10).collect()
+}
+
+fn has_notes(notes: &str) -> String {
+    if notes.trim().is_empty() { "" } else { "yes" }.to_string()
+}
+
+impl Row for Movie {
+    const HEADERS: &'static [&'static str] = &["ID", "TITLE"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![self.id.to_string(), self.title.clone()]
+    }
+}
+
+impl Row for Tv {
+    const HEADERS: &'static [&'static str] = &["ID", "NAME"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![self.id.to_string(), self.name.clone()]
+    }
+}
+
+impl Row for MovieToWatch {
+    const HEADERS: &'static [&'static str] = &["ID", "TITLE", "NOTES"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![self.id.to_string(), self.title.clone(), has_notes(&self.notes)]
+    }
+}
+
+impl Row for TvShowToWatch {
+    const HEADERS: &'static [&'static str] = &["ID", "NAME", "FIRST AIRED", "TMDB", "NOTES"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.name.clone(),
+            self.first_air_date.clone(),
+            format_rating(self.vote_average),
+            has_notes(&self.notes),
+        ]
+    }
+}
+
+impl Row for WatchedMovie {
+    const HEADERS: &'static [&'static str] = &["ID", "TITLE", "RATING", "WATCHED", "NOTES"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.title.clone(),
+            format_rating(self.rating),
+            format_date(&self.watched_at),
+            has_notes(&self.notes),
+        ]
+    }
+}
+
+impl Row for WatchedTvShow {
+    const HEADERS: &'static [&'static str] = &["ID", "NAME", "RATING", "WATCHED", "NOTES"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.name.clone(),
+            format_rating(self.rating),
+            format_date(&self.watched_at),
+            has_notes(&self.notes),
+        ]
+    }
+}
+
+impl Row for TaskEntry {
+    const HEADERS: &'static [&'static str] = &["OFFSET", "DONE", "TASK"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![self.offset.to_string(), if self.checked { "x" } else { "" }.to_string(), self.text.clone()]
+    }
+}
+
+impl Record for MovieDetail {
+    fn fields(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("ID", self.id.to_string()),
+            ("Title", self.title.clone()),
+            ("Released", self.release_date.clone()),
+            ("Runtime", format!("{} min", self.runtime)),
+            ("Genres", self.genres.join(", ")),
+            ("TMDB", format_rating(self.vote_average)),
+            ("Overview", self.overview.clone()),
+        ]
+    }
+}
+
+impl Record for TvDetail {
+    fn fields(&self) -> Vec<(&'static str, String)> {
+        let runtimes: Vec<String> = self.episode_run_time.iter().map(|minutes| format!("{} min", minutes)).collect();
+
+        vec![
+            ("ID", self.id.to_string()),
+            ("Name", self.name.clone()),
+            ("First aired", self.first_air_date.clone()),
+            ("Status", self.status.clone()),
+            ("Seasons", self.number_of_seasons.to_string()),
+            ("Episodes", self.number_of_episodes.to_string()),
+            ("Runtime", runtimes.join(", ")),
+            ("Genres", self.genres.join(", ")),
+            ("TMDB", format_rating(self.vote_average)),
+            ("Overview", self.overview.clone()),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_table_pads_columns() {
+        let rows = vec![
+            vec!["603".to_string(), "The Matrix".to_string()],
+            vec!["27205".to_string(), "Inception".to_string()],
+        ];
+        let table = render_table(&["ID", "TITLE"], rows.into_iter());
+
+        assert_eq!(table, "ID     TITLE\n603    The Matrix\n27205  Inception\n");
+    }
+
+    #[test]
+    fn test_render_table_without_rows() {
+        let table = render_table(&["ID", "TITLE"], std::iter::empty());
+
+        assert_eq!(table, "ID  TITLE\n");
+    }
+
+    #[test]
+    fn test_render_table_counts_unicode_chars() {
+        let rows = vec![vec!["1".to_string(), "Amélie".to_string(), "x".to_string()]];
+        let table = render_table(&["ID", "TITLE", "N"], rows.into_iter());
+
+        assert_eq!(table, "ID  TITLE   N\n1   Amélie  x\n");
+    }
+
+    #[test]
+    fn test_render_record_aligns_labels() {
+        let fields = vec![("ID", "603".to_string()), ("Title", "The Matrix".to_string())];
+
+        assert_eq!(render_record(&fields), "ID     603\nTitle  The Matrix\n");
+    }
+
+    #[test]
+    fn test_watched_movie_row() {
+        let movie = WatchedMovie {
+            id: 603,
+            title: "The Matrix".to_string(),
+            poster_path: "/matrix.jpg".to_string(),
+            rating: 4.5,
+            watched_at: "2025-01-01T20:00:00+00:00".to_string(),
+            notes: "Rewatch soon".to_string(),
+        };
+
+        assert_eq!(movie.cells(), vec!["603", "The Matrix", "4.5", "2025-01-01", "yes"]);
+    }
+}
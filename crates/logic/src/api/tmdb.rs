@@ -2,11 +2,11 @@ use anyhow::{Context, Result};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::HashSet;
 
 use crate::config::TmdbConfig;
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct Movie {
     pub id: u32,
     pub title: String,
@@ -14,6 +14,7 @@ pub struct Movie {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct Tv {
     pub id: u32,
     pub name: String,
@@ -21,6 +22,7 @@ pub struct Tv {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct TvDetail {
     pub id: u32,
     pub name: String,
@@ -36,6 +38,7 @@ pub struct TvDetail {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct MovieDetail {
     pub id: u32,
     pub title: String,
@@ -113,9 +116,9 @@ impl Tmdb {
             }
         }
         if movies.is_empty() {
-            return None;
+            None
         } else {
-            return Some(movies);
+            Some(movies)
         }
     }
 
@@ -153,9 +156,9 @@ impl Tmdb {
             }
         }
         if shows.is_empty() {
-            return None;
+            None
         } else {
-            return Some(shows);
+            Some(shows)
         }
     }
 
@@ -410,6 +413,6 @@ mod test {
     async fn test() {
         let tmdb = Tmdb::new(TmdbConfig::default());
 
-        let movies = tmdb.get_movie_details(123).await.unwrap();
+        let _movie = tmdb.get_movie_details(123).await.unwrap();
     }
 }
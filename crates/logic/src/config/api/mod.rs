@@ -1,2 +1,3 @@
+pub mod server_table;
 pub mod tmdb_table;
 pub mod write_config;
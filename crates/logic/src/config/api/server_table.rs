@@ -0,0 +1,60 @@
+use anyhow::{bail, Context, Result};
+
+use crate::config::toml_parser::{ConfigToml, ServerTable};
+
+pub const DEFAULT_PORT: u16 = 7878;
+
+pub struct ServerConfig {
+    token: String,
+    port: u16,
+}
+
+impl ServerConfig {
+    fn new(table: ServerTable) -> Result<Self> {
+        let token = table.token.unwrap_or_default();
+        if token.trim().is_empty() {
+            bail!("Set `token` in the [server] table of .config/tv/config.toml");
+        }
+
+        Ok(ServerConfig {
+            token,
+            port: table.port.unwrap_or(DEFAULT_PORT),
+        })
+    }
+
+    pub fn initialize() -> Result<Self> {
+        let server_table = ConfigToml::new()
+            .context("Failed to load config")?
+            .get_server()
+            .context("Failed to get server table")?;
+
+        ServerConfig::new(server_table)
+    }
+
+    pub fn token(&self) -> String {
+        self.token.clone()
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_port() {
+        let config = ServerConfig::new(ServerTable { token: Some("secret".to_string()), port: None }).unwrap();
+
+        assert_eq!(config.token(), "secret");
+        assert_eq!(config.port(), DEFAULT_PORT);
+    }
+
+    #[test]
+    fn test_token_is_required() {
+        assert!(ServerConfig::new(ServerTable { token: None, port: Some(8080) }).is_err());
+        assert!(ServerConfig::new(ServerTable { token: Some("  ".to_string()), port: None }).is_err());
+    }
+}
@@ -7,11 +7,11 @@ pub struct TmdbConfig {
 }
 
 impl TmdbConfig {
-    fn new(api_key: String) -> Self {
+    pub fn new(api_key: String) -> Self {
         TmdbConfig { api_key }
     }
 
-    fn initialize() -> Result<Self> {
+    pub fn initialize() -> Result<Self> {
         let tmdb_table: TmdbTable = ConfigToml::new()
             .context("Failed to load config")?
             .get_tmdb()
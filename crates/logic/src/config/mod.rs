@@ -2,5 +2,6 @@ mod api;
 mod dir;
 mod toml_parser;
 
+pub use api::server_table::ServerConfig;
 pub use api::tmdb_table::TmdbConfig;
 pub use api::write_config::write_api_key_to_config;
@@ -10,9 +10,16 @@ pub struct TmdbTable {
     pub api_key: Option<String>,
 }
 
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ServerTable {
+    pub token: Option<String>,
+    pub port: Option<u16>,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct ConfigToml {
     pub tmdb: Option<TmdbTable>,
+    pub server: Option<ServerTable>,
 }
 
 impl ConfigToml {
@@ -41,4 +48,8 @@ impl ConfigToml {
     pub fn get_tmdb(&self) -> Option<TmdbTable> {
         self.tmdb.clone()
     }
+
+    pub fn get_server(&self) -> Option<ServerTable> {
+        self.server.clone()
+    }
 }
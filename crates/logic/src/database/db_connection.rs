@@ -1,6 +1,7 @@
 use dirs::home_dir;
 use rusqlite::{params, Connection, Result};
 use std::fs;
+use std::path::Path;
 use std::sync::{Arc, Mutex, OnceLock};
 
 use crate::database::entities::*;
@@ -28,141 +29,158 @@ impl Sqlight {
         instance.get_or_init(|| {
             create_directory(&db_path);
 
-            let db_path = db_path.join("tv_tracker.db");
+            let db =
+                Sqlight::open(db_path.join("tv_tracker.db")).expect("Failed to open the database");
 
-            let conn = Connection::open(db_path).expect("Failed to open the database");
+            Arc::new(Mutex::new(db))
+        });
 
-            conn.execute(
-                "CREATE TABLE IF NOT EXISTS movies_to_watch (
-                    id INTEGER PRIMARY KEY,
-                    title TEXT NOT NULL,
-                    poster_path TEXT NOT NULL,
-                    notes TEXT NOT NULL DEFAULT ''
-                )",
-                [],
-            )
-            .expect("Failed to create movies_to_watch table");
-
-            conn.execute(
-                "CREATE TABLE IF NOT EXISTS tv_shows_to_watch (
-                    id INTEGER PRIMARY KEY,
-                    name TEXT NOT NULL,
-                    poster_path TEXT NOT NULL,
-                    first_air_date TEXT NOT NULL DEFAULT '',
-                    vote_average REAL NOT NULL DEFAULT 0.0,
-                    overview TEXT NOT NULL DEFAULT '',
-                    notes TEXT NOT NULL DEFAULT ''
-                )",
-                [],
-            )
-            .expect("Failed to create tv_shows_to_watch table");
+        Ok(instance.get().unwrap().clone())
+    }
 
-            // Add migration for existing databases
-            let _ = conn.execute(
-                "ALTER TABLE tv_shows_to_watch ADD COLUMN first_air_date TEXT NOT NULL DEFAULT ''",
-                [],
-            );
-            let _ = conn.execute(
-                "ALTER TABLE tv_shows_to_watch ADD COLUMN vote_average REAL NOT NULL DEFAULT 0.0",
-                [],
-            );
-            let _ = conn.execute(
-                "ALTER TABLE tv_shows_to_watch ADD COLUMN overview TEXT NOT NULL DEFAULT ''",
-                [],
-            );
-            let _ = conn.execute(
-                "ALTER TABLE movies_to_watch ADD COLUMN notes TEXT NOT NULL DEFAULT ''",
-                [],
-            );
-            let _ = conn.execute(
-                "ALTER TABLE tv_shows_to_watch ADD COLUMN notes TEXT NOT NULL DEFAULT ''",
-                [],
-            );
+    /// Open the database at `path`, creating and migrating the schema if needed.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Sqlight::from_connection(Connection::open(path)?)
+    }
 
-            conn.execute(
-                "CREATE TABLE IF NOT EXISTS watched_movies (
-                    id INTEGER PRIMARY KEY,
-                    title TEXT NOT NULL,
-                    poster_path TEXT NOT NULL,
-                    rating REAL CHECK(rating >= 0.5 AND rating <= 5.0) NOT NULL,
-                    notes TEXT NOT NULL DEFAULT ''
-                )",
-                [],
-            )
-            .expect("Failed to create watched_movies table");
-
-            conn.execute(
-                "CREATE TABLE IF NOT EXISTS watched_tv_shows (
-                    id INTEGER PRIMARY KEY,
-                    name TEXT NOT NULL,
-                    poster_path TEXT NOT NULL,
-                    first_air_date TEXT NOT NULL DEFAULT '',
-                    vote_average REAL NOT NULL DEFAULT 0.0,
-                    overview TEXT NOT NULL DEFAULT '',
-                    rating REAL CHECK(rating >= 0.5 AND rating <= 5.0) NOT NULL,
-                    watched_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
-                    notes TEXT NOT NULL DEFAULT ''
-                )",
-                [],
-            )
-            .expect("Failed to create watched_tv_shows table");
+    /// Open a private in-memory database, mainly useful for tests.
+    pub fn open_in_memory() -> Result<Self> {
+        Sqlight::from_connection(Connection::open_in_memory()?)
+    }
 
-            // Add watched_at column to existing TV shows table if it doesn't exist
-            let _ = conn.execute(
-                "ALTER TABLE watched_tv_shows ADD COLUMN watched_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP",
-                [],
-            );
-            
-            // Add notes columns to existing tables if they don't exist
-            let _ = conn.execute(
-                "ALTER TABLE watched_movies ADD COLUMN notes TEXT NOT NULL DEFAULT ''",
-                [],
-            );
-            let _ = conn.execute(
-                "ALTER TABLE watched_tv_shows ADD COLUMN notes TEXT NOT NULL DEFAULT ''",
-                [],
-            );
+    fn from_connection(conn: Connection) -> Result<Self> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS movies_to_watch (
+                id INTEGER PRIMARY KEY,
+                title TEXT NOT NULL,
+                poster_path TEXT NOT NULL,
+                notes TEXT NOT NULL DEFAULT ''
+            )",
+            [],
+        )?;
 
-            // Migration: Always recreate watched_movies table with correct constraint and timestamp
-            // This ensures any existing wrong constraints are fixed
-            let _ = conn.execute("DROP TABLE IF EXISTS watched_movies_temp", []);
-            let _ = conn.execute(
-                "CREATE TABLE watched_movies_temp AS SELECT * FROM watched_movies WHERE 1=0",
-                [],
-            );
-            
-            // Try to copy existing data if table exists
-            let _ = conn.execute(
-                "INSERT INTO watched_movies_temp SELECT * FROM watched_movies",
-                [],
-            );
-            
-            // Drop old table and create new one with correct constraint and timestamp
-            let _ = conn.execute("DROP TABLE IF EXISTS watched_movies", []);
-            conn.execute(
-                "CREATE TABLE watched_movies (
-                    id INTEGER PRIMARY KEY,
-                    title TEXT NOT NULL,
-                    poster_path TEXT NOT NULL,
-                    rating REAL CHECK(rating >= 0.5 AND rating <= 5.0) NOT NULL,
-                    watched_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
-                    notes TEXT NOT NULL DEFAULT ''
-                )",
-                [],
-            ).expect("Failed to create watched_movies table with correct constraint");
-            
-            // Copy back valid data with default timestamp for existing records
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS tv_shows_to_watch (
+                id INTEGER PRIMARY KEY,
+                name TEXT NOT NULL,
+                poster_path TEXT NOT NULL,
+                first_air_date TEXT NOT NULL DEFAULT '',
+                vote_average REAL NOT NULL DEFAULT 0.0,
+                overview TEXT NOT NULL DEFAULT '',
+                notes TEXT NOT NULL DEFAULT ''
+            )",
+            [],
+        )?;
+
+        // Add migration for existing databases
+        let _ = conn.execute(
+            "ALTER TABLE tv_shows_to_watch ADD COLUMN first_air_date TEXT NOT NULL DEFAULT ''",
+            [],
+        );
+        let _ = conn.execute(
+            "ALTER TABLE tv_shows_to_watch ADD COLUMN vote_average REAL NOT NULL DEFAULT 0.0",
+            [],
+        );
+        let _ = conn.execute(
+            "ALTER TABLE tv_shows_to_watch ADD COLUMN overview TEXT NOT NULL DEFAULT ''",
+            [],
+        );
+        let _ = conn.execute(
+            "ALTER TABLE movies_to_watch ADD COLUMN notes TEXT NOT NULL DEFAULT ''",
+            [],
+        );
+        let _ = conn.execute(
+            "ALTER TABLE tv_shows_to_watch ADD COLUMN notes TEXT NOT NULL DEFAULT ''",
+            [],
+        );
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS watched_movies (
+                id INTEGER PRIMARY KEY,
+                title TEXT NOT NULL,
+                poster_path TEXT NOT NULL,
+                rating REAL CHECK(rating >= 0.5 AND rating <= 5.0) NOT NULL,
+                notes TEXT NOT NULL DEFAULT ''
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS watched_tv_shows (
+                id INTEGER PRIMARY KEY,
+                name TEXT NOT NULL,
+                poster_path TEXT NOT NULL,
+                first_air_date TEXT NOT NULL DEFAULT '',
+                vote_average REAL NOT NULL DEFAULT 0.0,
+                overview TEXT NOT NULL DEFAULT '',
+                rating REAL CHECK(rating >= 0.5 AND rating <= 5.0) NOT NULL,
+                watched_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                notes TEXT NOT NULL DEFAULT ''
+            )",
+            [],
+        )?;
+
+        // Add watched_at column to existing TV shows table if it doesn't exist
+        let _ = conn.execute(
+            "ALTER TABLE watched_tv_shows ADD COLUMN watched_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP",
+            [],
+        );
+
+        // Add notes columns to existing tables if they don't exist
+        let _ = conn.execute(
+            "ALTER TABLE watched_movies ADD COLUMN notes TEXT NOT NULL DEFAULT ''",
+            [],
+        );
+        let _ = conn.execute(
+            "ALTER TABLE watched_tv_shows ADD COLUMN notes TEXT NOT NULL DEFAULT ''",
+            [],
+        );
+
+        // Migration: Always recreate watched_movies table with correct constraint and timestamp
+        // This ensures any existing wrong constraints are fixed
+        let _ = conn.execute("DROP TABLE IF EXISTS watched_movies_temp", []);
+        let _ = conn.execute(
+            "CREATE TABLE watched_movies_temp AS SELECT * FROM watched_movies WHERE 1=0",
+            [],
+        );
+
+        // Try to copy existing data if table exists
+        let _ = conn.execute(
+            "INSERT INTO watched_movies_temp SELECT * FROM watched_movies",
+            [],
+        );
+
+        // Drop old table and create new one with correct constraint and timestamp
+        let _ = conn.execute("DROP TABLE IF EXISTS watched_movies", []);
+        conn.execute(
+            "CREATE TABLE watched_movies (
+                id INTEGER PRIMARY KEY,
+                title TEXT NOT NULL,
+                poster_path TEXT NOT NULL,
+                rating REAL CHECK(rating >= 0.5 AND rating <= 5.0) NOT NULL,
+                watched_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                notes TEXT NOT NULL DEFAULT ''
+            )",
+            [],
+        )?;
+
+        // Copy back valid data, keeping timestamps and notes when the old table already had them
+        let copied = conn.execute(
+            "INSERT INTO watched_movies (id, title, poster_path, rating, watched_at, notes)
+             SELECT id, title, poster_path, rating, watched_at, notes FROM watched_movies_temp WHERE rating >= 0.5 AND rating <= 5.0",
+            [],
+        );
+        if copied.is_err() {
+            // Older tables lack these columns, fall back to a default timestamp for existing records
             let _ = conn.execute(
-                "INSERT INTO watched_movies (id, title, poster_path, rating, watched_at, notes) 
+                "INSERT INTO watched_movies (id, title, poster_path, rating, watched_at, notes)
                  SELECT id, title, poster_path, rating, CURRENT_TIMESTAMP, '' FROM watched_movies_temp WHERE rating >= 0.5 AND rating <= 5.0",
                 [],
             );
-            let _ = conn.execute("DROP TABLE watched_movies_temp", []);
-
-            Arc::new(Mutex::new(Sqlight { conn }))
-        });
+        }
+        let _ = conn.execute("DROP TABLE watched_movies_temp", []);
 
-        Ok(instance.get().unwrap().clone())
+        Ok(Sqlight { conn })
     }
 
     pub fn insert_movie_to_watch(&self, movie: &MovieToWatch) -> Result<()> {
@@ -175,14 +193,14 @@ impl Sqlight {
 
     pub fn delete_movie_to_watch(&self, id: i32) -> Result<()> {
         self.conn
-            .execute("DELETE FROM movies_to_watch WHERE id = ?1", &[&id])?;
+            .execute("DELETE FROM movies_to_watch WHERE id = ?1", [&id])?;
         Ok(())
     }
 
     pub fn get_all_movies_to_watch(&self) -> Result<Vec<MovieToWatch>> {
-        let mut stmt = self
-            .conn
-            .prepare("SELECT id, title, poster_path, COALESCE(notes, '') as notes FROM movies_to_watch")?;
+        let mut stmt = self.conn.prepare(
+            "SELECT id, title, poster_path, COALESCE(notes, '') as notes FROM movies_to_watch",
+        )?;
         let movie_iter = stmt.query_map([], |row| {
             Ok(MovieToWatch {
                 id: row.get(0)?,
@@ -216,7 +234,7 @@ impl Sqlight {
 
     pub fn delete_watched_movie(&self, id: i32) -> Result<()> {
         self.conn
-            .execute("DELETE FROM watched_movies WHERE id = ?1", &[&id])?;
+            .execute("DELETE FROM watched_movies WHERE id = ?1", [&id])?;
         Ok(())
     }
 
@@ -252,7 +270,7 @@ impl Sqlight {
 
     pub fn delete_tv_show_to_watch(&self, id: i32) -> Result<()> {
         self.conn
-            .execute("DELETE FROM tv_shows_to_watch WHERE id = ?1", &[&id])?;
+            .execute("DELETE FROM tv_shows_to_watch WHERE id = ?1", [&id])?;
         Ok(())
     }
 
@@ -292,8 +310,8 @@ impl Sqlight {
         let mut stmt = self
             .conn
             .prepare("SELECT rating FROM watched_movies WHERE id = ?1")?;
-        let result = stmt.query_row([movie_id], |row| Ok(row.get::<_, f32>(0)?));
-        
+        let result = stmt.query_row([movie_id], |row| row.get::<_, f32>(0));
+
         match result {
             Ok(rating) => Ok(Some(rating)),
             Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
@@ -303,7 +321,7 @@ impl Sqlight {
 
     pub fn remove_movie_rating(&self, movie_id: i32) -> Result<()> {
         self.conn
-            .execute("DELETE FROM watched_movies WHERE id = ?1", &[&movie_id])?;
+            .execute("DELETE FROM watched_movies WHERE id = ?1", [&movie_id])?;
         Ok(())
     }
 
@@ -342,8 +360,8 @@ impl Sqlight {
         let mut stmt = self
             .conn
             .prepare("SELECT rating FROM watched_tv_shows WHERE id = ?1")?;
-        let result = stmt.query_row([tv_show_id], |row| Ok(row.get::<_, f32>(0)?));
-        
+        let result = stmt.query_row([tv_show_id], |row| row.get::<_, f32>(0));
+
         match result {
             Ok(rating) => Ok(Some(rating)),
             Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
@@ -353,7 +371,7 @@ impl Sqlight {
 
     pub fn remove_tv_show_rating(&self, tv_show_id: i32) -> Result<()> {
         self.conn
-            .execute("DELETE FROM watched_tv_shows WHERE id = ?1", &[&tv_show_id])?;
+            .execute("DELETE FROM watched_tv_shows WHERE id = ?1", [&tv_show_id])?;
         Ok(())
     }
 
@@ -382,37 +400,37 @@ impl Sqlight {
         Ok(tv_shows)
     }
 
-    // Methods for updating notes
-    pub fn update_movie_notes(&self, movie_id: i32, notes: &str) -> Result<()> {
-        self.conn.execute(
+    // Methods for updating notes, returning whether there was an entry to update
+    pub fn update_movie_notes(&self, movie_id: i32, notes: &str) -> Result<bool> {
+        let updated = self.conn.execute(
             "UPDATE movies_to_watch SET notes = ?1 WHERE id = ?2",
             params![notes, movie_id],
         )?;
-        Ok(())
+        Ok(updated > 0)
     }
 
-    pub fn update_tv_show_notes(&self, tv_show_id: i32, notes: &str) -> Result<()> {
-        self.conn.execute(
+    pub fn update_tv_show_notes(&self, tv_show_id: i32, notes: &str) -> Result<bool> {
+        let updated = self.conn.execute(
             "UPDATE tv_shows_to_watch SET notes = ?1 WHERE id = ?2",
             params![notes, tv_show_id],
         )?;
-        Ok(())
+        Ok(updated > 0)
     }
 
-    pub fn update_watched_movie_notes(&self, movie_id: i32, notes: &str) -> Result<()> {
-        self.conn.execute(
+    pub fn update_watched_movie_notes(&self, movie_id: i32, notes: &str) -> Result<bool> {
+        let updated = self.conn.execute(
             "UPDATE watched_movies SET notes = ?1 WHERE id = ?2",
             params![notes, movie_id],
         )?;
-        Ok(())
+        Ok(updated > 0)
     }
 
-    pub fn update_watched_tv_show_notes(&self, tv_show_id: i32, notes: &str) -> Result<()> {
-        self.conn.execute(
+    pub fn update_watched_tv_show_notes(&self, tv_show_id: i32, notes: &str) -> Result<bool> {
+        let updated = self.conn.execute(
             "UPDATE watched_tv_shows SET notes = ?1 WHERE id = ?2",
             params![notes, tv_show_id],
         )?;
-        Ok(())
+        Ok(updated > 0)
     }
 
     // Methods for getting notes
@@ -420,8 +438,8 @@ impl Sqlight {
         let mut stmt = self
             .conn
             .prepare("SELECT notes FROM movies_to_watch WHERE id = ?1")?;
-        let result = stmt.query_row([movie_id], |row| Ok(row.get::<_, String>(0)?));
-        
+        let result = stmt.query_row([movie_id], |row| row.get::<_, String>(0));
+
         match result {
             Ok(notes) => Ok(Some(notes)),
             Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
@@ -433,8 +451,8 @@ impl Sqlight {
         let mut stmt = self
             .conn
             .prepare("SELECT notes FROM tv_shows_to_watch WHERE id = ?1")?;
-        let result = stmt.query_row([tv_show_id], |row| Ok(row.get::<_, String>(0)?));
-        
+        let result = stmt.query_row([tv_show_id], |row| row.get::<_, String>(0));
+
         match result {
             Ok(notes) => Ok(Some(notes)),
             Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
@@ -446,8 +464,8 @@ impl Sqlight {
         let mut stmt = self
             .conn
             .prepare("SELECT notes FROM watched_movies WHERE id = ?1")?;
-        let result = stmt.query_row([movie_id], |row| Ok(row.get::<_, String>(0)?));
-        
+        let result = stmt.query_row([movie_id], |row| row.get::<_, String>(0));
+
         match result {
             Ok(notes) => Ok(Some(notes)),
             Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
@@ -459,18 +477,183 @@ impl Sqlight {
         let mut stmt = self
             .conn
             .prepare("SELECT notes FROM watched_tv_shows WHERE id = ?1")?;
-        let result = stmt.query_row([tv_show_id], |row| Ok(row.get::<_, String>(0)?));
-        
+        let result = stmt.query_row([tv_show_id], |row| row.get::<_, String>(0));
+
         match result {
             Ok(notes) => Ok(Some(notes)),
             Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
             Err(e) => Err(e),
         }
     }
+
+    // Methods for looking up single watched entries
+    pub fn get_watched_movie(&self, movie_id: i32) -> Result<Option<WatchedMovie>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, title, poster_path, rating, watched_at, COALESCE(notes, '') as notes FROM watched_movies WHERE id = ?1")?;
+        let result = stmt.query_row([movie_id], |row| {
+            Ok(WatchedMovie {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                poster_path: row.get(2)?,
+                rating: row.get(3)?,
+                watched_at: row.get(4)?,
+                notes: row.get(5)?,
+            })
+        });
+
+        match result {
+            Ok(movie) => Ok(Some(movie)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn get_watched_tv_show(&self, tv_show_id: i32) -> Result<Option<WatchedTvShow>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, name, poster_path, first_air_date, vote_average, overview, rating, watched_at, COALESCE(notes, '') as notes FROM watched_tv_shows WHERE id = ?1")?;
+        let result = stmt.query_row([tv_show_id], |row| {
+            Ok(WatchedTvShow {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                poster_path: row.get(2)?,
+                first_air_date: row.get(3)?,
+                vote_average: row.get(4)?,
+                overview: row.get(5)?,
+                rating: row.get(6)?,
+                watched_at: row.get(7)?,
+                notes: row.get(8)?,
+            })
+        });
+
+        match result {
+            Ok(tv_show) => Ok(Some(tv_show)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    // Methods for exporting and importing the whole library
+    pub fn export_library(&self) -> Result<LibraryExport> {
+        Ok(LibraryExport {
+            version: LibraryExport::VERSION,
+            movies_to_watch: self.get_all_movies_to_watch()?,
+            tv_shows_to_watch: self.get_all_tv_shows_to_watch()?,
+            watched_movies: self.get_all_rated_movies()?,
+            watched_tv_shows: self.get_all_rated_tv_shows()?,
+        })
+    }
+
+    /// Merge an export into the library. Entries with an existing id are replaced.
+    pub fn import_library(&self, library: &LibraryExport) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+
+        for movie in &library.movies_to_watch {
+            self.insert_movie_to_watch(movie)?;
+        }
+        for tv_show in &library.tv_shows_to_watch {
+            self.insert_tv_show_to_watch(tv_show)?;
+        }
+        for movie in &library.watched_movies {
+            self.rate_movie(movie)?;
+        }
+        for tv_show in &library.watched_tv_shows {
+            self.rate_tv_show(tv_show)?;
+        }
+
+        tx.commit()
+    }
 }
 
 fn create_directory(dir_path: &std::path::PathBuf) {
     if !dir_path.exists() {
-        fs::create_dir_all(&dir_path).expect("Failed to create directory");
+        fs::create_dir_all(dir_path).expect("Failed to create directory");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn watched_movie(id: i32, rating: f32) -> WatchedMovie {
+        WatchedMovie {
+            id,
+            title: format!("Movie {}", id),
+            poster_path: "/poster.jpg".to_string(),
+            rating,
+            watched_at: "2025-01-01T20:00:00+00:00".to_string(),
+            notes: "Great *ending*".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_open_in_memory_creates_schema() {
+        let db = Sqlight::open_in_memory().unwrap();
+
+        assert!(db.get_all_movies_to_watch().unwrap().is_empty());
+        assert!(db.get_all_tv_shows_to_watch().unwrap().is_empty());
+        assert!(db.get_all_rated_movies().unwrap().is_empty());
+        assert!(db.get_all_rated_tv_shows().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_reopen_keeps_watched_at_and_notes() {
+        let path =
+            std::env::temp_dir().join(format!("tv_tracker_reopen_{}.db", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        Sqlight::open(&path)
+            .unwrap()
+            .rate_movie(&watched_movie(603, 4.5))
+            .unwrap();
+        let movie = Sqlight::open(&path)
+            .unwrap()
+            .get_watched_movie(603)
+            .unwrap()
+            .unwrap();
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(movie.watched_at, "2025-01-01T20:00:00+00:00");
+        assert_eq!(movie.notes, "Great *ending*");
+    }
+
+    #[test]
+    fn test_get_watched_movie() {
+        let db = Sqlight::open_in_memory().unwrap();
+        db.rate_movie(&watched_movie(603, 4.5)).unwrap();
+
+        let movie = db.get_watched_movie(603).unwrap().unwrap();
+        assert_eq!(movie.title, "Movie 603");
+        assert_eq!(movie.rating, 4.5);
+        assert!(db.get_watched_movie(604).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_export_import_round_trip() {
+        let source = Sqlight::open_in_memory().unwrap();
+        source
+            .insert_movie_to_watch(&MovieToWatch {
+                id: 27205,
+                title: "Inception".to_string(),
+                poster_path: "/inception.jpg".to_string(),
+                notes: String::new(),
+            })
+            .unwrap();
+        source.rate_movie(&watched_movie(603, 5.0)).unwrap();
+
+        let export = source.export_library().unwrap();
+        assert_eq!(export.version, LibraryExport::VERSION);
+
+        let target = Sqlight::open_in_memory().unwrap();
+        target.import_library(&export).unwrap();
+
+        let movies = target.get_all_movies_to_watch().unwrap();
+        assert_eq!(movies.len(), 1);
+        assert_eq!(movies[0].title, "Inception");
+
+        let watched = target.get_watched_movie(603).unwrap().unwrap();
+        assert_eq!(watched.rating, 5.0);
+        assert_eq!(watched.notes, "Great *ending*");
     }
 }
@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct MovieToWatch {
     pub id: i32,
     pub title: String,
@@ -9,6 +10,7 @@ pub struct MovieToWatch {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct TvShowToWatch {
     pub id: i32,
     pub name: String,
@@ -20,6 +22,7 @@ pub struct TvShowToWatch {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct WatchedMovie {
     pub id: i32,
     pub title: String,
@@ -30,6 +33,7 @@ pub struct WatchedMovie {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct WatchedTvShow {
     pub id: i32,
     pub name: String,
@@ -41,3 +45,17 @@ pub struct WatchedTvShow {
     pub watched_at: String,
     pub notes: String,
 }
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct LibraryExport {
+    pub version: u32,
+    pub movies_to_watch: Vec<MovieToWatch>,
+    pub tv_shows_to_watch: Vec<TvShowToWatch>,
+    pub watched_movies: Vec<WatchedMovie>,
+    pub watched_tv_shows: Vec<WatchedTvShow>,
+}
+
+impl LibraryExport {
+    pub const VERSION: u32 = 1;
+}
@@ -1,6 +1,7 @@
 pub mod api;
 pub mod config;
 pub mod database;
+pub mod service;
 
 pub use crate::config::TmdbConfig;
 
@@ -13,6 +14,6 @@ mod tests {
         let conn = database::Sqlight::get_connection().unwrap();
         let db = conn.lock().unwrap();
 
-        let movies = db.get_all_movies_to_watch().unwrap();
+        let _movies = db.get_all_movies_to_watch().unwrap();
     }
 }
@@ -0,0 +1,27 @@
+use thiserror::Error;
+
+use crate::service::ValidationErrors;
+
+/// Errors returned by [`Library`](super::Library), split so callers can map them to
+/// their own responses (exit codes, HTTP statuses, toast messages)
+#[derive(Debug, Error)]
+pub enum LibraryError {
+    /// The input was rejected before touching the database
+    #[error("{0}")]
+    Invalid(#[from] ValidationErrors),
+
+    #[error("{0}")]
+    NotFound(String),
+
+    /// TMDB is not configured or the request to it failed
+    #[error("{0}")]
+    Tmdb(String),
+
+    #[error("Database error: {0}")]
+    Database(#[from] rusqlite::Error),
+
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+pub type LibraryResult<T> = std::result::Result<T, LibraryError>;
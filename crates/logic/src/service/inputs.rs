@@ -0,0 +1,256 @@
+use serde::{Deserialize, Serialize};
+
+use crate::database::entities::{LibraryExport, MovieToWatch, TvShowToWatch, WatchedMovie, WatchedTvShow};
+use crate::service::validation::{
+    check_id, check_notes, check_rating, check_title, check_vote_average, check_watched_at, validate_id,
+};
+use crate::service::{Validate, ValidationErrors};
+
+/// Movie or TV show
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum MediaKind {
+    Movie,
+    Tv,
+}
+
+/// Which list the notes of a title are stored in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum NotesLocation {
+    Watchlist,
+    Watched,
+}
+
+/// Any payload that carries a TMDB id; other fields are ignored
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct EntryId {
+    pub id: i32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct NewMovie {
+    pub id: i32,
+    pub title: String,
+    #[serde(default)]
+    pub poster_path: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct NewTvShow {
+    pub id: i32,
+    pub name: String,
+    #[serde(default)]
+    pub poster_path: String,
+    #[serde(default)]
+    pub first_air_date: String,
+    #[serde(default)]
+    pub vote_average: f32,
+    #[serde(default)]
+    pub overview: String,
+}
+
+/// Rate a movie. The title fields are only used the first time a movie is rated.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RateMovie {
+    pub id: i32,
+    pub rating: f32,
+    /// RFC 3339 timestamp; defaults to now for new entries and is kept otherwise
+    pub watched_at: Option<String>,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub poster_path: String,
+}
+
+/// Rate a TV show. The show fields are only used the first time a show is rated.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RateTvShow {
+    pub id: i32,
+    pub rating: f32,
+    /// RFC 3339 timestamp; defaults to now for new entries and is kept otherwise
+    pub watched_at: Option<String>,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub poster_path: String,
+    #[serde(default)]
+    pub first_air_date: String,
+    #[serde(default)]
+    pub vote_average: f32,
+    #[serde(default)]
+    pub overview: String,
+}
+
+impl Validate for EntryId {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        validate_id(self.id)
+    }
+}
+
+impl Validate for NewMovie {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        check_id(&mut errors, "id", self.id);
+        check_title(&mut errors, "title", &self.title);
+        errors.into_result()
+    }
+}
+
+impl Validate for NewTvShow {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        check_id(&mut errors, "id", self.id);
+        check_title(&mut errors, "name", &self.name);
+        check_vote_average(&mut errors, self.vote_average);
+        errors.into_result()
+    }
+}
+
+// The title fields of rating inputs are checked by the library, and only for new entries
+
+impl Validate for RateMovie {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        check_id(&mut errors, "id", self.id);
+        check_rating(&mut errors, self.rating);
+        check_watched_at(&mut errors, self.watched_at.as_deref());
+        errors.into_result()
+    }
+}
+
+impl Validate for RateTvShow {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        check_id(&mut errors, "id", self.id);
+        check_rating(&mut errors, self.rating);
+        check_watched_at(&mut errors, self.watched_at.as_deref());
+        check_vote_average(&mut errors, self.vote_average);
+        errors.into_result()
+    }
+}
+
+// Entries of an imported library. Their `watched_at` isn't checked, since rows rated before it was
+// validated hold SQLite's `CURRENT_TIMESTAMP` format and have to survive an export and import.
+
+impl Validate for LibraryExport {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        if self.version > LibraryExport::VERSION {
+            let message =
+                format!("Export version {} is newer than the supported version {}", self.version, LibraryExport::VERSION);
+            errors.add("version", message);
+        }
+        check_entries(&mut errors, "movies_to_watch", &self.movies_to_watch);
+        check_entries(&mut errors, "tv_shows_to_watch", &self.tv_shows_to_watch);
+        check_entries(&mut errors, "watched_movies", &self.watched_movies);
+        check_entries(&mut errors, "watched_tv_shows", &self.watched_tv_shows);
+        errors.into_result()
+    }
+}
+
+/// Validate each entry of a list, naming the fields of the invalid ones by their index, e.g.
+/// `watched_movies[2].rating`
+fn check_entries<T: Validate>(errors: &mut ValidationErrors, field: &str, entries: &[T]) {
+    for (index, entry) in entries.iter().enumerate() {
+        if let Err(entry_errors) = entry.validate() {
+            errors.add_nested(&format!("{}[{}]", field, index), entry_errors);
+        }
+    }
+}
+
+impl Validate for MovieToWatch {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        check_id(&mut errors, "id", self.id);
+        check_title(&mut errors, "title", &self.title);
+        check_notes(&mut errors, &self.notes);
+        errors.into_result()
+    }
+}
+
+impl Validate for TvShowToWatch {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        check_id(&mut errors, "id", self.id);
+        check_title(&mut errors, "name", &self.name);
+        check_vote_average(&mut errors, self.vote_average);
+        check_notes(&mut errors, &self.notes);
+        errors.into_result()
+    }
+}
+
+impl Validate for WatchedMovie {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        check_id(&mut errors, "id", self.id);
+        check_title(&mut errors, "title", &self.title);
+        check_rating(&mut errors, self.rating);
+        check_notes(&mut errors, &self.notes);
+        errors.into_result()
+    }
+}
+
+impl Validate for WatchedTvShow {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        check_id(&mut errors, "id", self.id);
+        check_title(&mut errors, "name", &self.name);
+        check_vote_average(&mut errors, self.vote_average);
+        check_rating(&mut errors, self.rating);
+        check_notes(&mut errors, &self.notes);
+        errors.into_result()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rate(rating: f32) -> RateMovie {
+        RateMovie { id: 603, rating, watched_at: None, title: String::new(), poster_path: String::new() }
+    }
+
+    #[test]
+    fn test_rating_steps() {
+        for rating in [0.5, 1.0, 3.5, 5.0] {
+            assert!(rate(rating).validate().is_ok(), "{} should be valid", rating);
+        }
+        for rating in [0.0, 0.25, 4.2, 5.5, -1.0] {
+            assert!(rate(rating).validate().is_err(), "{} should be invalid", rating);
+        }
+    }
+
+    #[test]
+    fn test_watched_at_must_be_rfc3339() {
+        let mut input = rate(4.0);
+        input.watched_at = Some("2025-01-01T20:00:00Z".to_string());
+        assert!(input.validate().is_ok());
+
+        input.watched_at = Some("yesterday".to_string());
+        assert!(input.validate().is_err());
+    }
+
+    #[test]
+    fn test_new_movie_needs_id_and_title() {
+        let movie = NewMovie { id: 0, title: "The Matrix".to_string(), poster_path: String::new() };
+        assert!(movie.validate().is_err());
+
+        let movie = NewMovie { id: 603, title: "  ".to_string(), poster_path: String::new() };
+        assert!(movie.validate().is_err());
+    }
+
+    #[test]
+    fn test_inputs_ignore_extra_fields() {
+        let movie: NewMovie =
+            serde_json::from_str(r#"{ "id": 603, "title": "The Matrix", "poster_path": "/m.jpg", "runtime": 136 }"#).unwrap();
+
+        assert_eq!(movie.id, 603);
+        assert_eq!(movie.poster_path, "/m.jpg");
+    }
+}
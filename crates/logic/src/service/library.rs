@@ -0,0 +1,749 @@
+use std::collections::HashSet;
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use anyhow::anyhow;
+use lru::LruCache;
+use markdown_renderer::{AstNode, HtmlAllowlist, HtmlOptions, UrlPolicy};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use crate::api::{Movie, MovieDetail, Tmdb, Tv, TvDetail};
+use crate::config::{write_api_key_to_config, TmdbConfig};
+use crate::database::entities::{LibraryExport, MovieToWatch, TvShowToWatch, WatchedMovie, WatchedTvShow};
+use crate::database::Sqlight;
+use crate::service::references::references;
+use crate::service::validation::{check_id, check_notes, check_title, validate_id};
+use crate::service::{
+    Backlink, LibraryError, LibraryResult, MediaKind, NewMovie, NewTvShow, NotesLocation, RateMovie, RateTvShow,
+    ResolvedTitles, Validate, ValidationErrors,
+};
+
+/// Title and poster path of TMDB titles by kind and id
+type TmdbTitles = LruCache<(MediaKind, u32), (String, String)>;
+
+/// How many TMDB titles are cached, the least recently used making way for new ones
+const TMDB_TITLES_CACHED: usize = 1000;
+
+/// How many TMDB lookups run at the same time, across all the notes being resolved
+const TMDB_LOOKUPS: usize = 8;
+
+/// The tracker's operations in one place: owns the database handle and the TMDB client
+/// so the desktop app, CLI, TUI and HTTP server all behave the same way
+pub struct Library {
+    db: Arc<Mutex<Sqlight>>,
+    // Replaced when a new API key is saved; cloned out before awaiting so no lock is held across requests
+    tmdb: Mutex<Option<Arc<Tmdb>>>,
+    // Titles outside the library that notes referenced, looked up on TMDB once rather than on every
+    // render of the live preview
+    tmdb_titles: Mutex<TmdbTitles>,
+    // Permits for TMDB lookups, so notes referencing many unknown titles don't flood the API
+    tmdb_lookups: Arc<Semaphore>,
+}
+
+impl Library {
+    pub fn new(db: Arc<Mutex<Sqlight>>, tmdb: Option<Tmdb>) -> Self {
+        let cached = NonZeroUsize::new(TMDB_TITLES_CACHED).expect("The TMDB title cache isn't empty");
+        Library {
+            db,
+            tmdb: Mutex::new(tmdb.map(Arc::new)),
+            tmdb_titles: Mutex::new(LruCache::new(cached)),
+            tmdb_lookups: Arc::new(Semaphore::new(TMDB_LOOKUPS)),
+        }
+    }
+
+    /// Open the shared database and, when an API key is configured, a TMDB client
+    pub fn open() -> anyhow::Result<Self> {
+        let tmdb = TmdbConfig::initialize().ok().map(Tmdb::new);
+        Ok(Library::new(Sqlight::get_connection()?, tmdb))
+    }
+
+    /// A library over an empty in-memory database without TMDB access
+    pub fn open_in_memory() -> anyhow::Result<Self> {
+        Ok(Library::new(Arc::new(Mutex::new(Sqlight::open_in_memory()?)), None))
+    }
+
+    fn db(&self) -> LibraryResult<MutexGuard<'_, Sqlight>> {
+        self.db.lock().map_err(|_| anyhow!("Failed to lock the database").into())
+    }
+
+    fn tmdb(&self) -> LibraryResult<Arc<Tmdb>> {
+        let tmdb = self.tmdb.lock().map_err(|_| anyhow!("Failed to lock the TMDB client"))?;
+        tmdb.clone().ok_or_else(|| {
+            LibraryError::Tmdb("No TMDB API key found, add one to ~/.config/tv/config.toml".to_string())
+        })
+    }
+
+    // TMDB
+
+    pub async fn trending_movies(&self) -> LibraryResult<Vec<Movie>> {
+        let movies = self.tmdb()?.trending_movies().await;
+        movies.ok_or_else(|| LibraryError::Tmdb("Failed to fetch trending movies".to_string()))
+    }
+
+    pub async fn trending_tv(&self) -> LibraryResult<Vec<Tv>> {
+        let shows = self.tmdb()?.trending_tv().await;
+        shows.ok_or_else(|| LibraryError::Tmdb("Failed to fetch trending TV shows".to_string()))
+    }
+
+    pub async fn search_movies(&self, query: &str) -> LibraryResult<Vec<Movie>> {
+        let movies = self.tmdb()?.find_movies(query).await;
+        movies.ok_or_else(|| LibraryError::Tmdb("Failed to fetch movies".to_string()))
+    }
+
+    pub async fn search_tv(&self, query: &str) -> LibraryResult<Vec<Tv>> {
+        let shows = self.tmdb()?.find_tv(query).await;
+        shows.ok_or_else(|| LibraryError::Tmdb("Failed to fetch TV shows".to_string()))
+    }
+
+    pub async fn movie_details(&self, id: u32) -> LibraryResult<MovieDetail> {
+        let movie = self.tmdb()?.get_movie_details(id).await;
+        movie.map_err(|e| LibraryError::Tmdb(format!("Failed to fetch movie details: {:#}", e)))
+    }
+
+    pub async fn tv_details(&self, id: u32) -> LibraryResult<TvDetail> {
+        let show = self.tmdb()?.get_tv_show_details(id).await;
+        show.map_err(|e| LibraryError::Tmdb(format!("Failed to fetch TV show details: {:#}", e)))
+    }
+
+    /// Whether TMDB accepts the configured API key; `false` when none is configured yet
+    pub async fn valid_key(&self) -> LibraryResult<bool> {
+        let tmdb = match self.tmdb() {
+            Ok(tmdb) => tmdb,
+            Err(LibraryError::Tmdb(_)) => return Ok(false),
+            Err(e) => return Err(e),
+        };
+        let valid = tmdb.valid_key().await;
+        valid.map_err(|e| LibraryError::Tmdb(format!("{:#}", e)))
+    }
+
+    /// Save a new TMDB API key to the config file and use it from now on
+    pub fn set_api_key(&self, key: &str) -> LibraryResult<()> {
+        if key.trim().is_empty() {
+            return Err(ValidationErrors::single("key", "API key is required").into());
+        }
+        write_api_key_to_config(key)?;
+
+        let mut tmdb = self.tmdb.lock().map_err(|_| anyhow!("Failed to lock the TMDB client"))?;
+        *tmdb = Some(Arc::new(Tmdb::new(TmdbConfig::new(key.to_string()))));
+        Ok(())
+    }
+
+    // Watchlist
+
+    pub fn watchlist_movies(&self) -> LibraryResult<Vec<MovieToWatch>> {
+        Ok(self.db()?.get_all_movies_to_watch()?)
+    }
+
+    pub fn watchlist_tv_shows(&self) -> LibraryResult<Vec<TvShowToWatch>> {
+        Ok(self.db()?.get_all_tv_shows_to_watch()?)
+    }
+
+    /// Add a movie, keeping the notes of an earlier entry
+    pub fn add_movie_to_watchlist(&self, movie: NewMovie) -> LibraryResult<MovieToWatch> {
+        movie.validate()?;
+
+        let db = self.db()?;
+        let movie = MovieToWatch {
+            notes: db.get_movie_notes(movie.id)?.unwrap_or_default(),
+            id: movie.id,
+            title: movie.title,
+            poster_path: movie.poster_path,
+        };
+        db.insert_movie_to_watch(&movie)?;
+        Ok(movie)
+    }
+
+    /// Add a TV show, keeping the notes of an earlier entry
+    pub fn add_tv_show_to_watchlist(&self, show: NewTvShow) -> LibraryResult<TvShowToWatch> {
+        show.validate()?;
+
+        let db = self.db()?;
+        let show = TvShowToWatch {
+            notes: db.get_tv_show_notes(show.id)?.unwrap_or_default(),
+            id: show.id,
+            name: show.name,
+            poster_path: show.poster_path,
+            first_air_date: show.first_air_date,
+            vote_average: show.vote_average,
+            overview: show.overview,
+        };
+        db.insert_tv_show_to_watch(&show)?;
+        Ok(show)
+    }
+
+    pub fn remove_from_watchlist(&self, kind: MediaKind, id: i32) -> LibraryResult<()> {
+        validate_id(id)?;
+        let db = self.db()?;
+        match kind {
+            MediaKind::Movie => db.delete_movie_to_watch(id)?,
+            MediaKind::Tv => db.delete_tv_show_to_watch(id)?,
+        }
+        Ok(())
+    }
+
+    // Watched
+
+    pub fn watched_movies(&self) -> LibraryResult<Vec<WatchedMovie>> {
+        Ok(self.db()?.get_all_rated_movies()?)
+    }
+
+    pub fn watched_tv_shows(&self) -> LibraryResult<Vec<WatchedTvShow>> {
+        Ok(self.db()?.get_all_rated_tv_shows()?)
+    }
+
+    pub fn watched_movie(&self, id: i32) -> LibraryResult<Option<WatchedMovie>> {
+        validate_id(id)?;
+        Ok(self.db()?.get_watched_movie(id)?)
+    }
+
+    pub fn watched_tv_show(&self, id: i32) -> LibraryResult<Option<WatchedTvShow>> {
+        validate_id(id)?;
+        Ok(self.db()?.get_watched_tv_show(id)?)
+    }
+
+    pub fn rating(&self, kind: MediaKind, id: i32) -> LibraryResult<Option<f32>> {
+        validate_id(id)?;
+        let db = self.db()?;
+        Ok(match kind {
+            MediaKind::Movie => db.get_movie_rating(id)?,
+            MediaKind::Tv => db.get_tv_show_rating(id)?,
+        })
+    }
+
+    /// Rate a movie. Re-rating keeps its notes and, unless a new one is given, its watch date.
+    pub fn rate_movie(&self, input: RateMovie) -> LibraryResult<WatchedMovie> {
+        input.validate()?;
+
+        let db = self.db()?;
+        let movie = match db.get_watched_movie(input.id)? {
+            Some(movie) => WatchedMovie {
+                rating: input.rating,
+                watched_at: input.watched_at.unwrap_or(movie.watched_at),
+                ..movie
+            },
+            None => {
+                let mut errors = ValidationErrors::new();
+                check_title(&mut errors, "title", &input.title);
+                errors.into_result()?;
+                WatchedMovie {
+                    id: input.id,
+                    title: input.title,
+                    poster_path: input.poster_path,
+                    rating: input.rating,
+                    watched_at: input.watched_at.unwrap_or_else(|| chrono::Utc::now().to_rfc3339()),
+                    notes: String::new(),
+                }
+            }
+        };
+        db.rate_movie(&movie)?;
+        Ok(movie)
+    }
+
+    /// Rate a TV show. Re-rating keeps its notes and, unless a new one is given, its watch date.
+    pub fn rate_tv_show(&self, input: RateTvShow) -> LibraryResult<WatchedTvShow> {
+        input.validate()?;
+
+        let db = self.db()?;
+        let show = match db.get_watched_tv_show(input.id)? {
+            Some(show) => WatchedTvShow {
+                rating: input.rating,
+                watched_at: input.watched_at.unwrap_or(show.watched_at),
+                ..show
+            },
+            None => {
+                let mut errors = ValidationErrors::new();
+                check_title(&mut errors, "name", &input.name);
+                errors.into_result()?;
+                WatchedTvShow {
+                    id: input.id,
+                    name: input.name,
+                    poster_path: input.poster_path,
+                    first_air_date: input.first_air_date,
+                    vote_average: input.vote_average,
+                    overview: input.overview,
+                    rating: input.rating,
+                    watched_at: input.watched_at.unwrap_or_else(|| chrono::Utc::now().to_rfc3339()),
+                    notes: String::new(),
+                }
+            }
+        };
+        db.rate_tv_show(&show)?;
+        Ok(show)
+    }
+
+    pub fn remove_rating(&self, kind: MediaKind, id: i32) -> LibraryResult<()> {
+        validate_id(id)?;
+        let db = self.db()?;
+        match kind {
+            MediaKind::Movie => db.remove_movie_rating(id)?,
+            MediaKind::Tv => db.remove_tv_show_rating(id)?,
+        }
+        Ok(())
+    }
+
+    // Notes
+
+    /// Notes of one entry, or `None` when the title isn't in that list
+    pub fn notes(&self, location: NotesLocation, kind: MediaKind, id: i32) -> LibraryResult<Option<String>> {
+        validate_id(id)?;
+        read_notes(&*self.db()?, location, kind, id)
+    }
+
+    /// Look up the notes of a title, preferring the watched entry over the watchlist one
+    pub fn find_notes(&self, kind: MediaKind, id: i32) -> LibraryResult<Option<(NotesLocation, String)>> {
+        if let Some(notes) = self.notes(NotesLocation::Watched, kind, id)? {
+            return Ok(Some((NotesLocation::Watched, notes)));
+        }
+        Ok(self.notes(NotesLocation::Watchlist, kind, id)?.map(|notes| (NotesLocation::Watchlist, notes)))
+    }
+
+    /// Replace the notes of an existing entry
+    pub fn update_notes(&self, location: NotesLocation, kind: MediaKind, id: i32, notes: &str) -> LibraryResult<()> {
+        let mut errors = ValidationErrors::new();
+        check_id(&mut errors, "id", id);
+        check_notes(&mut errors, notes);
+        errors.into_result()?;
+
+        write_notes(&*self.db()?, location, kind, id, notes)
+    }
+
+    /// Check or uncheck the task list item at byte `offset` of an entry's notes, the `data-task-offset`
+    /// of its rendered checkbox, and return the new notes. Notes without a task item starting there are
+    /// kept as they are.
+    pub fn toggle_notes_task(&self, location: NotesLocation, kind: MediaKind, id: i32, offset: usize) -> LibraryResult<String> {
+        validate_id(id)?;
+        // Read and written under one lock, so an edit in between isn't lost
+        let db = self.db()?;
+        let notes = read_notes(&db, location, kind, id)?.unwrap_or_default();
+        let notes = markdown_renderer::toggle_task(&notes, offset);
+        write_notes(&db, location, kind, id, &notes)?;
+        Ok(notes)
+    }
+
+    /// Every entry whose notes reference the title, watchlist entries first. A title's own notes
+    /// mentioning itself don't count.
+    pub fn backlinks(&self, kind: MediaKind, id: i32) -> LibraryResult<Vec<Backlink>> {
+        validate_id(id)?;
+        let library = self.db()?.export_library()?;
+
+        let entries = library
+            .movies_to_watch
+            .into_iter()
+            .map(|movie| (NotesLocation::Watchlist, MediaKind::Movie, movie.id, movie.title, movie.notes))
+            .chain(
+                library
+                    .tv_shows_to_watch
+                    .into_iter()
+                    .map(|show| (NotesLocation::Watchlist, MediaKind::Tv, show.id, show.name, show.notes)),
+            )
+            .chain(
+                library
+                    .watched_movies
+                    .into_iter()
+                    .map(|movie| (NotesLocation::Watched, MediaKind::Movie, movie.id, movie.title, movie.notes)),
+            )
+            .chain(
+                library
+                    .watched_tv_shows
+                    .into_iter()
+                    .map(|show| (NotesLocation::Watched, MediaKind::Tv, show.id, show.name, show.notes)),
+            );
+
+        Ok(entries
+            .filter(|&(_, entry_kind, entry_id, _, ref notes)| (entry_kind, entry_id) != (kind, id) && references(notes, kind, id))
+            .map(|(location, kind, id, title, _)| Backlink { location, kind, id, title })
+            .collect())
+    }
+
+    /// Look up the titles referenced in the notes, first in the library and then on TMDB when
+    /// it's configured. Titles neither knows are left out. TMDB is asked about each title once, with
+    /// the titles it hasn't been asked about yet looked up a few at a time.
+    pub async fn resolve_titles(&self, notes: &str) -> LibraryResult<ResolvedTitles> {
+        let mut titles = ResolvedTitles::default();
+        let media_refs = markdown_renderer::media_refs(notes);
+        if media_refs.is_empty() {
+            return Ok(titles);
+        }
+
+        {
+            let library = self.db()?.export_library()?;
+            for movie in &library.watched_movies {
+                titles.insert(MediaKind::Movie, movie.id as u32, movie.title.clone(), &movie.poster_path);
+            }
+            for movie in &library.movies_to_watch {
+                titles.insert(MediaKind::Movie, movie.id as u32, movie.title.clone(), &movie.poster_path);
+            }
+            for show in &library.watched_tv_shows {
+                titles.insert(MediaKind::Tv, show.id as u32, show.name.clone(), &show.poster_path);
+            }
+            for show in &library.tv_shows_to_watch {
+                titles.insert(MediaKind::Tv, show.id as u32, show.name.clone(), &show.poster_path);
+            }
+        }
+
+        let mut missing = HashSet::new();
+        {
+            let mut cache = self.tmdb_titles()?;
+            for media_ref in media_refs {
+                let kind = MediaKind::from(media_ref.media_type);
+                if titles.contains(kind, media_ref.id) {
+                    continue;
+                }
+                match cache.get(&(kind, media_ref.id)) {
+                    Some((title, poster_path)) => titles.insert(kind, media_ref.id, title.clone(), poster_path),
+                    None => {
+                        missing.insert((kind, media_ref.id));
+                    },
+                }
+            }
+        }
+
+        let Ok(tmdb) = self.tmdb() else {
+            return Ok(titles);
+        };
+        let mut lookups = JoinSet::new();
+        for (kind, id) in missing {
+            let tmdb = Arc::clone(&tmdb);
+            let permits = Arc::clone(&self.tmdb_lookups);
+            lookups.spawn(async move {
+                // The semaphore is never closed
+                let Ok(_permit) = permits.acquire_owned().await else { return (kind, id, None) };
+                (kind, id, tmdb_title(&tmdb, kind, id).await)
+            });
+        }
+        while let Some(lookup) = lookups.join_next().await {
+            // A failed lookup only costs the chip its title, so it doesn't fail the notes, and is tried
+            // again next time
+            let Ok((kind, id, Some((title, poster_path)))) = lookup else { continue };
+            titles.insert(kind, id, title.clone(), &poster_path);
+            self.tmdb_titles()?.put((kind, id), (title, poster_path));
+        }
+        Ok(titles)
+    }
+
+    fn tmdb_titles(&self) -> LibraryResult<MutexGuard<'_, TmdbTitles>> {
+        self.tmdb_titles.lock().map_err(|_| anyhow!("Failed to lock the TMDB title cache").into())
+    }
+
+    /// Render notes to HTML, with title references shown as chips of the resolved titles. Raw HTML is
+    /// limited to simple inline tags and links to safe URLs, since the result is shown in a webview.
+    /// Blocks carry `data-sourcepos` attributes so the editor can find their lines in the notes, and
+    /// headings carry ids so `#heading` links in notes work.
+    pub async fn render_notes(&self, notes: &str) -> LibraryResult<String> {
+        let options = HtmlOptions {
+            media_resolver: Some(Arc::new(self.resolve_titles(notes).await?)),
+            html_allowlist: Some(HtmlAllowlist::default()),
+            url_policy: UrlPolicy { rel: Some("noopener noreferrer nofollow".to_string()), ..Default::default() },
+            source_positions: true,
+            heading_ids: true,
+            ..Default::default()
+        };
+        markdown_renderer::markdown_to_html_with_options(notes, options)
+            .map_err(|e| ValidationErrors::single("notes", e.to_string()).into())
+    }
+
+    /// Parse notes into a syntax tree, for clients that render it themselves
+    pub fn parse_notes(&self, notes: &str) -> LibraryResult<AstNode> {
+        markdown_renderer::parse_markdown(notes).map_err(|e| ValidationErrors::single("notes", e.to_string()).into())
+    }
+
+    // Export and import
+
+    pub fn export(&self) -> LibraryResult<LibraryExport> {
+        Ok(self.db()?.export_library()?)
+    }
+
+    /// Add the exported entries to the library, replacing entries of the same ids. Nothing is imported
+    /// unless every entry is valid.
+    pub fn import(&self, library: &LibraryExport) -> LibraryResult<()> {
+        library.validate()?;
+        Ok(self.db()?.import_library(library)?)
+    }
+}
+
+fn read_notes(db: &Sqlight, location: NotesLocation, kind: MediaKind, id: i32) -> LibraryResult<Option<String>> {
+    Ok(match (location, kind) {
+        (NotesLocation::Watchlist, MediaKind::Movie) => db.get_movie_notes(id)?,
+        (NotesLocation::Watchlist, MediaKind::Tv) => db.get_tv_show_notes(id)?,
+        (NotesLocation::Watched, MediaKind::Movie) => db.get_watched_movie_notes(id)?,
+        (NotesLocation::Watched, MediaKind::Tv) => db.get_watched_tv_show_notes(id)?,
+    })
+}
+
+/// Replace the notes of an entry, checking it exists with the same statement rather than a lookup
+/// before it, which another writer could get in between
+fn write_notes(db: &Sqlight, location: NotesLocation, kind: MediaKind, id: i32, notes: &str) -> LibraryResult<()> {
+    let updated = match (location, kind) {
+        (NotesLocation::Watchlist, MediaKind::Movie) => db.update_movie_notes(id, notes)?,
+        (NotesLocation::Watchlist, MediaKind::Tv) => db.update_tv_show_notes(id, notes)?,
+        (NotesLocation::Watched, MediaKind::Movie) => db.update_watched_movie_notes(id, notes)?,
+        (NotesLocation::Watched, MediaKind::Tv) => db.update_watched_tv_show_notes(id, notes)?,
+    };
+    if !updated {
+        let list = match location {
+            NotesLocation::Watchlist => "watchlist",
+            NotesLocation::Watched => "watched list",
+        };
+        return Err(LibraryError::NotFound(format!("Title {} is not on the {}", id, list)));
+    }
+    Ok(())
+}
+
+/// Title and poster path of a movie or TV show on TMDB
+async fn tmdb_title(tmdb: &Tmdb, kind: MediaKind, id: u32) -> Option<(String, String)> {
+    match kind {
+        MediaKind::Movie => tmdb.get_movie_details(id).await.ok().map(|movie| (movie.title, movie.poster_path)),
+        MediaKind::Tv => tmdb.get_tv_show_details(id).await.ok().map(|show| (show.name, show.poster_path)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matrix() -> NewMovie {
+        NewMovie { id: 603, title: "The Matrix".to_string(), poster_path: "/matrix.jpg".to_string() }
+    }
+
+    fn rate_matrix(rating: f32, watched_at: Option<&str>) -> RateMovie {
+        RateMovie {
+            id: 603,
+            rating,
+            watched_at: watched_at.map(str::to_string),
+            title: "The Matrix".to_string(),
+            poster_path: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_add_to_watchlist_keeps_notes() {
+        let library = Library::open_in_memory().unwrap();
+
+        library.add_movie_to_watchlist(matrix()).unwrap();
+        library.update_notes(NotesLocation::Watchlist, MediaKind::Movie, 603, "With friends").unwrap();
+        let movie = library.add_movie_to_watchlist(matrix()).unwrap();
+
+        assert_eq!(movie.notes, "With friends");
+        assert_eq!(library.watchlist_movies().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_add_rejects_invalid_input() {
+        let library = Library::open_in_memory().unwrap();
+
+        let result = library.add_tv_show_to_watchlist(NewTvShow {
+            id: 1399,
+            name: String::new(),
+            poster_path: String::new(),
+            first_air_date: String::new(),
+            vote_average: 0.0,
+            overview: String::new(),
+        });
+
+        assert!(matches!(result, Err(LibraryError::Invalid(_))));
+    }
+
+    #[test]
+    fn test_rerating_keeps_notes_and_date() {
+        let library = Library::open_in_memory().unwrap();
+        library.rate_movie(rate_matrix(3.0, Some("2025-01-01T20:00:00+00:00"))).unwrap();
+        library.update_notes(NotesLocation::Watched, MediaKind::Movie, 603, "Better the second time").unwrap();
+
+        let movie = library.rate_movie(rate_matrix(4.5, None)).unwrap();
+
+        assert_eq!(movie.rating, 4.5);
+        assert_eq!(movie.watched_at, "2025-01-01T20:00:00+00:00");
+        assert_eq!(movie.notes, "Better the second time");
+        assert_eq!(library.rating(MediaKind::Movie, 603).unwrap(), Some(4.5));
+    }
+
+    #[test]
+    fn test_first_rating_needs_title() {
+        let library = Library::open_in_memory().unwrap();
+        let mut input = rate_matrix(4.0, None);
+        input.title = String::new();
+
+        assert!(matches!(library.rate_movie(input), Err(LibraryError::Invalid(_))));
+    }
+
+    #[test]
+    fn test_toggle_notes_task() {
+        let library = Library::open_in_memory().unwrap();
+        library.add_movie_to_watchlist(matrix()).unwrap();
+        library.update_notes(NotesLocation::Watchlist, MediaKind::Movie, 603, "- [ ] popcorn\n- [ ] rewatch").unwrap();
+
+        let notes = library.toggle_notes_task(NotesLocation::Watchlist, MediaKind::Movie, 603, 14).unwrap();
+        assert_eq!(notes, "- [ ] popcorn\n- [x] rewatch");
+        assert_eq!(library.notes(NotesLocation::Watchlist, MediaKind::Movie, 603).unwrap(), Some(notes));
+        assert!(matches!(
+            library.toggle_notes_task(NotesLocation::Watched, MediaKind::Movie, 603, 0),
+            Err(LibraryError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_find_notes_prefers_watched_entry() {
+        let library = Library::open_in_memory().unwrap();
+        library.add_movie_to_watchlist(matrix()).unwrap();
+        library.update_notes(NotesLocation::Watchlist, MediaKind::Movie, 603, "Before").unwrap();
+
+        assert_eq!(
+            library.find_notes(MediaKind::Movie, 603).unwrap(),
+            Some((NotesLocation::Watchlist, "Before".to_string()))
+        );
+
+        library.rate_movie(rate_matrix(5.0, None)).unwrap();
+        library.update_notes(NotesLocation::Watched, MediaKind::Movie, 603, "After").unwrap();
+
+        assert_eq!(
+            library.find_notes(MediaKind::Movie, 603).unwrap(),
+            Some((NotesLocation::Watched, "After".to_string()))
+        );
+        assert_eq!(library.find_notes(MediaKind::Tv, 1399).unwrap(), None);
+    }
+
+    #[test]
+    fn test_update_notes_requires_entry() {
+        let library = Library::open_in_memory().unwrap();
+
+        let result = library.update_notes(NotesLocation::Watched, MediaKind::Tv, 1399, "x");
+
+        assert!(matches!(result, Err(LibraryError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_rejects_invalid_ids_and_notes() {
+        let library = Library::open_in_memory().unwrap();
+        library.add_movie_to_watchlist(matrix()).unwrap();
+
+        assert!(matches!(library.rating(MediaKind::Movie, 0), Err(LibraryError::Invalid(_))));
+        assert!(matches!(library.remove_from_watchlist(MediaKind::Tv, -1), Err(LibraryError::Invalid(_))));
+
+        let notes = "a".repeat(crate::service::MAX_NOTES_LEN + 1);
+        let Err(LibraryError::Invalid(errors)) = library.update_notes(NotesLocation::Watchlist, MediaKind::Movie, 603, &notes)
+        else {
+            panic!("oversized notes were accepted");
+        };
+        assert_eq!(errors.fields()[0].field, "notes");
+    }
+
+    #[test]
+    fn test_backlinks() {
+        let library = Library::open_in_memory().unwrap();
+        library.add_movie_to_watchlist(matrix()).unwrap();
+        library.update_notes(NotesLocation::Watchlist, MediaKind::Movie, 603, "Sequel: [[movie:604]], not [[movie:603]]").unwrap();
+        library.rate_movie(RateMovie { id: 604, title: "The Matrix Reloaded".to_string(), ..rate_matrix(3.0, None) }).unwrap();
+        library.update_notes(NotesLocation::Watched, MediaKind::Movie, 604, "Worse than [[movie:603]]").unwrap();
+
+        assert_eq!(
+            library.backlinks(MediaKind::Movie, 603).unwrap(),
+            vec![Backlink { location: NotesLocation::Watched, kind: MediaKind::Movie, id: 604, title: "The Matrix Reloaded".to_string() }]
+        );
+        assert_eq!(library.backlinks(MediaKind::Movie, 604).unwrap()[0].id, 603);
+        assert!(library.backlinks(MediaKind::Tv, 603).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_notes() {
+        let library = Library::open_in_memory().unwrap();
+
+        let ast = library.parse_notes("# Heat\n\nSee [[movie:949]]").unwrap();
+
+        let AstNode::Document { children, .. } = ast else { panic!("Expected a document") };
+        assert_eq!(children.len(), 2);
+        assert!(matches!(&children[1], AstNode::Paragraph { content, .. } if matches!(content[1], AstNode::MediaRef(..))));
+    }
+
+    #[tokio::test]
+    async fn test_render_notes_resolves_library_titles() {
+        let library = Library::open_in_memory().unwrap();
+        library.add_movie_to_watchlist(matrix()).unwrap();
+
+        let html = library.render_notes("[[movie:603]] [[tv:1399]]").await.unwrap();
+
+        assert_eq!(
+            html,
+            concat!(
+                r#"<p data-sourcepos="1:1-1:25"><a href="/movie/603" class="media-ref" data-media-type="movie" data-media-id="603">"#,
+                r#"<img src="https://image.tmdb.org/t/p/w92/matrix.jpg" alt="" loading="lazy"><span>The Matrix</span></a> "#,
+                r#"<a href="/tv/1399" class="media-ref unresolved" data-media-type="tv" data-media-id="1399">tv:1399</a></p>"#,
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resolve_titles_uses_tmdb_cache() {
+        let library = Library::open_in_memory().unwrap();
+        library.tmdb_titles().unwrap().put((MediaKind::Tv, 1399), ("Game of Thrones".to_string(), "/got.jpg".to_string()));
+
+        // Without a TMDB key only the titles looked up before resolve
+        let html = library.render_notes("[[tv:1399]] [[tv:1396]]").await.unwrap();
+
+        assert!(html.contains("<span>Game of Thrones</span>"));
+        assert!(html.contains(">tv:1396</a>"));
+    }
+
+    #[tokio::test]
+    async fn test_tmdb_cache_keeps_recently_used_titles() {
+        let library = Library::open_in_memory().unwrap();
+        for id in 1..=TMDB_TITLES_CACHED as u32 {
+            library.tmdb_titles().unwrap().put((MediaKind::Movie, id), (format!("Movie {}", id), String::new()));
+        }
+
+        // Resolving the oldest title makes it the most recently used, so the next one is evicted instead
+        let titles = library.resolve_titles("[[movie:1]]").await.unwrap();
+        assert!(titles.contains(MediaKind::Movie, 1));
+        library.tmdb_titles().unwrap().put((MediaKind::Tv, 1399), ("Game of Thrones".to_string(), String::new()));
+
+        let cache = library.tmdb_titles().unwrap();
+        assert_eq!(cache.len(), TMDB_TITLES_CACHED);
+        assert!(cache.contains(&(MediaKind::Movie, 1)));
+        assert!(!cache.contains(&(MediaKind::Movie, 2)));
+    }
+
+    #[test]
+    fn test_import_rejects_newer_version() {
+        let library = Library::open_in_memory().unwrap();
+        let mut export = library.export().unwrap();
+        export.version = LibraryExport::VERSION + 1;
+
+        assert!(matches!(library.import(&export), Err(LibraryError::Invalid(_))));
+    }
+
+    #[test]
+    fn test_import_rejects_invalid_entries() {
+        let library = Library::open_in_memory().unwrap();
+        let watched_matrix = |rating| WatchedMovie {
+            id: 603,
+            title: "The Matrix".to_string(),
+            poster_path: String::new(),
+            rating,
+            watched_at: "2025-01-01 20:00:00".to_string(),
+            notes: String::new(),
+        };
+        let mut export = library.export().unwrap();
+        export.watched_movies = vec![watched_matrix(4.0), watched_matrix(4.2)];
+        export.movies_to_watch.push(MovieToWatch {
+            id: 0,
+            title: "The Matrix Reloaded".to_string(),
+            poster_path: String::new(),
+            notes: String::new(),
+        });
+
+        let Err(LibraryError::Invalid(errors)) = library.import(&export) else { panic!("Expected validation errors") };
+        let fields: Vec<&str> = errors.fields().iter().map(|error| error.field.as_str()).collect();
+        assert_eq!(fields, ["movies_to_watch[0].id", "watched_movies[1].rating"]);
+
+        // The valid entries aren't imported either
+        assert!(library.export().unwrap().watched_movies.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_tmdb_requires_api_key() {
+        let library = Library::open_in_memory().unwrap();
+
+        assert!(matches!(library.trending_movies().await, Err(LibraryError::Tmdb(_))));
+        assert!(!library.valid_key().await.unwrap());
+    }
+}
@@ -0,0 +1,11 @@
+mod error;
+mod inputs;
+mod library;
+mod references;
+mod validation;
+
+pub use error::{LibraryError, LibraryResult};
+pub use inputs::{EntryId, MediaKind, NewMovie, NewTvShow, NotesLocation, RateMovie, RateTvShow};
+pub use library::Library;
+pub use references::{Backlink, ResolvedTitles};
+pub use validation::{FieldError, Validate, ValidationErrors, MAX_NOTES_LEN, MAX_TITLE_LEN};
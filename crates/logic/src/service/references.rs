@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+
+use markdown_renderer::{MediaRef, MediaResolver, MediaType, ResolvedMedia};
+use serde::Serialize;
+
+use crate::service::{MediaKind, NotesLocation};
+
+/// Small posters are enough for the chips references render as
+const POSTER_BASE_URL: &str = "https://image.tmdb.org/t/p/w92";
+
+/// An entry whose notes reference a title through `[[movie:603]]` style links
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Backlink {
+    pub location: NotesLocation,
+    pub kind: MediaKind,
+    pub id: i32,
+    pub title: String,
+}
+
+/// Titles and posters of the references in some notes, looked up ahead of rendering since
+/// the renderer can't wait on the database or TMDB
+#[derive(Debug, Default)]
+pub struct ResolvedTitles(HashMap<(MediaKind, u32), ResolvedMedia>);
+
+impl ResolvedTitles {
+    pub(crate) fn insert(&mut self, kind: MediaKind, id: u32, title: String, poster_path: &str) {
+        let poster_url = (!poster_path.is_empty()).then(|| format!("{}{}", POSTER_BASE_URL, poster_path));
+        self.0.insert((kind, id), ResolvedMedia { title, poster_url });
+    }
+
+    pub(crate) fn contains(&self, kind: MediaKind, id: u32) -> bool {
+        self.0.contains_key(&(kind, id))
+    }
+}
+
+impl MediaResolver for ResolvedTitles {
+    fn resolve(&self, media_ref: &MediaRef) -> Option<ResolvedMedia> {
+        self.0.get(&(media_ref.media_type.into(), media_ref.id)).cloned()
+    }
+}
+
+impl From<MediaType> for MediaKind {
+    fn from(media_type: MediaType) -> Self {
+        match media_type {
+            MediaType::Movie => MediaKind::Movie,
+            MediaType::Tv => MediaKind::Tv,
+        }
+    }
+}
+
+/// Whether the notes reference the title, with or without a specific episode
+pub(crate) fn references(notes: &str, kind: MediaKind, id: i32) -> bool {
+    // Cheap check first, most notes don't reference anything
+    notes.contains("[[")
+        && markdown_renderer::media_refs(notes)
+            .iter()
+            .any(|media_ref| MediaKind::from(media_ref.media_type) == kind && i64::from(media_ref.id) == i64::from(id))
+}
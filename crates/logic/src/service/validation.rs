@@ -0,0 +1,224 @@
+use serde::Serialize;
+use std::fmt;
+
+/// Longest title or name accepted from a client
+pub const MAX_TITLE_LEN: usize = 500;
+
+/// Largest notes body in bytes; notes are markdown, so this is plenty for a few pages
+pub const MAX_NOTES_LEN: usize = 100 * 1024;
+
+/// One rejected input field, named as it appears in the JSON payload
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+/// Every problem found in one input, so clients can flag all fields at once
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct ValidationErrors(Vec<FieldError>);
+
+impl ValidationErrors {
+    pub fn new() -> Self {
+        ValidationErrors::default()
+    }
+
+    pub fn single(field: &str, message: impl Into<String>) -> Self {
+        let mut errors = ValidationErrors::new();
+        errors.add(field, message);
+        errors
+    }
+
+    pub fn add(&mut self, field: &str, message: impl Into<String>) {
+        self.0.push(FieldError { field: field.to_string(), message: message.into() });
+    }
+
+    /// Add the errors of a part of the input, their fields prefixed with its path, e.g.
+    /// `watched_movies[2].rating`
+    pub fn add_nested(&mut self, path: &str, errors: ValidationErrors) {
+        let errors = errors.0.into_iter().map(|error| FieldError { field: format!("{}.{}", path, error.field), ..error });
+        self.0.extend(errors);
+    }
+
+    pub fn fields(&self) -> &[FieldError] {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn into_result(self) -> Result<(), ValidationErrors> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let messages: Vec<&str> = self.0.iter().map(|error| error.message.as_str()).collect();
+        write!(f, "{}", messages.join("; "))
+    }
+}
+
+impl std::error::Error for ValidationErrors {}
+
+/// Inputs that can be checked before they reach the database
+pub trait Validate {
+    fn validate(&self) -> Result<(), ValidationErrors>;
+}
+
+pub(crate) fn check_id(errors: &mut ValidationErrors, field: &str, id: i32) {
+    if id <= 0 {
+        errors.add(field, "Invalid id");
+    }
+}
+
+pub(crate) fn check_title(errors: &mut ValidationErrors, field: &str, title: &str) {
+    if title.trim().is_empty() {
+        errors.add(field, "Title is required");
+    } else if title.chars().count() > MAX_TITLE_LEN {
+        errors.add(field, format!("Title must be at most {} characters", MAX_TITLE_LEN));
+    }
+}
+
+pub(crate) fn check_rating(errors: &mut ValidationErrors, rating: f32) {
+    if !(0.5..=5.0).contains(&rating) || (rating * 2.0).fract() != 0.0 {
+        errors.add("rating", "Rating must be between 0.5 and 5.0 in 0.5 increments");
+    }
+}
+
+pub(crate) fn check_watched_at(errors: &mut ValidationErrors, watched_at: Option<&str>) {
+    if let Some(watched_at) = watched_at {
+        if chrono::DateTime::parse_from_rfc3339(watched_at).is_err() {
+            errors.add("watched_at", "watched_at must be an RFC 3339 timestamp");
+        }
+    }
+}
+
+pub(crate) fn check_vote_average(errors: &mut ValidationErrors, vote_average: f32) {
+    if !(0.0..=10.0).contains(&vote_average) {
+        errors.add("vote_average", "vote_average must be between 0 and 10");
+    }
+}
+
+pub(crate) fn check_notes(errors: &mut ValidationErrors, notes: &str) {
+    if notes.len() > MAX_NOTES_LEN {
+        errors.add("notes", format!("Notes must be at most {} bytes", MAX_NOTES_LEN));
+    }
+}
+
+/// Validate a bare id taken from a path or command argument
+pub(crate) fn validate_id(id: i32) -> Result<(), ValidationErrors> {
+    let mut errors = ValidationErrors::new();
+    check_id(&mut errors, "id", id);
+    errors.into_result()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::{NewMovie, NewTvShow, RateMovie};
+    use proptest::prelude::*;
+
+    fn rate(rating: f32, watched_at: Option<String>) -> RateMovie {
+        RateMovie { id: 603, rating, watched_at, title: String::new(), poster_path: String::new() }
+    }
+
+    fn fields(errors: &ValidationErrors) -> Vec<&str> {
+        errors.fields().iter().map(|error| error.field.as_str()).collect()
+    }
+
+    #[test]
+    fn test_collects_every_field() {
+        let show = NewTvShow {
+            id: 0,
+            name: " ".to_string(),
+            poster_path: String::new(),
+            first_air_date: String::new(),
+            vote_average: 11.0,
+            overview: String::new(),
+        };
+
+        let errors = show.validate().unwrap_err();
+
+        assert_eq!(fields(&errors), ["id", "name", "vote_average"]);
+        assert_eq!(errors.to_string(), "Invalid id; Title is required; vote_average must be between 0 and 10");
+    }
+
+    #[test]
+    fn test_serializes_as_field_list() {
+        let errors = ValidationErrors::single("rating", "Rating must be between 0.5 and 5.0 in 0.5 increments");
+
+        assert_eq!(
+            serde_json::to_value(&errors).unwrap(),
+            serde_json::json!([{ "field": "rating", "message": "Rating must be between 0.5 and 5.0 in 0.5 increments" }])
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn prop_half_steps_are_valid(halves in 1u8..=10) {
+            prop_assert!(rate(halves as f32 / 2.0, None).validate().is_ok());
+        }
+
+        #[test]
+        fn prop_other_ratings_are_rejected(rating in any::<f32>()) {
+            prop_assume!(!(1..=10).any(|halves| halves as f32 / 2.0 == rating));
+
+            let errors = rate(rating, None).validate().unwrap_err();
+            prop_assert_eq!(fields(&errors), ["rating"]);
+        }
+
+        #[test]
+        fn prop_rfc3339_timestamps_are_valid(seconds in 0i64..4_102_444_800, offset in -12i32..=14) {
+            let offset = chrono::FixedOffset::east_opt(offset * 3600).unwrap();
+            let watched_at = chrono::DateTime::from_timestamp(seconds, 0).unwrap().with_timezone(&offset);
+
+            prop_assert!(rate(4.0, Some(watched_at.to_rfc3339())).validate().is_ok());
+        }
+
+        #[test]
+        fn prop_watched_at_matches_chrono(watched_at in "\\PC{0,40}") {
+            let valid = chrono::DateTime::parse_from_rfc3339(&watched_at).is_ok();
+
+            prop_assert_eq!(rate(4.0, Some(watched_at)).validate().is_ok(), valid);
+        }
+
+        #[test]
+        fn prop_ids_must_be_positive(id in any::<i32>()) {
+            let movie = NewMovie { id, title: "The Matrix".to_string(), poster_path: String::new() };
+
+            prop_assert_eq!(movie.validate().is_ok(), id > 0);
+            prop_assert_eq!(validate_id(id).is_ok(), id > 0);
+        }
+
+        #[test]
+        fn prop_blank_titles_are_rejected(title in "[ \\t\\n]{0,20}") {
+            let movie = NewMovie { id: 603, title, poster_path: String::new() };
+
+            let errors = movie.validate().unwrap_err();
+            prop_assert_eq!(fields(&errors), ["title"]);
+        }
+
+        #[test]
+        fn prop_titles_up_to_the_limit_are_valid(title in "[a-zA-Z0-9][a-zA-Z0-9 :'-]{0,498}[a-zA-Z0-9]") {
+            let movie = NewMovie { id: 603, title, poster_path: String::new() };
+
+            prop_assert!(movie.validate().is_ok());
+        }
+
+        #[test]
+        fn prop_notes_are_limited_in_size(len in 0..MAX_NOTES_LEN * 2) {
+            let mut errors = ValidationErrors::new();
+            check_notes(&mut errors, &"a".repeat(len));
+
+            prop_assert_eq!(errors.is_empty(), len <= MAX_NOTES_LEN);
+        }
+    }
+}
@@ -1,8 +1,8 @@
-use criterion::{black_box, criterion_group, criterion_main, Criterion, BenchmarkId, Throughput};
-use markdown_renderer::{tokenize, parse, parse_markdown};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion, BenchmarkId, Throughput};
+use markdown_renderer::{tokenize, parse, parse_markdown, media_refs, IncrementalParser, Lexer};
 
 #[cfg(feature = "html")]
-use markdown_renderer::render_html;
+use markdown_renderer::{render_html, HtmlRenderer, Renderer};
 
 // Sample markdown content of varying sizes and complexity
 const SMALL_MARKDOWN: &str = r#"# Hello World
@@ -474,6 +474,105 @@ fn bench_specific_patterns(c: &mut Criterion) {
     group.finish();
 }
 
+fn bench_streaming(c: &mut Criterion) {
+    let mut group = c.benchmark_group("streaming");
+    let huge_content = generate_huge_markdown();
+    group.throughput(Throughput::Bytes(huge_content.len() as u64));
+
+    // Collecting every token against reading them one at a time
+    group.bench_with_input(
+        BenchmarkId::new("tokenize", "huge"),
+        &huge_content,
+        |b, content| {
+            b.iter(|| {
+                let tokens = tokenize(black_box(content)).unwrap();
+                black_box(tokens.len())
+            });
+        },
+    );
+    group.bench_with_input(
+        BenchmarkId::new("lexer_iterator", "huge"),
+        &huge_content,
+        |b, content| {
+            b.iter(|| {
+                let count = Lexer::new(black_box(content)).filter(Result::is_ok).count();
+                black_box(count)
+            });
+        },
+    );
+    group.bench_with_input(
+        BenchmarkId::new("media_refs", "huge"),
+        &huge_content,
+        |b, content| {
+            b.iter(|| black_box(media_refs(black_box(content))));
+        },
+    );
+
+    #[cfg(feature = "html")]
+    {
+        // Building the HTML as one string against writing it out block by block
+        let ast = parse_markdown(&huge_content).unwrap();
+        let renderer = HtmlRenderer::new();
+        group.bench_with_input(
+            BenchmarkId::new("render", "huge"),
+            &ast,
+            |b, ast| {
+                b.iter(|| {
+                    let html = renderer.render(black_box(ast)).unwrap();
+                    std::io::Write::write_all(&mut std::io::sink(), html.as_bytes()).unwrap();
+                });
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("write_to", "huge"),
+            &ast,
+            |b, ast| {
+                b.iter(|| renderer.write_to(black_box(ast), &mut std::io::sink()).unwrap());
+            },
+        );
+    }
+
+    group.finish();
+}
+
+fn bench_incremental(c: &mut Criterion) {
+    let mut group = c.benchmark_group("incremental");
+
+    let huge_content = generate_huge_markdown();
+    let parser = IncrementalParser::new(huge_content.as_str()).unwrap();
+    // A keystroke in a paragraph halfway through the document
+    let offset = huge_content[huge_content.len() / 2..].find("\n\n").unwrap() + huge_content.len() / 2;
+
+    for (name, edit) in [("insert_char", "x"), ("insert_block", "\n\n## New heading\n\n- item")] {
+        let mut edited = huge_content.clone();
+        edited.insert_str(offset, edit);
+
+        group.bench_with_input(
+            BenchmarkId::new("full_parse", name),
+            &edited,
+            |b, edited| {
+                b.iter(|| black_box(parse_markdown(black_box(edited)).unwrap()));
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("set_source", name),
+            &edited,
+            |b, edited| {
+                b.iter_batched(
+                    || parser.clone(),
+                    |mut parser| {
+                        parser.set_source(black_box(edited)).unwrap();
+                        parser
+                    },
+                    BatchSize::LargeInput,
+                );
+            },
+        );
+    }
+
+    group.finish();
+}
+
 // Create benchmark groups
 #[cfg(feature = "html")]
 criterion_group!(
@@ -483,7 +582,9 @@ criterion_group!(
     bench_html_renderer,
     bench_end_to_end,
     bench_memory_usage,
-    bench_specific_patterns
+    bench_specific_patterns,
+    bench_streaming,
+    bench_incremental
 );
 
 #[cfg(not(feature = "html"))]
@@ -493,7 +594,9 @@ criterion_group!(
     bench_parser,
     bench_end_to_end,
     bench_memory_usage,
-    bench_specific_patterns
+    bench_specific_patterns,
+    bench_streaming,
+    bench_incremental
 );
 
 criterion_main!(benches);
\ No newline at end of file
@@ -52,16 +52,28 @@ Final paragraph with special characters: <script>alert('test')</script> & "quote
     println!();
 
     println!("=== HTML with CSS Classes ===");
-    let mut css_classes = CssClasses::default();
-    css_classes.document = Some("markdown-content".to_string());
-    css_classes.heading = Some("heading".to_string());
-    css_classes.paragraph = Some("paragraph".to_string());
-    css_classes.list_ordered = Some("ordered-list".to_string());
-    css_classes.list_unordered = Some("unordered-list".to_string());
-    css_classes.list_item = Some("list-item".to_string());
-    css_classes.bold = Some("bold".to_string());
-    css_classes.italic = Some("italic".to_string());
-    css_classes.link = Some("link".to_string());
+    let css_classes = CssClasses {
+        document: Some("markdown-content".to_string()),
+        heading: Some("heading".to_string()),
+        paragraph: Some("paragraph".to_string()),
+        list_ordered: Some("ordered-list".to_string()),
+        list_unordered: Some("unordered-list".to_string()),
+        list_item: Some("list-item".to_string()),
+        task_item: Some("task-item".to_string()),
+        bold: Some("bold".to_string()),
+        italic: Some("italic".to_string()),
+        strikethrough: Some("strikethrough".to_string()),
+        highlight: Some("highlight".to_string()),
+        spoiler: Some("spoiler".to_string()),
+        link: Some("link".to_string()),
+        image: Some("image".to_string()),
+        media_ref: Some("media-ref".to_string()),
+        table: Some("table".to_string()),
+        code: Some("code".to_string()),
+        code_block: Some("code-block".to_string()),
+        blockquote: Some("quote".to_string()),
+        thematic_break: Some("rule".to_string()),
+    };
 
     let css_options = HtmlOptions {
         pretty_print: true,
@@ -83,20 +95,24 @@ Final paragraph with special characters: <script>alert('test')</script> & "quote
     println!();
 
     println!("=== HTML with Custom Attributes ===");
-    let mut custom_attributes = CustomAttributes::default();
-    custom_attributes.document = vec![
-        ("data-theme".to_string(), "dark".to_string()),
-        ("role".to_string(), "main".to_string()),
-    ];
-    custom_attributes.heading = vec![
-        ("data-level".to_string(), "auto".to_string()),
-    ];
-    custom_attributes.link = vec![
-        ("data-track".to_string(), "click".to_string()),
-    ];
-
-    let mut css_classes_custom = CssClasses::default();
-    css_classes_custom.document = Some("markdown-document".to_string());
+    let custom_attributes = CustomAttributes {
+        document: vec![
+            ("data-theme".to_string(), "dark".to_string()),
+            ("role".to_string(), "main".to_string()),
+        ],
+        heading: vec![
+            ("data-level".to_string(), "auto".to_string()),
+        ],
+        link: vec![
+            ("data-track".to_string(), "click".to_string()),
+        ],
+        ..Default::default()
+    };
+
+    let css_classes_custom = CssClasses {
+        document: Some("markdown-document".to_string()),
+        ..Default::default()
+    };
 
     let custom_options = HtmlOptions {
         pretty_print: true,
@@ -5,6 +5,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let markdown_content = r#"# My Markdown Document
 
 This is a **bold** paragraph with some *italic* text and a [link](https://example.com).
+Reviews can ~~strike~~ or ==highlight== text and hide ||the ending|| in spoilers.
 
 ## Features
 
@@ -12,15 +13,34 @@ Here are some features:
 
 - **Headings** (H1-H6)
 - *Emphasis* and **strong** text
-- Links like [Rust](https://rust-lang.org)
+- Links like [Rust](https://rust-lang.org) and autolinks like https://www.themoviedb.org
+- Title references like [[movie:603]] and [[tv:1399 S02E03]]
+- Images: ![Poster](https://image.tmdb.org/t/p/w500/poster.jpg "The Matrix")
 - Lists (both ordered and unordered)
+- [x] Task lists
 
 ### Ordered List Example
 
 1. First item
 2. Second item
+   * nested under the second
+   * with `*` bullets
 3. Third item
 
+| Element | Supported |
+|:--------|:---------:|
+| **Tables** | yes |
+| Headings | yes |
+
+> Quotes can hold any block:
+> - even lists
+
+---
+
+```rust
+let html = markdown_to_html("*not* parsed in here")?;
+```
+
 That's all for now!
 "#;
 
@@ -52,56 +72,118 @@ fn print_ast(node: &AstNode, indent: usize) {
     let spaces = "  ".repeat(indent);
     
     match node {
-        AstNode::Document { children } => {
+        AstNode::Document { children, .. } => {
             println!("{}Document ({} children)", spaces, children.len());
             for child in children {
                 print_ast(child, indent + 1);
             }
         },
-        AstNode::Heading { level, content } => {
+        AstNode::Heading { level, content, .. } => {
             println!("{}Heading (level {})", spaces, level);
             for child in content {
                 print_ast(child, indent + 1);
             }
         },
-        AstNode::Paragraph { content } => {
+        AstNode::Paragraph { content, .. } => {
             println!("{}Paragraph ({} elements)", spaces, content.len());
             for child in content {
                 print_ast(child, indent + 1);
             }
         },
-        AstNode::List { ordered, items } => {
+        AstNode::List { ordered, start, items, .. } => {
             let list_type = if *ordered { "Ordered" } else { "Unordered" };
-            println!("{}{} List ({} items)", spaces, list_type, items.len());
+            println!("{}{} List ({} items, starting at {})", spaces, list_type, items.len(), start);
             for child in items {
                 print_ast(child, indent + 1);
             }
         },
-        AstNode::ListItem { content } => {
+        AstNode::ListItem { content, .. } => {
             println!("{}ListItem", spaces);
             for child in content {
                 print_ast(child, indent + 1);
             }
         },
-        AstNode::Bold(content) => {
+        AstNode::TaskItem { checked, content, .. } => {
+            println!("{}TaskItem (checked: {})", spaces, checked);
+            for child in content {
+                print_ast(child, indent + 1);
+            }
+        },
+        AstNode::Table { alignments, header, rows, .. } => {
+            println!("{}Table ({} columns, {} rows)", spaces, alignments.len(), rows.len());
+            for cell in header.iter().chain(rows.iter().flatten()) {
+                print_ast(cell, indent + 1);
+            }
+        },
+        AstNode::TableCell { content, .. } => {
+            println!("{}TableCell", spaces);
+            for child in content {
+                print_ast(child, indent + 1);
+            }
+        },
+        AstNode::CodeBlock { info, content, .. } => {
+            println!("{}CodeBlock (info: {:?}, {} lines)", spaces, info, content.lines().count());
+        },
+        AstNode::BlockQuote { children, .. } => {
+            println!("{}BlockQuote ({} children)", spaces, children.len());
+            for child in children {
+                print_ast(child, indent + 1);
+            }
+        },
+        AstNode::ThematicBreak(_) => {
+            println!("{}ThematicBreak", spaces);
+        },
+        AstNode::FootnoteDefinition { label, children, .. } => {
+            println!("{}FootnoteDefinition (label: \"{}\")", spaces, label);
+            for child in children {
+                print_ast(child, indent + 1);
+            }
+        },
+        AstNode::Bold(content, _) => {
             println!("{}Bold", spaces);
             for child in content {
                 print_ast(child, indent + 1);
             }
         },
-        AstNode::Italic(content) => {
+        AstNode::Italic(content, _) => {
             println!("{}Italic", spaces);
             for child in content {
                 print_ast(child, indent + 1);
             }
         },
-        AstNode::Link { text, url } => {
-            println!("{}Link (url: \"{}\")", spaces, url);
+        AstNode::Strikethrough(content, _) | AstNode::Highlight(content, _) | AstNode::Spoiler(content, _) => {
+            let name = match node {
+                AstNode::Strikethrough(..) => "Strikethrough",
+                AstNode::Highlight(..) => "Highlight",
+                _ => "Spoiler",
+            };
+            println!("{}{}", spaces, name);
+            for child in content {
+                print_ast(child, indent + 1);
+            }
+        },
+        AstNode::Link { text, url, title, .. } => {
+            println!("{}Link (url: \"{}\", title: {:?})", spaces, url, title);
             for child in text {
                 print_ast(child, indent + 1);
             }
         },
-        AstNode::Text(text) => {
+        AstNode::Image { alt, url, title, .. } => {
+            println!("{}Image (alt: \"{}\", url: \"{}\", title: {:?})", spaces, alt, url, title);
+        },
+        AstNode::MediaRef(media_ref, _) => {
+            println!("{}MediaRef: {}", spaces, media_ref);
+        },
+        AstNode::FootnoteReference { label, .. } => {
+            println!("{}FootnoteReference: {}", spaces, label);
+        },
+        AstNode::Code(code, _) => {
+            println!("{}Code: \"{}\"", spaces, code);
+        },
+        AstNode::Html(html, _) => {
+            println!("{}Html: {}", spaces, html);
+        },
+        AstNode::Text(text, _) => {
             let display_text = if text.len() > 30 {
                 format!("{}...", &text[..27])
             } else {
@@ -109,7 +191,7 @@ fn print_ast(node: &AstNode, indent: usize) {
             };
             println!("{}Text: \"{}\"", spaces, display_text);
         },
-        AstNode::LineBreak => {
+        AstNode::LineBreak(_) => {
             println!("{}LineBreak", spaces);
         },
     }
@@ -124,7 +206,11 @@ fn analyze_ast(node: &AstNode) {
     println!("  Headings: {}", stats.headings);
     println!("  Paragraphs: {}", stats.paragraphs);
     println!("  Lists: {}", stats.lists);
+    println!("  Tables: {}", stats.tables);
+    println!("  Code elements: {}", stats.code);
     println!("  Links: {}", stats.links);
+    println!("  Images: {}", stats.images);
+    println!("  Title references: {}", stats.media_refs);
     println!("  Bold elements: {}", stats.bold);
     println!("  Italic elements: {}", stats.italic);
     println!("  Text nodes: {}", stats.text_nodes);
@@ -137,7 +223,11 @@ struct AstStats {
     headings: usize,
     paragraphs: usize,
     lists: usize,
+    tables: usize,
+    code: usize,
     links: usize,
+    images: usize,
+    media_refs: usize,
     bold: usize,
     italic: usize,
     text_nodes: usize,
@@ -154,7 +244,7 @@ fn collect_stats(node: &AstNode, stats: &mut AstStats) {
     stats.total_nodes += 1;
     
     match node {
-        AstNode::Document { children } => {
+        AstNode::Document { children, .. } | AstNode::BlockQuote { children, .. } => {
             for child in children {
                 collect_stats(child, stats);
             }
@@ -165,7 +255,7 @@ fn collect_stats(node: &AstNode, stats: &mut AstStats) {
                 collect_stats(child, stats);
             }
         },
-        AstNode::Paragraph { content } => {
+        AstNode::Paragraph { content, .. } => {
             stats.paragraphs += 1;
             for child in content {
                 collect_stats(child, stats);
@@ -177,35 +267,60 @@ fn collect_stats(node: &AstNode, stats: &mut AstStats) {
                 collect_stats(child, stats);
             }
         },
-        AstNode::ListItem { content } => {
+        AstNode::ListItem { content, .. } | AstNode::TaskItem { content, .. } | AstNode::TableCell { content, .. } => {
             for child in content {
                 collect_stats(child, stats);
             }
         },
-        AstNode::Bold(content) => {
+        AstNode::Table { header, rows, .. } => {
+            stats.tables += 1;
+            for cell in header.iter().chain(rows.iter().flatten()) {
+                collect_stats(cell, stats);
+            }
+        },
+        AstNode::Bold(content, _) => {
             stats.bold += 1;
             for child in content {
                 collect_stats(child, stats);
             }
         },
-        AstNode::Italic(content) => {
+        AstNode::Italic(content, _) => {
             stats.italic += 1;
             for child in content {
                 collect_stats(child, stats);
             }
         },
+        AstNode::Strikethrough(content, _) | AstNode::Highlight(content, _) | AstNode::Spoiler(content, _) => {
+            for child in content {
+                collect_stats(child, stats);
+            }
+        },
         AstNode::Link { text, .. } => {
             stats.links += 1;
             for child in text {
                 collect_stats(child, stats);
             }
         },
-        AstNode::Text(text) => {
+        AstNode::Image { .. } => {
+            stats.images += 1;
+        },
+        AstNode::MediaRef(..) => {
+            stats.media_refs += 1;
+        },
+        AstNode::CodeBlock { .. } | AstNode::Code(..) => {
+            stats.code += 1;
+        },
+        AstNode::Text(text, _) => {
             stats.text_nodes += 1;
             stats.total_text_length += text.len();
         },
-        AstNode::LineBreak => {
-            // LineBreak doesn't need special counting
+        AstNode::FootnoteDefinition { children, .. } => {
+            for child in children {
+                collect_stats(child, stats);
+            }
+        },
+        AstNode::LineBreak(_) | AstNode::ThematicBreak(_) | AstNode::FootnoteReference { .. } | AstNode::Html(..) => {
+            // Breaks don't need special counting
         },
     }
 }
\ No newline at end of file
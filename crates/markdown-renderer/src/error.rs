@@ -1,5 +1,9 @@
+use std::fmt;
+
 use thiserror::Error;
 
+use crate::span::Span;
+
 #[derive(Error, Debug, Clone, PartialEq)]
 pub enum LexerError {
     #[error("Unexpected character '{char}' at line {line}, column {column}")]
@@ -67,16 +71,44 @@ pub enum ParseError {
     #[error("Expected URL in link")]
     ExpectedUrl,
 
-    #[error("Expected token {expected} but found {found}")]
+    #[error("No definition for reference '{label}'")]
+    UndefinedReference {
+        label: String,
+    },
+
+    #[error("Link opened at line {line}, column {column} contains another link")]
+    NestedLink {
+        line: usize,
+        column: usize
+    },
+
+    #[error("Expected token {expected} but found {found} at line {line}, column {column}")]
     ExpectedToken {
         expected: String,
         found: String,
+        line: usize,
+        column: usize,
     },
 
     #[error("Unexpected end of input")]
     UnexpectedEndOfInput,
 }
 
+/// Malformed markup the parser recovered from by reading it as literal text, e.g. a `**` that is never
+/// closed. Reported as a warning next to the best-effort AST rather than failing the parse.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub error: ParseError,
+    /// The markup that was kept as text
+    pub span: Span,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
 // General error type for the entire markdown renderer
 #[derive(Error, Debug)]
 pub enum MarkdownError {
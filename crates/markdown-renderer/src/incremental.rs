@@ -0,0 +1,300 @@
+//! Keeping a document parsed while it is edited, for live previews of long notes where a keystroke
+//! changes one block and leaves the rest as it was.
+//!
+//! An edit parses again only the top-level blocks around it: from a block before the edit that starts
+//! after a blank line, so no block above it can reach into it, up to the first such block after the
+//! edit. When that last block comes out as it was before, parsing has caught up with the edit and the
+//! blocks after it are reused, their spans moved by the size of the edit. When it doesn't, e.g. after
+//! opening a code fence, everything from the edit on is parsed again.
+
+use std::ops::Range;
+
+use crate::error::MarkdownError;
+use crate::parse_markdown;
+use crate::parser::AstNode;
+use crate::span::{Position, Span};
+use crate::visit::{walk_node_mut, VisitorMut};
+
+/// A document and its tree, updated on each edit by parsing only the blocks the edit may have changed
+#[derive(Debug, Clone)]
+pub struct IncrementalParser {
+    source: String,
+    ast: AstNode,
+    // Top-level blocks the last edit parsed again
+    reparsed: Range<usize>,
+}
+
+impl IncrementalParser {
+    /// Parse `source` in full
+    pub fn new(source: impl Into<String>) -> Result<Self, MarkdownError> {
+        let source = source.into();
+        let ast = parse_markdown(&source)?;
+        let reparsed = 0..blocks(&ast).len();
+        Ok(Self { source, ast, reparsed })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// The tree of the current source, as `parse_markdown` would return it
+    pub fn ast(&self) -> &AstNode {
+        &self.ast
+    }
+
+    pub fn into_ast(self) -> AstNode {
+        self.ast
+    }
+
+    /// Indices of the top-level blocks the last edit parsed again; the others are the nodes from
+    /// before it, so a preview only needs to render these again
+    pub fn reparsed(&self) -> Range<usize> {
+        self.reparsed.clone()
+    }
+
+    /// Replace the whole source, parsing again what differs from the current one. Suits editors that
+    /// hand over their full text on every change.
+    pub fn set_source(&mut self, source: &str) -> Result<(), MarkdownError> {
+        let (old, new) = (self.source.as_bytes(), source.as_bytes());
+        let mut prefix = old.iter().zip(new).take_while(|(old, new)| old == new).count();
+        while !source.is_char_boundary(prefix) {
+            prefix -= 1;
+        }
+        let longest = (old.len() - prefix).min(new.len() - prefix);
+        let mut suffix = old.iter().rev().zip(new.iter().rev()).take(longest).take_while(|(old, new)| old == new).count();
+        while !source.is_char_boundary(new.len() - suffix) {
+            suffix -= 1;
+        }
+        self.edit(prefix..old.len() - suffix, &source[prefix..new.len() - suffix])
+    }
+
+    /// Replace the bytes of `range` in the source with `text` and update the tree. On error the source
+    /// and tree stay as they were.
+    ///
+    /// # Panics
+    ///
+    /// When `range` is out of bounds or doesn't lie on character boundaries, like `String::replace_range`
+    pub fn edit(&mut self, range: Range<usize>, text: &str) -> Result<(), MarkdownError> {
+        if range.is_empty() && text.is_empty() {
+            self.reparsed = 0..0;
+            return Ok(());
+        }
+        let mut source = self.source.clone();
+        source.replace_range(range.clone(), text);
+        if needs_full_parse(&self.source) || needs_full_parse(&source) {
+            *self = Self::new(source)?;
+            return Ok(());
+        }
+
+        let blocks = blocks(&self.ast);
+        let mut shift = ShiftSpans {
+            offset: text.len() as isize - range.len() as isize,
+            lines: text.matches('\n').count() as isize - self.source[range.clone()].matches('\n').count() as isize,
+        };
+
+        // Start at a block before the edit, which a line added under it could continue
+        let mut start = blocks.partition_point(|block| block.span().end.offset < range.start).saturating_sub(1);
+        while start > 0 && !starts_clean(&self.source, blocks[start].span().start) {
+            start -= 1;
+        }
+        let region_start = match start {
+            0 => Position::default(),
+            _ => {
+                let block = blocks[start].span().start;
+                Position { offset: line_start(&self.source, block.offset), ..block }
+            },
+        };
+
+        // and end at the first block after it that starts a line of its own
+        let after = blocks.partition_point(|block| line_start(&self.source, block.span().start.offset) <= range.end);
+        let sync = (after..blocks.len()).find(|&index| {
+            let mut position = blocks[index].span().start;
+            shift.apply(&mut position);
+            starts_clean(&source, position)
+        });
+
+        let mut region = None;
+        if let Some(sync) = sync {
+            // Caught up when the block comes out as it was, in its new place
+            let mut before = blocks[sync].clone();
+            shift.visit_node_mut(&mut before);
+            let (children, span) = parse_region(&source, region_start, before.span().end.offset)?;
+            let caught_up = children.last() == Some(&before);
+            if caught_up {
+                region = Some((children, span, Some(sync)));
+            }
+        }
+        let (children, span, sync) = match region {
+            Some(region) => region,
+            None => {
+                let (children, span) = parse_region(&source, region_start, source.len())?;
+                (children, span, None)
+            },
+        };
+
+        let AstNode::Document { children: blocks, span: document_span } = &mut self.ast else {
+            unreachable!("parse_markdown returns a document")
+        };
+        let reparsed = start..start + children.len();
+        blocks.splice(start..sync.map_or(blocks.len(), |sync| sync + 1), children);
+        for block in &mut blocks[reparsed.end..] {
+            shift.visit_node_mut(block);
+        }
+        if start == 0 {
+            document_span.start = span.start;
+        }
+        match sync {
+            Some(_) => shift.apply(&mut document_span.end),
+            None => document_span.end = span.end,
+        }
+
+        self.source = source;
+        self.reparsed = reparsed;
+        Ok(())
+    }
+}
+
+/// Moves the spans of a tree by a number of bytes and lines, for nodes after an edit. Columns stay as
+/// they are, which holds for nodes starting on a line after the edit.
+struct ShiftSpans {
+    offset: isize,
+    lines: isize,
+}
+
+impl ShiftSpans {
+    fn apply(&self, position: &mut Position) {
+        position.offset = position.offset.wrapping_add_signed(self.offset);
+        position.line = position.line.wrapping_add_signed(self.lines);
+    }
+}
+
+impl VisitorMut for ShiftSpans {
+    fn visit_node_mut(&mut self, node: &mut AstNode) {
+        let span = node.span_mut();
+        self.apply(&mut span.start);
+        self.apply(&mut span.end);
+        walk_node_mut(self, node);
+    }
+}
+
+/// Parse the source from `start` up to `end` as a document of its own, with spans into the whole source
+fn parse_region(source: &str, start: Position, end: usize) -> Result<(Vec<AstNode>, Span), MarkdownError> {
+    let mut region = parse_markdown(&source[start.offset..end])?;
+    let mut shift = ShiftSpans { offset: start.offset as isize, lines: start.line as isize - 1 };
+    shift.visit_node_mut(&mut region);
+    match region {
+        AstNode::Document { children, span } => Ok((children, span)),
+        node => {
+            let span = node.span();
+            Ok((vec![node], span))
+        },
+    }
+}
+
+fn blocks(ast: &AstNode) -> &[AstNode] {
+    match ast {
+        AstNode::Document { children, .. } => children,
+        _ => &[],
+    }
+}
+
+/// Reference definitions apply to the whole document, and a lone `\r` ends a line without starting a
+/// new one in spans, so where either appears edits parse everything again
+fn needs_full_parse(source: &str) -> bool {
+    source.contains("]:") || source.match_indices('\r').any(|(index, _)| !source[index + 1..].starts_with('\n'))
+}
+
+fn line_start(source: &str, offset: usize) -> usize {
+    source[..offset].rfind('\n').map_or(0, |newline| newline + 1)
+}
+
+/// Whether a block starting at `position` starts at the beginning of a line after a blank one, where
+/// nothing above it can carry on into it
+fn starts_clean(source: &str, position: Position) -> bool {
+    if position.column != 1 {
+        return false;
+    }
+    let Some(before) = source[..position.offset].strip_suffix('\n') else {
+        return position.offset == 0;
+    };
+    before[line_start(before, before.len())..].trim().is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOTES: &str = "# Heat\n\nA **heist** film\nby Michael Mann.\n\n- [x] watched\n- rewatch\n\n  with [[movie:949]]\n\n> Quoted\n> text\n\n```rust\nlet x;\n```\n\n| a | b |\n|---|---|\n| 1 | 2 |\n\nLast ~~line~~";
+
+    fn assert_parsed(parser: &IncrementalParser) {
+        let expected = parse_markdown(parser.source()).unwrap();
+        assert_eq!(parser.ast(), &expected, "\nsource: {:?}", parser.source());
+    }
+
+    #[test]
+    fn test_edit_reuses_blocks() {
+        let mut parser = IncrementalParser::new(NOTES).unwrap();
+        let offset = NOTES.find("Quoted").unwrap();
+
+        parser.edit(offset..offset + 6, "Quoted\n> more").unwrap();
+
+        assert_parsed(&parser);
+        assert_eq!(parser.reparsed(), 2..5);
+        assert_eq!(blocks(parser.ast()).len(), 7);
+    }
+
+    #[test]
+    fn test_edit_reparses_to_the_end_when_blocks_change_meaning() {
+        let mut parser = IncrementalParser::new(NOTES).unwrap();
+
+        parser.edit(8..8, "```\n").unwrap();
+
+        assert_parsed(&parser);
+        assert_eq!(parser.reparsed(), 0..blocks(parser.ast()).len());
+        assert!(matches!(&blocks(parser.ast())[1], AstNode::CodeBlock { .. }));
+    }
+
+    #[test]
+    fn test_every_edit_matches_a_full_parse() {
+        let boundaries: Vec<usize> = (0..=NOTES.len()).filter(|&offset| NOTES.is_char_boundary(offset)).collect();
+        for &offset in &boundaries {
+            for text in ["x", "\n", "\n\n", "# ", "- ", "> ", "    ", "```", "---", "|", "**", "[[tv:1]]", "1. "] {
+                let mut parser = IncrementalParser::new(NOTES).unwrap();
+                parser.edit(offset..offset, text).unwrap();
+                assert_parsed(&parser);
+            }
+            for end in boundaries.iter().copied().filter(|&end| end > offset).take(3) {
+                let mut parser = IncrementalParser::new(NOTES).unwrap();
+                parser.edit(offset..end, "").unwrap();
+                assert_parsed(&parser);
+            }
+        }
+    }
+
+    #[test]
+    fn test_set_source() {
+        let mut parser = IncrementalParser::new("").unwrap();
+        let mut typed = String::new();
+        for ch in NOTES.chars() {
+            typed.push(ch);
+            parser.set_source(&typed).unwrap();
+            assert_parsed(&parser);
+        }
+
+        parser.set_source(&NOTES.replace("heist", "héist")).unwrap();
+        assert_parsed(&parser);
+        assert_eq!(parser.reparsed(), 0..3);
+        parser.set_source(&NOTES.replace("heist", "héist")).unwrap();
+        assert_eq!(parser.reparsed(), 0..0);
+    }
+
+    #[test]
+    fn test_definitions_parse_everything() {
+        let mut parser = IncrementalParser::new("[Heat]\n\ntext\n\n[heat]: /movie/949").unwrap();
+
+        parser.edit(8..12, "more").unwrap();
+
+        assert_parsed(&parser);
+        assert_eq!(parser.reparsed(), 0..2);
+    }
+}
@@ -0,0 +1,85 @@
+//! Raw HTML tags written inline in markdown, such as `<kbd>` or `</abbr>`
+
+/// A start or end tag, with lowercased names. The lexer only needs its length, the fields are for the
+/// HTML renderer's allowlist.
+#[cfg_attr(not(feature = "html"), allow(dead_code))]
+pub(crate) struct Tag {
+    pub name: String,
+    pub closing: bool,
+    pub self_closing: bool,
+    pub attributes: Vec<(String, String)>,
+}
+
+impl Tag {
+    /// The tag at the start of `input` and its length in bytes
+    pub fn parse(input: &str) -> Option<(Tag, usize)> {
+        let mut chars = input.char_indices().peekable();
+        chars.next().filter(|&(_, ch)| ch == '<')?;
+
+        let closing = chars.next_if(|&(_, ch)| ch == '/').is_some();
+        let name = Self::read_while(&mut chars, |ch| ch.is_ascii_alphanumeric() || ch == '-');
+        if !name.starts_with(|ch: char| ch.is_ascii_alphabetic()) {
+            return None;
+        }
+
+        let mut attributes = Vec::new();
+        loop {
+            let had_space = chars.peek().is_some_and(|&(_, ch)| ch.is_ascii_whitespace());
+            Self::read_while(&mut chars, |ch| ch.is_ascii_whitespace());
+
+            match chars.next()? {
+                (end, '>') => {
+                    let tag = Tag { name: name.to_ascii_lowercase(), closing, self_closing: false, attributes };
+                    return Some((tag, end + 1));
+                },
+                (_, '/') if !closing => {
+                    let (end, _) = chars.next().filter(|&(_, ch)| ch == '>')?;
+                    let tag = Tag { name: name.to_ascii_lowercase(), closing, self_closing: true, attributes };
+                    return Some((tag, end + 1));
+                },
+                (_, first) if had_space && !closing && Self::is_attribute_name_char(first) => {
+                    let mut attribute = first.to_string();
+                    attribute.push_str(&Self::read_while(&mut chars, Self::is_attribute_name_char));
+
+                    Self::read_while(&mut chars, |ch| ch.is_ascii_whitespace());
+                    let value = if chars.next_if(|&(_, ch)| ch == '=').is_some() {
+                        Self::read_while(&mut chars, |ch| ch.is_ascii_whitespace());
+                        match chars.peek()?.1 {
+                            quote @ ('"' | '\'') => {
+                                chars.next();
+                                let value = Self::read_while(&mut chars, |ch| ch != quote);
+                                chars.next()?;
+                                value
+                            },
+                            _ => {
+                                let value = Self::read_while(&mut chars, |ch| {
+                                    !ch.is_ascii_whitespace() && !matches!(ch, '"' | '\'' | '=' | '<' | '>' | '`')
+                                });
+                                if value.is_empty() {
+                                    return None;
+                                }
+                                value
+                            },
+                        }
+                    } else {
+                        String::new()
+                    };
+                    attributes.push((attribute.to_ascii_lowercase(), value));
+                },
+                _ => return None,
+            }
+        }
+    }
+
+    fn is_attribute_name_char(ch: char) -> bool {
+        ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_' | ':' | '.')
+    }
+
+    fn read_while(chars: &mut std::iter::Peekable<std::str::CharIndices<'_>>, accept: impl Fn(char) -> bool) -> String {
+        let mut read = String::new();
+        while let Some((_, ch)) = chars.next_if(|&(_, ch)| accept(ch)) {
+            read.push(ch);
+        }
+        read
+    }
+}
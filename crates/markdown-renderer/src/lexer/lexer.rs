@@ -1,50 +1,181 @@
 use std::{iter::Peekable, str::Chars};
 
+use crate::lexer::entities::named_entity;
+use crate::lexer::html::Tag;
 use crate::lexer::tokens::Token;
 use crate::error::LexerError;
+use crate::media::MediaRef;
+use crate::span::{Position, Span};
 
 pub struct Lexer<'a> {
+    source: &'a str,
     input: Peekable<Chars<'a>>,
     current_pos: usize,
+    // Byte offset of the next character
+    offset: usize,
     line: usize,
-    column: usize
+    column: usize,
+    // Whether inline content has been emitted on the current line, so whitespace is significant
+    in_line: bool,
+    // Whether nothing but whitespace has been read on the current line
+    line_start: bool,
+    // Whether the previous line was blank, since an indented code block cannot interrupt a paragraph
+    after_blank_line: bool,
+    // Number of `>` markers read on the current line
+    quote_depth: usize,
+    // Whether the current line is an ATX heading, whose closing `#`s aren't part of its text
+    heading_line: bool,
+    // Whether the current line's indentation has been looked at
+    indent_checked: bool,
+    // Content column of the most recent list item while a list may still be open
+    list_content: Option<usize>,
+    // Last character consumed
+    previous: Option<char>,
+    // Indentation of the current line in columns, stripped from fenced code content
+    line_indent: usize,
+    // Token read along with the previous one, e.g. indented code following an item's indentation
+    pending: Option<(Token, Span)>,
+    // Character position and state of a task marker found after the current line's list marker
+    task_marker: Option<(usize, bool)>,
+    // Character positions of every task marker read so far, in document order
+    task_positions: Vec<usize>,
+    // Whether `Eof` or an error has been returned, after which iteration stops
+    finished: bool,
 }
 
 impl<'a> Lexer<'a> {
     pub fn new(input: &'a str) -> Self {
         Lexer {
+            source: input,
             input: input.chars().peekable(),
             current_pos: 0,
+            offset: 0,
             line: 1,
             column: 1,
+            in_line: false,
+            line_start: true,
+            after_blank_line: true,
+            quote_depth: 0,
+            heading_line: false,
+            indent_checked: false,
+            list_content: None,
+            previous: None,
+            line_indent: 0,
+            pending: None,
+            task_marker: None,
+            task_positions: Vec::new(),
+            finished: false,
         }
     }
 
     pub fn tokenize(&mut self) -> Result<Vec<Token>, LexerError> {
-        let mut tokens = Vec::new();
+        Ok(self.tokenize_with_spans()?.into_iter().map(|(token, _)| token).collect())
+    }
 
-        while let Some(token) = self.next_token()? {
-            if matches!(token, Token::Eof) {
-                tokens.push(token);
-                break;
+    /// Tokenize the input, pairing every token with the span of source it was read from. Iterate the
+    /// lexer instead to read the tokens one at a time without collecting them.
+    pub fn tokenize_with_spans(&mut self) -> Result<Vec<(Token, Span)>, LexerError> {
+        self.collect()
+    }
+
+    /// The next token with its span, or `None` after `Eof`
+    fn next_spanned(&mut self) -> Result<Option<(Token, Span)>, LexerError> {
+        // A token read along with the previous one already knows its span
+        let pending = self.pending.as_ref().map(|(_, span)| *span);
+        let start = self.source_position();
+        let Some(token) = self.next_token()? else {
+            return Ok(None);
+        };
+        let mut span = pending.unwrap_or_else(|| Span::new(start, self.source_position()));
+
+        // Whitespace read ahead of text, e.g. after a `#` or list marker, isn't part of it, nor of a
+        // backslash breaking the line
+        let text = match &token {
+            Token::Text(text) => Some(text.as_str()),
+            Token::LineBreak if self.source[span.range()].ends_with('\\') => Some("\\"),
+            _ => None,
+        };
+        if let Some(text) = text {
+            let skipped = self.source[span.range()].strip_suffix(text).unwrap_or_default();
+            if !skipped.is_empty() && skipped.chars().all(|ch| ch == ' ' || ch == '\t') {
+                span.start.offset += skipped.len();
+                span.start.column += skipped.chars().count();
             }
-            tokens.push(token);
         }
 
-        Ok(tokens)
+        Ok(Some((token, span)))
     }
 
     fn next_token(&mut self) -> Result<Option<Token>, LexerError> {
+        if let Some((token, _)) = self.pending.take() {
+            self.line_start = false;
+            return Ok(Some(token));
+        }
         match self.peek_char().copied() {
             None => Ok(Some(Token::Eof)),
             Some(ch) => {
                 let token = self.read_token(ch)?;
+                match token {
+                    Token::Newline => {
+                        self.after_blank_line = self.line_start;
+                        self.quote_depth = 0;
+                        self.heading_line = false;
+                        self.indent_checked = false;
+                    }
+                    Token::Quote => {
+                        self.quote_depth += 1;
+                        self.indent_checked = false;
+                    }
+                    _ => {}
+                }
+                // Quote markers and indentation leave the rest of the line free to start any block
+                self.line_start = matches!(token, Token::Newline | Token::Quote | Token::Indent(_));
+                match token {
+                    Token::Newline => self.in_line = false,
+                    Token::Text(_)
+                    | Token::Url(_)
+                    | Token::MediaRef(_)
+                    | Token::Pipe
+                    | Token::CodeSpan(_)
+                    | Token::Asterisk(_)
+                    | Token::Underscore(_)
+                    | Token::DoubleTilde
+                    | Token::DoubleEquals
+                    | Token::DoublePipe
+                    | Token::LeftBracket
+                    | Token::RightBracket
+                    | Token::LeftParen
+                    | Token::RightParen
+                    | Token::Number(_)
+                    | Token::Dot => self.in_line = true,
+                    _ => {}
+                }
                 Ok(Some(token))
             }
         }
     }
 
+    /// Character positions of the `[ ]`/`[x]` task markers tokenized so far, in document order
+    pub(crate) fn task_positions(&self) -> &[usize] {
+        &self.task_positions
+    }
+
     fn read_token(&mut self, ch: char) -> Result<Token, LexerError> {
+        if let Some((position, checked)) = self.task_marker {
+            if position == self.current_pos {
+                self.task_marker = None;
+                self.task_positions.push(position);
+                self.advance_by(3);
+                return Ok(Token::TaskMarker(checked));
+            }
+        }
+
+        if self.line_start {
+            if let Some(token) = self.read_line_start(ch) {
+                return Ok(token);
+            }
+        }
+
         match ch {
             '\n' => {
                 self.advance();
@@ -58,10 +189,48 @@ impl<'a> Lexer<'a> {
                 }
                 Ok(Token::Newline)
             }
-            '#' => Ok(self.read_hashes()),
-            '*' => Ok(self.read_asterisks()),
-            '_' => Ok(self.read_underscores()),
+            '#' if self.closes_heading() => {
+                self.advance_by(self.peek_line().chars().count());
+                match self.peek_char().copied() {
+                    Some(next_ch) => self.read_token(next_ch),
+                    None => Ok(Token::Eof),
+                }
+            }
+            // `#hashtag` is text
+            '#' if self.line_start && !self.starts_heading() => {
+                let hashes = self.peek_line().chars().take_while(|&ch| ch == '#').count();
+                Ok(self.read_text_after(hashes))
+            }
+            '#' => {
+                self.heading_line |= self.line_start;
+                Ok(self.read_hashes())
+            }
+            // A backslash ending a line breaks it, unless the paragraph ends there anyway
+            '\\' if matches!(self.peek_second(), Some('\n' | '\r')) && !self.next_line_blank() => {
+                self.advance();
+                Ok(Token::LineBreak)
+            }
+            '*' => Ok(self.read_delimiter_run(Self::read_asterisks)),
+            // Underscores inside a word, as in `snake_case`, never delimit emphasis
+            '_' => {
+                let after_word = matches!(self.previous, Some(previous) if previous.is_alphanumeric());
+                match self.read_delimiter_run(Self::read_underscores) {
+                    Token::Underscore(count) if after_word && self.peek_char().is_some_and(|next| next.is_alphanumeric()) => {
+                        Ok(Token::Text("_".repeat(count as usize)))
+                    }
+                    token => Ok(token),
+                }
+            }
+            '~' | '=' if self.peek_second() == Some(ch) => Ok(self.read_delimiter_run(|lexer| {
+                let token = if ch == '~' { Token::DoubleTilde } else { Token::DoubleEquals };
+                lexer.advance_by(2);
+                token
+            })),
             '[' => {
+                if let Some((media_ref, len)) = self.media_ref() {
+                    self.advance_by(len);
+                    return Ok(Token::MediaRef(media_ref));
+                }
                 self.advance();
                 Ok(Token::LeftBracket)
             }
@@ -81,24 +250,70 @@ impl<'a> Lexer<'a> {
                 self.advance();
                 Ok(Token::Hyphen)
             }
+            '!' if self.peek_second() == Some('[') => {
+                self.advance();
+                Ok(Token::Exclamation)
+            }
+            'h' if self.bare_url_len().is_some() => Ok(self.read_url()),
+            '<' if self.angle_autolink_len().is_some() => Ok(self.read_angle_autolink()),
+            '<' if self.html_tag_len().is_some() => Ok(self.read_html_tag()),
+            '|' => {
+                self.advance();
+                if self.peek_char() == Some(&'|') {
+                    self.advance();
+                    return Ok(Token::DoublePipe);
+                }
+                Ok(Token::Pipe)
+            }
+            '`' => Ok(self.read_code_span()),
             '.' => {
                 self.advance();
                 Ok(Token::Dot)
             }
             c if c.is_ascii_digit() => Ok(self.read_number()),
             c if c.is_whitespace() && c != '\n' => {
-                self.skip_whitespace();
-                // Recursively get the next token after whitespace
-                if let Some(&next_ch) = self.peek_char() {
-                    self.read_token(next_ch)
-                } else {
-                    Ok(Token::Eof)
+                let whitespace = self.skip_whitespace();
+                match self.peek_char().copied() {
+                    // Whitespace between inline elements separates words, so keep it
+                    Some(next_ch) if self.in_line && next_ch != '\n' && next_ch != '\r' => {
+                        if Self::starts_text(next_ch) {
+                            match self.read_text() {
+                                Token::Text(text) => Ok(Token::Text(whitespace + &text)),
+                                token => Ok(token),
+                            }
+                        } else {
+                            Ok(Token::Text(whitespace))
+                        }
+                    }
+                    // Two or more spaces ending a line break it, unless the paragraph ends there anyway
+                    Some('\n' | '\r') if self.in_line && whitespace.ends_with("  ") && !self.next_line_blank() => {
+                        Ok(Token::LineBreak)
+                    }
+                    // Recursively get the next token after whitespace
+                    Some(next_ch) => self.read_token(next_ch),
+                    None => Ok(Token::Eof),
                 }
             }
             _ => Ok(self.read_text()),
         }
     }
 
+    /// Whether the `#`s at the current position are the optional closing sequence of an ATX heading:
+    /// separated from its text by whitespace and followed by nothing else on the line
+    fn closes_heading(&self) -> bool {
+        self.heading_line
+            && matches!(self.previous, Some(' ' | '\t'))
+            && self.peek_line().trim_end_matches([' ', '\t']).chars().all(|ch| ch == '#')
+    }
+
+    /// Whether the `#`s at the current position are followed by a space or the end of the line, as a
+    /// heading's are
+    fn starts_heading(&self) -> bool {
+        let line = self.peek_line();
+        let rest = line.trim_start_matches('#');
+        rest.is_empty() || rest.starts_with([' ', '\t'])
+    }
+
     fn read_hashes(&mut self) -> Token {
         let mut count = 0u8;
         while self.peek_char() == Some(&'#') && count < 6 {
@@ -108,6 +323,25 @@ impl<'a> Lexer<'a> {
         Token::Hash(count)
     }
 
+    /// A lone `*` or `_`, or a `~~` or `==`, with whitespace on both sides, as in `2 * 3` or `a == b`,
+    /// is plain text rather than a delimiter
+    fn read_delimiter_run(&mut self, read: impl FnOnce(&mut Self) -> Token) -> Token {
+        let after_space = matches!(self.previous, Some(' ' | '\t'));
+        let token = read(self);
+        let literal = match token {
+            Token::Asterisk(1) => "*",
+            Token::Underscore(1) => "_",
+            Token::DoubleTilde => "~~",
+            Token::DoubleEquals => "==",
+            _ => return token,
+        };
+        if after_space && self.peek_char().map_or(true, |next| next.is_whitespace()) {
+            Token::Text(literal.to_string())
+        } else {
+            token
+        }
+    }
+
     fn read_asterisks(&mut self) -> Token {
         let mut count = 0u8;
         while self.peek_char() == Some(&'*') && count < 3 {
@@ -126,6 +360,386 @@ impl<'a> Lexer<'a> {
         Token::Underscore(count)
     }
 
+    /// Tokens that can only begin a line
+    fn read_line_start(&mut self, ch: char) -> Option<Token> {
+        if !self.indent_checked {
+            self.indent_checked = true;
+            if let Some(token) = self.read_indentation() {
+                return Some(token);
+            }
+        }
+
+        match ch {
+            '`' | '~' => self.read_fenced_code(ch),
+            '>' => {
+                self.advance();
+                if self.peek_char() == Some(&' ') {
+                    self.advance();
+                }
+                Some(Token::Quote)
+            }
+            '|' | ':' => self.read_table_delimiter().map(Token::TableDelimiter),
+            '=' => self.read_setext_underline('='),
+            '-' => self
+                .read_table_delimiter()
+                .map(Token::TableDelimiter)
+                .or_else(|| self.read_setext_underline('-'))
+                .or_else(|| self.read_thematic_break('-'))
+                // Only a space after it makes a hyphen a list marker, so `-5 degrees` is text
+                .or_else(|| {
+                    let hyphens = self.peek_line().chars().take_while(|&ch| ch == '-').count();
+                    let marker = hyphens == 1 && matches!(self.peek_second(), None | Some(' ' | '\t' | '\n' | '\r'));
+                    (!marker).then(|| self.read_text_after(hyphens))
+                }),
+            '*' | '+' | '_' => self.read_thematic_break(ch).or_else(|| self.read_bullet(ch)),
+            // Only `1.` or `1)` followed by a space makes a number a list marker, so `2024 was great` is text
+            ch if ch.is_ascii_digit() && Self::list_marker_width(&self.peek_line()).is_none() => {
+                let digits = self.peek_line().chars().take_while(char::is_ascii_digit).count();
+                Some(self.read_text_after(digits))
+            }
+            _ => None,
+        }
+    }
+
+    /// Handle a line's leading whitespace: indented code, and `Indent` tokens inside lists so the parser can nest items
+    fn read_indentation(&mut self) -> Option<Token> {
+        let line = self.peek_line();
+        if line.trim().is_empty() {
+            return None;
+        }
+        let indent = Self::indent_width(&line);
+        let rest = line.trim_start_matches([' ', '\t']);
+        self.line_indent = indent;
+
+        // Indented code cannot interrupt a paragraph, and inside a list it is measured from the item's content
+        let code_indent = self.list_content.unwrap_or(0) + 4;
+        if indent >= code_indent && self.after_blank_line {
+            let start = self.source_position();
+            let code = self.read_indented_code(code_indent);
+            if self.list_content.is_none() {
+                return Some(code);
+            }
+            // The parser needs the indentation to tell which item the code belongs to
+            self.pending = Some((code, Span::new(start, self.source_position())));
+            return Some(Token::Indent(indent));
+        }
+
+        let marker = Self::list_marker_width(rest);
+        let leading = line.len() - rest.len();
+        self.task_marker = Self::task_marker(rest).map(|(offset, checked)| (self.current_pos + leading + offset, checked));
+        match marker {
+            Some(width) => self.list_content = Some(indent + width),
+            // Text right after a `>` is inside the quote, which decides nothing about the list around it
+            None if indent == 0 && self.after_blank_line && self.quote_depth == 0 => self.list_content = None,
+            None => {}
+        }
+
+        if indent > 0 && (marker.is_some() || self.list_content.is_some()) {
+            self.advance_by(leading);
+            return Some(Token::Indent(indent));
+        }
+        None
+    }
+
+    /// Offset and state of a `[ ]`, `[x]` or `[X]` task marker directly after the list marker starting `rest`
+    fn task_marker(rest: &str) -> Option<(usize, bool)> {
+        let width = Self::list_marker_width(rest)?;
+        let after = rest.get(width..)?;
+        let checked = match after.get(..3)? {
+            "[ ]" => false,
+            "[x]" | "[X]" => true,
+            _ => return None,
+        };
+        match after[3..].chars().next() {
+            Some(' ') | Some('\t') => Some((width, checked)),
+            _ => None,
+        }
+    }
+
+    /// Width of the list marker at the start of `rest` plus the spaces after it, e.g. 2 for `- item` and 4 for `10. item`
+    fn list_marker_width(rest: &str) -> Option<usize> {
+        let marker_len = match rest.chars().next()? {
+            '-' | '*' | '+' if !Self::is_thematic_break(rest) => 1,
+            ch if ch.is_ascii_digit() => {
+                let digits = rest.chars().take_while(|ch| ch.is_ascii_digit()).count();
+                match rest[digits..].chars().next() {
+                    // A start the list can't hold is text
+                    Some('.') | Some(')') if rest[..digits].parse::<u32>().is_ok() => digits + 1,
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        };
+
+        let after = &rest[marker_len..];
+        let spaces = after.chars().take_while(|&ch| ch == ' ').count();
+        match after.chars().next() {
+            None => Some(marker_len + 1),
+            Some(' ') | Some('\t') if spaces == 0 || spaces > 4 => Some(marker_len + 1),
+            Some(' ') => Some(marker_len + spaces),
+            _ => None,
+        }
+    }
+
+    fn is_thematic_break(line: &str) -> bool {
+        let Some(marker) = line.trim_start().chars().next() else {
+            return false;
+        };
+        matches!(marker, '-' | '*' | '_')
+            && line.chars().filter(|&ch| ch == marker).count() >= 3
+            && line.chars().all(|ch| ch == marker || ch == ' ' || ch == '\t')
+    }
+
+    /// `*` and `+` followed by whitespace at the start of a line are bullets rather than emphasis or text
+    fn read_bullet(&mut self, ch: char) -> Option<Token> {
+        if ch == '_' {
+            return None;
+        }
+        let mut lookahead = self.input.clone();
+        lookahead.next();
+        match lookahead.peek() {
+            None | Some(' ') | Some('\t') | Some('\n') | Some('\r') => {
+                self.advance();
+                Some(Token::Bullet(ch))
+            }
+            _ => None,
+        }
+    }
+
+    /// Consume the line if it is a run of `=`, or of two or more `-`, directly below another line. Whether
+    /// it underlines a paragraph or is text or a thematic break after all is up to the parser.
+    fn read_setext_underline(&mut self, marker: char) -> Option<Token> {
+        if self.after_blank_line {
+            return None;
+        }
+        let line = self.peek_line();
+        let run = line.chars().take_while(|&ch| ch == marker).count();
+        let min_run = if marker == '-' { 2 } else { 1 };
+        if run < min_run || !line[run..].trim().is_empty() {
+            return None;
+        }
+        self.advance_by(line.chars().count());
+        Some(Token::SetextUnderline(marker, run))
+    }
+
+    /// Consume the line if it is three or more `-`, `*` or `_` with nothing but spaces between them
+    fn read_thematic_break(&mut self, marker: char) -> Option<Token> {
+        let line = self.peek_line();
+        if !line.starts_with(marker) || !Self::is_thematic_break(&line) {
+            return None;
+        }
+        self.advance_by(line.chars().count());
+        Some(Token::ThematicBreak)
+    }
+
+    /// The rest of the current line, without consuming it
+    fn peek_line(&self) -> String {
+        self.input.clone().take_while(|&ch| ch != '\n' && ch != '\r').collect()
+    }
+
+    fn advance_by(&mut self, count: usize) {
+        for _ in 0..count {
+            self.advance();
+        }
+    }
+
+    fn advance_newline(&mut self) {
+        if self.peek_char() == Some(&'\r') {
+            self.advance();
+        }
+        if self.peek_char() == Some(&'\n') {
+            self.advance();
+        }
+    }
+
+    /// Width of leading whitespace with tabs stopping every four columns
+    fn indent_width(line: &str) -> usize {
+        let mut width = 0;
+        for ch in line.chars() {
+            match ch {
+                ' ' => width += 1,
+                '\t' => width += 4 - width % 4,
+                _ => break,
+            }
+        }
+        width
+    }
+
+    /// Drop up to `columns` columns of leading whitespace
+    fn strip_indent(line: &str, columns: usize) -> &str {
+        let mut width = 0;
+        for (index, ch) in line.char_indices() {
+            if width >= columns {
+                return &line[index..];
+            }
+            match ch {
+                ' ' => width += 1,
+                '\t' => width += 4 - width % 4,
+                _ => return &line[index..],
+            }
+        }
+        ""
+    }
+
+    /// Read consecutive lines indented by at least `columns`; blank lines inside the block are kept
+    fn read_indented_code(&mut self, columns: usize) -> Token {
+        let mut lookahead = self.input.clone();
+        let mut lines = Vec::new();
+        let mut kept = 0;
+        loop {
+            let raw: String = lookahead.by_ref().take_while(|&ch| ch != '\n').collect();
+            // Later lines of a code block inside a quote carry the quote markers too
+            let line = if lines.is_empty() { Some(raw.as_str()) } else { Self::strip_quotes(&raw, self.quote_depth) };
+            match line {
+                Some(line) if line.trim().is_empty() => {}
+                Some(line) if Self::indent_width(line) >= columns => kept = lines.len() + 1,
+                _ => break,
+            }
+            lines.push(raw);
+            if lookahead.peek().is_none() {
+                break;
+            }
+        }
+
+        let mut content = String::new();
+        for (index, raw) in lines[..kept].iter().enumerate() {
+            let line = if index == 0 {
+                raw.as_str()
+            } else {
+                self.advance_newline();
+                Self::strip_quotes(raw, self.quote_depth).unwrap_or_default()
+            };
+            self.advance_by(raw.chars().count());
+            content.push_str(Self::strip_indent(line.trim_end_matches('\r'), columns));
+            content.push('\n');
+        }
+
+        Token::CodeBlock { info: None, content }
+    }
+
+    /// Strip `depth` leading `>` markers, each with one optional following space
+    fn strip_quotes(line: &str, depth: usize) -> Option<&str> {
+        let mut rest = line;
+        for _ in 0..depth {
+            rest = rest.trim_start_matches(' ').strip_prefix('>')?;
+            rest = rest.strip_prefix(' ').unwrap_or(rest);
+        }
+        Some(rest)
+    }
+
+    /// Read a code block fenced by three or more backticks or tildes, up to a closing fence or the end of input
+    fn read_fenced_code(&mut self, fence: char) -> Option<Token> {
+        let line = self.peek_line();
+        let fence_len = line.chars().take_while(|&ch| ch == fence).count();
+        let info = line[fence_len..].trim();
+        if fence_len < 3 || (fence == '`' && info.contains('`')) {
+            return None;
+        }
+        let info = (!info.is_empty()).then(|| Self::unescape(info));
+        self.advance_by(line.chars().count());
+
+        let mut content = String::new();
+        loop {
+            // Look at the next line before consuming the newline, so a line outside the quote ends the block
+            let mut lookahead = self.input.clone();
+            if lookahead.peek() == Some(&'\r') {
+                lookahead.next();
+            }
+            if lookahead.next() != Some('\n') || lookahead.peek().is_none() {
+                break;
+            }
+            let raw: String = lookahead.take_while(|&ch| ch != '\n' && ch != '\r').collect();
+            let Some(line) = Self::strip_quotes(&raw, self.quote_depth) else {
+                break;
+            };
+
+            self.advance_newline();
+            self.advance_by(raw.chars().count());
+
+            // Content is indented relative to the opening fence
+            let strip = line.chars().take(self.line_indent).take_while(|&ch| ch == ' ').count();
+            let line = &line[strip..];
+            let closing = line.trim_start_matches(' ');
+            let closing_len = closing.chars().take_while(|&ch| ch == fence).count();
+            if Self::indent_width(line) < 4 && closing_len >= fence_len && closing[closing_len..].trim().is_empty() {
+                break;
+            }
+            content.push_str(line);
+            content.push('\n');
+        }
+
+        Some(Token::CodeBlock { info, content })
+    }
+
+    /// Read an inline code span; a backtick run without a matching closing run on the same line is literal text
+    fn read_code_span(&mut self) -> Token {
+        let line = self.peek_line();
+        let ticks = line.chars().take_while(|&ch| ch == '`').count();
+        let rest = &line[ticks..];
+
+        let mut closing = None;
+        let mut run_start = 0;
+        let mut run_len = 0;
+        for (index, ch) in rest.char_indices() {
+            if ch == '`' {
+                if run_len == 0 {
+                    run_start = index;
+                }
+                run_len += 1;
+            } else {
+                if run_len == ticks {
+                    closing = Some(run_start);
+                    break;
+                }
+                run_len = 0;
+            }
+        }
+        if closing.is_none() && run_len == ticks {
+            closing = Some(run_start);
+        }
+
+        match closing {
+            Some(end) => {
+                self.advance_by(ticks + rest[..end].chars().count() + ticks);
+                // Table cells need `\|` for a pipe even inside code
+                let code = rest[..end].replace("\\|", "|");
+                let trimmed = code.strip_prefix(' ').and_then(|code| code.strip_suffix(' '));
+                match trimmed {
+                    Some(inner) if !code.chars().all(|ch| ch == ' ') => Token::CodeSpan(inner.to_string()),
+                    _ => Token::CodeSpan(code),
+                }
+            }
+            None => {
+                self.advance_by(ticks);
+                Token::Text("`".repeat(ticks))
+            }
+        }
+    }
+
+    /// Consume the rest of the line if it is a table delimiter row like `| :-- | --: |`, returning it as
+    /// written; the parser reads the column alignments from it
+    fn read_table_delimiter(&mut self) -> Option<String> {
+        let line = self.peek_line();
+        let row = line.trim();
+        if !row.contains('|') {
+            return None;
+        }
+
+        let cells = row.strip_prefix('|').unwrap_or(row);
+        let cells = cells.strip_suffix('|').unwrap_or(cells);
+        let valid = cells.split('|').all(|cell| {
+            let dashes = cell.trim().trim_start_matches(':').trim_end_matches(':');
+            !dashes.is_empty() && dashes.chars().all(|ch| ch == '-')
+        });
+        if !valid {
+            return None;
+        }
+
+        let row = row.to_string();
+        self.advance_by(line.chars().count());
+        Some(row)
+    }
+
     fn read_number(&mut self) -> Token {
         let mut number_str = String::new();
 
@@ -138,8 +752,22 @@ impl<'a> Lexer<'a> {
             }
         }
 
-        let number = number_str.parse::<u32>().unwrap_or(0);
-        Token::Number(number)
+        // A number only keeps its digits as written without leading zeros, which list markers may have
+        match number_str.parse::<u32>() {
+            Ok(number) if self.line_start || number.to_string() == number_str => Token::Number(number),
+            _ => Token::Text(number_str),
+        }
+    }
+
+    /// Read the next `len` characters as the start of text, e.g. markup that turned out not to be, and the
+    /// text following them
+    fn read_text_after(&mut self, len: usize) -> Token {
+        let start: String = self.peek_line().chars().take(len).collect();
+        self.advance_by(len);
+        match self.read_text() {
+            Token::Text(text) => Token::Text(start + &text),
+            _ => unreachable!("read_text only returns text"),
+        }
     }
 
     fn read_text(&mut self) -> Token {
@@ -148,7 +776,35 @@ impl<'a> Lexer<'a> {
         while let Some(&ch) = self.peek_char() {
             match ch {
                 '\n' | '\r' => break,
-                '#' | '*' | '_' | '[' | ']' | '(' | ')' => break,
+                '#' | '*' | '_' | '[' | ']' | '(' | ')' | '|' | '`' => break,
+                // Only doubled, these start strikethrough and highlight
+                '~' | '=' if self.peek_second() == Some(ch) => break,
+                '!' if self.peek_second() == Some('[') => break,
+                'h' if self.bare_url_len().is_some() => break,
+                '<' if self.angle_autolink_len().is_some() || self.html_tag_len().is_some() => break,
+                '\\' => match self.peek_second() {
+                    // Escaped punctuation is literal text, e.g. `\*` or the `\|` of a pipe inside a table cell
+                    Some(escaped) if escaped.is_ascii_punctuation() => {
+                        self.advance_by(2);
+                        text.push(escaped);
+                    }
+                    // A line break, read as a token of its own
+                    Some('\n' | '\r') if !self.next_line_blank() => break,
+                    _ => {
+                        text.push(ch);
+                        self.advance();
+                    }
+                },
+                '&' => match Self::character_reference(&self.peek_line()) {
+                    Some((decoded, len)) => {
+                        self.advance_by(len);
+                        text.push_str(&decoded);
+                    }
+                    None => {
+                        text.push(ch);
+                        self.advance();
+                    }
+                },
                 '-' => {
                     // Look ahead to see if hyphen is followed by whitespace
                     let mut lookahead = self.input.clone();
@@ -177,8 +833,191 @@ impl<'a> Lexer<'a> {
             }
         }
 
-        // Trim trailing whitespace from text tokens
-        Token::Text(text.trim_end().to_string())
+        // Trailing whitespace only matters when more inline content follows on the same line, or when
+        // two or more spaces break the line
+        match self.peek_char() {
+            None | Some('\n') | Some('\r') => {
+                let trimmed = text.trim_end();
+                let trailing = &text[trimmed.len()..];
+                if trailing.ends_with("  ") && self.peek_char().is_some() && !self.next_line_blank() {
+                    let end = self.source_position();
+                    let start = Position {
+                        offset: end.offset - trailing.len(),
+                        line: end.line,
+                        column: end.column - trailing.chars().count(),
+                    };
+                    self.pending = Some((Token::LineBreak, Span::new(start, end)));
+                }
+                Token::Text(trimmed.to_string())
+            }
+            Some(_) => Token::Text(text),
+        }
+    }
+
+    /// The `&copy;`, `&#169;` or `&#xA9;` character reference starting `line`, decoded, and its length
+    /// in characters
+    fn character_reference(line: &str) -> Option<(String, usize)> {
+        let end = line.find(';')?;
+        let name = line.get(1..end)?;
+
+        let decoded = match name.strip_prefix('#') {
+            Some(number) => {
+                let (digits, radix, max_digits) = match number.strip_prefix(['x', 'X']) {
+                    Some(hex) => (hex, 16, 6),
+                    None => (number, 10, 7),
+                };
+                if digits.is_empty() || digits.len() > max_digits || !digits.chars().all(|ch| ch.is_digit(radix)) {
+                    return None;
+                }
+                // NUL and invalid code points become the replacement character, as in HTML
+                let code = u32::from_str_radix(digits, radix).ok()?;
+                char::from_u32(code).filter(|&ch| ch != '\0').unwrap_or('\u{FFFD}').to_string()
+            }
+            None => named_entity(name)?.to_string(),
+        };
+        // Entity names and digits are ASCII, so bytes and characters match
+        Some((decoded, end + 1))
+    }
+
+    /// Resolve the backslash escapes and character references in `text`, e.g. a code block's info string
+    fn unescape(text: &str) -> String {
+        let mut unescaped = String::new();
+        let mut rest = text;
+        while let Some(ch) = rest.chars().next() {
+            let len = match ch {
+                '\\' => match rest[1..].chars().next() {
+                    Some(escaped) if escaped.is_ascii_punctuation() => {
+                        unescaped.push(escaped);
+                        2
+                    }
+                    _ => {
+                        unescaped.push(ch);
+                        1
+                    }
+                },
+                '&' => match Self::character_reference(rest) {
+                    Some((decoded, len)) => {
+                        unescaped.push_str(&decoded);
+                        len
+                    }
+                    None => {
+                        unescaped.push(ch);
+                        1
+                    }
+                },
+                _ => {
+                    unescaped.push(ch);
+                    ch.len_utf8()
+                }
+            };
+            rest = &rest[len..];
+        }
+        unescaped
+    }
+
+    /// Whether the line after the current one is blank or missing, so the paragraph ends with this line
+    fn next_line_blank(&self) -> bool {
+        let rest = &self.source[self.offset..];
+        let Some(end) = rest.find(['\n', '\r']) else {
+            return true;
+        };
+        let next = rest[end..].strip_prefix("\r\n").unwrap_or(&rest[end + 1..]);
+        next.lines().next().map_or(true, |line| line.trim().is_empty())
+    }
+
+    /// Length of an `http://` or `https://` URL starting a word at the current position. Like GFM's
+    /// extended autolinks it ends at whitespace or `<`, without trailing punctuation or unbalanced `)`.
+    fn bare_url_len(&self) -> Option<usize> {
+        if matches!(self.previous, Some(ch) if ch.is_alphanumeric()) {
+            return None;
+        }
+        let line = self.peek_line();
+        let scheme = ["https://", "http://"].into_iter().find(|scheme| line.starts_with(scheme))?;
+
+        // A `)` without a `(` before it in the URL closes something around it, like a link destination,
+        // and a backslash starts an escape there
+        let mut depth = 0usize;
+        let mut url: Vec<char> = line
+            .chars()
+            .take_while(|&ch| !ch.is_whitespace() && ch != '<' && ch != '\\')
+            .take_while(|&ch| {
+                match ch {
+                    '(' => depth += 1,
+                    ')' if depth == 0 => return false,
+                    ')' => depth -= 1,
+                    _ => {}
+                }
+                true
+            })
+            .collect();
+        while let Some('?' | '!' | '.' | ',' | ':' | ';' | '*' | '_' | '~' | '\'' | '"') = url.last() {
+            url.pop();
+        }
+        (url.len() > scheme.len()).then_some(url.len())
+    }
+
+    fn read_url(&mut self) -> Token {
+        let len = self.bare_url_len().unwrap_or(0);
+        let url = self.peek_line().chars().take(len).collect();
+        self.advance_by(len);
+        Token::Url(url)
+    }
+
+    /// Length of a CommonMark autolink such as `<https://example.com>` at the current position,
+    /// including the angle brackets
+    fn angle_autolink_len(&self) -> Option<usize> {
+        let line = self.peek_line();
+        let inner = line.strip_prefix('<')?;
+        let end = inner.find('>')?;
+        let url = &inner[..end];
+
+        let scheme_len = url.find(':')?;
+        let scheme = &url[..scheme_len];
+        let valid_scheme = (2..=32).contains(&scheme.len())
+            && scheme.starts_with(|ch: char| ch.is_ascii_alphabetic())
+            && scheme.chars().all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '+' | '.' | '-'));
+        if !valid_scheme || url.contains(|ch: char| ch.is_whitespace() || ch.is_control() || ch == '<') {
+            return None;
+        }
+        Some(url.chars().count() + 2)
+    }
+
+    fn read_angle_autolink(&mut self) -> Token {
+        let len = self.angle_autolink_len().unwrap_or(2);
+        let url = self.peek_line().chars().skip(1).take(len - 2).collect();
+        self.advance_by(len);
+        Token::Url(url)
+    }
+
+    /// Length in characters of a raw HTML tag such as `<kbd>` or `</abbr>` at the current position. Tags
+    /// are read before escapes and character references, so `\<b>` and `&lt;b&gt;` stay text.
+    fn html_tag_len(&self) -> Option<usize> {
+        let line = self.peek_line();
+        let (_, len) = Tag::parse(&line)?;
+        Some(line[..len].chars().count())
+    }
+
+    fn read_html_tag(&mut self) -> Token {
+        let len = self.html_tag_len().unwrap_or(1);
+        let tag = self.peek_line().chars().take(len).collect();
+        self.advance_by(len);
+        Token::Html(tag)
+    }
+
+    /// The `[[movie:603]]` reference at the current position and its length in characters
+    fn media_ref(&self) -> Option<(MediaRef, usize)> {
+        if self.peek_second() != Some('[') {
+            return None;
+        }
+        let line = self.peek_line();
+        let inner = line.strip_prefix("[[")?;
+        let end = inner.find("]]")?;
+        let media_ref = inner[..end].parse().ok()?;
+        Some((media_ref, inner[..end].chars().count() + 4))
+    }
+
+    fn starts_text(ch: char) -> bool {
+        !matches!(ch, '#' | '*' | '_' | '[' | ']' | '(' | ')' | '-' | '.' | '|' | '`') && !ch.is_ascii_digit()
     }
 
 
@@ -186,39 +1025,68 @@ impl<'a> Lexer<'a> {
         self.input.peek()
     }
 
+    fn peek_second(&self) -> Option<char> {
+        self.input.clone().nth(1)
+    }
+
     fn advance(&mut self) -> Option<char> {
+        self.previous = self.input.peek().copied();
         match self.input.next() {
             Some('\n') => {
                 self.line += 1;
                 self.column = 1;
                 self.current_pos += 1;
+                self.offset += 1;
                 Some('\n')
             }
             Some(ch) => {
                 self.column += 1;
                 self.current_pos += 1;
+                self.offset += ch.len_utf8();
                 Some(ch)
             }
             None => None,
         }
     }
 
-    fn skip_whitespace(&mut self) {
+    fn skip_whitespace(&mut self) -> String {
+        let mut whitespace = String::new();
         while let Some(&ch) = self.peek_char() {
             if ch.is_whitespace() && ch != '\n' && ch != '\r' {
+                whitespace.push(ch);
                 self.advance();
             } else {
                 break;
             }
         }
+        whitespace
     }
 
     pub fn position(&self) -> (usize, usize) {
         (self.line, self.column)
     }
 
+    fn source_position(&self) -> Position {
+        Position { offset: self.offset, line: self.line, column: self.column }
+    }
+
+
+}
+
+/// Tokens are read lazily, each with its span, ending with `Eof` or the first error
+impl Iterator for Lexer<'_> {
+    type Item = Result<(Token, Span), LexerError>;
 
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let next = self.next_spanned().transpose();
+        self.finished = matches!(next, None | Some(Err(_)) | Some(Ok((Token::Eof, _))));
+        next
+    }
 }
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -295,10 +1163,13 @@ mod tests {
     #[test]
     fn test_asterisk_symbols() {
         let test_cases = vec![
-            ("*", vec![Token::Asterisk(1)]),
+            ("*", vec![Token::Bullet('*')]), // A lone `*` at the start of a line is an empty list item
+            ("*x", vec![Token::Asterisk(1), Token::Text("x".to_string())]),
+            ("a * b", vec![Token::Text("a ".to_string()), Token::Text("*".to_string()), Token::Text(" b".to_string())]),
             ("**", vec![Token::Asterisk(2)]),
-            ("***", vec![Token::Asterisk(3)]),
-            ("****", vec![Token::Asterisk(3), Token::Asterisk(1)]), // Max 3, then another
+            // A line of three or more on their own is a thematic break, so follow them with text
+            ("***x", vec![Token::Asterisk(3), Token::Text("x".to_string())]),
+            ("****x", vec![Token::Asterisk(3), Token::Asterisk(1), Token::Text("x".to_string())]), // Max 3, then another
         ];
 
         for (input, expected) in test_cases {
@@ -314,8 +1185,12 @@ mod tests {
         let test_cases = vec![
             ("_", vec![Token::Underscore(1)]),
             ("__", vec![Token::Underscore(2)]),
-            ("___", vec![Token::Underscore(3)]),
-            ("____", vec![Token::Underscore(3), Token::Underscore(1)]), // Max 3, then another
+            // A line of three or more on their own is a thematic break, so follow them with text
+            ("___x", vec![Token::Underscore(3), Token::Text("x".to_string())]),
+            ("____x", vec![Token::Underscore(3), Token::Underscore(1), Token::Text("x".to_string())]), // Max 3, then another
+            // Inside a word they are text
+            ("a_b", vec![Token::Text("a".to_string()), Token::Text("_".to_string()), Token::Text("b".to_string())]),
+            ("a_", vec![Token::Text("a".to_string()), Token::Underscore(1)]),
         ];
 
         for (input, expected) in test_cases {
@@ -342,16 +1217,16 @@ mod tests {
     #[test]
     fn test_numbers() {
         let test_cases = vec![
-            ("0", Token::Number(0)),
-            ("1", Token::Number(1)),
-            ("123", Token::Number(123)),
-            ("999999", Token::Number(999999)),
+            ("0.", Token::Number(0)),
+            ("1)", Token::Number(1)),
+            ("123. x", Token::Number(123)),
+            ("(999999)", Token::Number(999999)),
         ];
 
         for (input, expected) in test_cases {
             let mut lexer = Lexer::new(input);
             let tokens = lexer.tokenize().unwrap();
-            assert_eq!(tokens[0], expected, "Failed for input: {}", input);
+            assert!(tokens.contains(&expected), "Failed for input: {}", input);
         }
     }
 
@@ -385,7 +1260,7 @@ mod tests {
             Token::Asterisk(1),
             Token::Text("italic".to_string()),
             Token::Asterisk(1),
-            Token::Text("and".to_string()),
+            Token::Text(" and ".to_string()),
             Token::Asterisk(2),
             Token::Text("bold".to_string()),
             Token::Asterisk(2),
@@ -402,7 +1277,7 @@ mod tests {
             Token::Text("link text".to_string()),
             Token::RightBracket,
             Token::LeftParen,
-            Token::Text("https://example.com".to_string()), // Should be one token
+            Token::Url("https://example.com".to_string()), // Should be one token
             Token::RightParen,
             Token::Eof
         ]);
@@ -429,11 +1304,11 @@ mod tests {
         assert_eq!(tokens, vec![
             Token::Number(1),
             Token::Dot,
-            Token::Text("First item".to_string()),
+            Token::Text(" First item".to_string()),
             Token::Newline,
             Token::Number(2),
             Token::Dot,
-            Token::Text("Second item".to_string()),
+            Token::Text(" Second item".to_string()),
             Token::Eof
         ]);
     }
@@ -449,15 +1324,15 @@ mod tests {
             Token::Text("Heading".to_string()),
             Token::Newline,
             Token::Newline,
-            Token::Text("This is".to_string()),
+            Token::Text("This is ".to_string()),
             Token::Asterisk(2),
             Token::Text("bold".to_string()),
             Token::Asterisk(2),
-            Token::Text("and".to_string()),
+            Token::Text(" and ".to_string()),
             Token::Asterisk(1),
             Token::Text("italic".to_string()),
             Token::Asterisk(1),
-            Token::Text("text.".to_string()),
+            Token::Text(" text.".to_string()),
             Token::Newline,
             Token::Newline,
             Token::Hyphen,
@@ -491,12 +1366,20 @@ mod tests {
 
     #[test]
     fn test_mixed_whitespace() {
-        let mut lexer = Lexer::new("  \t  text  \t  ");
+        let mut lexer = Lexer::new("   text  \t  ");
         let tokens = lexer.tokenize().unwrap();
         assert_eq!(tokens, vec![
             Token::Text("text".to_string()),
             Token::Eof
         ]);
+
+        // Four columns of indentation, counting a tab up to the next tab stop, make a code block
+        let mut lexer = Lexer::new("  \t  text  \t  ");
+        let tokens = lexer.tokenize().unwrap();
+        assert_eq!(tokens, vec![
+            Token::CodeBlock { info: None, content: "  text  \t  \n".to_string() },
+            Token::Eof
+        ]);
     }
 
     #[test]
@@ -583,6 +1466,26 @@ mod tests {
         ]);
     }
 
+    #[test]
+    fn test_lazy_iteration() {
+        let source = "# Hello\n*World*";
+        let mut lexer = Lexer::new(source);
+
+        let (token, span) = lexer.next().unwrap().unwrap();
+        assert_eq!(token, Token::Hash(1));
+        assert_eq!(&source[span.range()], "#");
+        let (token, span) = lexer.next().unwrap().unwrap();
+        assert_eq!(token, Token::Text("Hello".to_string()));
+        assert_eq!(&source[span.range()], "Hello");
+
+        let rest: Vec<(Token, Span)> = lexer.by_ref().collect::<Result<_, _>>().unwrap();
+        assert_eq!(rest.last().map(|(token, _)| token), Some(&Token::Eof));
+        assert_eq!(rest.len(), 5);
+        assert!(lexer.next().is_none());
+
+        assert_eq!(Lexer::new(source).collect::<Result<Vec<_>, _>>().unwrap(), Lexer::new(source).tokenize_with_spans().unwrap());
+    }
+
     #[test]
     fn test_dot_contexts() {
         // Standalone dot
@@ -600,7 +1503,7 @@ mod tests {
         let tokens = lexer.tokenize().unwrap();
         assert_eq!(tokens[0], Token::Number(1));
         assert_eq!(tokens[1], Token::Dot);
-        assert_eq!(tokens[2], Token::Text("Item".to_string()));
+        assert_eq!(tokens[2], Token::Text(" Item".to_string()));
     }
 
     #[test]
@@ -620,9 +1523,15 @@ mod tests {
     #[test]
     fn test_number_contexts() {
         // Standalone number
-        let mut lexer = Lexer::new("42 ");
+        let mut lexer = Lexer::new("(42)");
         let tokens = lexer.tokenize().unwrap();
-        assert_eq!(tokens[0], Token::Number(42));
+        assert_eq!(tokens[1], Token::Number(42));
+
+        // Starting a line without a list delimiter and space after it, numbers are text
+        for input in ["42", "2024 was great", "3 stars", "1.5 stars", "4.x"] {
+            let tokens = Lexer::new(input).tokenize().unwrap();
+            assert_eq!(tokens, vec![Token::Text(input.to_string()), Token::Eof], "Failed for input: {}", input);
+        }
 
         // Number in text
         let mut lexer = Lexer::new("version1.2.3");
@@ -635,4 +1544,433 @@ mod tests {
         assert_eq!(tokens[0], Token::Number(1));
         assert_eq!(tokens[1], Token::Dot);
     }
+
+    #[test]
+    fn test_table_rows() {
+        let mut lexer = Lexer::new("| Title | Year |\n|:------|-----:|\n| Heat | 1995 |");
+        let tokens = lexer.tokenize().unwrap();
+        assert_eq!(tokens, vec![
+            Token::Pipe,
+            Token::Text(" Title ".to_string()),
+            Token::Pipe,
+            Token::Text(" Year ".to_string()),
+            Token::Pipe,
+            Token::Newline,
+            Token::TableDelimiter("|:------|-----:|".to_string()),
+            Token::Newline,
+            Token::Pipe,
+            Token::Text(" Heat ".to_string()),
+            Token::Pipe,
+            Token::Text(" ".to_string()),
+            Token::Number(1995),
+            Token::Text(" ".to_string()),
+            Token::Pipe,
+            Token::Eof
+        ]);
+    }
+
+    #[test]
+    fn test_table_delimiter_alignments() {
+        let mut lexer = Lexer::new("--- | :-: | :-- | --:");
+        let tokens = lexer.tokenize().unwrap();
+        assert_eq!(tokens[0], Token::TableDelimiter("--- | :-: | :-- | --:".to_string()));
+
+        // A delimiter row needs at least one pipe and only dashes and colons
+        let mut lexer = Lexer::new("- item");
+        assert_eq!(lexer.tokenize().unwrap()[0], Token::Hyphen);
+        let mut lexer = Lexer::new("| --x |");
+        assert_eq!(lexer.tokenize().unwrap()[0], Token::Pipe);
+    }
+
+    #[test]
+    fn test_escaped_pipe() {
+        let mut lexer = Lexer::new("a \\| b | c");
+        let tokens = lexer.tokenize().unwrap();
+        assert_eq!(tokens, vec![
+            Token::Text("a | b ".to_string()),
+            Token::Pipe,
+            Token::Text(" c".to_string()),
+            Token::Eof
+        ]);
+    }
+
+    #[test]
+    fn test_code_span() {
+        let mut lexer = Lexer::new("run `cargo *test*` now");
+        let tokens = lexer.tokenize().unwrap();
+        assert_eq!(tokens, vec![
+            Token::Text("run ".to_string()),
+            Token::CodeSpan("cargo *test*".to_string()),
+            Token::Text(" now".to_string()),
+            Token::Eof
+        ]);
+
+        // Longer runs can contain shorter ones, and one padding space is stripped from each side
+        let mut lexer = Lexer::new("`` a ` b ``");
+        assert_eq!(lexer.tokenize().unwrap()[0], Token::CodeSpan("a ` b".to_string()));
+
+        // Without a matching closing run the backticks are literal
+        let mut lexer = Lexer::new("``a`");
+        let tokens = lexer.tokenize().unwrap();
+        assert_eq!(tokens[0], Token::Text("``".to_string()));
+    }
+
+    #[test]
+    fn test_fenced_code_block() {
+        let mut lexer = Lexer::new("```rust\nlet _x = *y;\n# not a heading\n```\nafter");
+        let tokens = lexer.tokenize().unwrap();
+        assert_eq!(tokens, vec![
+            Token::CodeBlock {
+                info: Some("rust".to_string()),
+                content: "let _x = *y;\n# not a heading\n".to_string(),
+            },
+            Token::Newline,
+            Token::Text("after".to_string()),
+            Token::Eof
+        ]);
+
+        // The closing fence must be at least as long and of the same character; unclosed fences run to the end
+        let mut lexer = Lexer::new("~~~~\n~~~\n```\n");
+        let tokens = lexer.tokenize().unwrap();
+        assert_eq!(tokens, vec![
+            Token::CodeBlock { info: None, content: "~~~\n```\n".to_string() },
+            Token::Newline,
+            Token::Eof
+        ]);
+    }
+
+    #[test]
+    fn test_indented_code_block() {
+        let mut lexer = Lexer::new("    fn main() {\n\n        todo!()\n    }\n\ntext");
+        let tokens = lexer.tokenize().unwrap();
+        assert_eq!(tokens, vec![
+            Token::CodeBlock { info: None, content: "fn main() {\n\n    todo!()\n}\n".to_string() },
+            Token::Newline,
+            Token::Newline,
+            Token::Text("text".to_string()),
+            Token::Eof
+        ]);
+
+        // An indented line cannot interrupt a paragraph
+        let mut lexer = Lexer::new("text\n    more");
+        let tokens = lexer.tokenize().unwrap();
+        assert_eq!(tokens[2], Token::Text("more".to_string()));
+    }
+
+    #[test]
+    fn test_block_quote_markers() {
+        let mut lexer = Lexer::new("> # Title\n>> - item\n> a > b");
+        let tokens = lexer.tokenize().unwrap();
+        assert_eq!(tokens, vec![
+            Token::Quote,
+            Token::Hash(1),
+            Token::Text("Title".to_string()),
+            Token::Newline,
+            Token::Quote,
+            Token::Quote,
+            Token::Hyphen,
+            Token::Text("item".to_string()),
+            Token::Newline,
+            Token::Quote,
+            Token::Text("a > b".to_string()),
+            Token::Eof
+        ]);
+    }
+
+    #[test]
+    fn test_thematic_breaks() {
+        for input in ["---", "***", "___", "- - -", " * * * *", "_____"] {
+            let mut lexer = Lexer::new(input);
+            assert_eq!(lexer.tokenize().unwrap(), vec![Token::ThematicBreak, Token::Eof], "Failed for input: {}", input);
+        }
+
+        // Two markers, mixed markers, or other text are not rules
+        let mut lexer = Lexer::new("--");
+        assert_eq!(lexer.tokenize().unwrap()[0], Token::Text("--".to_string()));
+        let mut lexer = Lexer::new("-*-");
+        assert_ne!(lexer.tokenize().unwrap()[0], Token::ThematicBreak);
+        let mut lexer = Lexer::new("***bold***");
+        assert_eq!(lexer.tokenize().unwrap()[0], Token::Asterisk(3));
+    }
+
+    #[test]
+    fn test_code_block_in_quote() {
+        let mut lexer = Lexer::new("> ```\n> *code*\n> ```\n> after");
+        let tokens = lexer.tokenize().unwrap();
+        assert_eq!(tokens, vec![
+            Token::Quote,
+            Token::CodeBlock { info: None, content: "*code*\n".to_string() },
+            Token::Newline,
+            Token::Quote,
+            Token::Text("after".to_string()),
+            Token::Eof
+        ]);
+
+        // Leaving the quote also closes a fence that was opened inside it
+        let mut lexer = Lexer::new("> ```\n> code\noutside");
+        let tokens = lexer.tokenize().unwrap();
+        assert_eq!(tokens[1], Token::CodeBlock { info: None, content: "code\n".to_string() });
+        assert_eq!(tokens[3], Token::Text("outside".to_string()));
+    }
+
+    #[test]
+    fn test_list_indentation() {
+        let mut lexer = Lexer::new("* one\n  + two\n\n    text\n10) ten");
+        let tokens = lexer.tokenize().unwrap();
+        assert_eq!(tokens, vec![
+            Token::Bullet('*'),
+            Token::Text("one".to_string()),
+            Token::Newline,
+            Token::Indent(2),
+            Token::Bullet('+'),
+            Token::Text("two".to_string()),
+            Token::Newline,
+            Token::Newline,
+            Token::Indent(4),
+            Token::Text("text".to_string()),
+            Token::Newline,
+            Token::Number(10),
+            Token::RightParen,
+            Token::Text(" ten".to_string()),
+            Token::Eof
+        ]);
+
+        // Outside lists indentation is dropped as before
+        let mut lexer = Lexer::new("  text");
+        assert_eq!(lexer.tokenize().unwrap(), vec![Token::Text("text".to_string()), Token::Eof]);
+    }
+
+    #[test]
+    fn test_code_in_list_item() {
+        // Indented code is measured from the item's content column
+        let mut lexer = Lexer::new("- a\n\n      code");
+        let tokens = lexer.tokenize().unwrap();
+        assert_eq!(tokens[4..], [
+            Token::Indent(6),
+            Token::CodeBlock { info: None, content: "code\n".to_string() },
+            Token::Eof
+        ]);
+
+        // Fenced content loses the fence's indentation
+        let mut lexer = Lexer::new("1. a\n   ```\n   code\n    more\n   ```");
+        let tokens = lexer.tokenize().unwrap();
+        assert_eq!(tokens[5], Token::CodeBlock { info: None, content: "code\n more\n".to_string() });
+    }
+
+    #[test]
+    fn test_task_markers() {
+        let mut lexer = Lexer::new("- [ ] todo\n* [x] done\n- [y] not a task\n- [ ]tight\n[ ] plain");
+        let tokens = lexer.tokenize().unwrap();
+        assert_eq!(tokens[..7], [
+            Token::Hyphen,
+            Token::TaskMarker(false),
+            Token::Text("todo".to_string()),
+            Token::Newline,
+            Token::Bullet('*'),
+            Token::TaskMarker(true),
+            Token::Text("done".to_string()),
+        ]);
+        assert_eq!(tokens.iter().filter(|token| matches!(token, Token::TaskMarker(_))).count(), 2);
+        assert_eq!(lexer.task_positions(), [2, 13]);
+    }
+
+    #[test]
+    fn test_double_delimiters() {
+        let mut lexer = Lexer::new("~~a~~ ==b== ||c||");
+        let tokens = lexer.tokenize().unwrap();
+        assert_eq!(tokens, vec![
+            Token::DoubleTilde,
+            Token::Text("a".to_string()),
+            Token::DoubleTilde,
+            Token::Text(" ".to_string()),
+            Token::DoubleEquals,
+            Token::Text("b".to_string()),
+            Token::DoubleEquals,
+            Token::Text(" ".to_string()),
+            Token::DoublePipe,
+            Token::Text("c".to_string()),
+            Token::DoublePipe,
+            Token::Eof
+        ]);
+
+        // Single characters and spaced-out pairs are text
+        let mut lexer = Lexer::new("~5 min, a=b, x == y");
+        assert_eq!(lexer.tokenize().unwrap(), vec![
+            Token::Text("~5 min, a=b, x ".to_string()),
+            Token::Text("==".to_string()),
+            Token::Text(" y".to_string()),
+            Token::Eof
+        ]);
+    }
+
+    #[test]
+    fn test_urls() {
+        let mut lexer = Lexer::new("See https://www.themoviedb.org/movie/603. Or <mailto:neo@example.com>!");
+        assert_eq!(lexer.tokenize().unwrap(), vec![
+            Token::Text("See ".to_string()),
+            Token::Url("https://www.themoviedb.org/movie/603".to_string()),
+            Token::Dot,
+            Token::Text(" Or ".to_string()),
+            Token::Url("mailto:neo@example.com".to_string()),
+            Token::Text("!".to_string()),
+            Token::Eof
+        ]);
+
+        // Balanced parentheses stay in the URL, the closing one of a wrapping pair does not
+        let mut lexer = Lexer::new("(https://en.wikipedia.org/wiki/Heat_(1995_film))");
+        assert_eq!(lexer.tokenize().unwrap(), vec![
+            Token::LeftParen,
+            Token::Url("https://en.wikipedia.org/wiki/Heat_(1995_film)".to_string()),
+            Token::RightParen,
+            Token::Eof
+        ]);
+    }
+
+    #[test]
+    fn test_image_marker() {
+        let mut lexer = Lexer::new("Wow! ![a](b)");
+        assert_eq!(lexer.tokenize().unwrap(), vec![
+            Token::Text("Wow! ".to_string()),
+            Token::Exclamation,
+            Token::LeftBracket,
+            Token::Text("a".to_string()),
+            Token::RightBracket,
+            Token::LeftParen,
+            Token::Text("b".to_string()),
+            Token::RightParen,
+            Token::Eof
+        ]);
+    }
+
+    #[test]
+    fn test_media_refs() {
+        let mut lexer = Lexer::new("See [[tv:1399 S02E03]] or [[movie:x]]");
+        assert_eq!(lexer.tokenize().unwrap(), vec![
+            Token::Text("See ".to_string()),
+            Token::MediaRef(MediaRef {
+                media_type: crate::media::MediaType::Tv,
+                id: 1399,
+                episode: Some(crate::media::Episode { season: 2, episode: 3 }),
+            }),
+            Token::Text(" or ".to_string()),
+            Token::LeftBracket,
+            Token::LeftBracket,
+            Token::Text("movie:x".to_string()),
+            Token::RightBracket,
+            Token::RightBracket,
+            Token::Eof
+        ]);
+    }
+
+    #[test]
+    fn test_token_spans() {
+        let input = "# Heat\nCafé *noir*";
+        let tokens = Lexer::new(input).tokenize_with_spans().unwrap();
+        let sources: Vec<&str> = tokens.iter().map(|(_, span)| &input[span.range()]).collect();
+
+        assert_eq!(sources, vec!["#", "Heat", "\n", "Café ", "*", "noir", "*", ""]);
+        // Offsets count bytes, columns count characters
+        let (_, noir) = tokens[5];
+        assert_eq!((noir.start.offset, noir.start.line, noir.start.column), (14, 2, 7));
+        assert_eq!((noir.end.offset, noir.end.column), (18, 11));
+    }
+
+    #[test]
+    fn test_backslash_escapes() {
+        let mut lexer = Lexer::new("\\# not a *heading\\* \\a");
+        assert_eq!(lexer.tokenize().unwrap(), vec![
+            Token::Text("# not a ".to_string()),
+            Token::Asterisk(1),
+            Token::Text("heading* \\a".to_string()),
+            Token::Eof
+        ]);
+
+        let mut lexer = Lexer::new("``` rust\\+&quot;x&quot;\ncode\n```");
+        assert_eq!(lexer.tokenize().unwrap(), vec![
+            Token::CodeBlock { info: Some("rust+\"x\"".to_string()), content: "code\n".to_string() },
+            Token::Eof
+        ]);
+    }
+
+    #[test]
+    fn test_html_tags() {
+        let mut lexer = Lexer::new(r#"Press <kbd>Ctrl</kbd> <abbr title="a > b"> \<b> &lt;i&gt; a < b <3"#);
+        assert_eq!(lexer.tokenize().unwrap(), vec![
+            Token::Text("Press ".to_string()),
+            Token::Html("<kbd>".to_string()),
+            Token::Text("Ctrl".to_string()),
+            Token::Html("</kbd>".to_string()),
+            Token::Text(" ".to_string()),
+            Token::Html(r#"<abbr title="a > b">"#.to_string()),
+            // Escaped and encoded tags are text
+            Token::Text(" <b> <i> a < b <3".to_string()),
+            Token::Eof
+        ]);
+    }
+
+    #[test]
+    fn test_character_references() {
+        let mut lexer = Lexer::new("&copy; 2024 &#72;&#x69; &amp;c &nope; &#0;");
+        assert_eq!(lexer.tokenize().unwrap(), vec![
+            Token::Text("© 2024 Hi &c &nope; \u{FFFD}".to_string()),
+            Token::Eof
+        ]);
+    }
+
+    #[test]
+    fn test_line_breaks() {
+        let mut lexer = Lexer::new("one  \ntwo\\\nthree  \n\nfour\\");
+        assert_eq!(lexer.tokenize().unwrap(), vec![
+            Token::Text("one".to_string()),
+            Token::LineBreak,
+            Token::Newline,
+            Token::Text("two".to_string()),
+            Token::LineBreak,
+            Token::Newline,
+            // Nothing follows in the paragraph, so neither is a break
+            Token::Text("three".to_string()),
+            Token::Newline,
+            Token::Newline,
+            Token::Text("four\\".to_string()),
+            Token::Eof
+        ]);
+    }
+
+    #[test]
+    fn test_setext_underlines_and_closing_hashes() {
+        let mut lexer = Lexer::new("Title\n===\n## Sub ##\n---\n\n---");
+        assert_eq!(lexer.tokenize().unwrap(), vec![
+            Token::Text("Title".to_string()),
+            Token::Newline,
+            Token::SetextUnderline('=', 3),
+            Token::Newline,
+            Token::Hash(2),
+            Token::Text("Sub ".to_string()),
+            Token::Newline,
+            Token::SetextUnderline('-', 3),
+            Token::Newline,
+            Token::Newline,
+            // Nothing above to underline
+            Token::ThematicBreak,
+            Token::Eof
+        ]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize_tokens() {
+        let mut lexer = Lexer::new("## [[tv:1399 S02E03]]");
+        let tokens = lexer.tokenize().unwrap();
+
+        let json = serde_json::to_value(&tokens).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([
+                { "type": "hash", "value": 2 },
+                { "type": "media_ref", "value": { "media_type": "tv", "id": 1399, "episode": { "season": 2, "episode": 3 } } },
+                { "type": "eof" },
+            ])
+        );
+        assert_eq!(serde_json::from_value::<Vec<Token>>(json).unwrap(), tokens);
+    }
 }
@@ -1,4 +1,7 @@
 pub mod tokens;
+mod entities;
+pub(crate) mod html;
+#[allow(clippy::module_inception)]
 mod lexer;
 
 pub use lexer::Lexer;
@@ -8,3 +11,9 @@ pub fn tokenize(input: &str) -> Result<Vec<Token>, crate::error::LexerError> {
     let mut lexer = Lexer::new(input);
     lexer.tokenize()
 }
+
+/// Tokenize markdown, pairing every token with the span of source it was read from
+pub fn tokenize_with_spans(input: &str) -> Result<Vec<(Token, crate::span::Span)>, crate::error::LexerError> {
+    let mut lexer = Lexer::new(input);
+    lexer.tokenize_with_spans()
+}
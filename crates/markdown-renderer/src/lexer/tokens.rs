@@ -1,22 +1,45 @@
+use crate::media::MediaRef;
+
+/// With the `serde` feature, tokens serialize tagged with their `type` and any data as `value`, e.g.
+/// `{"type": "hash", "value": 2}` or `{"type": "newline"}`
 #[allow(dead_code)]
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(tag = "type", content = "value", rename_all = "snake_case"))]
 pub enum Token {
     // Basic content
     Text(String),
     Newline,
+    LineBreak,         // two or more spaces or a `\` ending a line that the paragraph continues past
     Eof,
 
     // Markdown markers
     Hash(u8),          // # ## ### etc. - store level directly
     Asterisk(u8),      // * ** - store count
     Underscore(u8),    // _ __ - store count
+    Exclamation,       // ! directly before [ - starts an image
     LeftBracket,       // [
     RightBracket,      // ]
     LeftParen,         // (
     RightParen,        // )
     Hyphen,            // -
+    Bullet(char),      // * or + starting a list item
+    Indent(usize),     // leading whitespace in columns, only emitted where list nesting depends on it
+    TaskMarker(bool),  // [ ] or [x] right after a list marker, true when checked
+    Quote,             // > at the start of a line
+    ThematicBreak,     // --- *** ___ on a line of their own
+    SetextUnderline(char, usize), // a line of only `=` or `-`, with the marker and its count; makes the paragraph above a heading
+    Pipe,              // | - table cell separator
+    DoublePipe,        // || - spoiler delimiter, or an empty cell inside a table row
+    DoubleTilde,       // ~~ - strikethrough delimiter
+    DoubleEquals,      // == - highlight delimiter
+    TableDelimiter(String), // |:--|--:| - a whole table delimiter row, as written
+
+    CodeSpan(String),  // `code` - contents are never parsed as markdown
+    CodeBlock { info: Option<String>, content: String }, // fenced or indented code block
 
     Number(u32),
     Dot,
-    Url(String)
+    Url(String),
+    MediaRef(MediaRef), // [[movie:603]] - a whole title reference
+    Html(String),      // <kbd> or </kbd> - a raw inline HTML tag, as written
 }
@@ -1,28 +1,66 @@
 pub mod error;
+pub mod incremental;
 pub mod parser;
 pub mod renderer;
+pub mod span;
+pub mod tasks;
+pub mod toc;
+pub mod visit;
 
 mod lexer;
+mod media;
 
 // Re-export commonly used items
-pub use error::{LexerError, ParseError, MarkdownError};
-pub use lexer::{Lexer, Token, tokenize};
-pub use parser::{Parser, AstNode, parse};
+pub use error::{Diagnostic, LexerError, ParseError, MarkdownError};
+pub use incremental::IncrementalParser;
+pub use lexer::{Lexer, Token, tokenize, tokenize_with_spans};
+pub use parser::{Parser, AstNode, Alignment, Episode, MediaRef, MediaType, parse};
+pub use renderer::{render_markdown, render_plain_text, MarkdownRenderer, MediaResolver, PlainTextRenderer, Renderer, ResolvedMedia};
+pub use span::{Position, Span};
+pub use tasks::{tasks, toggle_task, TaskEntry};
+pub use toc::{toc, TocEntry};
+pub use visit::{Visitor, VisitorMut};
 
 // Re-export renderer items when feature is enabled
 #[cfg(feature = "html")]
-pub use renderer::{render_html, render_html_with_options, HtmlRenderer, Renderer};
+pub use renderer::{render_html, render_html_with_options, HtmlRenderer};
 
 #[cfg(feature = "html")]
-pub use renderer::html::{HtmlOptions, CssClasses, CustomAttributes};
+pub use renderer::html::{HtmlOptions, CssClasses, CustomAttributes, UrlRewriter};
 
-/// Parse markdown text into an AST
+#[cfg(feature = "html")]
+pub use renderer::sanitize::{HtmlAllowlist, UrlPolicy};
+
+#[cfg(feature = "terminal")]
+pub use renderer::ansi::{AnsiOptions, AnsiRenderer};
+
+/// Parse markdown text into an AST whose nodes carry their source spans
 pub fn parse_markdown(input: &str) -> Result<AstNode, MarkdownError> {
-    let tokens = tokenize(input).map_err(ParseError::LexerError)?;
-    let ast = parse(tokens)?;
+    let tokens = tokenize_with_spans(input).map_err(ParseError::LexerError)?;
+    let ast = Parser::with_spans(tokens).parse()?;
     Ok(ast)
 }
 
+/// Parse markdown text into a best-effort AST, along with a warning for each piece of malformed markup
+/// that was kept as literal text, e.g. an unclosed `**` or a link without a URL
+pub fn parse_markdown_with_diagnostics(input: &str) -> Result<(AstNode, Vec<Diagnostic>), MarkdownError> {
+    let tokens = tokenize_with_spans(input).map_err(ParseError::LexerError)?;
+    Ok(Parser::with_spans(tokens).parse_with_diagnostics())
+}
+
+/// Every `[[movie:603]]` style reference in the markdown, in document order. References inside code
+/// are skipped, and markdown that fails to tokenize has none.
+pub fn media_refs(markdown: &str) -> Vec<MediaRef> {
+    Lexer::new(markdown)
+        .filter_map(|token| match token {
+            Ok((Token::MediaRef(media_ref), _)) => Some(Ok(media_ref)),
+            Ok(_) => None,
+            Err(e) => Some(Err(e)),
+        })
+        .collect::<Result<_, _>>()
+        .unwrap_or_default()
+}
+
 /// Convert markdown text directly to HTML (only available with "html" feature)
 #[cfg(feature = "html")]
 pub fn markdown_to_html(input: &str) -> Result<String, MarkdownError> {
@@ -53,9 +91,9 @@ mod tests {
     fn test_parse_markdown() {
         let ast = parse_markdown("# Hello **World**").unwrap();
         
-        if let AstNode::Document { children } = ast {
+        if let AstNode::Document { children, .. } = ast {
             assert_eq!(children.len(), 1);
-            if let AstNode::Heading { level, content } = &children[0] {
+            if let AstNode::Heading { level, content, .. } = &children[0] {
                 assert_eq!(*level, 1);
                 // The content should be: "Hello ", Bold("World")
                 assert_eq!(content.len(), 2);
@@ -69,47 +107,47 @@ mod tests {
 
     #[test]
     fn test_parse_heading() {
-        let ast = parse_markdown("## Second Level").unwrap();
+        let ast = parse_markdown("## Second Level").unwrap().without_spans();
         
-        if let AstNode::Document { children } = ast {
+        if let AstNode::Document { children, .. } = ast {
             assert_eq!(children.len(), 1);
-            if let AstNode::Heading { level, content } = &children[0] {
+            if let AstNode::Heading { level, content, .. } = &children[0] {
                 assert_eq!(*level, 2);
                 assert_eq!(content.len(), 1);
-                assert_eq!(content[0], AstNode::Text("Second Level".to_string()));
+                assert_eq!(content[0], AstNode::Text("Second Level".to_string(), Span::default()));
             } else {
                 panic!("Expected heading node");
             }
         }
+    }
 
-        #[cfg(feature = "html")]
-        #[test]
-        fn test_html_rendering() {
-            let ast = parse_markdown("# Hello **World**").unwrap();
-            let html = render_html(&ast).unwrap();
-        
-            assert!(html.contains("<h1>Hello <strong>World</strong></h1>"));
-        }
+    #[cfg(feature = "html")]
+    #[test]
+    fn test_html_rendering() {
+        let ast = parse_markdown("# Hello **World**").unwrap();
+        let html = render_html(&ast).unwrap();
+    
+        assert!(html.contains("<h1>Hello <strong>World</strong></h1>"));
+    }
 
-        #[cfg(feature = "html")]
-        #[test]
-        fn test_markdown_to_html() {
-            let html = markdown_to_html("## Test\n\nParagraph with *italic* text.").unwrap();
-        
-            assert!(html.contains("<h2>Test</h2>"));
-            assert!(html.contains("<p>Paragraph with <em>italic</em> text.</p>"));
-        }
+    #[cfg(feature = "html")]
+    #[test]
+    fn test_markdown_to_html() {
+        let html = markdown_to_html("## Test\n\nParagraph with *italic* text.").unwrap();
+    
+        assert!(html.contains("<h2>Test</h2>"));
+        assert!(html.contains("<p>Paragraph with <em>italic</em> text.</p>"));
     }
 
     #[test]
     fn test_parse_paragraph() {
-        let ast = parse_markdown("This is a simple paragraph.").unwrap();
+        let ast = parse_markdown("This is a simple paragraph.").unwrap().without_spans();
         
-        if let AstNode::Document { children } = ast {
+        if let AstNode::Document { children, .. } = ast {
             assert_eq!(children.len(), 1);
-            if let AstNode::Paragraph { content } = &children[0] {
+            if let AstNode::Paragraph { content, .. } = &children[0] {
                 assert_eq!(content.len(), 1);
-                assert_eq!(content[0], AstNode::Text("This is a simple paragraph.".to_string()));
+                assert_eq!(content[0], AstNode::Text("This is a simple paragraph.".to_string(), Span::default()));
             } else {
                 panic!("Expected paragraph node");
             }
@@ -118,25 +156,25 @@ mod tests {
 
     #[test]
     fn test_parse_emphasis() {
-        let ast = parse_markdown("*italic* and **bold** text").unwrap();
+        let ast = parse_markdown("*italic* and **bold** text").unwrap().without_spans();
         
-        if let AstNode::Document { children } = ast {
+        if let AstNode::Document { children, .. } = ast {
             assert_eq!(children.len(), 1);
-            if let AstNode::Paragraph { content } = &children[0] {
+            if let AstNode::Paragraph { content, .. } = &children[0] {
                 assert_eq!(content.len(), 4); // "italic", " and ", "bold", " text"
                 
                 // Check italic
-                if let AstNode::Italic(italic_content) = &content[0] {
+                if let AstNode::Italic(italic_content, _) = &content[0] {
                     assert_eq!(italic_content.len(), 1);
-                    assert_eq!(italic_content[0], AstNode::Text("italic".to_string()));
+                    assert_eq!(italic_content[0], AstNode::Text("italic".to_string(), Span::default()));
                 } else {
                     panic!("Expected italic node");
                 }
                 
                 // Check bold
-                if let AstNode::Bold(bold_content) = &content[2] {
+                if let AstNode::Bold(bold_content, _) = &content[2] {
                     assert_eq!(bold_content.len(), 1);
-                    assert_eq!(bold_content[0], AstNode::Text("bold".to_string()));
+                    assert_eq!(bold_content[0], AstNode::Text("bold".to_string(), Span::default()));
                 } else {
                     panic!("Expected bold node");
                 }
@@ -146,24 +184,24 @@ mod tests {
 
     #[test]
     fn test_parse_unordered_list() {
-        let ast = parse_markdown("- First item\n- Second item").unwrap();
+        let ast = parse_markdown("- First item\n- Second item").unwrap().without_spans();
         
-        if let AstNode::Document { children } = ast {
+        if let AstNode::Document { children, .. } = ast {
             assert_eq!(children.len(), 1);
-            if let AstNode::List { ordered, items } = &children[0] {
-                assert_eq!(*ordered, false);
+            if let AstNode::List { ordered, items, .. } = &children[0] {
+                assert!(!*ordered);
                 assert_eq!(items.len(), 2);
                 
                 // Check first item
-                if let AstNode::ListItem { content } = &items[0] {
+                if let AstNode::ListItem { content, .. } = &items[0] {
                     assert_eq!(content.len(), 1);
-                    assert_eq!(content[0], AstNode::Text("First item".to_string()));
+                    assert_eq!(content[0], AstNode::Text("First item".to_string(), Span::default()));
                 }
                 
                 // Check second item
-                if let AstNode::ListItem { content } = &items[1] {
+                if let AstNode::ListItem { content, .. } = &items[1] {
                     assert_eq!(content.len(), 1);
-                    assert_eq!(content[0], AstNode::Text("Second item".to_string()));
+                    assert_eq!(content[0], AstNode::Text("Second item".to_string(), Span::default()));
                 }
             } else {
                 panic!("Expected unordered list node");
@@ -173,24 +211,24 @@ mod tests {
 
     #[test]
     fn test_parse_ordered_list() {
-        let ast = parse_markdown("1. First item\n2. Second item").unwrap();
+        let ast = parse_markdown("1. First item\n2. Second item").unwrap().without_spans();
         
-        if let AstNode::Document { children } = ast {
+        if let AstNode::Document { children, .. } = ast {
             assert_eq!(children.len(), 1);
-            if let AstNode::List { ordered, items } = &children[0] {
-                assert_eq!(*ordered, true);
+            if let AstNode::List { ordered, items, .. } = &children[0] {
+                assert!(*ordered);
                 assert_eq!(items.len(), 2);
                 
                 // Check first item
-                if let AstNode::ListItem { content } = &items[0] {
+                if let AstNode::ListItem { content, .. } = &items[0] {
                     assert_eq!(content.len(), 1);
-                    assert_eq!(content[0], AstNode::Text("First item".to_string()));
+                    assert_eq!(content[0], AstNode::Text("First item".to_string(), Span::default()));
                 }
                 
                 // Check second item
-                if let AstNode::ListItem { content } = &items[1] {
+                if let AstNode::ListItem { content, .. } = &items[1] {
                     assert_eq!(content.len(), 1);
-                    assert_eq!(content[0], AstNode::Text("Second item".to_string()));
+                    assert_eq!(content[0], AstNode::Text("Second item".to_string(), Span::default()));
                 }
             } else {
                 panic!("Expected ordered list node");
@@ -200,15 +238,15 @@ mod tests {
 
     #[test]
     fn test_parse_link() {
-        let ast = parse_markdown("[OpenAI](https://openai.com)").unwrap();
+        let ast = parse_markdown("[OpenAI](https://openai.com)").unwrap().without_spans();
         
-        if let AstNode::Document { children } = ast {
+        if let AstNode::Document { children, .. } = ast {
             assert_eq!(children.len(), 1);
-            if let AstNode::Paragraph { content } = &children[0] {
+            if let AstNode::Paragraph { content, .. } = &children[0] {
                 assert_eq!(content.len(), 1);
-                if let AstNode::Link { text, url } = &content[0] {
+                if let AstNode::Link { text, url, .. } = &content[0] {
                     assert_eq!(text.len(), 1);
-                    assert_eq!(text[0], AstNode::Text("OpenAI".to_string()));
+                    assert_eq!(text[0], AstNode::Text("OpenAI".to_string(), Span::default()));
                     assert_eq!(url, "https://openai.com");
                 } else {
                     panic!("Expected link node");
@@ -222,7 +260,7 @@ mod tests {
         let markdown = "# Title\n\nThis is a **bold** paragraph with *italic* text.\n\n- Item 1\n- Item 2";
         let ast = parse_markdown(markdown).unwrap();
         
-        if let AstNode::Document { children } = ast {
+        if let AstNode::Document { children, .. } = ast {
             assert_eq!(children.len(), 3); // heading, paragraph, list
             
             // Check heading
@@ -233,15 +271,15 @@ mod tests {
             }
             
             // Check paragraph
-            if let AstNode::Paragraph { content } = &children[1] {
+            if let AstNode::Paragraph { content, .. } = &children[1] {
                 assert!(content.len() > 1); // Should have mixed content
             } else {
                 panic!("Expected paragraph");
             }
             
             // Check list
-            if let AstNode::List { ordered, items } = &children[2] {
-                assert_eq!(*ordered, false);
+            if let AstNode::List { ordered, items, .. } = &children[2] {
+                assert!(!*ordered);
                 assert_eq!(items.len(), 2);
             } else {
                 panic!("Expected list");
@@ -253,7 +291,7 @@ mod tests {
     fn test_empty_document() {
         let ast = parse_markdown("").unwrap();
         
-        if let AstNode::Document { children } = ast {
+        if let AstNode::Document { children, .. } = ast {
             assert_eq!(children.len(), 0);
         } else {
             panic!("Expected empty document");
@@ -264,7 +302,7 @@ mod tests {
     fn test_multiple_newlines() {
         let ast = parse_markdown("# Title\n\n\nParagraph").unwrap();
         
-        if let AstNode::Document { children } = ast {
+        if let AstNode::Document { children, .. } = ast {
             assert_eq!(children.len(), 2); // Should skip empty lines
             
             if let AstNode::Heading { .. } = &children[0] {
@@ -280,4 +318,15 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_media_refs() {
+        let notes = "Like [[movie:603]], see [[tv:1399 s2e3]].\n\n`[[movie:1]]` and [[movie:abc]] [[book:1]]";
+
+        assert_eq!(media_refs(notes), vec![
+            MediaRef { media_type: MediaType::Movie, id: 603, episode: None },
+            MediaRef { media_type: MediaType::Tv, id: 1399, episode: Some(Episode { season: 2, episode: 3 }) },
+        ]);
+        assert_eq!(media_refs(notes)[1].to_string(), "tv:1399 S02E03");
+    }
 }
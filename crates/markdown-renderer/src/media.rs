@@ -0,0 +1,96 @@
+//! References to TMDB titles in notes, read by the lexer and kept as they are in the syntax tree
+
+use std::fmt;
+use std::str::FromStr;
+
+/// A reference to a TMDB title from within notes, written `[[movie:603]]`, `[[tv:1399]]`
+/// or `[[tv:1399 S02E03]]` for a single episode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MediaRef {
+    pub media_type: MediaType,
+    pub id: u32,
+    pub episode: Option<Episode>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "lowercase"))]
+pub enum MediaType {
+    Movie,
+    Tv,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Episode {
+    pub season: u32,
+    pub episode: u32,
+}
+
+impl MediaType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MediaType::Movie => "movie",
+            MediaType::Tv => "tv",
+        }
+    }
+}
+
+/// Parses the part between the brackets, e.g. `tv:1399 S02E03`
+impl FromStr for MediaRef {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (media_type, rest) = match s.split_once(':').ok_or(())? {
+            ("movie", rest) => (MediaType::Movie, rest),
+            ("tv", rest) => (MediaType::Tv, rest),
+            _ => return Err(()),
+        };
+        let (id, episode) = match rest.split_once(' ') {
+            Some((id, episode)) if media_type == MediaType::Tv => (id, Some(episode.parse()?)),
+            Some(_) => return Err(()),
+            None => (rest, None),
+        };
+        if id.is_empty() || !id.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(());
+        }
+        let id = id.parse().map_err(|_| ())?;
+        if id == 0 {
+            return Err(());
+        }
+        Ok(MediaRef { media_type, id, episode })
+    }
+}
+
+/// Parses `S02E03`, case-insensitively and with or without leading zeros
+impl FromStr for Episode {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.to_ascii_uppercase();
+        let (season, episode) = s.strip_prefix('S').and_then(|rest| rest.split_once('E')).ok_or(())?;
+        let number = |digits: &str| -> Result<u32, ()> {
+            if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(());
+            }
+            digits.parse().map_err(|_| ())
+        };
+        Ok(Episode { season: number(season)?, episode: number(episode)? })
+    }
+}
+
+impl fmt::Display for MediaRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.media_type.as_str(), self.id)?;
+        if let Some(episode) = self.episode {
+            write!(f, " {}", episode)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Episode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "S{:02}E{:02}", self.season, self.episode)
+    }
+}
@@ -1,34 +1,177 @@
+pub use crate::media::{Episode, MediaRef, MediaType};
+use crate::span::Span;
+use crate::visit::{walk_node_mut, VisitorMut};
+
+/// A node of the syntax tree. Every node carries the `Span` of the source it was parsed from, which
+/// equality compares along with the rest; see `eq_ignoring_spans` to compare content alone.
+///
+/// With the `serde` feature, nodes serialize as objects tagged with their `type`, see `ast_serde`.
 #[derive(Debug, Clone, PartialEq)]
 pub enum AstNode {
-    Document { children: Vec<AstNode> },
+    Document { children: Vec<AstNode>, span: Span },
 
     // Block elements
-    Heading { level: u8, content: Vec<AstNode> },
-    Paragraph { content: Vec<AstNode> },
-    List { ordered: bool, items: Vec<AstNode> },
-    ListItem { content: Vec<AstNode> },
+    Heading { level: u8, content: Vec<AstNode>, span: Span },
+    Paragraph { content: Vec<AstNode>, span: Span },
+    /// `start` is the number of the first item of an ordered list, and 1 for bullet lists
+    List { ordered: bool, start: u32, items: Vec<AstNode>, span: Span },
+    /// Items of tight lists hold their text inline, items of loose lists wrap it in paragraphs;
+    /// either may be followed by nested blocks such as sub-lists
+    ListItem { content: Vec<AstNode>, span: Span },
+    /// A `- [ ]` or `- [x]` list item, holding its content like `ListItem`
+    TaskItem { checked: bool, content: Vec<AstNode>, span: Span },
+    Table { alignments: Vec<Alignment>, header: Vec<AstNode>, rows: Vec<Vec<AstNode>>, span: Span },
+    TableCell { content: Vec<AstNode>, span: Span },
+    CodeBlock { info: Option<String>, content: String, span: Span },
+    BlockQuote { children: Vec<AstNode>, span: Span },
+    ThematicBreak(Span),
+    /// The `[^label]: text` definition of a footnote, rendered with the other footnotes at the end of
+    /// the document rather than where it was written
+    FootnoteDefinition { label: String, children: Vec<AstNode>, span: Span },
 
     // Inline elements
-    Text(String),
-    Bold(Vec<AstNode>),
-    Italic(Vec<AstNode>),
-    Link { text: Vec<AstNode>, url: String },
+    Text(String, Span),
+    Bold(Vec<AstNode>, Span),
+    Italic(Vec<AstNode>, Span),
+    Strikethrough(Vec<AstNode>, Span),
+    Highlight(Vec<AstNode>, Span),
+    /// Hidden until the reader reveals it, for plot details in reviews
+    Spoiler(Vec<AstNode>, Span),
+    /// An inline `[text](url "title")` link, or a `[text][label]` one whose url and title come from the
+    /// `[label]: url "title"` definition
+    Link { text: Vec<AstNode>, url: String, title: Option<String>, span: Span },
+    Image { alt: String, url: String, title: Option<String>, span: Span },
+    /// A `[[movie:603]]` style reference to another title
+    MediaRef(MediaRef, Span),
+    Code(String, Span),
+    /// A `[^label]` reference to the footnote of that label
+    FootnoteReference { label: String, span: Span },
+    /// A raw HTML tag such as `<kbd>` or `</kbd>`, as written. Renderers decide what to let through, see
+    /// `HtmlOptions::html_allowlist`; text never holds markup of its own.
+    Html(String, Span),
+
+    LineBreak(Span)
+}
 
-    LineBreak
+/// Column alignment taken from the colons in a table's delimiter row
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "lowercase"))]
+pub enum Alignment {
+    None,
+    Left,
+    Center,
+    Right,
 }
 
 impl AstNode {
     pub fn is_inline(&self) -> bool {
         matches!(self,
-            AstNode::Text(_) |
-            AstNode::Bold(_) |
-            AstNode::Italic(_) |
+            AstNode::Text(..) |
+            AstNode::Bold(..) |
+            AstNode::Italic(..) |
+            AstNode::Strikethrough(..) |
+            AstNode::Highlight(..) |
+            AstNode::Spoiler(..) |
             AstNode::Link { .. } |
-            AstNode::LineBreak
+            AstNode::Image { .. } |
+            AstNode::MediaRef(..) |
+            AstNode::Code(..) |
+            AstNode::FootnoteReference { .. } |
+            AstNode::Html(..) |
+            AstNode::LineBreak(_)
         )
     }
 
+    /// Where in the source the node was parsed from
+    pub fn span(&self) -> Span {
+        match self {
+            AstNode::Document { span, .. }
+            | AstNode::Heading { span, .. }
+            | AstNode::Paragraph { span, .. }
+            | AstNode::List { span, .. }
+            | AstNode::ListItem { span, .. }
+            | AstNode::TaskItem { span, .. }
+            | AstNode::Table { span, .. }
+            | AstNode::TableCell { span, .. }
+            | AstNode::CodeBlock { span, .. }
+            | AstNode::BlockQuote { span, .. }
+            | AstNode::FootnoteDefinition { span, .. }
+            | AstNode::Link { span, .. }
+            | AstNode::FootnoteReference { span, .. }
+            | AstNode::Image { span, .. }
+            | AstNode::ThematicBreak(span)
+            | AstNode::Text(_, span)
+            | AstNode::Bold(_, span)
+            | AstNode::Italic(_, span)
+            | AstNode::Strikethrough(_, span)
+            | AstNode::Highlight(_, span)
+            | AstNode::Spoiler(_, span)
+            | AstNode::MediaRef(_, span)
+            | AstNode::Code(_, span)
+            | AstNode::Html(_, span)
+            | AstNode::LineBreak(span) => *span,
+        }
+    }
+
+    /// The node's span, for moving a node to another place in the source
+    pub fn span_mut(&mut self) -> &mut Span {
+        match self {
+            AstNode::Document { span, .. }
+            | AstNode::Heading { span, .. }
+            | AstNode::Paragraph { span, .. }
+            | AstNode::List { span, .. }
+            | AstNode::ListItem { span, .. }
+            | AstNode::TaskItem { span, .. }
+            | AstNode::Table { span, .. }
+            | AstNode::TableCell { span, .. }
+            | AstNode::CodeBlock { span, .. }
+            | AstNode::BlockQuote { span, .. }
+            | AstNode::FootnoteDefinition { span, .. }
+            | AstNode::Link { span, .. }
+            | AstNode::FootnoteReference { span, .. }
+            | AstNode::Image { span, .. }
+            | AstNode::ThematicBreak(span)
+            | AstNode::Text(_, span)
+            | AstNode::Bold(_, span)
+            | AstNode::Italic(_, span)
+            | AstNode::Strikethrough(_, span)
+            | AstNode::Highlight(_, span)
+            | AstNode::Spoiler(_, span)
+            | AstNode::MediaRef(_, span)
+            | AstNode::Code(_, span)
+            | AstNode::Html(_, span)
+            | AstNode::LineBreak(span) => span,
+        }
+    }
+
     pub fn is_block(&self) -> bool {
         !self.is_inline()
     }
+
+    /// Reset the spans of the node and all its descendants to `Span::default()`, e.g. to compare a
+    /// parsed tree with one built by hand
+    pub fn strip_spans(&mut self) {
+        struct StripSpans;
+
+        impl VisitorMut for StripSpans {
+            fn visit_node_mut(&mut self, node: &mut AstNode) {
+                *node.span_mut() = Span::default();
+                walk_node_mut(self, node);
+            }
+        }
+
+        StripSpans.visit_node_mut(self);
+    }
+
+    /// The node with all its spans reset, see `strip_spans`
+    pub fn without_spans(&self) -> AstNode {
+        let mut node = self.clone();
+        node.strip_spans();
+        node
+    }
+
+    /// Whether the two trees have the same content, wherever in the source they were parsed from
+    pub fn eq_ignoring_spans(&self, other: &AstNode) -> bool {
+        self.without_spans() == other.without_spans()
+    }
 }
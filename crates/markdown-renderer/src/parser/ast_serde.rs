@@ -0,0 +1,212 @@
+//! Serialization of syntax trees as tagged objects, e.g. `{"type": "text", "text": "Heat", "span": …}`.
+//!
+//! Every node has a `type` naming its variant in snake case next to its fields. Variants with struct
+//! fields keep their names; tuple variants name theirs `text`, `code` and `html` for the text of `Text`,
+//! `Code` and `Html`, `content` for the children of inline formatting and `media_ref` for references.
+//! Spans are optional when deserializing, so trees can be written by hand.
+
+use std::borrow::Cow;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::ast::{Alignment, AstNode, MediaRef};
+use crate::span::Span;
+
+/// The serialized form of a node, borrowing from the node when serializing
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Node<'a> {
+    Document { children: Cow<'a, [AstNode]>, #[serde(default)] span: Span },
+    Heading { level: u8, content: Cow<'a, [AstNode]>, #[serde(default)] span: Span },
+    Paragraph { content: Cow<'a, [AstNode]>, #[serde(default)] span: Span },
+    List { ordered: bool, start: u32, items: Cow<'a, [AstNode]>, #[serde(default)] span: Span },
+    ListItem { content: Cow<'a, [AstNode]>, #[serde(default)] span: Span },
+    TaskItem { checked: bool, content: Cow<'a, [AstNode]>, #[serde(default)] span: Span },
+    Table {
+        alignments: Cow<'a, [Alignment]>,
+        header: Cow<'a, [AstNode]>,
+        rows: Cow<'a, [Vec<AstNode>]>,
+        #[serde(default)]
+        span: Span,
+    },
+    TableCell { content: Cow<'a, [AstNode]>, #[serde(default)] span: Span },
+    CodeBlock { info: Option<Cow<'a, str>>, content: Cow<'a, str>, #[serde(default)] span: Span },
+    BlockQuote { children: Cow<'a, [AstNode]>, #[serde(default)] span: Span },
+    ThematicBreak { #[serde(default)] span: Span },
+    FootnoteDefinition { label: Cow<'a, str>, children: Cow<'a, [AstNode]>, #[serde(default)] span: Span },
+    Text { text: Cow<'a, str>, #[serde(default)] span: Span },
+    Bold { content: Cow<'a, [AstNode]>, #[serde(default)] span: Span },
+    Italic { content: Cow<'a, [AstNode]>, #[serde(default)] span: Span },
+    Strikethrough { content: Cow<'a, [AstNode]>, #[serde(default)] span: Span },
+    Highlight { content: Cow<'a, [AstNode]>, #[serde(default)] span: Span },
+    Spoiler { content: Cow<'a, [AstNode]>, #[serde(default)] span: Span },
+    Link { text: Cow<'a, [AstNode]>, url: Cow<'a, str>, title: Option<Cow<'a, str>>, #[serde(default)] span: Span },
+    Image { alt: Cow<'a, str>, url: Cow<'a, str>, title: Option<Cow<'a, str>>, #[serde(default)] span: Span },
+    MediaRef { media_ref: MediaRef, #[serde(default)] span: Span },
+    Code { code: Cow<'a, str>, #[serde(default)] span: Span },
+    FootnoteReference { label: Cow<'a, str>, #[serde(default)] span: Span },
+    Html { html: Cow<'a, str>, #[serde(default)] span: Span },
+    LineBreak { #[serde(default)] span: Span },
+}
+
+impl<'a> From<&'a AstNode> for Node<'a> {
+    fn from(node: &'a AstNode) -> Self {
+        let str = |text: &'a String| Cow::Borrowed(text.as_str());
+        let nodes = |nodes: &'a Vec<AstNode>| Cow::Borrowed(nodes.as_slice());
+        match node {
+            AstNode::Document { children, span } => Node::Document { children: nodes(children), span: *span },
+            AstNode::Heading { level, content, span } => Node::Heading { level: *level, content: nodes(content), span: *span },
+            AstNode::Paragraph { content, span } => Node::Paragraph { content: nodes(content), span: *span },
+            AstNode::List { ordered, start, items, span } => {
+                Node::List { ordered: *ordered, start: *start, items: nodes(items), span: *span }
+            },
+            AstNode::ListItem { content, span } => Node::ListItem { content: nodes(content), span: *span },
+            AstNode::TaskItem { checked, content, span } => {
+                Node::TaskItem { checked: *checked, content: nodes(content), span: *span }
+            },
+            AstNode::Table { alignments, header, rows, span } => Node::Table {
+                alignments: Cow::Borrowed(alignments),
+                header: nodes(header),
+                rows: Cow::Borrowed(rows),
+                span: *span,
+            },
+            AstNode::TableCell { content, span } => Node::TableCell { content: nodes(content), span: *span },
+            AstNode::CodeBlock { info, content, span } => {
+                Node::CodeBlock { info: info.as_ref().map(str), content: str(content), span: *span }
+            },
+            AstNode::BlockQuote { children, span } => Node::BlockQuote { children: nodes(children), span: *span },
+            AstNode::ThematicBreak(span) => Node::ThematicBreak { span: *span },
+            AstNode::FootnoteDefinition { label, children, span } => {
+                Node::FootnoteDefinition { label: str(label), children: nodes(children), span: *span }
+            },
+            AstNode::Text(text, span) => Node::Text { text: str(text), span: *span },
+            AstNode::Bold(content, span) => Node::Bold { content: nodes(content), span: *span },
+            AstNode::Italic(content, span) => Node::Italic { content: nodes(content), span: *span },
+            AstNode::Strikethrough(content, span) => Node::Strikethrough { content: nodes(content), span: *span },
+            AstNode::Highlight(content, span) => Node::Highlight { content: nodes(content), span: *span },
+            AstNode::Spoiler(content, span) => Node::Spoiler { content: nodes(content), span: *span },
+            AstNode::Link { text, url, title, span } => {
+                Node::Link { text: nodes(text), url: str(url), title: title.as_ref().map(str), span: *span }
+            },
+            AstNode::Image { alt, url, title, span } => {
+                Node::Image { alt: str(alt), url: str(url), title: title.as_ref().map(str), span: *span }
+            },
+            AstNode::MediaRef(media_ref, span) => Node::MediaRef { media_ref: *media_ref, span: *span },
+            AstNode::Code(code, span) => Node::Code { code: str(code), span: *span },
+            AstNode::FootnoteReference { label, span } => Node::FootnoteReference { label: str(label), span: *span },
+            AstNode::Html(html, span) => Node::Html { html: str(html), span: *span },
+            AstNode::LineBreak(span) => Node::LineBreak { span: *span },
+        }
+    }
+}
+
+impl From<Node<'_>> for AstNode {
+    fn from(node: Node<'_>) -> Self {
+        match node {
+            Node::Document { children, span } => AstNode::Document { children: children.into_owned(), span },
+            Node::Heading { level, content, span } => AstNode::Heading { level, content: content.into_owned(), span },
+            Node::Paragraph { content, span } => AstNode::Paragraph { content: content.into_owned(), span },
+            Node::List { ordered, start, items, span } => AstNode::List { ordered, start, items: items.into_owned(), span },
+            Node::ListItem { content, span } => AstNode::ListItem { content: content.into_owned(), span },
+            Node::TaskItem { checked, content, span } => AstNode::TaskItem { checked, content: content.into_owned(), span },
+            Node::Table { alignments, header, rows, span } => AstNode::Table {
+                alignments: alignments.into_owned(),
+                header: header.into_owned(),
+                rows: rows.into_owned(),
+                span,
+            },
+            Node::TableCell { content, span } => AstNode::TableCell { content: content.into_owned(), span },
+            Node::CodeBlock { info, content, span } => {
+                AstNode::CodeBlock { info: info.map(Cow::into_owned), content: content.into_owned(), span }
+            },
+            Node::BlockQuote { children, span } => AstNode::BlockQuote { children: children.into_owned(), span },
+            Node::ThematicBreak { span } => AstNode::ThematicBreak(span),
+            Node::FootnoteDefinition { label, children, span } => {
+                AstNode::FootnoteDefinition { label: label.into_owned(), children: children.into_owned(), span }
+            },
+            Node::Text { text, span } => AstNode::Text(text.into_owned(), span),
+            Node::Bold { content, span } => AstNode::Bold(content.into_owned(), span),
+            Node::Italic { content, span } => AstNode::Italic(content.into_owned(), span),
+            Node::Strikethrough { content, span } => AstNode::Strikethrough(content.into_owned(), span),
+            Node::Highlight { content, span } => AstNode::Highlight(content.into_owned(), span),
+            Node::Spoiler { content, span } => AstNode::Spoiler(content.into_owned(), span),
+            Node::Link { text, url, title, span } => {
+                AstNode::Link { text: text.into_owned(), url: url.into_owned(), title: title.map(Cow::into_owned), span }
+            },
+            Node::Image { alt, url, title, span } => {
+                AstNode::Image { alt: alt.into_owned(), url: url.into_owned(), title: title.map(Cow::into_owned), span }
+            },
+            Node::MediaRef { media_ref, span } => AstNode::MediaRef(media_ref, span),
+            Node::Code { code, span } => AstNode::Code(code.into_owned(), span),
+            Node::FootnoteReference { label, span } => AstNode::FootnoteReference { label: label.into_owned(), span },
+            Node::Html { html, span } => AstNode::Html(html.into_owned(), span),
+            Node::LineBreak { span } => AstNode::LineBreak(span),
+        }
+    }
+}
+
+impl Serialize for AstNode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Node::from(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for AstNode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Node::deserialize(deserializer).map(AstNode::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::parse_markdown;
+
+    #[test]
+    fn test_tagged_shape() {
+        let ast = parse_markdown("# **Heat**\n\nSee [[movie:949]]").unwrap();
+
+        let value = serde_json::to_value(&ast).unwrap();
+        assert_eq!(value["type"], "document");
+        assert_eq!(value["span"]["end"], json!({ "offset": 29, "line": 3, "column": 18 }));
+        let heading = &value["children"][0];
+        assert_eq!(heading["type"], "heading");
+        assert_eq!(heading["level"], 1);
+        assert_eq!(heading["content"][0]["type"], "bold");
+        assert_eq!(heading["content"][0]["content"][0]["text"], "Heat");
+        assert_eq!(
+            value["children"][1]["content"][1]["media_ref"],
+            json!({ "media_type": "movie", "id": 949, "episode": null })
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let markdown = "| a | b |\n|:-|-:|\n| `x` | ~~y~~ |\n\n- [x] [Heat](/949 \"T\")\n\n```rust\nlet x;\n```\n\n> ![p](/p.png)[^1]\\\n> ==z== ||w||\n\n[^1]: *n*\n\n---";
+        let ast = parse_markdown(markdown).unwrap();
+
+        let json = serde_json::to_string(&ast).unwrap();
+        let parsed: AstNode = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, ast);
+        assert_eq!(parsed.span().end, ast.span().end);
+    }
+
+    #[test]
+    fn test_spans_are_optional() {
+        let node: AstNode = serde_json::from_value(json!({
+            "type": "paragraph",
+            "content": [{ "type": "text", "text": "Hand written" }, { "type": "line_break" }],
+        }))
+        .unwrap();
+
+        assert_eq!(
+            node,
+            AstNode::Paragraph {
+                content: vec![AstNode::Text("Hand written".to_string(), Span::default()), AstNode::LineBreak(Span::default())],
+                span: Span::default(),
+            }
+        );
+    }
+}
@@ -1,7 +1,10 @@
 pub mod ast;
+#[cfg(feature = "serde")]
+mod ast_serde;
+#[allow(clippy::module_inception)]
 pub mod parser;
 
-pub use ast::AstNode;
+pub use ast::{Alignment, AstNode, Episode, MediaRef, MediaType};
 pub use parser::Parser;
 
 use crate::error::ParseError;
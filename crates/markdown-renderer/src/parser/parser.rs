@@ -1,226 +1,1187 @@
-use crate::{parser::ast::AstNode, Token};
-use crate::error::ParseError;
+use std::collections::{HashMap, HashSet};
+
+use crate::{parser::ast::{Alignment, AstNode}, Token};
+use crate::error::{Diagnostic, ParseError};
+use crate::span::{Position, Span};
+
+/// The marker that starts a list item, e.g. `  - ` or `3. `
+#[derive(Debug, Clone, Copy)]
+struct ListMarker {
+    indent: usize,
+    ordered: bool,
+    number: u32,
+    // Bullet character, or the `.`/`)` after an ordered item's number; lists only continue with the same one
+    delimiter: char,
+    // Tokens making up the marker, including the indentation
+    len: usize,
+}
+
+impl ListMarker {
+    fn continues(&self, first: &ListMarker) -> bool {
+        self.ordered == first.ordered
+            && self.delimiter == first.delimiter
+            && self.indent + 1 >= first.indent
+            && self.indent < first.indent + 2
+    }
+}
+
+/// A line defining a reference, found where a block could start
+enum Definition {
+    /// `[label]: url "title"`, resolving the links that use the label
+    Link { label: String, url: String, title: Option<String> },
+    /// `[^label]: text`, whose text follows the `:`
+    Footnote { label: String },
+}
 
 pub struct Parser {
     tokens: Vec<Token>,
+    // Source span of each token, all empty when the tokens were built without them
+    spans: Vec<Span>,
     current: usize,
+    diagnostics: Vec<Diagnostic>,
+    // Url and title of each link reference definition, by normalized label; the first definition wins
+    links: HashMap<String, (String, Option<String>)>,
+    // Normalized labels of the footnotes defined so far
+    footnotes: HashSet<String>,
+    // First markers of the lists being parsed, innermost last; their next items end any paragraph
+    open_lists: Vec<ListMarker>,
 }
 
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Self {
-        Self { tokens, current: 0 }
+        let spans = vec![Span::default(); tokens.len()];
+        Self { tokens, spans, current: 0, diagnostics: Vec::new(), links: HashMap::new(), footnotes: HashSet::new(), open_lists: Vec::new() }
+    }
+
+    /// A parser for tokens paired with their spans, as returned by `Lexer::tokenize_with_spans`, so
+    /// nodes and errors point into the source
+    pub fn with_spans(tokens: Vec<(Token, Span)>) -> Self {
+        let (tokens, spans) = tokens.into_iter().unzip();
+        Self { tokens, spans, current: 0, diagnostics: Vec::new(), links: HashMap::new(), footnotes: HashSet::new(), open_lists: Vec::new() }
     }
 
+    /// Parse the tokens into a document. Malformed markup never fails the parse but is read as literal
+    /// text instead; use `parse_with_diagnostics` to find out where that happened.
     pub fn parse(&mut self) -> Result<AstNode, ParseError> {
+        Ok(self.parse_with_diagnostics().0)
+    }
+
+    /// Parse the tokens into a best-effort document, along with a warning for each piece of malformed
+    /// markup that was read as literal text, e.g. the `**` of a half-typed `**bold`
+    pub fn parse_with_diagnostics(&mut self) -> (AstNode, Vec<Diagnostic>) {
+        let defined = self.links.len() + self.footnotes.len();
+        let mut document = self.parse_document();
+
+        // References may come before their definitions, so when the first pass left some unresolved
+        // and found new definitions, parse again now that all of them are known. A link whose URL is
+        // malformed may be a reference too, e.g. `[foo](not a link)`.
+        let unresolved = self
+            .diagnostics
+            .iter()
+            .any(|diagnostic| matches!(diagnostic.error, ParseError::UndefinedReference { .. } | ParseError::ExpectedUrl));
+        if unresolved && self.links.len() + self.footnotes.len() > defined {
+            self.current = 0;
+            self.diagnostics.clear();
+            document = self.parse_document();
+        }
+
+        (document, std::mem::take(&mut self.diagnostics))
+    }
+
+    fn parse_document(&mut self) -> AstNode {
         let mut children = Vec::new();
+        let span = self.token_span(0).to(self.token_span(self.tokens.len().saturating_sub(1)));
 
         while !self.is_at_end() {
-            if let Some(node) = self.parse_block()? {
+            if let Some(node) = self.parse_block() {
                 children.push(node);
             }
         }
 
-        Ok(AstNode::Document { children: children })
+        AstNode::Document { children, span }
     }
 
-    fn parse_block(&mut self) -> Result<Option<AstNode>, ParseError> {
+    fn parse_block(&mut self) -> Option<AstNode> {
+        if let Some((definition, colon)) = self.definition(self.current) {
+            return self.parse_definition(definition, colon);
+        }
+        if let Some(alignments) = self.table_alignments(self.current) {
+            return Some(self.parse_table(alignments));
+        }
+        if let Some(marker) = self.list_marker(self.current) {
+            return Some(self.parse_list(marker));
+        }
+
         match self.current_token().cloned() {
             Some(Token::Hash(level)) => {
+                Some(self.parse_heading(level))
+            },
+            Some(Token::Indent(_)) => {
+                self.advance(); // indentation only matters for lists
+                None
+            },
+            Some(Token::CodeBlock { info, content }) => {
+                let span = self.token_span(self.current);
                 self.advance();
-                Ok(Some(self.parse_heading(level)?))
+                Some(AstNode::CodeBlock { info, content, span })
             },
-            Some(Token::Number(_)) => {
-                Ok(Some(self.parse_ordered_list()?))
+            Some(Token::Quote) => {
+                Some(self.parse_block_quote())
             },
-            Some(Token::Hyphen) => {
-                Ok(Some(self.parse_unordered_list()?))
+            Some(Token::ThematicBreak) | Some(Token::SetextUnderline('-', 3..)) => {
+                let span = self.token_span(self.current);
+                self.advance();
+                Some(AstNode::ThematicBreak(span))
             },
             Some(Token::Newline) => {
                 self.advance();
-                Ok(None) // Skip empty lines
+                None // Skip empty lines
             },
             Some(_) => {
-                Ok(Some(self.parse_paragraph()?))
+                Some(self.parse_paragraph())
+            },
+            None => None
+        }
+    }
+
+    /// Strip one `>` from each consecutive quoted line and parse what is left as a document of its own,
+    /// so a quote can hold any block, including another quote
+    fn parse_block_quote(&mut self) -> AstNode {
+        let start = self.current;
+        let mut inner = Vec::new();
+
+        loop {
+            match (self.current_token(), self.tokens.get(self.current + 1)) {
+                (Some(Token::Quote), _) => {},
+                // In a list item the quote's lines are indented like the item's text
+                (Some(Token::Indent(indent)), Some(Token::Quote)) if self.open_lists.last().is_some_and(|list| *indent >= list.indent + 2) => {
+                    self.advance();
+                },
+                _ => break,
+            }
+            self.advance();
+            while let Some(token) = self.current_token().cloned() {
+                if matches!(token, Token::Eof) {
+                    break;
+                }
+                let span = self.token_span(self.current);
+                self.advance();
+                let end_of_line = matches!(token, Token::Newline);
+                inner.push((token, span));
+                if end_of_line {
+                    break;
+                }
+            }
+        }
+        let span = self.span_from(start);
+        inner.push((Token::Eof, Span::at(span.end)));
+
+        // The quote sees the definitions made so far and adds its own
+        let mut parser = Parser::with_spans(inner);
+        parser.links = std::mem::take(&mut self.links);
+        parser.footnotes = std::mem::take(&mut self.footnotes);
+        let (document, diagnostics) = parser.parse_with_diagnostics();
+        self.links = parser.links;
+        self.footnotes = parser.footnotes;
+        self.diagnostics.extend(diagnostics);
+        match document {
+            AstNode::Document { children, .. } => AstNode::BlockQuote { children, span },
+            other => AstNode::BlockQuote { children: vec![other], span },
+        }
+    }
+
+    fn parse_heading(&mut self, level: u8) -> AstNode {
+        let start = self.current;
+        self.advance(); // consume the `#`s
+        let content = self.parse_inline_content_until_newline();
+        let mut heading = AstNode::Heading { level, content, span: self.span_from(start) };
+        self.keep_trailing_backslash(&mut heading, self.current);
+        let AstNode::Heading { content, .. } = &mut heading else { unreachable!() };
+        // Whitespace before a closing `##` the lexer dropped
+        if let Some(AstNode::Text(text, span)) = content.last_mut() {
+            Self::trim_text_end(text, span);
+            if text.is_empty() {
+                content.pop();
+            }
+        }
+        heading
+    }
+
+    /// Parse a paragraph and the lines continuing it, or a setext heading when a line of `=` or `-`
+    /// follows them
+    fn parse_paragraph(&mut self) -> AstNode {
+        let start = self.current;
+        let content = self.parse_inline_content_until_newline();
+        let paragraph = AstNode::Paragraph { content, span: self.span_from(start) };
+        self.parse_paragraph_continuation(paragraph, true)
+    }
+
+    /// Add the lines continuing `paragraph` to it, or make it a setext heading when they are followed
+    /// by a line of `=` or `-` and `setext` allows it
+    fn parse_paragraph_continuation(&mut self, paragraph: AstNode, setext: bool) -> AstNode {
+        let mut blocks = [paragraph];
+
+        while matches!(self.current_token(), Some(Token::Newline)) {
+            let next = self.current + 1;
+            // The lexer reads a lone `-` as a list marker, but an empty item can't interrupt a paragraph
+            let underline = match (self.tokens.get(next), self.tokens.get(next + 1)) {
+                (Some(&Token::SetextUnderline(marker, _)), _) => Some(marker),
+                (Some(Token::Hyphen), None | Some(Token::Newline | Token::Eof)) => Some('-'),
+                _ => None,
+            };
+            match underline {
+                // An underline below the text of a list item would have to be indented like it, and the
+                // lexer reads indented ones as thematic breaks
+                Some(marker) if setext && self.open_lists.is_empty() => {
+                    let [mut paragraph] = blocks;
+                    self.keep_trailing_backslash(&mut paragraph, self.current);
+                    self.current = next + 1;
+                    let AstNode::Paragraph { content, span } = paragraph else { unreachable!() };
+                    let level = if marker == '=' { 1 } else { 2 };
+                    return AstNode::Heading { level, content, span: span.to(self.token_span(next)) };
+                },
+                _ if self.continues_paragraph_at(next) && self.table_alignments(next).is_none() => {
+                    self.current = next;
+                    self.continue_paragraph(&mut blocks);
+                },
+                _ => break,
+            }
+        }
+
+        let [mut paragraph] = blocks;
+        self.keep_trailing_backslash(&mut paragraph, self.current);
+        paragraph
+    }
+
+    /// The lexer reads a backslash ending a line as a hard break whenever the next line isn't blank, but
+    /// when that line doesn't continue the paragraph or heading ending at `line_end` the backslash is text.
+    /// Trailing spaces also break a line, and unlike the backslash are at least two bytes of source.
+    fn keep_trailing_backslash(&self, paragraph: &mut AstNode, line_end: usize) {
+        let Some(index) = line_end.checked_sub(1).filter(|&index| {
+            self.tokens[index] == Token::LineBreak && self.token_span(index).range().len() == 1
+        }) else {
+            return;
+        };
+        if let AstNode::Paragraph { content, .. } | AstNode::Heading { content, .. } = paragraph {
+            content.push(AstNode::Text("\\".to_string(), self.token_span(index)));
+        }
+    }
+
+    /// The reference definition starting at `position`, along with the position of the token holding
+    /// its `:`. Link definitions are a whole line of `[label]: url` with an optional `"title"`, `'title'`
+    /// or `(title)`; footnote definitions are `[^label]:` followed by the footnote's text.
+    fn definition(&self, position: usize) -> Option<(Definition, usize)> {
+        let (label, colon) = self.bracket_label(position)?;
+        match self.tokens.get(colon) {
+            Some(Token::Text(text)) if text.starts_with(':') => {},
+            _ => return None,
+        }
+        if let Some(label) = Self::footnote_label(&label) {
+            return Some((Definition::Footnote { label }, colon));
+        }
+
+        let label = self.link_label(&label, position, colon);
+        let mut rest = String::new();
+        for token in self.tokens[colon..].iter().take_while(|token| !matches!(token, Token::Newline | Token::Eof)) {
+            if *token != Token::LineBreak {
+                rest.push_str(&Self::token_text(token)?);
+            }
+        }
+        let (url, title) = Self::url_and_title(rest[1..].trim())?;
+        if label.is_empty() || url.is_empty() {
+            return None;
+        }
+
+        Some((Definition::Link { label, url: url.to_string(), title }, colon))
+    }
+
+    /// Split the `url "title"` of a link or definition. The URL may be written in `<>`, and the optional
+    /// title in `"`, `'` or parentheses, set apart from it by whitespace.
+    fn url_and_title(text: &str) -> Option<(&str, Option<String>)> {
+        let (url, rest) = match text.strip_prefix('<') {
+            Some(bracketed) => bracketed.split_once('>')?,
+            None => text.split_at(text.find(char::is_whitespace).unwrap_or(text.len())),
+        };
+        if rest.starts_with(|ch: char| !ch.is_whitespace()) {
+            return None;
+        }
+        let rest = rest.trim();
+        let title = match (rest.chars().next(), rest.chars().last()) {
+            (None, _) => None,
+            (Some('"'), Some('"')) | (Some('\''), Some('\'')) | (Some('('), Some(')')) if rest.len() >= 2 => {
+                Some(rest[1..rest.len() - 1].to_string())
+            },
+            _ => return None,
+        };
+        Some((url, title))
+    }
+
+    /// Record a link reference definition, which leaves nothing in the document, or parse a footnote
+    /// definition's text
+    fn parse_definition(&mut self, definition: Definition, colon: usize) -> Option<AstNode> {
+        let start = self.current;
+        match definition {
+            Definition::Link { label, url, title } => {
+                self.links.entry(label).or_insert((url, title));
+                while !matches!(self.current_token(), None | Some(Token::Newline) | Some(Token::Eof)) {
+                    self.advance();
+                }
+                None
+            },
+            Definition::Footnote { label } => {
+                self.footnotes.insert(label.clone());
+                self.current = colon;
+                let mut content = self.parse_inline_content_until_newline();
+                // The text starts after the `:` and the spaces following it
+                if let Some(AstNode::Text(text, span)) = content.first_mut() {
+                    text.remove(0);
+                    if !span.is_empty() {
+                        span.start.offset += 1;
+                        span.start.column += 1;
+                    }
+                    Self::trim_text_start(text, span);
+                    if text.is_empty() {
+                        content.remove(0);
+                    }
+                }
+
+                let children = match content.first() {
+                    Some(first) => {
+                        let span = Span::new(first.span().start, self.span_from(colon).end);
+                        // A footnote holds a paragraph, which an underline below it doesn't make a heading,
+                        // as with the paragraph of a block quote
+                        vec![self.parse_paragraph_continuation(AstNode::Paragraph { content, span }, false)]
+                    },
+                    None => Vec::new(),
+                };
+                Some(AstNode::FootnoteDefinition { label, children, span: self.span_from(start) })
+            },
+        }
+    }
+
+    /// The label of the `[label]` at `position`, as written, and the position after its `]`. Labels
+    /// don't span lines or contain brackets.
+    fn bracket_label(&self, position: usize) -> Option<(String, usize)> {
+        if !matches!(self.tokens.get(position), Some(Token::LeftBracket)) {
+            return None;
+        }
+        let mut label = String::new();
+        for (offset, token) in self.tokens[position + 1..].iter().enumerate() {
+            match token {
+                Token::RightBracket => return Some((label, position + offset + 2)),
+                Token::LeftBracket => return None,
+                Token::CodeSpan(code) => label.push_str(&format!("`{}`", code)),
+                token => label.push_str(&Self::token_text(token)?),
+            }
+        }
+        None
+    }
+
+    /// The normalized label of a footnote written `^label`, without whitespace
+    fn footnote_label(label: &str) -> Option<String> {
+        let label = label.strip_prefix('^')?;
+        if label.is_empty() || label.contains(char::is_whitespace) {
+            return None;
+        }
+        Some(Self::normalize_label(label))
+    }
+
+    /// Labels match case-insensitively, with any run of whitespace matching any other
+    fn normalize_label(label: &str) -> String {
+        label.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+    }
+
+    /// The key the link label between the brackets at `open` and before `end` is defined and looked up
+    /// by. Link labels match by Unicode case folding, under which `ẞ` is `SS` though neither lowercases
+    /// to the other, and as written: the lexer already resolved escapes and entities, so `[foo\!]` is
+    /// told apart from `[foo!]` by the source they took up. Footnote labels stay lowercased, since they
+    /// are written back out.
+    fn link_label(&self, label: &str, open: usize, end: usize) -> String {
+        let resolved: usize = (open + 1..end - 1)
+            .filter_map(|position| match &self.tokens[position] {
+                Token::Text(text) => Some(self.token_span(position).range().len().saturating_sub(text.len())),
+                _ => None,
+            })
+            .sum();
+        let label = Self::normalize_label(label).to_uppercase().to_lowercase();
+        if resolved == 0 { label } else { format!("{}\u{0}{}", label, resolved) }
+    }
+
+    /// A table starts with a row containing a pipe whose next line is a delimiter row with the same number of cells
+    fn table_alignments(&self, start: usize) -> Option<Vec<Alignment>> {
+        let line_end = self.tokens[start..]
+            .iter()
+            .position(|token| matches!(token, Token::Newline | Token::Eof))
+            .map(|offset| start + offset)?;
+        let header = &self.tokens[start..line_end];
+        // Trailing spaces after the last pipe aren't a cell
+        let header = header.strip_suffix(&[Token::LineBreak]).unwrap_or(header);
+        // A `>` starts a quote, which can hold a table of its own
+        if matches!(header.first(), Some(Token::Quote)) || !header.iter().any(|token| Self::pipes(token) > 0) {
+            return None;
+        }
+
+        // Inside a list item the delimiter row carries the item's indentation
+        let delimiter = match self.tokens.get(line_end + 1) {
+            Some(Token::Indent(_)) => line_end + 2,
+            _ => line_end + 1,
+        };
+        match self.tokens.get(delimiter) {
+            Some(Token::TableDelimiter(row)) => {
+                let alignments = Self::delimiter_alignments(row);
+                (Self::count_cells(header) == alignments.len()).then_some(alignments)
+            }
+            _ => None,
+        }
+    }
+
+    /// Column alignments of a delimiter row like `| :-- | :-: |`, from the colons around each cell's dashes
+    fn delimiter_alignments(row: &str) -> Vec<Alignment> {
+        let row = row.strip_prefix('|').unwrap_or(row);
+        let row = row.strip_suffix('|').unwrap_or(row);
+        row.split('|')
+            .map(|cell| {
+                let cell = cell.trim();
+                match (cell.starts_with(':'), cell.len() > 1 && cell.ends_with(':')) {
+                    (true, true) => Alignment::Center,
+                    (true, false) => Alignment::Left,
+                    (false, true) => Alignment::Right,
+                    (false, false) => Alignment::None,
+                }
+            })
+            .collect()
+    }
+
+    fn count_cells(row: &[Token]) -> usize {
+        let separators: usize = row.iter().map(Self::pipes).sum();
+        let (first, last) = match (row.first(), row.last()) {
+            (Some(first), Some(last)) => (Self::pipes(first), Self::pipes(last)),
+            _ => return 1,
+        };
+        // One leading and one trailing pipe are optional and don't separate cells
+        let leading = first.min(1);
+        let trailing = if row.len() > 1 || first > 1 { last.min(1) } else { 0 };
+        separators - leading - trailing + 1
+    }
+
+    /// Cell separators in a token; inside a table `||` is two pipes around an empty cell
+    fn pipes(token: &Token) -> usize {
+        match token {
+            Token::Pipe => 1,
+            Token::DoublePipe => 2,
+            _ => 0,
+        }
+    }
+
+    /// Step over a cell separator, adding the empty cell that a `||` encloses
+    fn skip_cell_separator(&mut self, cells: &mut Vec<AstNode>) {
+        match self.current_token() {
+            Some(Token::Pipe) => self.advance(),
+            Some(Token::DoublePipe) => {
+                let span = self.token_span(self.current);
+                self.advance();
+                cells.push(AstNode::TableCell { content: Vec::new(), span });
             },
-            None => Ok(None)
+            _ => {}
         }
     }
 
-    fn parse_heading(&mut self, level: u8) -> Result<AstNode, ParseError> {
-        let content = self.parse_inline_content_until_newline()?;
-        Ok(AstNode::Heading { level, content })
+    fn parse_table(&mut self, alignments: Vec<Alignment>) -> AstNode {
+        let start = self.current;
+        let header = self.parse_table_row(alignments.len());
+        if matches!(self.current_token(), Some(Token::Indent(_))) {
+            self.advance();
+        }
+        self.advance(); // consume the delimiter row
+        if matches!(self.current_token(), Some(Token::Newline)) {
+            self.advance();
+        }
+
+        // Body rows run until a line without a pipe
+        let mut rows = Vec::new();
+        while self.line_has_pipe() {
+            rows.push(self.parse_table_row(alignments.len()));
+        }
+
+        AstNode::Table { alignments, header, rows, span: self.span_from(start) }
+    }
+
+    /// Parse one row into exactly `columns` cells, padding short rows and dropping extra cells
+    fn parse_table_row(&mut self, columns: usize) -> Vec<AstNode> {
+        let mut cells = Vec::new();
+
+        if matches!(self.current_token(), Some(Token::Indent(_))) {
+            self.advance();
+        }
+        let row_start = self.current;
+        self.skip_cell_separator(&mut cells); // optional leading pipe
+        while !matches!(self.current_token(), None | Some(Token::Newline) | Some(Token::LineBreak) | Some(Token::Eof)) {
+            let start = self.current;
+            let content = self.parse_inline_content_until(&|token| matches!(token, Token::Pipe | Token::DoublePipe));
+            cells.push(AstNode::TableCell { content: Self::trim_cell(content), span: self.span_from(start) });
+            self.skip_cell_separator(&mut cells);
+        }
+        let row_end = self.span_from(row_start).end;
+        if matches!(self.current_token(), Some(Token::LineBreak)) {
+            self.advance();
+        }
+        if matches!(self.current_token(), Some(Token::Newline)) {
+            self.advance();
+        }
+
+        // Padding cells sit at the end of the row
+        cells.resize(columns, AstNode::TableCell { content: Vec::new(), span: Span::at(row_end) });
+        cells
+    }
+
+    fn line_has_pipe(&self) -> bool {
+        self.tokens[self.current..]
+            .iter()
+            .take_while(|token| !matches!(token, Token::Newline | Token::Eof))
+            .any(|token| Self::pipes(token) > 0)
+    }
+
+    /// Join neighbouring text and strip the padding around the cell separators
+    fn trim_cell(content: Vec<AstNode>) -> Vec<AstNode> {
+        let mut merged: Vec<AstNode> = Vec::new();
+        for node in content {
+            match (merged.last_mut(), node) {
+                (Some(AstNode::Text(previous, previous_span)), AstNode::Text(text, span)) => {
+                    previous.push_str(&text);
+                    *previous_span = previous_span.to(span);
+                },
+                (_, node) => merged.push(node),
+            }
+        }
+
+        if let Some(AstNode::Text(text, span)) = merged.first_mut() {
+            Self::trim_text_start(text, span);
+        }
+        if let Some(AstNode::Text(text, span)) = merged.last_mut() {
+            Self::trim_text_end(text, span);
+        }
+        merged.retain(|node| !matches!(node, AstNode::Text(text, _) if text.is_empty()));
+        merged
     }
 
-    fn parse_paragraph(&mut self) -> Result<AstNode, ParseError> {
-        let content = self.parse_inline_content_until_newline()?;
-        Ok(AstNode::Paragraph { content })
+    /// Strip leading whitespace from text, moving the start of its span past it. Text never
+    /// spans a line break, so the line stays the same.
+    fn trim_text_start(text: &mut String, span: &mut Span) {
+        let trimmed = text.trim_start();
+        let removed = &text[..text.len() - trimmed.len()];
+        if !span.is_empty() {
+            span.start.offset = (span.start.offset + removed.len()).min(span.end.offset);
+            span.start.column += removed.chars().count();
+        }
+        *text = trimmed.to_string();
     }
 
-    fn parse_inline_content_until_newline(&mut self) -> Result<Vec<AstNode>, ParseError> {
+    /// Strip trailing whitespace from text, moving the end of its span back before it
+    fn trim_text_end(text: &mut String, span: &mut Span) {
+        let trimmed = text.trim_end();
+        let removed = &text[trimmed.len()..];
+        if !span.is_empty() {
+            span.end.offset = span.end.offset.saturating_sub(removed.len()).max(span.start.offset);
+            span.end.column = span.end.column.saturating_sub(removed.chars().count());
+        }
+        *text = trimmed.to_string();
+    }
+
+    fn parse_inline_content_until_newline(&mut self) -> Vec<AstNode> {
+        self.parse_inline_content_until(&|_| false)
+    }
+
+    /// Parse inline content up to the end of the line or the first token matching `stop`
+    fn parse_inline_content_until(&mut self, stop: &dyn Fn(&Token) -> bool) -> Vec<AstNode> {
         let mut content = Vec::new();
 
         while let Some(token) = self.current_token().cloned() {
+            let span = self.token_span(self.current);
             match token {
                 Token::Newline | Token::Eof => break,
+                ref token if stop(token) => break,
                 Token::Text(text) => {
-                    content.push(AstNode::Text(text));
+                    content.push(AstNode::Text(text, span));
+                    self.advance();
+                },
+                // Spans open only right before their content, so `* not emphasis*` is text and `||` followed
+                // by a space reads as "or"
+                Token::Asterisk(_) | Token::Underscore(_) | Token::DoubleTilde | Token::DoubleEquals | Token::DoublePipe
+                    if !self.opens_span() =>
+                {
+                    content.extend(Self::token_text(&token).map(|text| AstNode::Text(text, span)));
                     self.advance();
                 },
                 Token::Asterisk(count) => {
-                    content.push(self.parse_emphasis(count)?);
+                    let node = self.parse_or_literal(1, |parser| parser.parse_emphasis(Token::Asterisk(count), '*', count, stop));
+                    content.push(node);
+                },
+                Token::Underscore(count) => {
+                    let node = self.parse_or_literal(1, |parser| parser.parse_emphasis(Token::Underscore(count), '_', count, stop));
+                    content.push(node);
+                },
+                Token::DoubleTilde => {
+                    let node = self.parse_or_literal(1, |parser| parser.parse_delimited(Token::DoubleTilde, '~', AstNode::Strikethrough, stop));
+                    content.push(node);
+                },
+                Token::DoubleEquals => {
+                    let node = self.parse_or_literal(1, |parser| parser.parse_delimited(Token::DoubleEquals, '=', AstNode::Highlight, stop));
+                    content.push(node);
+                },
+                Token::DoublePipe => {
+                    let node = self.parse_or_literal(1, |parser| parser.parse_delimited(Token::DoublePipe, '|', AstNode::Spoiler, stop));
+                    content.push(node);
                 },
                 Token::LeftBracket => {
-                    content.push(self.parse_link()?);
+                    let node = self.parse_or_literal(1, Self::parse_link);
+                    content.push(node);
+                },
+                Token::Exclamation if matches!(self.tokens.get(self.current + 1), Some(Token::LeftBracket)) => {
+                    let node = self.parse_or_literal(2, Self::parse_image);
+                    content.push(node);
                 },
-                _ => {
-                   self.advance();
+                Token::Url(url) => {
+                    content.push(AstNode::Link { text: vec![AstNode::Text(url.clone(), span)], url, title: None, span });
+                    self.advance();
+                },
+                Token::MediaRef(media_ref) => {
+                    content.push(AstNode::MediaRef(media_ref, span));
+                    self.advance();
+                },
+                Token::CodeSpan(code) => {
+                    content.push(AstNode::Code(code, span));
+                    self.advance();
+                },
+                Token::Html(html) => {
+                    content.push(AstNode::Html(html, span));
+                    self.advance();
+                },
+                // Anything else reads as its source text: numbers and pipes outside a list or table, parentheses
+                // around an autolink, or markup left over from what was read as text, e.g. the `]` of
+                // `[not a link]` or a `#` that doesn't start a heading. Only tokens without text of their own,
+                // like a line break, are dropped.
+                token => {
+                    if let Some(text) = Self::token_text(&token) {
+                        content.push(AstNode::Text(text, span));
+                    }
+                    self.advance();
                 }
             }
         }
 
-        Ok(content)
+        content
     }
 
-    fn parse_emphasis(&mut self, count: u8) -> Result<AstNode, ParseError> {
+    /// Run `parse` for the span opening at the current token. When its markup turns out to be malformed,
+    /// record a diagnostic, rewind and read the `opener_len` tokens that opened it as literal text, so
+    /// e.g. a half-typed `**bold` renders as `**bold` rather than failing the whole document.
+    fn parse_or_literal(&mut self, opener_len: usize, parse: impl FnOnce(&mut Self) -> Result<AstNode, ParseError>) -> AstNode {
+        let start = self.current;
+        let reported = self.diagnostics.len();
+        match parse(self) {
+            Ok(node) => node,
+            Err(error) => {
+                // What's inside is read again after the opener, reporting the same again
+                self.diagnostics.truncate(reported);
+                self.current = start + opener_len;
+                let span = self.span_from(start);
+                self.diagnostics.push(Diagnostic { error, span });
+                let text = self.tokens[start..self.current].iter().filter_map(Self::token_text).collect();
+                AstNode::Text(text, span)
+            }
+        }
+    }
+
+    /// Parse `*italic*`, `__bold__` and the like, opened by `delimiter`: a run of `count` of `ch`, inside
+    /// content that ends where `stop` does
+    fn parse_emphasis(&mut self, delimiter: Token, ch: char, count: u8, stop: &dyn Fn(&Token) -> bool) -> Result<AstNode, ParseError> {
+        let start = self.current;
         self.advance();
 
-        let content = self.parse_inline_until_asterisk(count)?;
+        let content = self.parse_inline_until_closing(&delimiter, ch, stop)?;
 
+        let span = self.span_from(start);
         match count {
-            1 => Ok(AstNode::Italic(content)),
-            2 => Ok(AstNode::Bold(content)),
+            1 => Ok(AstNode::Italic(content, span)),
+            2 => Ok(AstNode::Bold(content, span)),
+            // `***both***` is bold inside italic, as in CommonMark
+            3 => Ok(AstNode::Italic(vec![AstNode::Bold(content, span)], span)),
             _ => Err(ParseError::InvalidEmphasis(count))
         }
     }
 
+    /// Whether the delimiter at the current position is directly followed by content, which the same
+    /// delimiter closing an empty span, as in `****`, isn't
+    fn opens_span(&self) -> bool {
+        match self.tokens.get(self.current + 1) {
+            None | Some(Token::Newline) | Some(Token::Eof) => false,
+            Some(Token::Text(text)) => !text.starts_with(char::is_whitespace),
+            Some(next) => Some(next) != self.current_token(),
+        }
+    }
+
+    /// Parse a span between two identical delimiters, e.g. `~~gone~~`, wrapping its content with `node`
+    fn parse_delimited(&mut self, delimiter: Token, ch: char, node: fn(Vec<AstNode>, Span) -> AstNode, stop: &dyn Fn(&Token) -> bool) -> Result<AstNode, ParseError> {
+        let start = self.current;
+        self.advance();
+
+        let content = self.parse_inline_until_closing(&delimiter, ch, stop)?;
+        Ok(node(content, self.span_from(start)))
+    }
+
     fn parse_link(&mut self) -> Result<AstNode, ParseError> {
+        let start = self.current;
+        let label = self.bracket_label(start);
+        if let Some((footnote, end)) = label.as_ref().and_then(|(label, end)| Some((Self::footnote_label(label)?, *end))) {
+            self.current = end;
+            if !self.footnotes.contains(&footnote) {
+                return Err(ParseError::UndefinedReference { label: footnote });
+            }
+            return Ok(AstNode::FootnoteReference { label: footnote, span: self.span_from(start) });
+        }
         self.advance(); // consume '['
 
         let text = self.parse_inline_until_right_bracket()?;
 
         self.expect_token(&Token::RightBracket)?;
-        self.expect_token(&Token::LeftParen)?;
+        let (url, title) = self.parse_link_target(label.map(|(label, end)| self.link_label(&label, start, end)))?;
 
-        let url = match self.current_token().cloned() {
-            Some(Token::Url(url)) => {
-                self.advance();
-                url
-            },
-            Some(Token::Text(text)) => {
-                // Allow plain text as URL
-                self.advance();
-                text
+        // Links don't nest, the innermost one wins as in CommonMark
+        if Self::contains_link(&text) {
+            let open = self.token_span(start).start;
+            return Err(ParseError::NestedLink { line: open.line, column: open.column });
+        }
+        Ok(AstNode::Link { text, url, title, span: self.span_from(start) })
+    }
+
+    fn contains_link(nodes: &[AstNode]) -> bool {
+        nodes.iter().any(|node| match node {
+            AstNode::Link { .. } => true,
+            AstNode::Bold(content, _)
+            | AstNode::Italic(content, _)
+            | AstNode::Strikethrough(content, _)
+            | AstNode::Highlight(content, _)
+            | AstNode::Spoiler(content, _) => Self::contains_link(content),
+            _ => false,
+        })
+    }
+
+    fn parse_image(&mut self) -> Result<AstNode, ParseError> {
+        let start = self.current;
+        let label = self.bracket_label(start + 1).map(|(label, end)| self.link_label(&label, start + 1, end));
+        self.advance(); // consume '!'
+        self.advance(); // consume '['
+
+        let alt = self.parse_inline_until_right_bracket()?;
+
+        self.expect_token(&Token::RightBracket)?;
+        let (url, title) = self.parse_link_target(label)?;
+
+        Ok(AstNode::Image { alt: Self::plain_text(&alt), url, title, span: self.span_from(start) })
+    }
+
+    /// Parse the url and title following a link or image's text: an inline `(url "title")`, or those of
+    /// the definition referenced by a `[label]`. `[text][]` and a `[text]` on its own use the text as
+    /// the label, passed in as `text_label` keyed like definitions are.
+    fn parse_link_target(&mut self, text_label: Option<String>) -> Result<(String, Option<String>), ParseError> {
+        if matches!(self.current_token(), Some(Token::LeftParen)) {
+            let start = self.current;
+            let destination = self.parse_link_destination();
+            // `[foo](not a link)` still links to a definition of `foo`, and the parenthesis is text
+            let reference = text_label.and_then(|label| self.links.get(&label).cloned());
+            return match (destination, reference) {
+                (Err(_), Some(target)) => {
+                    self.current = start;
+                    Ok(target)
+                },
+                (destination, _) => destination,
+            };
+        }
+        let label = match self.bracket_label(self.current) {
+            Some((label, end)) => {
+                let open = std::mem::replace(&mut self.current, end);
+                if label.trim().is_empty() { text_label } else { Some(self.link_label(&label, open, end)) }
             },
-            _ => return Err(ParseError::ExpectedUrl)
+            None => text_label,
         };
 
+        let label = label.unwrap_or_default();
+        match self.links.get(&label) {
+            Some((url, title)) => Ok((url.clone(), title.clone())),
+            None => Err(ParseError::UndefinedReference { label }),
+        }
+    }
+
+    /// Parse `(url)` or `(url "title")` after a link or image's text
+    fn parse_link_destination(&mut self) -> Result<(String, Option<String>), ParseError> {
+        self.expect_token(&Token::LeftParen)?;
+
+        // Plain text URLs may have been split into several tokens, so rebuild the source.
+        // Balanced parentheses belong to the URL, e.g. `wiki/Heat_(1995_film)`
+        let mut destination = String::new();
+        let mut depth = 0;
+        loop {
+            let text = match self.current_token() {
+                Some(Token::RightParen) if depth == 0 => break,
+                Some(Token::LeftParen) => {
+                    depth += 1;
+                    "(".to_string()
+                },
+                Some(Token::RightParen) => {
+                    depth -= 1;
+                    ")".to_string()
+                },
+                Some(Token::Newline) | Some(Token::Eof) | None => return Err(ParseError::ExpectedUrl),
+                Some(Token::Url(url)) => url.clone(),
+                Some(token) => Self::token_text(token).ok_or(ParseError::ExpectedUrl)?,
+            };
+            destination.push_str(&text);
+            self.advance();
+        }
         self.expect_token(&Token::RightParen)?;
 
-        Ok(AstNode::Link { text, url })
+        match Self::url_and_title(destination.trim()) {
+            Some((url, title)) if !url.is_empty() => Ok((url.to_string(), title)),
+            _ => Err(ParseError::ExpectedUrl),
+        }
     }
 
-    fn parse_ordered_list(&mut self) -> Result<AstNode, ParseError> {
-        let mut items = Vec::new();
-        
-        while matches!(self.current_token(), Some(Token::Number(_))) {
-            self.advance(); // consume number
-            if matches!(self.current_token(), Some(Token::Dot)) {
-                self.advance(); // consume dot
-            }
-            
-            let content = self.parse_inline_content_until_newline()?;
-            items.push(AstNode::ListItem { content });
-            
-            // Skip optional newline
-            if matches!(self.current_token(), Some(Token::Newline)) {
-                self.advance();
-            }
-            
-            // Check if next line continues the list
-            if !matches!(self.current_token(), Some(Token::Number(_))) {
-                break;
+    /// Source text of a token that reads as literal text, e.g. inside a link destination
+    fn token_text(token: &Token) -> Option<String> {
+        Some(match token {
+            Token::Text(text) => text.clone(),
+            Token::Number(number) => number.to_string(),
+            Token::Dot => ".".to_string(),
+            Token::Hyphen => "-".to_string(),
+            Token::Asterisk(count) => "*".repeat(*count as usize),
+            Token::Underscore(count) => "_".repeat(*count as usize),
+            Token::DoubleTilde => "~~".to_string(),
+            Token::DoubleEquals => "==".to_string(),
+            Token::Pipe => "|".to_string(),
+            Token::DoublePipe => "||".to_string(),
+            Token::Exclamation => "!".to_string(),
+            Token::Hash(count) => "#".repeat(*count as usize),
+            Token::SetextUnderline(marker, count) => marker.to_string().repeat(*count),
+            Token::LeftBracket => "[".to_string(),
+            Token::RightBracket => "]".to_string(),
+            Token::LeftParen => "(".to_string(),
+            Token::RightParen => ")".to_string(),
+            Token::Bullet(bullet) => bullet.to_string(),
+            Token::Quote => ">".to_string(),
+            Token::TaskMarker(checked) => if *checked { "[x]" } else { "[ ]" }.to_string(),
+            Token::TableDelimiter(row) => row.clone(),
+            Token::Url(url) => url.clone(),
+            Token::MediaRef(media_ref) => format!("[[{}]]", media_ref),
+            Token::Html(html) => html.clone(),
+            _ => return None,
+        })
+    }
+
+    /// Text content of inline nodes with the formatting dropped, e.g. for an image's alt text
+    pub(crate) fn plain_text(nodes: &[AstNode]) -> String {
+        nodes
+            .iter()
+            .map(|node| match node {
+                AstNode::Text(text, _) | AstNode::Code(text, _) | AstNode::Html(text, _) => text.clone(),
+                AstNode::Bold(content, _)
+                | AstNode::Italic(content, _)
+                | AstNode::Strikethrough(content, _)
+                | AstNode::Highlight(content, _)
+                | AstNode::Spoiler(content, _)
+                | AstNode::Link { text: content, .. } => Self::plain_text(content),
+                AstNode::Image { alt, .. } => alt.clone(),
+                AstNode::MediaRef(media_ref, _) => media_ref.to_string(),
+                _ => String::new(),
+            })
+            .collect()
+    }
+
+    fn list_marker(&self, position: usize) -> Option<ListMarker> {
+        let (indent, marker_at) = match self.tokens.get(position) {
+            Some(Token::Indent(indent)) => (*indent, position + 1),
+            _ => (0, position),
+        };
+
+        let (ordered, number, delimiter, marker_len) = match self.tokens.get(marker_at)? {
+            Token::Hyphen => (false, 1, '-', 1),
+            Token::Bullet(bullet) => (false, 1, *bullet, 1),
+            Token::Number(number) => match self.tokens.get(marker_at + 1) {
+                Some(Token::Dot) => (true, *number, '.', 2),
+                Some(Token::RightParen) => (true, *number, ')', 2),
+                _ => return None,
+            },
+            _ => return None,
+        };
+
+        Some(ListMarker { indent, ordered, number, delimiter, len: marker_at - position + marker_len })
+    }
+
+    /// Position after any blank lines starting at `position`
+    fn skip_blank_lines(&self, mut position: usize) -> usize {
+        loop {
+            match (self.tokens.get(position), self.tokens.get(position + 1)) {
+                (Some(Token::Newline), _) => position += 1,
+                (Some(Token::Indent(_)), Some(Token::Newline)) => position += 2,
+                _ => return position,
             }
         }
-        
-        Ok(AstNode::List { ordered: true, items })
     }
 
-    fn parse_unordered_list(&mut self) -> Result<AstNode, ParseError> {
+    /// Parse a list whose first item starts with `first`
+    fn parse_list(&mut self, first: ListMarker) -> AstNode {
+        let start = self.current;
+        let mut marker = first;
         let mut items = Vec::new();
-        
-        while matches!(self.current_token(), Some(Token::Hyphen)) {
-            self.advance(); // consume hyphen
-            
-            let content = self.parse_inline_content_until_newline()?;
-            items.push(AstNode::ListItem { content });
-            
-            // Skip optional newline
-            if matches!(self.current_token(), Some(Token::Newline)) {
-                self.advance();
+        let mut loose = false;
+        self.open_lists.push(first);
+
+        loop {
+            let item_start = self.current;
+            let (task, blocks, item_loose) = self.parse_list_item(marker);
+            items.push((task, blocks, self.span_from(item_start)));
+            loose |= item_loose;
+
+            // The next item may follow blank lines, which make the whole list loose
+            let next = self.skip_blank_lines(self.current);
+            match self.list_marker(next) {
+                Some(next_marker) if next_marker.continues(&first) => {
+                    loose |= next != self.current;
+                    self.current = next;
+                    marker = next_marker;
+                },
+                _ => break,
             }
-            
-            // Check if next line continues the list
-            if !matches!(self.current_token(), Some(Token::Hyphen)) {
+        }
+
+        let items = items
+            .into_iter()
+            .map(|(task, blocks, span)| {
+                let content = if loose {
+                    blocks
+                } else {
+                    blocks
+                        .into_iter()
+                        .flat_map(|block| match block {
+                            AstNode::Paragraph { content, .. } => content,
+                            other => vec![other],
+                        })
+                        .collect()
+                };
+                match task {
+                    Some(checked) => AstNode::TaskItem { checked, content, span },
+                    None => AstNode::ListItem { content, span },
+                }
+            })
+            .collect();
+
+        self.open_lists.pop();
+        AstNode::List { ordered: first.ordered, start: first.number, items, span: self.span_from(start) }
+    }
+
+    /// Parse one item: its first line plus every following line indented past the marker, or lazily
+    /// continuing its paragraph. Returns the item's task state, its blocks and whether a blank line
+    /// separated any of them.
+    fn parse_list_item(&mut self, marker: ListMarker) -> (Option<bool>, Vec<AstNode>, bool) {
+        self.current += marker.len;
+
+        let task_at = match self.current_token() {
+            Some(Token::Text(text)) if text.trim().is_empty() => self.current + 1,
+            _ => self.current,
+        };
+        let task = match self.tokens.get(task_at) {
+            Some(Token::TaskMarker(checked)) => {
+                self.current = task_at + 1;
+                Some(*checked)
+            },
+            _ => None,
+        };
+
+        let mut blocks = Vec::new();
+        let line_start = self.current;
+        // A heading can start an item, e.g. `- ## Watched`
+        if let Some(Token::Hash(_)) = self.current_token() {
+            blocks.extend(self.parse_block());
+        }
+        let mut first_line = self.parse_inline_content_until_newline();
+        // The lexer keeps the spaces after a `.` or `)` delimiter, since both are also inline text
+        if let Some(AstNode::Text(text, span)) = first_line.first_mut() {
+            Self::trim_text_start(text, span);
+            if text.is_empty() {
+                first_line.remove(0);
+            }
+        }
+        let mut paragraph_end = None;
+        if let Some(first) = first_line.first() {
+            let span = Span::new(first.span().start, self.span_from(line_start).end);
+            blocks.push(AstNode::Paragraph { content: first_line, span });
+            paragraph_end = Some(self.current);
+        }
+        self.skip_newline();
+
+        let mut loose = false;
+        loop {
+            let next = self.skip_blank_lines(self.current);
+            let blank = next != self.current;
+            let (indent, line_start) = match self.tokens.get(next) {
+                Some(Token::Indent(indent)) => (*indent, next + 1),
+                _ => (0, next),
+            };
+            let continues_paragraph = !blank
+                && match blocks.last() {
+                    Some(AstNode::FootnoteDefinition { children, .. }) => matches!(children.last(), Some(AstNode::Paragraph { .. })),
+                    last => matches!(last, Some(AstNode::Paragraph { .. })),
+                }
+                && self.continues_paragraph_at(line_start);
+
+            if matches!(self.tokens.get(line_start), None | Some(Token::Eof)) {
+                break;
+            } else if indent >= marker.indent + 2 {
+                loose |= blank && !blocks.is_empty();
+                self.current = next;
+                let nested = self.list_marker(next);
+                if nested.is_some() || !continues_paragraph {
+                    if let (Some(line_end), Some(paragraph)) = (paragraph_end.take(), blocks.last_mut()) {
+                        self.keep_trailing_backslash(paragraph, line_end);
+                    }
+                }
+                if let Some(nested) = nested {
+                    blocks.push(self.parse_list(nested));
+                } else if continues_paragraph {
+                    self.current = line_start;
+                    self.continue_paragraph(&mut blocks);
+                    paragraph_end = Some(self.current);
+                    self.skip_newline();
+                } else {
+                    self.current = line_start;
+                    if let Some(block) = self.parse_block() {
+                        blocks.push(block);
+                    }
+                    // A quote takes the newline ending its last line along, so a newline here is a blank line
+                    if self.tokens[self.current - 1] != Token::Newline {
+                        self.skip_newline();
+                    }
+                }
+            } else if continues_paragraph && self.list_marker(next).is_none() {
+                // Lazy continuation: an unindented line right after the item's text still belongs to it
+                self.current = line_start;
+                self.continue_paragraph(&mut blocks);
+                paragraph_end = Some(self.current);
+                self.skip_newline();
+            } else {
                 break;
             }
         }
-        
-        Ok(AstNode::List { ordered: false, items })
+        if let (Some(line_end), Some(paragraph)) = (paragraph_end, blocks.last_mut()) {
+            self.keep_trailing_backslash(paragraph, line_end);
+        }
+
+        (task, blocks, loose)
     }
 
-    fn parse_inline_until_asterisk(&mut self, count: u8) -> Result<Vec<AstNode>, ParseError> {
-        let mut content = Vec::new();
-        
-        while let Some(token) = self.current_token().cloned() {
-            match token {
-                Token::Asterisk(c) if c == count => {
-                    self.advance(); // consume closing asterisks
-                    break;
-                },
-                Token::Text(text) => {
-                    content.push(AstNode::Text(text));
-                    self.advance();
-                },
-                Token::Newline | Token::Eof => {
-                    return Err(ParseError::UnclosedDelimiter {
-                        delimiter: '*',
-                        open_line: 0, // TODO: track position properly
-                        open_column: 0,
-                    });
-                },
-                _ => {
-                    self.advance(); // Skip other tokens
+    /// Whether the line at `position` continues the paragraph before it rather than starting a block.
+    /// Footnote definitions start a block, link reference definitions don't, and of ordered lists only
+    /// one starting at 1 does, so `in\n2. stars` stays a single paragraph. Neither does an empty list
+    /// item. The next item of a list being parsed ends any paragraph inside it.
+    fn continues_paragraph_at(&self, position: usize) -> bool {
+        let marker = self.list_marker(position);
+        let next_item = marker.is_some_and(|marker| self.open_lists.iter().any(|list| marker.continues(list)));
+        let empty_item = marker.is_some_and(|marker| matches!(self.tokens.get(position + marker.len), None | Some(Token::Newline | Token::Eof)));
+        (empty_item || self.tokens.get(position).is_some_and(Self::continues_paragraph))
+            && !next_item
+            && !matches!(self.definition(position), Some((Definition::Footnote { .. }, _)))
+    }
+
+    fn continues_paragraph(token: &Token) -> bool {
+        !matches!(
+            token,
+            Token::Hash(_)
+                | Token::Quote
+                | Token::CodeBlock { .. }
+                | Token::ThematicBreak
+                | Token::SetextUnderline(..)
+                | Token::TableDelimiter(_)
+                | Token::Hyphen
+                | Token::Bullet(_)
+                | Token::Number(1)
+                | Token::Indent(_)
+                | Token::Newline
+                | Token::Eof
+        )
+    }
+
+    /// Append the current line to the last paragraph, joined by a soft line break, or a hard one when
+    /// the previous line ended with one
+    fn continue_paragraph(&mut self, blocks: &mut [AstNode]) {
+        let start = self.current;
+        let mut previous_end = start;
+        while previous_end > 0 && matches!(self.tokens[previous_end - 1], Token::Newline | Token::Indent(_)) {
+            previous_end -= 1;
+        }
+        let hard_break = previous_end > 0 && matches!(self.tokens[previous_end - 1], Token::LineBreak);
+
+        let line = self.parse_inline_content_until_newline();
+        let end = self.span_from(start).end;
+        if let Some(AstNode::Paragraph { content, span }) = Self::last_paragraph(blocks, end) {
+            if hard_break {
+                let line_break = self.token_span(previous_end - 1);
+                // A paragraph parsed on its own, e.g. in a list item, kept the backslash as text before it
+                // knew this line continues it
+                let kept = |span: &Span| span.start.offset == line_break.start.offset && span.end.offset == line_break.end.offset;
+                if matches!(content.last(), Some(AstNode::Text(text, span)) if text == "\\" && kept(span)) {
+                    content.pop();
                 }
+                content.push(AstNode::LineBreak(line_break));
+            } else {
+                // The soft break spans from the end of the paragraph to the start of the new line
+                content.push(AstNode::Text("\n".to_string(), Span::new(span.end, self.token_span(start).start)));
             }
+            content.extend(line);
+            span.end = end;
         }
-        
+    }
+
+    /// The paragraph ending `blocks`, which a continuation line up to `end` is added to: the last block or
+    /// the text of a footnote definition
+    fn last_paragraph(blocks: &mut [AstNode], end: Position) -> Option<&mut AstNode> {
+        match blocks.last_mut()? {
+            AstNode::FootnoteDefinition { children, span, .. } => {
+                let paragraph = children.last_mut().filter(|last| matches!(last, AstNode::Paragraph { .. }))?;
+                span.end = end;
+                Some(paragraph)
+            },
+            last => Some(last),
+        }
+    }
+
+    fn skip_newline(&mut self) {
+        if matches!(self.current_token(), Some(Token::Newline)) {
+            self.advance();
+        }
+    }
+
+    /// Content of an emphasis-like span up to its `closing` delimiter. Spans nest, and end with the
+    /// content around them: a span that reaches what that `stop`s at, e.g. the `]` of the link text it's
+    /// in, is unclosed.
+    fn parse_inline_until_closing(&mut self, closing: &Token, delimiter: char, stop: &dyn Fn(&Token) -> bool) -> Result<Vec<AstNode>, ParseError> {
+        let open = self.token_span(self.current.saturating_sub(1)).start;
+        let content = self.parse_inline_content_until(&|token| token == closing || stop(token));
+
+        if self.current_token() != Some(closing) {
+            return Err(ParseError::UnclosedDelimiter { delimiter, open_line: open.line, open_column: open.column });
+        }
+        self.advance(); // consume the closing delimiter
         Ok(content)
     }
 
+    /// Content of a link's text or an image's alt text, up to the `]` closing it
     fn parse_inline_until_right_bracket(&mut self) -> Result<Vec<AstNode>, ParseError> {
-        let mut content = Vec::new();
-        
-        while let Some(token) = self.current_token().cloned() {
-            match token {
-                Token::RightBracket => break,
-                Token::Text(text) => {
-                    content.push(AstNode::Text(text));
-                    self.advance();
-                },
-                Token::Newline | Token::Eof => {
-                    return Err(ParseError::UnclosedDelimiter {
-                        delimiter: '[',
-                        open_line: 0, // TODO: track position properly
-                        open_column: 0,
-                    });
-                },
-                _ => {
-                    self.advance(); // Skip other tokens
-                }
-            }
+        let open = self.token_span(self.current.saturating_sub(1)).start;
+        let content = self.parse_inline_content_until(&|token| matches!(token, Token::RightBracket));
+
+        match self.current_token() {
+            Some(Token::RightBracket) => Ok(content),
+            _ => Err(ParseError::UnclosedDelimiter { delimiter: '[', open_line: open.line, open_column: open.column }),
         }
-        
-        Ok(content)
     }
 
     fn expect_token(&mut self, expected: &Token) -> Result<(), ParseError> {
@@ -230,15 +1191,39 @@ impl Parser {
                 Ok(())
             },
             Some(token) => {
+                let position = self.token_span(self.current).start;
                 Err(ParseError::ExpectedToken {
                     expected: format!("{:?}", expected),
                     found: format!("{:?}", token),
+                    line: position.line,
+                    column: position.column,
                 })
             },
             None => Err(ParseError::UnexpectedEndOfInput),
         }
     }
 
+    /// Span of the token at `position`, or an empty span at the end of the input past the last token
+    fn token_span(&self, position: usize) -> Span {
+        match self.spans.get(position) {
+            Some(span) => *span,
+            None => self.spans.last().map(|span| Span::at(span.end)).unwrap_or_default(),
+        }
+    }
+
+    /// Span from the token at `start` to the last token consumed since, leaving out the line breaks
+    /// and indentation that end a block
+    fn span_from(&self, start: usize) -> Span {
+        let mut end = self.current;
+        while end > start && matches!(self.tokens.get(end - 1), Some(Token::Newline | Token::LineBreak | Token::Indent(_) | Token::Eof)) {
+            end -= 1;
+        }
+        if end <= start {
+            return Span::at(self.token_span(start).start);
+        }
+        self.token_span(start).to(self.token_span(end - 1))
+    }
+
     fn current_token(&self) -> Option<&Token> {
         self.tokens.get(self.current)
     }
@@ -259,6 +1244,7 @@ impl Parser {
 mod tests {
     use super::*;
     use crate::Token;
+    use crate::media::{MediaRef, MediaType};
 
     fn create_parser(tokens: Vec<Token>) -> Parser {
         Parser::new(tokens)
@@ -270,7 +1256,7 @@ mod tests {
         let result = parser.parse().unwrap();
         
         match result {
-            AstNode::Document { children } => {
+            AstNode::Document { children, .. } => {
                 assert!(children.is_empty());
             },
             _ => panic!("Expected document node"),
@@ -283,7 +1269,7 @@ mod tests {
         let result = parser.parse().unwrap();
         
         match result {
-            AstNode::Document { children } => {
+            AstNode::Document { children, .. } => {
                 assert!(children.is_empty());
             },
             _ => panic!("Expected document node"),
@@ -302,13 +1288,13 @@ mod tests {
         let result = parser.parse().unwrap();
         
         match result {
-            AstNode::Document { children } => {
+            AstNode::Document { children, .. } => {
                 assert_eq!(children.len(), 1);
                 match &children[0] {
-                    AstNode::Heading { level, content } => {
+                    AstNode::Heading { level, content, .. } => {
                         assert_eq!(*level, 1);
                         assert_eq!(content.len(), 1);
-                        assert_eq!(content[0], AstNode::Text("Simple Heading".to_string()));
+                        assert_eq!(content[0], AstNode::Text("Simple Heading".to_string(), Span::default()));
                     },
                     _ => panic!("Expected heading node"),
                 }
@@ -329,7 +1315,7 @@ mod tests {
             let result = parser.parse().unwrap();
             
             match result {
-                AstNode::Document { children } => {
+                AstNode::Document { children, .. } => {
                     assert_eq!(children.len(), 1);
                     match &children[0] {
                         AstNode::Heading { level: parsed_level, .. } => {
@@ -354,10 +1340,10 @@ mod tests {
         let result = parser.parse().unwrap();
         
         match result {
-            AstNode::Document { children } => {
+            AstNode::Document { children, .. } => {
                 assert_eq!(children.len(), 1);
                 match &children[0] {
-                    AstNode::Heading { level, content } => {
+                    AstNode::Heading { level, content, .. } => {
                         assert_eq!(*level, 2);
                         assert!(content.is_empty());
                     },
@@ -378,12 +1364,12 @@ mod tests {
         let result = parser.parse().unwrap();
         
         match result {
-            AstNode::Document { children } => {
+            AstNode::Document { children, .. } => {
                 assert_eq!(children.len(), 1);
                 match &children[0] {
-                    AstNode::Paragraph { content } => {
+                    AstNode::Paragraph { content, .. } => {
                         assert_eq!(content.len(), 1);
-                        assert_eq!(content[0], AstNode::Text("This is a simple paragraph.".to_string()));
+                        assert_eq!(content[0], AstNode::Text("This is a simple paragraph.".to_string(), Span::default()));
                     },
                     _ => panic!("Expected paragraph node"),
                 }
@@ -410,26 +1396,26 @@ mod tests {
         let result = parser.parse().unwrap();
         
         match result {
-            AstNode::Document { children } => {
+            AstNode::Document { children, .. } => {
                 assert_eq!(children.len(), 1);
                 match &children[0] {
-                    AstNode::Paragraph { content } => {
+                    AstNode::Paragraph { content, .. } => {
                         assert_eq!(content.len(), 5); // "Start ", Bold, " middle ", Italic, " end"
                         
                         // Check bold content
                         match &content[1] {
-                            AstNode::Bold(bold_content) => {
+                            AstNode::Bold(bold_content, _) => {
                                 assert_eq!(bold_content.len(), 1);
-                                assert_eq!(bold_content[0], AstNode::Text("bold".to_string()));
+                                assert_eq!(bold_content[0], AstNode::Text("bold".to_string(), Span::default()));
                             },
                             _ => panic!("Expected bold node"),
                         }
                         
                         // Check italic content
                         match &content[3] {
-                            AstNode::Italic(italic_content) => {
+                            AstNode::Italic(italic_content, _) => {
                                 assert_eq!(italic_content.len(), 1);
-                                assert_eq!(italic_content[0], AstNode::Text("italic".to_string()));
+                                assert_eq!(italic_content[0], AstNode::Text("italic".to_string(), Span::default()));
                             },
                             _ => panic!("Expected italic node"),
                         }
@@ -454,12 +1440,12 @@ mod tests {
         let result = parser.parse().unwrap();
         
         match result {
-            AstNode::Document { children } => {
+            AstNode::Document { children, .. } => {
                 match &children[0] {
-                    AstNode::Paragraph { content } => {
+                    AstNode::Paragraph { content, .. } => {
                         match &content[0] {
-                            AstNode::Italic(content) => {
-                                assert_eq!(content[0], AstNode::Text("italic".to_string()));
+                            AstNode::Italic(content, _) => {
+                                assert_eq!(content[0], AstNode::Text("italic".to_string(), Span::default()));
                             },
                             _ => panic!("Expected italic node"),
                         }
@@ -481,12 +1467,12 @@ mod tests {
         let result = parser.parse().unwrap();
         
         match result {
-            AstNode::Document { children } => {
+            AstNode::Document { children, .. } => {
                 match &children[0] {
-                    AstNode::Paragraph { content } => {
+                    AstNode::Paragraph { content, .. } => {
                         match &content[0] {
-                            AstNode::Bold(content) => {
-                                assert_eq!(content[0], AstNode::Text("bold".to_string()));
+                            AstNode::Bold(content, _) => {
+                                assert_eq!(content[0], AstNode::Text("bold".to_string(), Span::default()));
                             },
                             _ => panic!("Expected bold node"),
                         }
@@ -498,22 +1484,40 @@ mod tests {
         }
     }
 
+    /// Content of the single paragraph `tokens` parse into, along with the diagnostics
+    fn parse_paragraph_with_diagnostics(tokens: Vec<Token>) -> (Vec<AstNode>, Vec<Diagnostic>) {
+        let (ast, diagnostics) = create_parser(tokens).parse_with_diagnostics();
+        let AstNode::Document { mut children, .. } = ast else { panic!("Expected document node") };
+        let Some(AstNode::Paragraph { content, .. }) = children.pop() else { panic!("Expected paragraph") };
+        (content, diagnostics)
+    }
+
     #[test]
-    fn test_invalid_emphasis_level() {
+    fn test_triple_emphasis() {
         let tokens = vec![
-            Token::Asterisk(3), // Invalid level
+            Token::Asterisk(3),
             Token::Text("text".to_string()),
             Token::Asterisk(3),
             Token::Eof,
         ];
-        let mut parser = create_parser(tokens);
-        let result = parser.parse();
-        
-        assert!(result.is_err());
-        match result {
-            Err(ParseError::InvalidEmphasis(3)) => {}, // Expected
-            _ => panic!("Expected InvalidEmphasis error"),
-        }
+        let (content, diagnostics) = parse_paragraph_with_diagnostics(tokens);
+
+        assert_eq!(content, vec![AstNode::Italic(vec![AstNode::Bold(vec![text("text")], Span::default())], Span::default())]);
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_invalid_emphasis_level() {
+        let tokens = vec![
+            Token::Asterisk(4), // Invalid level
+            Token::Text("text".to_string()),
+            Token::Asterisk(4),
+            Token::Eof,
+        ];
+        let (content, diagnostics) = parse_paragraph_with_diagnostics(tokens);
+
+        assert_eq!(content, vec![text("****"), text("text"), text("****")]);
+        assert_eq!(diagnostics[0].error, ParseError::InvalidEmphasis(4));
     }
 
     #[test]
@@ -524,14 +1528,50 @@ mod tests {
             Token::Newline, // No closing asterisk
             Token::Eof,
         ];
-        let mut parser = create_parser(tokens);
-        let result = parser.parse();
-        
-        assert!(result.is_err());
-        match result {
-            Err(ParseError::UnclosedDelimiter { delimiter: '*', .. }) => {}, // Expected
-            _ => panic!("Expected UnclosedDelimiter error"),
-        }
+        let (content, diagnostics) = parse_paragraph_with_diagnostics(tokens);
+
+        // The opening delimiter is kept as text
+        assert_eq!(content, vec![text("*"), text("unclosed")]);
+        assert!(matches!(
+            diagnostics.as_slice(),
+            [Diagnostic { error: ParseError::UnclosedDelimiter { delimiter: '*', .. }, .. }]
+        ));
+    }
+
+    #[test]
+    fn test_underscore_emphasis() {
+        let tokens = vec![
+            Token::Underscore(1),
+            Token::Text("italic".to_string()),
+            Token::Underscore(1),
+            Token::Text(" and ".to_string()),
+            Token::Underscore(2),
+            Token::Text("unclosed".to_string()),
+            Token::Eof,
+        ];
+        let (content, diagnostics) = parse_paragraph_with_diagnostics(tokens);
+
+        assert_eq!(content, vec![AstNode::Italic(vec![text("italic")], Span::default()), text(" and "), text("__"), text("unclosed")]);
+        assert!(matches!(
+            diagnostics.as_slice(),
+            [Diagnostic { error: ParseError::UnclosedDelimiter { delimiter: '_', .. }, .. }]
+        ));
+    }
+
+    #[test]
+    fn test_recovery_keeps_inner_markup() {
+        let tokens = vec![
+            Token::Asterisk(2),
+            Token::Text("bold ".to_string()),
+            Token::Asterisk(1),
+            Token::Text("and italic".to_string()),
+            Token::Asterisk(1),
+            Token::Eof,
+        ];
+        let (content, diagnostics) = parse_paragraph_with_diagnostics(tokens);
+
+        assert_eq!(content, vec![text("**"), text("bold "), AstNode::Italic(vec![text("and italic")], Span::default())]);
+        assert_eq!(diagnostics.len(), 1);
     }
 
     #[test]
@@ -549,13 +1589,13 @@ mod tests {
         let result = parser.parse().unwrap();
         
         match result {
-            AstNode::Document { children } => {
+            AstNode::Document { children, .. } => {
                 match &children[0] {
-                    AstNode::Paragraph { content } => {
+                    AstNode::Paragraph { content, .. } => {
                         match &content[0] {
-                            AstNode::Link { text, url } => {
+                            AstNode::Link { text, url, .. } => {
                                 assert_eq!(text.len(), 1);
-                                assert_eq!(text[0], AstNode::Text("Link Text".to_string()));
+                                assert_eq!(text[0], AstNode::Text("Link Text".to_string(), Span::default()));
                                 assert_eq!(url, "https://example.com");
                             },
                             _ => panic!("Expected link node"),
@@ -583,11 +1623,11 @@ mod tests {
         let result = parser.parse().unwrap();
         
         match result {
-            AstNode::Document { children } => {
+            AstNode::Document { children, .. } => {
                 match &children[0] {
-                    AstNode::Paragraph { content } => {
+                    AstNode::Paragraph { content, .. } => {
                         match &content[0] {
-                            AstNode::Link { text: _, url } => {
+                            AstNode::Link { text: _, url, .. } => {
                                 assert_eq!(url, "example.com");
                             },
                             _ => panic!("Expected link node"),
@@ -608,14 +1648,13 @@ mod tests {
             Token::Newline, // No closing bracket
             Token::Eof,
         ];
-        let mut parser = create_parser(tokens);
-        let result = parser.parse();
-        
-        assert!(result.is_err());
-        match result {
-            Err(ParseError::UnclosedDelimiter { delimiter: '[', .. }) => {}, // Expected
-            _ => panic!("Expected UnclosedDelimiter error"),
-        }
+        let (content, diagnostics) = parse_paragraph_with_diagnostics(tokens);
+
+        assert_eq!(content, vec![text("["), text("Unclosed")]);
+        assert!(matches!(
+            diagnostics.as_slice(),
+            [Diagnostic { error: ParseError::UnclosedDelimiter { delimiter: '[', .. }, .. }]
+        ));
     }
 
     #[test]
@@ -628,14 +1667,10 @@ mod tests {
             Token::RightParen, // No URL
             Token::Eof,
         ];
-        let mut parser = create_parser(tokens);
-        let result = parser.parse();
-        
-        assert!(result.is_err());
-        match result {
-            Err(ParseError::ExpectedUrl) => {}, // Expected
-            _ => panic!("Expected ExpectedUrl error"),
-        }
+        let (content, diagnostics) = parse_paragraph_with_diagnostics(tokens);
+
+        assert_eq!(content, vec![text("["), text("Link"), text("]"), text("("), text(")")]);
+        assert_eq!(diagnostics[0].error, ParseError::ExpectedUrl);
     }
 
     #[test]
@@ -652,27 +1687,27 @@ mod tests {
         let result = parser.parse().unwrap();
         
         match result {
-            AstNode::Document { children } => {
+            AstNode::Document { children, .. } => {
                 assert_eq!(children.len(), 1);
                 match &children[0] {
-                    AstNode::List { ordered, items } => {
-                        assert_eq!(*ordered, false);
+                    AstNode::List { ordered, items, .. } => {
+                        assert!(!*ordered);
                         assert_eq!(items.len(), 2);
                         
                         // Check first item
                         match &items[0] {
-                            AstNode::ListItem { content } => {
+                            AstNode::ListItem { content, .. } => {
                                 assert_eq!(content.len(), 1);
-                                assert_eq!(content[0], AstNode::Text("First item".to_string()));
+                                assert_eq!(content[0], AstNode::Text("First item".to_string(), Span::default()));
                             },
                             _ => panic!("Expected list item"),
                         }
                         
                         // Check second item
                         match &items[1] {
-                            AstNode::ListItem { content } => {
+                            AstNode::ListItem { content, .. } => {
                                 assert_eq!(content.len(), 1);
-                                assert_eq!(content[0], AstNode::Text("Second item".to_string()));
+                                assert_eq!(content[0], AstNode::Text("Second item".to_string(), Span::default()));
                             },
                             _ => panic!("Expected list item"),
                         }
@@ -700,11 +1735,11 @@ mod tests {
         let result = parser.parse().unwrap();
         
         match result {
-            AstNode::Document { children } => {
+            AstNode::Document { children, .. } => {
                 assert_eq!(children.len(), 1);
                 match &children[0] {
-                    AstNode::List { ordered, items } => {
-                        assert_eq!(*ordered, true);
+                    AstNode::List { ordered, items, .. } => {
+                        assert!(*ordered);
                         assert_eq!(items.len(), 2);
                     },
                     _ => panic!("Expected ordered list node"),
@@ -724,16 +1759,10 @@ mod tests {
         let mut parser = create_parser(tokens);
         let result = parser.parse().unwrap();
         
+        // Without a `.` or `)` the number is text
         match result {
-            AstNode::Document { children } => {
-                assert_eq!(children.len(), 1);
-                match &children[0] {
-                    AstNode::List { ordered, items } => {
-                        assert_eq!(*ordered, true);
-                        assert_eq!(items.len(), 1);
-                    },
-                    _ => panic!("Expected ordered list node"),
-                }
+            AstNode::Document { children, .. } => {
+                assert_eq!(children, vec![AstNode::Paragraph { content: vec![text("1"), text("Item without dot")], span: Span::default() }]);
             },
             _ => panic!("Expected document node"),
         }
@@ -751,10 +1780,10 @@ mod tests {
         let result = parser.parse().unwrap();
         
         match result {
-            AstNode::Document { children } => {
+            AstNode::Document { children, .. } => {
                 match &children[0] {
-                    AstNode::List { ordered, items } => {
-                        assert_eq!(*ordered, false);
+                    AstNode::List { ordered, items, .. } => {
+                        assert!(!*ordered);
                         assert_eq!(items.len(), 1);
                     },
                     _ => panic!("Expected list node"),
@@ -780,7 +1809,7 @@ mod tests {
         let result = parser.parse().unwrap();
         
         match result {
-            AstNode::Document { children } => {
+            AstNode::Document { children, .. } => {
                 assert_eq!(children.len(), 3); // heading, paragraph, list
                 
                 // Check structure types
@@ -806,7 +1835,7 @@ mod tests {
         let result = parser.parse().unwrap();
         
         match result {
-            AstNode::Document { children } => {
+            AstNode::Document { children, .. } => {
                 assert_eq!(children.len(), 2); // Should only have 2 paragraphs
             },
             _ => panic!("Expected document node"),
@@ -820,15 +1849,14 @@ mod tests {
             Token::Text("Link".to_string()),
             Token::Text("Wrong token instead of ]".to_string()), // Should be RightBracket
             Token::Eof,
-        ];
-        let mut parser = create_parser(tokens);
-        let result = parser.parse();
-        
-        assert!(result.is_err());
-        match result {
-            Err(ParseError::UnclosedDelimiter { delimiter: '[', .. }) => {}, // Expected - unclosed bracket
-            _ => panic!("Expected UnclosedDelimiter error"),
-        }
+        ];
+        let (content, diagnostics) = parse_paragraph_with_diagnostics(tokens);
+
+        assert_eq!(content, vec![text("["), text("Link"), text("Wrong token instead of ]")]);
+        assert!(matches!(
+            diagnostics.as_slice(),
+            [Diagnostic { error: ParseError::UnclosedDelimiter { delimiter: '[', .. }, .. }]
+        ));
     }
 
     #[test]
@@ -861,22 +1889,17 @@ mod tests {
     fn test_empty_emphasis() {
         let tokens = vec![
             Token::Asterisk(1),
-            Token::Asterisk(1), // Empty italic
+            Token::Asterisk(1), // Nothing to emphasize
             Token::Eof,
         ];
         let mut parser = create_parser(tokens);
         let result = parser.parse().unwrap();
         
         match result {
-            AstNode::Document { children } => {
+            AstNode::Document { children, .. } => {
                 match &children[0] {
-                    AstNode::Paragraph { content } => {
-                        match &content[0] {
-                            AstNode::Italic(content) => {
-                                assert!(content.is_empty());
-                            },
-                            _ => panic!("Expected italic node"),
-                        }
+                    AstNode::Paragraph { content, .. } => {
+                        assert_eq!(content, &vec![text("*"), text("*")]);
                     },
                     _ => panic!("Expected paragraph node"),
                 }
@@ -900,13 +1923,13 @@ mod tests {
         let result = parser.parse().unwrap();
         
         match result {
-            AstNode::Document { children } => {
+            AstNode::Document { children, .. } => {
                 match &children[0] {
                     AstNode::List { items, .. } => {
                         match &items[0] {
-                            AstNode::ListItem { content } => {
+                            AstNode::ListItem { content, .. } => {
                                 assert_eq!(content.len(), 3); // "Item with ", Bold, " text"
-                                assert!(matches!(content[1], AstNode::Bold(_)));
+                                assert!(matches!(content[1], AstNode::Bold(..)));
                             },
                             _ => panic!("Expected list item"),
                         }
@@ -969,14 +1992,14 @@ mod tests {
         let result = parser.parse().unwrap();
         
         match result {
-            AstNode::Document { children } => {
+            AstNode::Document { children, .. } => {
                 assert_eq!(children.len(), 2); // heading and list
                 
                 // Check heading with italic
                 match &children[0] {
                     AstNode::Heading { content, .. } => {
                         assert_eq!(content.len(), 2); // "Title with ", Italic
-                        assert!(matches!(content[1], AstNode::Italic(_)));
+                        assert!(matches!(content[1], AstNode::Italic(..)));
                     },
                     _ => panic!("Expected heading"),
                 }
@@ -985,7 +2008,7 @@ mod tests {
                 match &children[1] {
                     AstNode::List { items, .. } => {
                         match &items[0] {
-                            AstNode::ListItem { content } => {
+                            AstNode::ListItem { content, .. } => {
                                 assert!(matches!(content[0], AstNode::Link { .. }));
                             },
                             _ => panic!("Expected list item"),
@@ -997,4 +2020,686 @@ mod tests {
             _ => panic!("Expected document node"),
         }
     }
+
+    fn cell(text: &str) -> AstNode {
+        AstNode::TableCell { content: vec![AstNode::Text(text.to_string(), Span::default())], span: Span::default() }
+    }
+
+    fn table_tokens(header: &[&str], delimiter: &str) -> Vec<Token> {
+        let mut tokens = Vec::new();
+        for text in header {
+            tokens.push(Token::Pipe);
+            tokens.push(Token::Text(format!(" {} ", text)));
+        }
+        tokens.push(Token::Pipe);
+        tokens.push(Token::Newline);
+        tokens.push(Token::TableDelimiter(delimiter.to_string()));
+        tokens
+    }
+
+    #[test]
+    fn test_table() {
+        let mut tokens = table_tokens(&["Title", "Year"], "|:--|--:|");
+        tokens.extend([
+            Token::Newline,
+            Token::Pipe,
+            Token::Text(" Heat ".to_string()),
+            Token::Pipe,
+            Token::Text(" ".to_string()),
+            Token::Number(1995),
+            Token::Text(" ".to_string()),
+            Token::Pipe,
+            Token::Newline,
+            Token::Text("After".to_string()),
+            Token::Eof,
+        ]);
+        let mut parser = create_parser(tokens);
+        let result = parser.parse().unwrap();
+
+        match result {
+            AstNode::Document { children, .. } => {
+                assert_eq!(children.len(), 2);
+                assert_eq!(children[0], AstNode::Table {
+                    alignments: vec![Alignment::Left, Alignment::Right],
+                    header: vec![cell("Title"), cell("Year")],
+                    rows: vec![vec![cell("Heat"), cell("1995")]],
+                    span: Span::default(),
+                });
+                assert!(matches!(children[1], AstNode::Paragraph { .. }));
+            },
+            _ => panic!("Expected document node"),
+        }
+    }
+
+    #[test]
+    fn test_table_ragged_rows() {
+        let mut tokens = table_tokens(&["a", "b"], "|---|---|");
+        tokens.extend([
+            Token::Newline,
+            Token::Text("only".to_string()),
+            Token::Pipe,
+            Token::Newline,
+            Token::Text("1 ".to_string()),
+            Token::Pipe,
+            Token::Text(" 2 ".to_string()),
+            Token::Pipe,
+            Token::Text(" 3".to_string()),
+            Token::Eof,
+        ]);
+        let mut parser = create_parser(tokens);
+        let result = parser.parse().unwrap();
+
+        match result {
+            AstNode::Document { children, .. } => match &children[0] {
+                AstNode::Table { rows, .. } => {
+                    assert_eq!(rows, &vec![
+                        vec![cell("only"), AstNode::TableCell { content: vec![], span: Span::default() }],
+                        vec![cell("1"), cell("2")],
+                    ]);
+                },
+                _ => panic!("Expected table node"),
+            },
+            _ => panic!("Expected document node"),
+        }
+    }
+
+    #[test]
+    fn test_table_header_must_match_delimiter() {
+        let tokens = table_tokens(&["a", "b"], "|---|");
+        let mut parser = create_parser(tokens);
+        let result = parser.parse().unwrap();
+
+        match result {
+            AstNode::Document { children, .. } => {
+                assert_eq!(children.len(), 2);
+                assert!(children.iter().all(|child| matches!(child, AstNode::Paragraph { .. })));
+                // The delimiter row stays as written
+                assert_eq!(children[1], AstNode::Paragraph { content: vec![AstNode::Text("|---|".to_string(), Span::default())], span: Span::default() });
+            },
+            _ => panic!("Expected document node"),
+        }
+    }
+
+    #[test]
+    fn test_code() {
+        let tokens = vec![
+            Token::Asterisk(2),
+            Token::CodeSpan("x".to_string()),
+            Token::Asterisk(2),
+            Token::Newline,
+            Token::CodeBlock { info: Some("sh".to_string()), content: "ls\n".to_string() },
+            Token::Eof,
+        ];
+        let mut parser = create_parser(tokens);
+        let result = parser.parse().unwrap();
+
+        assert_eq!(result, AstNode::Document {
+            children: vec![
+                AstNode::Paragraph { content: vec![AstNode::Bold(vec![AstNode::Code("x".to_string(), Span::default())], Span::default())], span: Span::default() },
+                AstNode::CodeBlock { info: Some("sh".to_string()), content: "ls\n".to_string(), span: Span::default() },
+            ],
+            span: Span::default(),
+        });
+    }
+
+    #[test]
+    fn test_block_quote() {
+        let tokens = vec![
+            Token::Quote,
+            Token::Hash(2),
+            Token::Text("Quoted".to_string()),
+            Token::Newline,
+            Token::Quote,
+            Token::Quote,
+            Token::Hyphen,
+            Token::Text("nested item".to_string()),
+            Token::Newline,
+            Token::ThematicBreak,
+            Token::Eof,
+        ];
+        let mut parser = create_parser(tokens);
+        let result = parser.parse().unwrap();
+
+        assert_eq!(result, AstNode::Document {
+            children: vec![
+                AstNode::BlockQuote {
+                    children: vec![
+                        AstNode::Heading { level: 2, content: vec![AstNode::Text("Quoted".to_string(), Span::default())], span: Span::default() },
+                        AstNode::BlockQuote {
+                            children: vec![AstNode::List {
+                                ordered: false,
+                                start: 1,
+                                items: vec![AstNode::ListItem { content: vec![AstNode::Text("nested item".to_string(), Span::default())], span: Span::default() }],
+                                span: Span::default(),
+                            }],
+                            span: Span::default(),
+                        },
+                    ],
+                    span: Span::default(),
+                },
+                AstNode::ThematicBreak(Span::default()),
+            ],
+            span: Span::default(),
+        });
+    }
+
+    fn item(content: Vec<AstNode>) -> AstNode {
+        AstNode::ListItem { content, span: Span::default() }
+    }
+
+    fn text(text: &str) -> AstNode {
+        AstNode::Text(text.to_string(), Span::default())
+    }
+
+    #[test]
+    fn test_nested_mixed_lists() {
+        let tokens = vec![
+            Token::Number(3),
+            Token::Dot,
+            Token::Text("outer".to_string()),
+            Token::Newline,
+            Token::Indent(3),
+            Token::Bullet('*'),
+            Token::Text("inner".to_string()),
+            Token::Newline,
+            Token::Number(4),
+            Token::Dot,
+            Token::Text("next".to_string()),
+            Token::Eof,
+        ];
+        let mut parser = create_parser(tokens);
+        let result = parser.parse().unwrap();
+
+        assert_eq!(result, AstNode::Document {
+            children: vec![AstNode::List {
+                ordered: true,
+                start: 3,
+                items: vec![
+                    item(vec![
+                        text("outer"),
+                        AstNode::List { ordered: false, start: 1, items: vec![item(vec![text("inner")])], span: Span::default() },
+                    ]),
+                    item(vec![text("next")]),
+                ],
+                span: Span::default(),
+            }],
+            span: Span::default(),
+        });
+    }
+
+    #[test]
+    fn test_loose_list() {
+        // A blank line between any two items makes every item hold paragraphs
+        let tokens = vec![
+            Token::Hyphen,
+            Token::Text("one".to_string()),
+            Token::Newline,
+            Token::Indent(2),
+            Token::Text("continued".to_string()),
+            Token::Newline,
+            Token::Newline,
+            Token::Indent(2),
+            Token::Text("second paragraph".to_string()),
+            Token::Newline,
+            Token::Hyphen,
+            Token::Text("two".to_string()),
+            Token::Eof,
+        ];
+        let mut parser = create_parser(tokens);
+        let result = parser.parse().unwrap();
+
+        let paragraph = |content| AstNode::Paragraph { content, span: Span::default() };
+        assert_eq!(result, AstNode::Document {
+            children: vec![AstNode::List {
+                ordered: false,
+                start: 1,
+                items: vec![
+                    item(vec![
+                        paragraph(vec![text("one"), text("\n"), text("continued")]),
+                        paragraph(vec![text("second paragraph")]),
+                    ]),
+                    item(vec![paragraph(vec![text("two")])]),
+                ],
+                span: Span::default(),
+            }],
+            span: Span::default(),
+        });
+    }
+
+    #[test]
+    fn test_lazy_continuation() {
+        let tokens = vec![
+            Token::Bullet('+'),
+            Token::Text("item".to_string()),
+            Token::Newline,
+            Token::Text("lazy line".to_string()),
+            Token::Newline,
+            Token::Newline,
+            Token::Text("paragraph".to_string()),
+            Token::Eof,
+        ];
+        let mut parser = create_parser(tokens);
+        let result = parser.parse().unwrap();
+
+        assert_eq!(result, AstNode::Document {
+            children: vec![
+                AstNode::List { ordered: false, start: 1, items: vec![item(vec![text("item"), text("\n"), text("lazy line")])], span: Span::default() },
+                AstNode::Paragraph { content: vec![text("paragraph")], span: Span::default() },
+            ],
+            span: Span::default(),
+        });
+    }
+
+    #[test]
+    fn test_task_items() {
+        let tokens = vec![
+            Token::Number(1),
+            Token::RightParen,
+            Token::Text(" ".to_string()),
+            Token::TaskMarker(true),
+            Token::Text(" done".to_string()),
+            Token::Newline,
+            Token::Number(2),
+            Token::RightParen,
+            Token::Text(" plain".to_string()),
+            Token::Eof,
+        ];
+        let mut parser = create_parser(tokens);
+        let result = parser.parse().unwrap();
+
+        assert_eq!(result, AstNode::Document {
+            children: vec![AstNode::List {
+                ordered: true,
+                start: 1,
+                items: vec![
+                    AstNode::TaskItem { checked: true, content: vec![text("done")], span: Span::default() },
+                    item(vec![text("plain")]),
+                ],
+                span: Span::default(),
+            }],
+            span: Span::default(),
+        });
+    }
+
+    #[test]
+    fn test_strikethrough_highlight_spoiler() {
+        let tokens = vec![
+            Token::DoubleTilde,
+            Token::Text("old".to_string()),
+            Token::DoubleTilde,
+            Token::DoubleEquals,
+            Token::CodeSpan("new".to_string()),
+            Token::DoubleEquals,
+            Token::DoublePipe,
+            Token::Text("twist".to_string()),
+            Token::DoublePipe,
+            Token::DoublePipe,
+            Token::Text(" or".to_string()),
+            Token::Eof,
+        ];
+        let mut parser = create_parser(tokens);
+        let result = parser.parse().unwrap();
+
+        assert_eq!(result, AstNode::Document {
+            children: vec![AstNode::Paragraph {
+                content: vec![
+                    AstNode::Strikethrough(vec![text("old")], Span::default()),
+                    AstNode::Highlight(vec![AstNode::Code("new".to_string(), Span::default())], Span::default()),
+                    AstNode::Spoiler(vec![text("twist")], Span::default()),
+                    text("||"),
+                    text(" or"),
+                ],
+                span: Span::default(),
+            }],
+            span: Span::default(),
+        });
+    }
+
+    #[test]
+    fn test_unclosed_spoiler() {
+        let (content, diagnostics) =
+            parse_paragraph_with_diagnostics(vec![Token::DoublePipe, Token::Text("open".to_string()), Token::Eof]);
+
+        assert_eq!(content, vec![text("||"), text("open")]);
+        assert_eq!(diagnostics[0].error, ParseError::UnclosedDelimiter { delimiter: '|', open_line: 1, open_column: 1 });
+    }
+
+    #[test]
+    fn test_spans_nest() {
+        let inline = |markdown| {
+            let AstNode::Document { children, .. } = parse_with_spans(markdown).unwrap().without_spans() else {
+                panic!("Expected document node")
+            };
+            let [AstNode::Paragraph { content, .. }] = &children[..] else { panic!("Expected a paragraph") };
+            content.clone()
+        };
+        let bold = |content| AstNode::Bold(content, Span::default());
+        let italic = |content| AstNode::Italic(content, Span::default());
+        let link = |text, url: &str| AstNode::Link { text, url: url.to_string(), title: None, span: Span::default() };
+
+        assert_eq!(inline("||**x**||"), vec![AstNode::Spoiler(vec![bold(vec![text("x")])], Span::default())]);
+        assert_eq!(inline("~~**x**~~"), vec![AstNode::Strikethrough(vec![bold(vec![text("x")])], Span::default())]);
+        assert_eq!(inline("==*x*=="), vec![AstNode::Highlight(vec![italic(vec![text("x")])], Span::default())]);
+        assert_eq!(inline("*a **b** c*"), vec![italic(vec![text("a "), bold(vec![text("b")]), text(" c")])]);
+        assert_eq!(inline("__a _b_ c__"), vec![bold(vec![text("a "), italic(vec![text("b")]), text(" c")])]);
+        assert_eq!(inline("**~~a ==b== ||c||~~**"), vec![bold(vec![AstNode::Strikethrough(
+            vec![
+                text("a "),
+                AstNode::Highlight(vec![text("b")], Span::default()),
+                text(" "),
+                AstNode::Spoiler(vec![text("c")], Span::default()),
+            ],
+            Span::default(),
+        )])]);
+        assert_eq!(inline("[**b**](/x)"), vec![link(vec![bold(vec![text("b")])], "/x")]);
+        assert_eq!(inline("*see [Heat](/x)*"), vec![italic(vec![text("see "), link(vec![text("Heat")], "/x")])]);
+        // A span inside a link's text ends with it, and the link wins
+        assert_eq!(inline("[*a](/x)*"), vec![link(vec![text("*"), text("a")], "/x"), text("*")]);
+    }
+
+    #[test]
+    fn test_links_dont_nest() {
+        let (ast, diagnostics) =
+            Parser::with_spans(crate::Lexer::new("[a [b](/x)](/y)").tokenize_with_spans().unwrap()).parse_with_diagnostics();
+        let AstNode::Document { children, .. } = ast.without_spans() else { panic!("Expected document node") };
+
+        assert_eq!(children, vec![AstNode::Paragraph {
+            content: vec![
+                text("["),
+                text("a "),
+                AstNode::Link { text: vec![text("b")], url: "/x".to_string(), title: None, span: Span::default() },
+                text("]"),
+                text("("),
+                text("/y"),
+                text(")"),
+            ],
+            span: Span::default(),
+        }]);
+        assert_eq!(diagnostics[0].error, ParseError::NestedLink { line: 1, column: 1 });
+    }
+
+    #[test]
+    fn test_image_with_title() {
+        let tokens = vec![
+            Token::Exclamation,
+            Token::LeftBracket,
+            Token::Text("The ".to_string()),
+            Token::CodeSpan("Matrix".to_string()),
+            Token::RightBracket,
+            Token::LeftParen,
+            Token::Url("https://image.tmdb.org/poster.jpg".to_string()),
+            Token::Text(" \"Poster\"".to_string()),
+            Token::RightParen,
+            Token::Eof,
+        ];
+        let mut parser = create_parser(tokens);
+        let result = parser.parse().unwrap();
+
+        assert_eq!(result, AstNode::Document {
+            children: vec![AstNode::Paragraph {
+                content: vec![AstNode::Image {
+                    alt: "The Matrix".to_string(),
+                    url: "https://image.tmdb.org/poster.jpg".to_string(),
+                    title: Some("Poster".to_string()),
+                    span: Span::default(),
+                }],
+                span: Span::default(),
+            }],
+            span: Span::default(),
+        });
+    }
+
+    #[test]
+    fn test_autolink() {
+        let tokens = vec![
+            Token::LeftParen,
+            Token::Url("https://example.com".to_string()),
+            Token::RightParen,
+            Token::Eof,
+        ];
+        let mut parser = create_parser(tokens);
+        let result = parser.parse().unwrap();
+
+        assert_eq!(result, AstNode::Document {
+            children: vec![AstNode::Paragraph {
+                content: vec![
+                    text("("),
+                    AstNode::Link { text: vec![text("https://example.com")], url: "https://example.com".to_string(), title: None, span: Span::default() },
+                    text(")"),
+                ],
+                span: Span::default(),
+            }],
+            span: Span::default(),
+        });
+    }
+
+    #[test]
+    fn test_link_destination_with_parentheses() {
+        let tokens = vec![
+            Token::LeftBracket,
+            Token::Text("Heat".to_string()),
+            Token::RightBracket,
+            Token::LeftParen,
+            Token::Text("Heat_".to_string()),
+            Token::LeftParen,
+            Token::Number(1995),
+            Token::RightParen,
+            Token::RightParen,
+            Token::Eof,
+        ];
+        let mut parser = create_parser(tokens);
+        let result = parser.parse().unwrap();
+
+        assert_eq!(result, AstNode::Document {
+            children: vec![AstNode::Paragraph {
+                content: vec![AstNode::Link { text: vec![text("Heat")], url: "Heat_(1995)".to_string(), title: None, span: Span::default() }],
+                span: Span::default(),
+            }],
+            span: Span::default(),
+        });
+    }
+
+    #[test]
+    fn test_media_ref() {
+        let matrix = MediaRef { media_type: MediaType::Movie, id: 603, episode: None };
+        let tokens = vec![
+            Token::Asterisk(2),
+            Token::MediaRef(matrix),
+            Token::Asterisk(2),
+            Token::Text(" again".to_string()),
+            Token::Eof,
+        ];
+        let mut parser = create_parser(tokens);
+        let result = parser.parse().unwrap();
+
+        assert_eq!(result, AstNode::Document {
+            children: vec![AstNode::Paragraph {
+                content: vec![AstNode::Bold(vec![AstNode::MediaRef(matrix, Span::default())], Span::default()), text(" again")],
+                span: Span::default(),
+            }],
+            span: Span::default(),
+        });
+    }
+
+    fn parse_with_spans(input: &str) -> Result<AstNode, ParseError> {
+        Parser::with_spans(crate::Lexer::new(input).tokenize_with_spans().unwrap()).parse()
+    }
+
+    #[test]
+    fn test_node_spans() {
+        let input = "# Heat\n\nA *crime* film\n\n- one\n- [x] two";
+        let ast = parse_with_spans(input).unwrap();
+        let source = |node: &AstNode| &input[node.span().range()];
+
+        let AstNode::Document { children, .. } = &ast else { panic!("Expected document node") };
+        assert_eq!(source(&ast), input);
+        assert_eq!(source(&children[0]), "# Heat");
+        assert_eq!(source(&children[1]), "A *crime* film");
+        assert_eq!(source(&children[2]), "- one\n- [x] two");
+
+        let AstNode::Paragraph { content, .. } = &children[1] else { panic!("Expected paragraph node") };
+        assert_eq!(source(&content[1]), "*crime*");
+        let AstNode::Italic(emphasis, _) = &content[1] else { panic!("Expected italic node") };
+        assert_eq!(source(&emphasis[0]), "crime");
+
+        let AstNode::List { items, .. } = &children[2] else { panic!("Expected list node") };
+        assert_eq!(source(&items[1]), "- [x] two");
+        let span = items[1].span();
+        assert_eq!((span.start.line, span.start.column, span.end.line, span.end.column), (6, 1, 6, 10));
+    }
+
+    #[test]
+    fn test_spans_in_block_quotes_and_tables() {
+        let input = "> quoted *text*\n\n| a | b |\n|---|---|\n| 1 ||";
+        let ast = parse_with_spans(input).unwrap();
+        let source = |node: &AstNode| &input[node.span().range()];
+
+        let AstNode::Document { children, .. } = &ast else { panic!("Expected document node") };
+        let AstNode::BlockQuote { children: quoted, .. } = &children[0] else { panic!("Expected block quote") };
+        assert_eq!(source(&children[0]), "> quoted *text*");
+        assert_eq!(source(&quoted[0]), "quoted *text*");
+
+        let AstNode::Table { header, rows, .. } = &children[1] else { panic!("Expected table") };
+        let AstNode::TableCell { content, .. } = &header[1] else { panic!("Expected table cell") };
+        assert_eq!(source(&content[0]), "b");
+        assert_eq!(source(&rows[0][0]), " 1 ");
+    }
+
+    #[test]
+    fn test_diagnostic_positions() {
+        let diagnostics = |input: &str| {
+            Parser::with_spans(crate::Lexer::new(input).tokenize_with_spans().unwrap()).parse_with_diagnostics().1
+        };
+
+        let unclosed = diagnostics("Intro\n\nsome **bold");
+        assert_eq!(unclosed[0].error, ParseError::UnclosedDelimiter { delimiter: '*', open_line: 3, open_column: 6 });
+        assert_eq!((unclosed[0].span.start.line, unclosed[0].span.start.column), (3, 6));
+        assert_eq!(diagnostics("a [link](x\n")[0].error, ParseError::ExpectedUrl);
+        assert_eq!(diagnostics("see [text] here")[0].error, ParseError::UndefinedReference { label: "text".to_string() });
+        // Quoted lines are parsed on their own, so their diagnostics are passed up
+        assert_eq!(diagnostics("> ~~gone").len(), 1);
+        // Spans nested in an unclosed one are reported once
+        assert_eq!(diagnostics("~~gone **bold").len(), 2);
+    }
+
+    #[test]
+    fn test_paragraph_line_breaks() {
+        let ast = parse_with_spans("Heat  \nby Mann\nin 1995").unwrap().without_spans();
+
+        assert_eq!(ast, AstNode::Document {
+            children: vec![AstNode::Paragraph {
+                content: vec![
+                    text("Heat"),
+                    AstNode::LineBreak(Span::default()),
+                    text("by Mann"),
+                    text("\n"),
+                    text("in 1995"),
+                ],
+                span: Span::default(),
+            }],
+            span: Span::default(),
+        });
+    }
+
+    #[test]
+    fn test_setext_headings() {
+        let ast = parse_with_spans("Heat\n(1995)\n===\n\nCast\n---\n- Pacino\n---").unwrap().without_spans();
+        let AstNode::Document { children, .. } = ast else { panic!("Expected document node") };
+
+        assert_eq!(children[0], AstNode::Heading { level: 1, content: vec![text("Heat"), text("\n"), text("("), text("1995"), text(")")], span: Span::default() });
+        assert_eq!(children[1], AstNode::Heading { level: 2, content: vec![text("Cast")], span: Span::default() });
+        // A list item's text can't be underlined
+        assert!(matches!(children[2], AstNode::List { .. }));
+        assert!(matches!(children[3], AstNode::ThematicBreak(_)));
+    }
+
+    #[test]
+    fn test_atx_closing_sequence() {
+        let ast = parse_with_spans("## Heat ##\n# C# #").unwrap().without_spans();
+        let AstNode::Document { children, .. } = ast else { panic!("Expected document node") };
+
+        assert_eq!(children[0], AstNode::Heading { level: 2, content: vec![text("Heat")], span: Span::default() });
+        assert_eq!(children[1], AstNode::Heading { level: 1, content: vec![text("C"), text("#")], span: Span::default() });
+    }
+
+    fn link(content: &str, url: &str, title: Option<&str>) -> AstNode {
+        AstNode::Link { text: vec![text(content)], url: url.to_string(), title: title.map(str::to_string), span: Span::default() }
+    }
+
+    #[test]
+    fn test_reference_links() {
+        let input = "[Heat][mann], [Heat][] and [heat]\n\n[mann]: https://example.com/mann \"Michael Mann\"\n[HEAT]: /heat\n[heat]: /ignored";
+        let ast = parse_with_spans(input).unwrap().without_spans();
+        let AstNode::Document { children, .. } = ast else { panic!("Expected document node") };
+
+        // Definitions leave nothing behind, and labels match regardless of case
+        assert_eq!(children, vec![AstNode::Paragraph {
+            content: vec![
+                link("Heat", "https://example.com/mann", Some("Michael Mann")),
+                text(", "),
+                link("Heat", "/heat", None),
+                text(" and "),
+                link("heat", "/heat", None),
+            ],
+            span: Span::default(),
+        }]);
+    }
+
+    #[test]
+    fn test_undefined_reference_is_text() {
+        let (ast, diagnostics) = Parser::with_spans(crate::Lexer::new("[Heat][missing] [^1]").tokenize_with_spans().unwrap()).parse_with_diagnostics();
+
+        let AstNode::Document { children, .. } = ast else { panic!("Expected document node") };
+        let AstNode::Paragraph { content, .. } = &children[0] else { panic!("Expected paragraph node") };
+        assert!(content.iter().all(|node| matches!(node, AstNode::Text(..))));
+        assert_eq!(diagnostics[0].error, ParseError::UndefinedReference { label: "missing".to_string() });
+        assert_eq!(diagnostics.last().unwrap().error, ParseError::UndefinedReference { label: "1".to_string() });
+    }
+
+    #[test]
+    fn test_reference_label_matching() {
+        let input = "[Straße] [bar][foo\\!] [Heat](not a link) *[film*]\n\n[STRASSE]: /fold\n[foo!]: /bang\n[heat]: /heat\n[film*]: /film";
+        let ast = parse_with_spans(input).unwrap().without_spans();
+        let AstNode::Document { children, .. } = ast else { panic!("Expected document node") };
+        let AstNode::Paragraph { content, .. } = &children[0] else { panic!("Expected paragraph node") };
+        let links: Vec<_> = content.iter().filter(|node| matches!(node, AstNode::Link { .. })).collect();
+
+        // Labels match by case folding but as written, so `[foo\!]` isn't `[foo!]`, a destination that
+        // isn't one falls back to the label, and a link can't be cut short by the emphasis around it
+        assert_eq!(links, vec![
+            &link("Straße", "/fold", None),
+            &link("Heat", "/heat", None),
+            &AstNode::Link { text: vec![text("film"), text("*")], url: "/film".to_string(), title: None, span: Span::default() },
+        ]);
+        assert!(content.iter().any(|node| *node == text("*")));
+    }
+
+    #[test]
+    fn test_footnotes() {
+        let input = "Long[^1].\n\n> Quoted[^note].\n\n[^1]: Nearly *three* hours\nof film.\n[^Note]: From the trailer.";
+        let ast = parse_with_spans(input).unwrap();
+        let AstNode::Document { children, .. } = &ast else { panic!("Expected document node") };
+
+        let reference = |label: &str| AstNode::FootnoteReference { label: label.to_string(), span: Span::default() };
+        assert_eq!(children[0].without_spans(), AstNode::Paragraph { content: vec![text("Long"), reference("1"), text(".")], span: Span::default() });
+        assert_eq!(children[1].without_spans(), AstNode::BlockQuote {
+            children: vec![AstNode::Paragraph { content: vec![text("Quoted"), reference("note"), text(".")], span: Span::default() }],
+            span: Span::default(),
+        });
+        // A footnote definition ends the paragraph of the one before it
+        assert_eq!(children[2].without_spans(), AstNode::FootnoteDefinition {
+            label: "1".to_string(),
+            children: vec![AstNode::Paragraph {
+                content: vec![text("Nearly "), AstNode::Italic(vec![text("three")], Span::default()), text(" hours"), text("\n"), text("of film.")],
+                span: Span::default(),
+            }],
+            span: Span::default(),
+        });
+        assert!(matches!(&children[3], AstNode::FootnoteDefinition { label, .. } if label == "note"));
+
+        let source = |node: &AstNode| &input[node.span().range()];
+        assert_eq!(source(&children[2]), "[^1]: Nearly *three* hours\nof film.");
+        let AstNode::FootnoteDefinition { children: note, .. } = &children[2] else { unreachable!() };
+        assert_eq!(source(&note[0]), "Nearly *three* hours\nof film.");
+    }
 }
@@ -0,0 +1,540 @@
+use std::convert::Infallible;
+use std::sync::Arc;
+
+use crate::parser::{AstNode, MediaRef};
+use crate::visit::{walk_node, Visitor};
+use super::{MediaResolver, Renderer};
+
+/// Configuration options for terminal rendering
+#[derive(Debug, Clone)]
+pub struct AnsiOptions {
+    /// Column paragraphs are wrapped at, counted in characters. `None` keeps the line breaks of the
+    /// source instead.
+    pub width: Option<usize>,
+    /// Whether links are OSC 8 hyperlinks, which most terminals make clickable. Without them the URL
+    /// is written after the link text.
+    pub hyperlinks: bool,
+    /// Supplies titles for `[[movie:603]]` references; without one references are written as they are
+    pub media_resolver: Option<Arc<dyn MediaResolver>>,
+}
+
+impl Default for AnsiOptions {
+    fn default() -> Self {
+        Self {
+            width: Some(80),
+            hyperlinks: true,
+            media_resolver: None,
+        }
+    }
+}
+
+/// Renders markdown for a terminal, styled with ANSI escape codes
+pub struct AnsiRenderer {
+    options: AnsiOptions,
+}
+
+impl AnsiRenderer {
+    /// Create a new terminal renderer with default options
+    pub fn new() -> Self {
+        Self::with_options(AnsiOptions::default())
+    }
+
+    /// Create a new terminal renderer with custom options
+    pub fn with_options(options: AnsiOptions) -> Self {
+        Self { options }
+    }
+}
+
+impl Default for AnsiRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// SGR colors, the same the TUI uses for notes
+const YELLOW: u8 = 33;
+const MAGENTA: u8 = 35;
+const CYAN: u8 = 36;
+const GREEN: u8 = 32;
+const BLACK: u8 = 30;
+const DARK_GRAY: u8 = 90;
+
+/// Narrowest column paragraphs are wrapped at, however deeply they are nested
+const MIN_WIDTH: usize = 20;
+
+/// A combination of SGR attributes
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Style {
+    bold: bool,
+    italic: bool,
+    underline: bool,
+    strikethrough: bool,
+    foreground: Option<u8>,
+    // Background colors are the foreground ones plus 10
+    background: Option<u8>,
+}
+
+impl Style {
+    fn fg(self, color: u8) -> Self {
+        Self { foreground: Some(color), ..self }
+    }
+
+    /// The escape sequence switching to this style from the default one
+    fn sgr(&self) -> String {
+        let mut codes = Vec::new();
+        if self.bold {
+            codes.push("1".to_string());
+        }
+        if self.italic {
+            codes.push("3".to_string());
+        }
+        if self.underline {
+            codes.push("4".to_string());
+        }
+        if self.strikethrough {
+            codes.push("9".to_string());
+        }
+        codes.extend(self.foreground.map(|color| color.to_string()));
+        codes.extend(self.background.map(|color| (color + 10).to_string()));
+        if codes.is_empty() {
+            String::new()
+        } else {
+            format!("\x1b[{}m", codes.join(";"))
+        }
+    }
+}
+
+/// A run of text in one style, part of a line or a word of a paragraph
+#[derive(Debug, Clone, PartialEq)]
+struct Piece {
+    text: String,
+    style: Style,
+    link: Option<String>,
+}
+
+/// Inline content as pieces of text, split at hard line breaks
+struct Inlines<'a> {
+    options: &'a AnsiOptions,
+    style: Style,
+    link: Option<String>,
+    lines: Vec<Vec<Piece>>,
+}
+
+impl<'ast> Visitor<'ast> for Inlines<'_> {
+    fn visit_node(&mut self, node: &'ast AstNode) {
+        let (style, link) = (self.style, self.link.clone());
+        match node {
+            // Raw HTML shows as written, a terminal has no use for tags
+            AstNode::Text(text, _) | AstNode::Html(text, _) => self.push(text),
+            AstNode::Bold(..) => self.style.bold = true,
+            AstNode::Italic(..) => self.style.italic = true,
+            AstNode::Strikethrough(..) => self.style.strikethrough = true,
+            AstNode::Highlight(..) => {
+                self.style.foreground = Some(BLACK);
+                self.style.background = Some(YELLOW);
+            },
+            // Spoilers stay unreadable, dark gray on dark gray, like in the TUI
+            AstNode::Spoiler(..) => {
+                self.style.foreground = Some(DARK_GRAY);
+                self.style.background = Some(DARK_GRAY);
+            },
+            AstNode::Link { url, .. } => {
+                self.style = self.style.fg(CYAN);
+                self.style.underline = true;
+                if self.options.hyperlinks {
+                    self.link = Some(url.clone());
+                }
+            },
+            AstNode::Image { alt, url, .. } => {
+                self.style = Style { italic: true, ..self.style.fg(CYAN) };
+                if self.options.hyperlinks {
+                    self.link = Some(url.clone());
+                }
+                self.push(&format!("[image: {}]", alt));
+            },
+            AstNode::MediaRef(media_ref, _) => {
+                self.style = self.style.fg(MAGENTA);
+                let text = self.media_ref_text(media_ref);
+                self.push(&text);
+            },
+            AstNode::Code(code, _) => {
+                self.style = self.style.fg(GREEN);
+                self.push(code);
+            },
+            AstNode::FootnoteReference { label, .. } => {
+                self.style = self.style.fg(DARK_GRAY);
+                self.push(&format!("[^{}]", label));
+            },
+            AstNode::LineBreak(_) => self.lines.push(Vec::new()),
+            _ => {},
+        }
+        walk_node(self, node);
+
+        // Without hyperlinks, the URL is shown unless it is the link text already
+        if let AstNode::Link { text, url, .. } = node {
+            let autolink = matches!(text.as_slice(), [AstNode::Text(text, _)] if text == url);
+            if !self.options.hyperlinks && !autolink {
+                self.style = style.fg(DARK_GRAY);
+                self.push(&format!(" ({})", url));
+            }
+        }
+        self.style = style;
+        self.link = link;
+    }
+}
+
+impl<'a> Inlines<'a> {
+    fn new(options: &'a AnsiOptions, style: Style) -> Self {
+        Self { options, style, link: None, lines: vec![Vec::new()] }
+    }
+
+    fn push(&mut self, text: &str) {
+        let piece = Piece { text: text.to_string(), style: self.style, link: self.link.clone() };
+        if let Some(line) = self.lines.last_mut() {
+            line.push(piece);
+        }
+    }
+
+    fn media_ref_text(&self, media_ref: &MediaRef) -> String {
+        let resolved = self.options.media_resolver.as_ref().and_then(|resolver| resolver.resolve(media_ref));
+        match (resolved, media_ref.episode) {
+            (Some(media), Some(episode)) => format!("{} {}", media.title, episode),
+            (Some(media), None) => media.title,
+            (None, _) => format!("[[{}]]", media_ref),
+        }
+    }
+}
+
+/// Writes the lines of one tree, each block into its own buffer so containers can prefix its lines
+struct AnsiWriter<'a> {
+    options: &'a AnsiOptions,
+    // Width left for the block being written, once the prefixes of its containers are taken off
+    width: Option<usize>,
+    lines: Vec<String>,
+}
+
+impl<'ast> Visitor<'ast> for AnsiWriter<'_> {
+    fn visit_node(&mut self, node: &'ast AstNode) {
+        self.render_node(node);
+    }
+}
+
+impl<'a> AnsiWriter<'a> {
+    fn new(options: &'a AnsiOptions) -> Self {
+        Self { options, width: options.width, lines: Vec::new() }
+    }
+
+    fn render_node(&mut self, node: &AstNode) {
+        match node {
+            AstNode::Document { children, .. } => {
+                let lines = self.render_blocks(children, true);
+                self.lines.extend(lines);
+            },
+            AstNode::Heading { level, content, .. } => {
+                let style = Style { bold: true, underline: *level == 1, ..Style::default().fg(YELLOW) };
+                self.render_inline(content, style);
+            },
+            AstNode::Paragraph { content, .. } => self.render_inline(content, Style::default()),
+            AstNode::List { ordered, start, items, .. } => self.render_list(*ordered, *start, items),
+            AstNode::ListItem { .. } | AstNode::TaskItem { .. } => self.render_list_item("•", node),
+            AstNode::Table { header, rows, .. } => self.render_table(header, rows),
+            AstNode::CodeBlock { content, .. } => {
+                let style = Style::default().fg(GREEN);
+                for line in content.lines() {
+                    self.lines.push(format!("  {}", encode(&[piece(line, style)])));
+                }
+            },
+            AstNode::BlockQuote { children, .. } => {
+                let bar = encode(&[piece("│", Style::default().fg(DARK_GRAY))]);
+                for line in self.render_nested(children, 2, true) {
+                    self.lines.push(if line.is_empty() { bar.clone() } else { format!("{} {}", bar, line) });
+                }
+            },
+            AstNode::ThematicBreak(_) => {
+                let rule = "─".repeat(self.width.unwrap_or(20));
+                self.lines.push(encode(&[piece(&rule, Style::default().fg(DARK_GRAY))]));
+            },
+            // Footnotes stay where they were written, marked with their label
+            AstNode::FootnoteDefinition { label, children, .. } => {
+                let marker = format!("[^{}]", label);
+                let lines = self.render_nested(children, marker.chars().count() + 1, true);
+                self.push_prefixed(lines, &encode(&[piece(&marker, Style::default().fg(DARK_GRAY))]), marker.chars().count());
+            },
+            inline => self.render_inline(std::slice::from_ref(inline), Style::default()),
+        }
+    }
+
+    /// Render each of `nodes` on its own, separated by blank lines when `spaced`
+    fn render_blocks(&mut self, nodes: &[AstNode], spaced: bool) -> Vec<String> {
+        let lines = std::mem::take(&mut self.lines);
+        let mut blocks = Vec::new();
+        for node in nodes {
+            self.visit_node(node);
+            if !blocks.is_empty() && spaced {
+                blocks.push(String::new());
+            }
+            blocks.append(&mut self.lines);
+        }
+        self.lines = lines;
+        blocks
+    }
+
+    /// Render blocks `indent` columns further in
+    fn render_nested(&mut self, nodes: &[AstNode], indent: usize, spaced: bool) -> Vec<String> {
+        let width = self.width;
+        self.width = width.map(|width| width.saturating_sub(indent).max(MIN_WIDTH));
+        let lines = self.render_blocks(nodes, spaced);
+        self.width = width;
+        lines
+    }
+
+    /// Add `lines` with `marker` before the first and the lines after it lined up with the first's text
+    fn push_prefixed(&mut self, lines: Vec<String>, marker: &str, marker_width: usize) {
+        let padding = " ".repeat(marker_width + 1);
+        if lines.is_empty() {
+            self.lines.push(marker.to_string());
+        }
+        for (index, line) in lines.into_iter().enumerate() {
+            self.lines.push(match (index, line.is_empty()) {
+                (_, true) => String::new(),
+                (0, false) => format!("{} {}", marker, line),
+                _ => format!("{}{}", padding, line),
+            });
+        }
+    }
+
+    /// Lay out inline content in `style`, wrapped at the width left
+    fn render_inline(&mut self, content: &[AstNode], style: Style) {
+        let mut inlines = Inlines::new(self.options, style);
+        inlines.visit_nodes(content);
+        let lines = self.layout(inlines.lines);
+        self.lines.extend(lines);
+    }
+
+    /// Wrap lines of pieces at the width left and encode them
+    fn layout(&self, lines: Vec<Vec<Piece>>) -> Vec<String> {
+        lines.iter().flat_map(|line| wrap(line, self.width)).map(|line| encode(&line)).collect()
+    }
+
+    fn render_list(&mut self, ordered: bool, start: u32, items: &[AstNode]) {
+        let loose = items.iter().any(|item| match item {
+            AstNode::ListItem { content, .. } | AstNode::TaskItem { content, .. } => {
+                content.iter().any(|child| matches!(child, AstNode::Paragraph { .. }))
+            },
+            _ => false,
+        });
+        for (number, item) in (start..).zip(items) {
+            if loose && number != start {
+                self.lines.push(String::new());
+            }
+            let marker = if ordered { format!("{}.", number) } else { "•".to_string() };
+            self.render_list_item(&marker, item);
+        }
+    }
+
+    /// Tight items hold inline nodes directly, loose ones paragraphs; both may contain nested blocks
+    fn render_list_item(&mut self, marker: &str, item: &AstNode) {
+        let (content, checkbox) = match item {
+            AstNode::ListItem { content, .. } => (content.as_slice(), None),
+            AstNode::TaskItem { checked, content, .. } => (content.as_slice(), Some(if *checked { "☑" } else { "☐" })),
+            other => (std::slice::from_ref(other), None),
+        };
+        let marker_width = marker.chars().count();
+        let style = Style::default().fg(CYAN);
+
+        // Inline content is laid out like a paragraph, with the checkbox in front of it
+        let inline_len = content.iter().take_while(|child| child.is_inline()).count();
+        let mut lines = Vec::new();
+        if inline_len > 0 || checkbox.is_some() {
+            let mut inlines = Inlines::new(self.options, Style::default());
+            inlines.visit_nodes(&content[..inline_len]);
+            if let (Some(checkbox), Some(first)) = (checkbox, inlines.lines.first_mut()) {
+                first.insert(0, piece(&format!("{} ", checkbox), style));
+            }
+            let width = self.width;
+            self.width = width.map(|width| width.saturating_sub(marker_width + 1).max(MIN_WIDTH));
+            lines = self.layout(inlines.lines);
+            self.width = width;
+        }
+        let loose = content.iter().any(|child| matches!(child, AstNode::Paragraph { .. }));
+        lines.extend(self.render_nested(&content[inline_len..], marker_width + 1, loose));
+
+        self.push_prefixed(lines, &encode(&[piece(marker, style)]), marker_width);
+    }
+
+    /// Tables are drawn with columns padded to their widest cell and the header in bold, without wrapping
+    fn render_table(&mut self, header: &[AstNode], rows: &[Vec<AstNode>]) {
+        let render_row = |cells: &[AstNode], style: Style| -> Vec<Vec<Piece>> {
+            cells
+                .iter()
+                .map(|cell| {
+                    let mut inlines = Inlines::new(self.options, style);
+                    inlines.visit_node(cell);
+                    let mut pieces = Vec::new();
+                    for (index, line) in inlines.lines.into_iter().enumerate() {
+                        if index > 0 {
+                            pieces.push(piece(" ", style));
+                        }
+                        pieces.extend(line);
+                    }
+                    pieces
+                })
+                .collect()
+        };
+        let header_style = Style { bold: true, ..Style::default() };
+        let table: Vec<Vec<Vec<Piece>>> = std::iter::once(render_row(header, header_style))
+            .chain(rows.iter().map(|row| render_row(row, Style::default())))
+            .collect();
+
+        let mut widths = vec![0; header.len()];
+        for row in &table {
+            for (column, cell) in row.iter().enumerate() {
+                if let Some(width) = widths.get_mut(column) {
+                    *width = (*width).max(text_width(cell));
+                }
+            }
+        }
+
+        let border = Style::default().fg(DARK_GRAY);
+        for (index, row) in table.into_iter().enumerate() {
+            let mut pieces = Vec::new();
+            for (column, cell) in row.into_iter().enumerate() {
+                if column > 0 {
+                    pieces.push(piece(" │ ", border));
+                }
+                let padding = widths.get(column).copied().unwrap_or(0).saturating_sub(text_width(&cell));
+                pieces.extend(cell);
+                pieces.push(piece(&" ".repeat(padding), Style::default()));
+            }
+            self.lines.push(encode(&pieces).trim_end().to_string());
+            if index == 0 {
+                let rule: Vec<String> = widths.iter().map(|width| "─".repeat(*width)).collect();
+                self.lines.push(encode(&[piece(&rule.join("─┼─"), border)]));
+            }
+        }
+    }
+}
+
+fn piece(text: &str, style: Style) -> Piece {
+    Piece { text: text.to_string(), style, link: None }
+}
+
+/// Width of the pieces' text in characters
+fn text_width(pieces: &[Piece]) -> usize {
+    pieces.iter().map(|piece| piece.text.chars().count()).sum()
+}
+
+/// Break a line of pieces at spaces into lines no wider than `width`, where possible; words longer
+/// than the width get a line of their own. Runs of whitespace, including soft line breaks, become a
+/// single space when wrapping and line breaks are kept as they are without.
+fn wrap(line: &[Piece], width: Option<usize>) -> Vec<Vec<Piece>> {
+    let Some(width) = width else {
+        let mut lines = vec![Vec::new()];
+        for piece in line {
+            for (index, text) in piece.text.split('\n').enumerate() {
+                if index > 0 {
+                    lines.push(Vec::new());
+                }
+                if let Some(line) = lines.last_mut() {
+                    line.push(Piece { text: text.to_string(), ..piece.clone() });
+                }
+            }
+        }
+        return lines;
+    };
+
+    // Words made of the pieces they span, each with the space before it
+    let mut words: Vec<(Option<Piece>, Vec<Piece>)> = Vec::new();
+    let mut space: Option<Piece> = None;
+    let mut in_word = false;
+    for piece in line {
+        let mut rest = piece.text.as_str();
+        while !rest.is_empty() {
+            let whitespace = rest.len() - rest.trim_start().len();
+            if whitespace > 0 {
+                space = Some(Piece { text: " ".to_string(), ..piece.clone() });
+                in_word = false;
+                rest = &rest[whitespace..];
+                continue;
+            }
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            let text = Piece { text: rest[..end].to_string(), ..piece.clone() };
+            match words.last_mut() {
+                Some((_, word)) if in_word => word.push(text),
+                _ => words.push((space.take(), vec![text])),
+            }
+            in_word = true;
+            rest = &rest[end..];
+        }
+    }
+
+    let mut lines = vec![Vec::new()];
+    let mut line_width = 0;
+    for (space, word) in words {
+        let word_width = text_width(&word);
+        let Some(line) = lines.last_mut() else { break };
+        if line.is_empty() {
+            line_width = 0;
+        } else if line_width + 1 + word_width > width {
+            lines.push(Vec::new());
+            line_width = 0;
+        } else if let Some(space) = space {
+            line.push(space);
+            line_width += 1;
+        }
+        if let Some(line) = lines.last_mut() {
+            line.extend(word);
+            line_width += word_width;
+        }
+    }
+    lines
+}
+
+/// Write pieces with the escape codes switching between their styles and links, leaving the default
+/// style and no link at the end so lines can be prefixed and joined freely
+fn encode(pieces: &[Piece]) -> String {
+    let mut output = String::new();
+    let mut style = Style::default();
+    let mut link: Option<&str> = None;
+    for piece in pieces.iter().filter(|piece| !piece.text.is_empty()) {
+        if piece.link.as_deref() != link {
+            if link.is_some() {
+                output.push_str("\x1b]8;;\x1b\\");
+            }
+            link = piece.link.as_deref();
+            if let Some(url) = link {
+                output.push_str(&format!("\x1b]8;;{}\x1b\\", url));
+            }
+        }
+        if piece.style != style {
+            if style != Style::default() {
+                output.push_str("\x1b[0m");
+            }
+            style = piece.style;
+            output.push_str(&style.sgr());
+        }
+        output.push_str(&piece.text);
+    }
+    if style != Style::default() {
+        output.push_str("\x1b[0m");
+    }
+    if link.is_some() {
+        output.push_str("\x1b]8;;\x1b\\");
+    }
+    output
+}
+
+impl Renderer for AnsiRenderer {
+    type Output = String;
+    type Error = Infallible;
+
+    fn render(&self, node: &AstNode) -> Result<Self::Output, Self::Error> {
+        let mut writer = AnsiWriter::new(&self.options);
+        writer.visit_node(node);
+        let mut output = writer.lines.join("\n");
+        if !output.is_empty() {
+            output.push('\n');
+        }
+        Ok(output)
+    }
+}
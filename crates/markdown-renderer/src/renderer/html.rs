@@ -1,7 +1,14 @@
-use crate::parser::AstNode;
+use crate::parser::{Alignment, AstNode, MediaRef};
 use crate::error::MarkdownError;
-use super::Renderer;
-use std::fmt::Write;
+use crate::span::Span;
+use crate::toc::{heading_text, Slugger};
+use crate::visit::{walk_node, walk_nodes, Visitor};
+use super::sanitize::{close_tags, url_scheme, HtmlAllowlist, UrlPolicy};
+use super::{MediaResolver, Renderer};
+use std::collections::HashMap;
+use std::fmt::{self, Write};
+use std::io;
+use std::sync::Arc;
 
 /// Configuration options for HTML rendering
 #[derive(Debug, Clone)]
@@ -10,16 +17,61 @@ pub struct HtmlOptions {
     pub pretty_print: bool,
     /// Custom CSS classes for different elements
     pub css_classes: CssClasses,
-    /// Whether to escape HTML in text content
+    /// Whether to escape the raw HTML tags written in the markdown. Turning this off passes them through
+    /// unchecked, prefer `html_allowlist` for markdown from users. Text is always escaped, including
+    /// markup written as `&lt;b&gt;` or `\<b>`.
     pub escape_html: bool,
+    /// Raw HTML tags and attributes passed through while `escape_html` is on; everything else is escaped.
+    /// Tags are balanced per block: closing tags only close tags opened in the same block, and tags left
+    /// open are closed at its end.
+    pub html_allowlist: Option<HtmlAllowlist>,
+    /// Which URLs links may point at
+    pub url_policy: UrlPolicy,
     /// Whether to add target="_blank" to links
     pub external_links_new_tab: bool,
     /// Custom attributes to add to specific elements
     pub custom_attributes: CustomAttributes,
+    /// URL schemes images may load from, any other image is rendered as its alt text. Relative image
+    /// URLs follow `url_policy`.
+    pub image_schemes: Vec<String>,
+    /// Whether to add loading="lazy" to images
+    pub lazy_load_images: bool,
+    /// Applied to image URLs that passed the scheme check, e.g. to serve posters from a local cache
+    pub rewrite_image_url: Option<UrlRewriter>,
+    /// Supplies titles and posters for `[[movie:603]]` references; without one, or for titles it
+    /// doesn't know, references render as plain links showing the reference itself
+    pub media_resolver: Option<Arc<dyn MediaResolver>>,
+    /// Whether to mark block elements with the part of the markdown they came from, as
+    /// `data-sourcepos="3:1-4:12"` (start line:column to end line:column, both inclusive), so an
+    /// editor can scroll the preview along with the source
+    pub source_positions: bool,
+    /// Whether to give headings an `id` slugged from their text, e.g. `<h2 id="season-2">`, so `#season-2`
+    /// links to them. Ids are unique within the document and match the slugs of `toc`.
+    pub heading_ids: bool,
+}
+
+/// A function mapping one URL to another at render time
+#[derive(Clone)]
+pub struct UrlRewriter(Arc<dyn Fn(&str) -> String + Send + Sync>);
+
+impl UrlRewriter {
+    pub fn new(rewrite: impl Fn(&str) -> String + Send + Sync + 'static) -> Self {
+        Self(Arc::new(rewrite))
+    }
+
+    pub fn rewrite(&self, url: &str) -> String {
+        (self.0)(url)
+    }
+}
+
+impl fmt::Debug for UrlRewriter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("UrlRewriter(..)")
+    }
 }
 
 /// CSS class configuration
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct CssClasses {
     pub document: Option<String>,
     pub heading: Option<String>,
@@ -27,13 +79,26 @@ pub struct CssClasses {
     pub list_ordered: Option<String>,
     pub list_unordered: Option<String>,
     pub list_item: Option<String>,
+    pub task_item: Option<String>,
     pub bold: Option<String>,
     pub italic: Option<String>,
+    pub strikethrough: Option<String>,
+    pub highlight: Option<String>,
+    /// Class of spoiler spans, `spoiler` when unset since the text is hidden through it
+    pub spoiler: Option<String>,
     pub link: Option<String>,
+    pub image: Option<String>,
+    /// Class of title reference chips, `media-ref` when unset
+    pub media_ref: Option<String>,
+    pub table: Option<String>,
+    pub code: Option<String>,
+    pub code_block: Option<String>,
+    pub blockquote: Option<String>,
+    pub thematic_break: Option<String>,
 }
 
 /// Custom HTML attributes
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct CustomAttributes {
     pub document: Vec<(String, String)>,
     pub heading: Vec<(String, String)>,
@@ -48,36 +113,16 @@ impl Default for HtmlOptions {
             pretty_print: false,
             css_classes: CssClasses::default(),
             escape_html: true,
+            html_allowlist: None,
+            url_policy: UrlPolicy::default(),
             external_links_new_tab: false,
             custom_attributes: CustomAttributes::default(),
-        }
-    }
-}
-
-impl Default for CssClasses {
-    fn default() -> Self {
-        Self {
-            document: None,
-            heading: None,
-            paragraph: None,
-            list_ordered: None,
-            list_unordered: None,
-            list_item: None,
-            bold: None,
-            italic: None,
-            link: None,
-        }
-    }
-}
-
-impl Default for CustomAttributes {
-    fn default() -> Self {
-        Self {
-            document: Vec::new(),
-            heading: Vec::new(),
-            paragraph: Vec::new(),
-            list: Vec::new(),
-            link: Vec::new(),
+            image_schemes: vec!["http".to_string(), "https".to_string()],
+            lazy_load_images: true,
+            rewrite_image_url: None,
+            media_resolver: None,
+            source_positions: false,
+            heading_ids: false,
         }
     }
 }
@@ -90,9 +135,7 @@ pub struct HtmlRenderer {
 impl HtmlRenderer {
     /// Create a new HTML renderer with default options
     pub fn new() -> Self {
-        Self {
-            options: HtmlOptions::default(),
-        }
+        Self::with_options(HtmlOptions::default())
     }
 
     /// Create a new HTML renderer with custom options
@@ -100,136 +143,368 @@ impl HtmlRenderer {
         Self { options }
     }
 
-    /// Render AST node to HTML string
-    fn render_node(&self, node: &AstNode, output: &mut String, depth: usize) -> Result<(), MarkdownError> {
+    /// Render `node` into `out`, handing over the HTML of each top-level block as soon as it's written
+    /// rather than building the whole document in memory first
+    pub fn render_to<W: fmt::Write>(&self, node: &AstNode, out: &mut W) -> Result<(), MarkdownError> {
+        let mut writer = HtmlWriter::new(&self.options);
+        writer.sink = Some(out);
+        writer.visit_node(node);
+        writer.finish();
+        match writer.error {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    /// Render `node` into a byte stream such as a file or socket, like `render_to`. Each block is one
+    /// write, so unbuffered streams are best wrapped in a `BufWriter`.
+    pub fn write_to<W: io::Write>(&self, node: &AstNode, out: &mut W) -> Result<(), MarkdownError> {
+        let mut out = IoWriter { inner: out, error: None };
+        let rendered = self.render_to(node, &mut out);
+        match out.error {
+            Some(error) => Err(error.into()),
+            None => rendered,
+        }
+    }
+}
+
+/// Writes formatted text to a byte stream, keeping the I/O error that `fmt::Error` can't carry
+struct IoWriter<'a, W: io::Write> {
+    inner: &'a mut W,
+    error: Option<io::Error>,
+}
+
+impl<W: io::Write> fmt::Write for IoWriter<'_, W> {
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        self.inner.write_all(text.as_bytes()).map_err(|error| {
+            self.error = Some(error);
+            fmt::Error
+        })
+    }
+}
+
+/// Writes the HTML of one tree, visiting each node to write its tags around the HTML of its children
+struct HtmlWriter<'a> {
+    options: &'a HtmlOptions,
+    output: String,
+    // Indentation level of the block being written when pretty printing
+    depth: usize,
+    // Labels of the footnotes referenced so far in the order they are numbered, each with the number
+    // of references to it
+    footnotes: Vec<(String, usize)>,
+    // Slugs given to the headings so far, when `heading_ids` is on
+    slugs: Slugger,
+    // Raw HTML tags the allowlist let through and that are still open, for each list of children being
+    // written
+    open_tags: Vec<Vec<String>>,
+    // The first error, after which nothing more is rendered
+    error: Option<MarkdownError>,
+    // Where `output` is flushed after each top-level block when streaming, see `HtmlRenderer::render_to`
+    sink: Option<&'a mut dyn fmt::Write>,
+}
+
+impl<'ast> Visitor<'ast> for HtmlWriter<'_> {
+    // Raw HTML tags opened among the children of a node are closed after the last of them, before the
+    // node's own closing tag
+    fn visit_nodes(&mut self, nodes: &'ast [AstNode]) {
+        self.open_tags.push(Vec::new());
+        walk_nodes(self, nodes);
+        self.close_open_tags();
+    }
+
+    fn visit_document(&mut self, children: &'ast [AstNode], _span: Span) {
+        self.render(|writer| writer.render_document(children));
+    }
+
+    fn visit_heading(&mut self, level: u8, content: &'ast [AstNode], span: Span) {
+        self.render(|writer| writer.render_heading(level, content, span));
+    }
+
+    fn visit_paragraph(&mut self, content: &'ast [AstNode], span: Span) {
+        self.render(|writer| writer.render_paragraph(content, span));
+    }
+
+    fn visit_list(&mut self, ordered: bool, start: u32, items: &'ast [AstNode], span: Span) {
+        self.render(|writer| writer.render_list(ordered, start, items, span));
+    }
+
+    fn visit_list_item(&mut self, content: &'ast [AstNode], span: Span) {
+        self.render(|writer| writer.render_list_item(None, content, span));
+    }
+
+    fn visit_task_item(&mut self, checked: bool, content: &'ast [AstNode], span: Span) {
+        self.render(|writer| writer.render_list_item(Some(checked), content, span));
+    }
+
+    fn visit_table(&mut self, alignments: &'ast [Alignment], header: &'ast [AstNode], rows: &'ast [Vec<AstNode>], span: Span) {
+        self.render(|writer| writer.render_table(alignments, header, rows, span));
+    }
+
+    fn visit_table_cell(&mut self, content: &'ast [AstNode], _span: Span) {
+        self.render(|writer| writer.render_table_cell("td", Alignment::None, content));
+    }
+
+    fn visit_code_block(&mut self, info: Option<&'ast str>, content: &'ast str, span: Span) {
+        self.render(|writer| writer.render_code_block(info, content, span));
+    }
+
+    fn visit_block_quote(&mut self, children: &'ast [AstNode], span: Span) {
+        self.render(|writer| writer.render_block_quote(children, span));
+    }
+
+    fn visit_thematic_break(&mut self, span: Span) {
+        self.render(|writer| writer.render_thematic_break(span));
+    }
+
+    // Rendered at the end of the document, see `render_footnotes`
+    fn visit_footnote_definition(&mut self, _label: &'ast str, _children: &'ast [AstNode], _span: Span) {}
+
+    fn visit_text(&mut self, text: &'ast str, _span: Span) {
+        self.render(|writer| writer.render_text(text));
+    }
+
+    fn visit_bold(&mut self, content: &'ast [AstNode], _span: Span) {
+        self.render(|writer| writer.render_bold(content));
+    }
+
+    fn visit_italic(&mut self, content: &'ast [AstNode], _span: Span) {
+        self.render(|writer| writer.render_italic(content));
+    }
+
+    fn visit_strikethrough(&mut self, content: &'ast [AstNode], _span: Span) {
+        self.render(|writer| {
+            let class = writer.options.css_classes.strikethrough.as_deref();
+            writer.render_inline_tag("del", class, "", content)
+        });
+    }
+
+    fn visit_highlight(&mut self, content: &'ast [AstNode], _span: Span) {
+        self.render(|writer| {
+            let class = writer.options.css_classes.highlight.as_deref();
+            writer.render_inline_tag("mark", class, "", content)
+        });
+    }
+
+    fn visit_spoiler(&mut self, content: &'ast [AstNode], _span: Span) {
+        // Focusable, so clicking the span can reveal it with plain CSS
+        self.render(|writer| {
+            let class = writer.options.css_classes.spoiler.as_deref().unwrap_or("spoiler");
+            writer.render_inline_tag("span", Some(class), r#" tabindex="0""#, content)
+        });
+    }
+
+    fn visit_link(&mut self, text: &'ast [AstNode], url: &'ast str, title: Option<&'ast str>, _span: Span) {
+        self.render(|writer| writer.render_link(text, url, title));
+    }
+
+    fn visit_image(&mut self, alt: &'ast str, url: &'ast str, title: Option<&'ast str>, _span: Span) {
+        self.render(|writer| writer.render_image(alt, url, title));
+    }
+
+    fn visit_media_ref(&mut self, media_ref: &'ast MediaRef, _span: Span) {
+        self.render(|writer| writer.render_media_ref(media_ref));
+    }
+
+    fn visit_code(&mut self, code: &'ast str, _span: Span) {
+        self.render(|writer| writer.render_code(code));
+    }
+
+    fn visit_footnote_reference(&mut self, label: &'ast str, _span: Span) {
+        self.render(|writer| writer.render_footnote_reference(label));
+    }
+
+    fn visit_html(&mut self, html: &'ast str, _span: Span) {
+        self.render(|writer| writer.render_html(html));
+    }
+
+    fn visit_line_break(&mut self, _span: Span) {
+        self.render(|writer| {
+            writer.output.push_str("<br>");
+            if writer.options.pretty_print {
+                writer.output.push('\n');
+            }
+            Ok(())
+        });
+    }
+}
+
+/// The content of each footnote definition by label, the first definition of a label winning
+#[derive(Default)]
+struct FootnoteDefinitions<'ast>(HashMap<&'ast str, &'ast [AstNode]>);
+
+impl<'ast> Visitor<'ast> for FootnoteDefinitions<'ast> {
+    fn visit_node(&mut self, node: &'ast AstNode) {
         match node {
-            AstNode::Document { children } => {
-                self.render_document(children, output, depth)?;
-            },
-            AstNode::Heading { level, content } => {
-                self.render_heading(*level, content, output, depth)?;
-            },
-            AstNode::Paragraph { content } => {
-                self.render_paragraph(content, output, depth)?;
-            },
-            AstNode::List { ordered, items } => {
-                self.render_list(*ordered, items, output, depth)?;
-            },
-            AstNode::ListItem { content } => {
-                self.render_list_item(content, output, depth)?;
-            },
-            AstNode::Bold(content) => {
-                self.render_bold(content, output, depth)?;
-            },
-            AstNode::Italic(content) => {
-                self.render_italic(content, output, depth)?;
-            },
-            AstNode::Link { text, url } => {
-                self.render_link(text, url, output, depth)?;
-            },
-            AstNode::Text(text) => {
-                self.render_text(text, output)?;
-            },
-            AstNode::LineBreak => {
-                output.push_str("<br>");
-                if self.options.pretty_print {
-                    output.push('\n');
-                }
+            AstNode::FootnoteDefinition { label, children, .. } => {
+                self.0.entry(label.as_str()).or_insert(children.as_slice());
             },
+            // Definitions are blocks, so there is no need to look inside inline content
+            node if node.is_inline() => {},
+            node => walk_node(self, node),
+        }
+    }
+}
+
+impl<'a> HtmlWriter<'a> {
+    fn new(options: &'a HtmlOptions) -> Self {
+        Self {
+            options,
+            output: String::new(),
+            depth: 0,
+            footnotes: Vec::new(),
+            slugs: Slugger::default(),
+            open_tags: vec![Vec::new()],
+            error: None,
+            sink: None,
         }
-        Ok(())
     }
 
-    fn render_document(&self, children: &[AstNode], output: &mut String, depth: usize) -> Result<(), MarkdownError> {
-        let indent = if self.options.pretty_print { "  ".repeat(depth) } else { String::new() };
+    /// Hand what has been written so far to the sink, if there is one
+    fn flush(&mut self) {
+        let Some(sink) = self.sink.as_mut() else { return };
+        if self.error.is_none() {
+            if let Err(e) = sink.write_str(&self.output) {
+                self.error = Some(MarkdownError::RenderError { message: e.to_string() });
+            }
+        }
+        self.output.clear();
+    }
+
+    /// Close the raw HTML tags opened since the last push to `open_tags`
+    fn close_open_tags(&mut self) {
+        let mut open_tags = self.open_tags.pop().unwrap_or_default();
+        self.output.push_str(&close_tags(&mut open_tags, 0));
+    }
+
+    /// Close the raw HTML tags still open, which only an inline node rendered on its own leaves, and flush
+    fn finish(&mut self) {
+        self.close_open_tags();
+        self.flush();
+    }
+
+    /// Visit `nodes` as blocks nested one level deeper than the current one
+    fn visit_nested(&mut self, nodes: &[AstNode]) {
+        self.depth += 1;
+        self.visit_nodes(nodes);
+        self.depth -= 1;
+    }
+
+    /// Run one of the `render_*` methods, unless an earlier one failed, keeping the first error
+    fn render(&mut self, render: impl FnOnce(&mut Self) -> Result<(), MarkdownError>) {
+        if self.error.is_none() {
+            if let Err(error) = render(self) {
+                self.error = Some(error);
+            }
+        }
+    }
+
+    /// The `data-sourcepos` attribute of a block when `source_positions` is on
+    fn source_position(&self, span: Span) -> String {
+        if !self.options.source_positions {
+            return String::new();
+        }
+        // Span ends are exclusive, the attribute's are inclusive
+        let end_column = span.end.column.saturating_sub(1).max(1);
+        format!(r#" data-sourcepos="{}:{}-{}:{}""#, span.start.line, span.start.column, span.end.line, end_column)
+    }
+
+    fn render_document(&mut self, children: &[AstNode]) -> Result<(), MarkdownError> {
+        let indent = if self.options.pretty_print { "  ".repeat(self.depth) } else { String::new() };
         
         if let Some(ref class) = self.options.css_classes.document {
-            write!(output, r#"{}<div class="{}"{}>"#, indent, class, self.format_attributes(&self.options.custom_attributes.document))
+            write!(self.output, r#"{}<div class="{}"{}>"#, indent, class, self.format_attributes(&self.options.custom_attributes.document))
                 .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
         } else if !self.options.custom_attributes.document.is_empty() {
-            write!(output, r#"{}<div{}>"#, indent, self.format_attributes(&self.options.custom_attributes.document))
+            write!(self.output, r#"{}<div{}>"#, indent, self.format_attributes(&self.options.custom_attributes.document))
                 .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
         }
 
         if self.options.pretty_print && (self.options.css_classes.document.is_some() || !self.options.custom_attributes.document.is_empty()) {
-            output.push('\n');
+            self.output.push('\n');
         }
 
+        let wrapped = self.options.css_classes.document.is_some() || !self.options.custom_attributes.document.is_empty();
+        self.depth += usize::from(wrapped);
         for child in children {
-            self.render_node(child, output, depth + if self.options.css_classes.document.is_some() || !self.options.custom_attributes.document.is_empty() { 1 } else { 0 })?;
+            self.visit_node(child);
+            self.flush();
         }
+        self.render_footnotes(children)?;
+        self.depth -= usize::from(wrapped);
 
         if self.options.css_classes.document.is_some() || !self.options.custom_attributes.document.is_empty() {
             if self.options.pretty_print {
-                output.push_str(&format!("{}</div>\n", indent));
+                self.output.push_str(&format!("{}</div>\n", indent));
             } else {
-                output.push_str("</div>");
+                self.output.push_str("</div>");
             }
         }
 
         Ok(())
     }
 
-    fn render_heading(&self, level: u8, content: &[AstNode], output: &mut String, depth: usize) -> Result<(), MarkdownError> {
-        let indent = if self.options.pretty_print { "  ".repeat(depth) } else { String::new() };
+    fn render_heading(&mut self, level: u8, content: &[AstNode], span: Span) -> Result<(), MarkdownError> {
+        let indent = if self.options.pretty_print { "  ".repeat(self.depth) } else { String::new() };
         let tag = format!("h{}", level);
         
-        write!(output, "{}<{}", indent, tag)
+        write!(self.output, "{}<{}", indent, tag)
             .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
 
+        if self.options.heading_ids {
+            let slug = self.slugs.slug(&heading_text(content));
+            write!(self.output, r#" id="{}""#, self.escape_attribute(&slug))
+                .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
+        }
+
         if let Some(ref class) = self.options.css_classes.heading {
-            write!(output, r#" class="{}""#, class)
+            write!(self.output, r#" class="{}""#, class)
                 .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
         }
 
-        write!(output, "{}>{}", self.format_attributes(&self.options.custom_attributes.heading), if self.options.pretty_print { "" } else { "" })
+        write!(self.output, "{}{}>", self.source_position(span), self.format_attributes(&self.options.custom_attributes.heading))
             .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
 
-        for child in content {
-            self.render_node(child, output, depth)?;
-        }
+        self.visit_nodes(content);
 
-        write!(output, "</{}>", tag)
+        write!(self.output, "</{}>", tag)
             .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
 
         if self.options.pretty_print {
-            output.push('\n');
+            self.output.push('\n');
         }
 
         Ok(())
     }
 
-    fn render_paragraph(&self, content: &[AstNode], output: &mut String, depth: usize) -> Result<(), MarkdownError> {
-        let indent = if self.options.pretty_print { "  ".repeat(depth) } else { String::new() };
+    fn render_paragraph(&mut self, content: &[AstNode], span: Span) -> Result<(), MarkdownError> {
+        let indent = if self.options.pretty_print { "  ".repeat(self.depth) } else { String::new() };
         
-        write!(output, "{}<p", indent)
+        write!(self.output, "{}<p", indent)
             .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
 
         if let Some(ref class) = self.options.css_classes.paragraph {
-            write!(output, r#" class="{}""#, class)
+            write!(self.output, r#" class="{}""#, class)
                 .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
         }
 
-        write!(output, "{}>", self.format_attributes(&self.options.custom_attributes.paragraph))
+        write!(self.output, "{}{}>", self.source_position(span), self.format_attributes(&self.options.custom_attributes.paragraph))
             .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
 
-        for child in content {
-            self.render_node(child, output, depth)?;
-        }
+        self.visit_nodes(content);
 
-        output.push_str("</p>");
+        self.output.push_str("</p>");
         if self.options.pretty_print {
-            output.push('\n');
+            self.output.push('\n');
         }
 
         Ok(())
     }
 
-    fn render_list(&self, ordered: bool, items: &[AstNode], output: &mut String, depth: usize) -> Result<(), MarkdownError> {
-        let indent = if self.options.pretty_print { "  ".repeat(depth) } else { String::new() };
+    fn render_list(&mut self, ordered: bool, start: u32, items: &[AstNode], span: Span) -> Result<(), MarkdownError> {
+        let indent = if self.options.pretty_print { "  ".repeat(self.depth) } else { String::new() };
         let tag = if ordered { "ol" } else { "ul" };
         
-        write!(output, "{}<{}", indent, tag)
+        write!(self.output, "{}<{}", indent, tag)
             .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
 
         let class = if ordered {
@@ -239,122 +514,555 @@ impl HtmlRenderer {
         };
 
         if let Some(ref class) = class {
-            write!(output, r#" class="{}""#, class)
+            write!(self.output, r#" class="{}""#, class)
+                .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
+        }
+
+        if ordered && start != 1 {
+            write!(self.output, r#" start="{}""#, start)
                 .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
         }
 
-        write!(output, "{}>", self.format_attributes(&self.options.custom_attributes.list))
+        write!(self.output, "{}{}>", self.source_position(span), self.format_attributes(&self.options.custom_attributes.list))
             .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
 
         if self.options.pretty_print {
-            output.push('\n');
+            self.output.push('\n');
         }
 
-        for item in items {
-            self.render_node(item, output, depth + 1)?;
-        }
+        self.visit_nested(items);
 
         if self.options.pretty_print {
-            output.push_str(&format!("{}</{}>", indent, tag));
+            self.output.push_str(&format!("{}</{}>", indent, tag));
         } else {
-            write!(output, "</{}>", tag)
+            write!(self.output, "</{}>", tag)
                 .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
         }
 
         if self.options.pretty_print {
-            output.push('\n');
+            self.output.push('\n');
         }
 
         Ok(())
     }
 
-    fn render_list_item(&self, content: &[AstNode], output: &mut String, depth: usize) -> Result<(), MarkdownError> {
-        let indent = if self.options.pretty_print { "  ".repeat(depth) } else { String::new() };
+    /// Render a list item; `task` holds the checkbox state of task items
+    fn render_list_item(&mut self, task: Option<bool>, content: &[AstNode], span: Span) -> Result<(), MarkdownError> {
+        let indent = if self.options.pretty_print { "  ".repeat(self.depth) } else { String::new() };
         
-        write!(output, "{}<li", indent)
+        write!(self.output, "{}<li", indent)
             .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
 
-        if let Some(ref class) = self.options.css_classes.list_item {
-            write!(output, r#" class="{}""#, class)
+        let class = match task {
+            Some(_) => self.options.css_classes.task_item.as_ref().or(self.options.css_classes.list_item.as_ref()),
+            None => self.options.css_classes.list_item.as_ref(),
+        };
+        if let Some(class) = class {
+            write!(self.output, r#" class="{}""#, class)
                 .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
         }
 
-        output.push('>');
+        self.output.push_str(&self.source_position(span));
+        self.output.push('>');
+
+        // The item's offset lets the UI find the checkbox again in the source, see `toggle_task`
+        if let Some(checked) = task {
+            write!(self.output, r#"<input type="checkbox" data-task-offset="{}"{}> "#, span.start.offset, if checked { " checked" } else { "" })
+                .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
+        }
 
+        // Block children (paragraphs of loose items, nested lists) go on their own lines when pretty printing
+        self.open_tags.push(Vec::new());
+        let mut after_block = false;
         for child in content {
-            self.render_node(child, output, depth)?;
+            if child.is_inline() {
+                self.visit_node(child);
+                after_block = false;
+            } else {
+                if self.options.pretty_print && !self.output.ends_with('\n') {
+                    self.output.push('\n');
+                }
+                self.visit_nested(std::slice::from_ref(child));
+                after_block = true;
+            }
         }
+        self.close_open_tags();
 
-        output.push_str("</li>");
+        if after_block {
+            self.output.push_str(&indent);
+        }
+        self.output.push_str("</li>");
         if self.options.pretty_print {
-            output.push('\n');
+            self.output.push('\n');
         }
 
         Ok(())
     }
 
-    fn render_bold(&self, content: &[AstNode], output: &mut String, depth: usize) -> Result<(), MarkdownError> {
-        if let Some(ref class) = self.options.css_classes.bold {
-            write!(output, r#"<strong class="{}">"#, class)
+    fn render_table(&mut self, alignments: &[Alignment], header: &[AstNode], rows: &[Vec<AstNode>], span: Span) -> Result<(), MarkdownError> {
+        let indent = if self.options.pretty_print { "  ".repeat(self.depth) } else { String::new() };
+
+        write!(self.output, "{}<table", indent)
+            .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
+
+        if let Some(ref class) = self.options.css_classes.table {
+            write!(self.output, r#" class="{}""#, class)
                 .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
+        }
+
+        self.output.push_str(&self.source_position(span));
+        self.output.push('>');
+        if self.options.pretty_print {
+            self.output.push('\n');
+        }
+
+        self.depth += 1;
+        self.render_table_section("thead", "th", alignments, [header])?;
+        if !rows.is_empty() {
+            self.render_table_section("tbody", "td", alignments, rows.iter().map(Vec::as_slice))?;
+        }
+        self.depth -= 1;
+
+        write!(self.output, "{}</table>", indent)
+            .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
+        if self.options.pretty_print {
+            self.output.push('\n');
+        }
+
+        Ok(())
+    }
+
+    fn render_table_section<'r>(&mut self, tag: &str, cell_tag: &str, alignments: &[Alignment], rows: impl IntoIterator<Item = &'r [AstNode]>) -> Result<(), MarkdownError> {
+        let (indent, row_indent, newline) = if self.options.pretty_print {
+            ("  ".repeat(self.depth), "  ".repeat(self.depth + 1), "\n")
         } else {
-            output.push_str("<strong>");
+            (String::new(), String::new(), "")
+        };
+
+        write!(self.output, "{}<{}>{}", indent, tag, newline)
+            .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
+
+        for row in rows {
+            write!(self.output, "{}<tr>{}", row_indent, newline)
+                .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
+            for (cell, alignment) in row.iter().zip(alignments) {
+                let content = match cell {
+                    AstNode::TableCell { content, .. } => content.as_slice(),
+                    other => std::slice::from_ref(other),
+                };
+                self.depth += 2;
+                self.render_table_cell(cell_tag, *alignment, content)?;
+                self.depth -= 2;
+            }
+            write!(self.output, "{}</tr>{}", row_indent, newline)
+                .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
         }
 
-        for child in content {
-            self.render_node(child, output, depth)?;
+        write!(self.output, "{}</{}>{}", indent, tag, newline)
+            .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
+
+        Ok(())
+    }
+
+    fn render_table_cell(&mut self, tag: &str, alignment: Alignment, content: &[AstNode]) -> Result<(), MarkdownError> {
+        let indent = if self.options.pretty_print { "  ".repeat(self.depth) } else { String::new() };
+
+        write!(self.output, "{}<{}", indent, tag)
+            .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
+
+        let align = match alignment {
+            Alignment::None => None,
+            Alignment::Left => Some("left"),
+            Alignment::Center => Some("center"),
+            Alignment::Right => Some("right"),
+        };
+        if let Some(align) = align {
+            write!(self.output, r#" align="{}""#, align)
+                .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
+        }
+
+        self.output.push('>');
+
+        self.visit_nodes(content);
+
+        write!(self.output, "</{}>", tag)
+            .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
+        if self.options.pretty_print {
+            self.output.push('\n');
         }
 
-        output.push_str("</strong>");
         Ok(())
     }
 
-    fn render_italic(&self, content: &[AstNode], output: &mut String, depth: usize) -> Result<(), MarkdownError> {
+    fn render_code_block(&mut self, info: Option<&str>, content: &str, span: Span) -> Result<(), MarkdownError> {
+        let indent = if self.options.pretty_print { "  ".repeat(self.depth) } else { String::new() };
+
+        write!(self.output, "{}<pre", indent)
+            .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
+
+        if let Some(ref class) = self.options.css_classes.code_block {
+            write!(self.output, r#" class="{}""#, class)
+                .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
+        }
+
+        self.output.push_str(&self.source_position(span));
+        self.output.push_str("><code");
+
+        // The first word of the info string names the language, e.g. ```rust ignore
+        if let Some(language) = info.and_then(|info| info.split_whitespace().next()) {
+            write!(self.output, r#" class="language-{}""#, self.escape_attribute(language))
+                .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
+        }
+
+        // Code is always escaped, since it is shown as written rather than interpreted
+        write!(self.output, ">{}</code></pre>", self.escape_html_text(content))
+            .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
+
+        if self.options.pretty_print {
+            self.output.push('\n');
+        }
+
+        Ok(())
+    }
+
+    fn render_block_quote(&mut self, children: &[AstNode], span: Span) -> Result<(), MarkdownError> {
+        let indent = if self.options.pretty_print { "  ".repeat(self.depth) } else { String::new() };
+
+        write!(self.output, "{}<blockquote", indent)
+            .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
+
+        if let Some(ref class) = self.options.css_classes.blockquote {
+            write!(self.output, r#" class="{}""#, class)
+                .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
+        }
+
+        self.output.push_str(&self.source_position(span));
+        self.output.push('>');
+        if self.options.pretty_print {
+            self.output.push('\n');
+        }
+
+        self.visit_nested(children);
+
+        write!(self.output, "{}</blockquote>", indent)
+            .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
+        if self.options.pretty_print {
+            self.output.push('\n');
+        }
+
+        Ok(())
+    }
+
+    fn render_thematic_break(&mut self, span: Span) -> Result<(), MarkdownError> {
+        let indent = if self.options.pretty_print { "  ".repeat(self.depth) } else { String::new() };
+
+        if let Some(ref class) = self.options.css_classes.thematic_break {
+            write!(self.output, r#"{}<hr class="{}"{}>"#, indent, class, self.source_position(span))
+                .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
+        } else {
+            write!(self.output, "{}<hr{}>", indent, self.source_position(span))
+                .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
+        }
+
+        if self.options.pretty_print {
+            self.output.push('\n');
+        }
+
+        Ok(())
+    }
+
+    fn render_code(&mut self, code: &str) -> Result<(), MarkdownError> {
+        if let Some(ref class) = self.options.css_classes.code {
+            write!(self.output, r#"<code class="{}">"#, class)
+                .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
+        } else {
+            self.output.push_str("<code>");
+        }
+
+        self.output.push_str(&self.escape_html_text(code));
+        self.output.push_str("</code>");
+        Ok(())
+    }
+
+    fn render_bold(&mut self, content: &[AstNode]) -> Result<(), MarkdownError> {
+        if let Some(ref class) = self.options.css_classes.bold {
+            write!(self.output, r#"<strong class="{}">"#, class)
+                .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
+        } else {
+            self.output.push_str("<strong>");
+        }
+
+        self.visit_nodes(content);
+
+        self.output.push_str("</strong>");
+        Ok(())
+    }
+
+    fn render_italic(&mut self, content: &[AstNode]) -> Result<(), MarkdownError> {
         if let Some(ref class) = self.options.css_classes.italic {
-            write!(output, r#"<em class="{}">"#, class)
+            write!(self.output, r#"<em class="{}">"#, class)
                 .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
         } else {
-            output.push_str("<em>");
+            self.output.push_str("<em>");
         }
 
-        for child in content {
-            self.render_node(child, output, depth)?;
+        self.visit_nodes(content);
+
+        self.output.push_str("</em>");
+        Ok(())
+    }
+
+    fn render_inline_tag(&mut self, tag: &str, class: Option<&str>, attributes: &str, content: &[AstNode]) -> Result<(), MarkdownError> {
+        write!(self.output, "<{}", tag)
+            .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
+
+        if let Some(class) = class {
+            write!(self.output, r#" class="{}""#, class)
+                .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
         }
 
-        output.push_str("</em>");
+        write!(self.output, "{}>", attributes)
+            .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
+
+        self.visit_nodes(content);
+
+        write!(self.output, "</{}>", tag)
+            .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
         Ok(())
     }
 
-    fn render_link(&self, text: &[AstNode], url: &str, output: &mut String, depth: usize) -> Result<(), MarkdownError> {
-        write!(output, r#"<a href="{}""#, self.escape_attribute(url))
+    fn render_link(&mut self, text: &[AstNode], url: &str, title: Option<&str>) -> Result<(), MarkdownError> {
+        // Links to URLs outside the policy, such as `javascript:`, keep only their text
+        if !self.options.url_policy.allows(url) {
+            self.visit_nodes(text);
+            return Ok(());
+        }
+
+        write!(self.output, r#"<a href="{}""#, self.escape_attribute(url))
             .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
 
         if let Some(ref class) = self.options.css_classes.link {
-            write!(output, r#" class="{}""#, class)
+            write!(self.output, r#" class="{}""#, class)
+                .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
+        }
+
+        if let Some(title) = title {
+            write!(self.output, r#" title="{}""#, self.escape_attribute(title))
                 .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
         }
 
-        if self.options.external_links_new_tab && (url.starts_with("http://") || url.starts_with("https://")) {
-            output.push_str(r#" target="_blank" rel="noopener noreferrer""#);
+        let external = matches!(url_scheme(url).as_deref(), Some("http" | "https"));
+        if external && self.options.external_links_new_tab {
+            self.output.push_str(r#" target="_blank""#);
         }
+        let rel = match self.options.url_policy.rel {
+            Some(ref rel) if external => Some(rel.as_str()),
+            None if external && self.options.external_links_new_tab => Some("noopener noreferrer"),
+            _ => None,
+        };
+        if let Some(rel) = rel {
+            write!(self.output, r#" rel="{}""#, self.escape_attribute(rel))
+                .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
+        }
+
+        write!(self.output, "{}>", self.format_attributes(&self.options.custom_attributes.link))
+            .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
+
+        self.visit_nodes(text);
 
-        write!(output, "{}>{}", self.format_attributes(&self.options.custom_attributes.link), if self.options.pretty_print { "" } else { "" })
+        self.output.push_str("</a>");
+        Ok(())
+    }
+
+    fn render_image(&mut self, alt: &str, url: &str, title: Option<&str>) -> Result<(), MarkdownError> {
+        let Some(url) = self.image_source(url) else {
+            return self.render_text(alt);
+        };
+        write!(self.output, r#"<img src="{}" alt="{}""#, self.escape_attribute(&url), self.escape_attribute(alt))
             .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
 
-        for child in text {
-            self.render_node(child, output, depth)?;
+        if let Some(title) = title {
+            write!(self.output, r#" title="{}""#, self.escape_attribute(title))
+                .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
+        }
+
+        if let Some(ref class) = self.options.css_classes.image {
+            write!(self.output, r#" class="{}""#, class)
+                .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
+        }
+
+        if self.options.lazy_load_images {
+            self.output.push_str(r#" loading="lazy""#);
         }
 
-        output.push_str("</a>");
+        self.output.push('>');
         Ok(())
     }
 
-    fn render_text(&self, text: &str, output: &mut String) -> Result<(), MarkdownError> {
-        if self.options.escape_html {
-            output.push_str(&self.escape_html_text(text));
+    /// The URL to load an image from, or `None` when its scheme isn't allowed
+    fn image_source(&self, url: &str) -> Option<String> {
+        if !self.options.url_policy.allows_scheme(url, &self.options.image_schemes) {
+            return None;
+        }
+
+        Some(match self.options.rewrite_image_url {
+            Some(ref rewriter) => rewriter.rewrite(url),
+            None => url.to_string(),
+        })
+    }
+
+    /// A link to the referenced title's page, shown as a chip with its poster and title once resolved
+    fn render_media_ref(&mut self, media_ref: &MediaRef) -> Result<(), MarkdownError> {
+        let resolved = self.options.media_resolver.as_ref().and_then(|resolver| resolver.resolve(media_ref));
+        let class = self.options.css_classes.media_ref.as_deref().unwrap_or("media-ref");
+        let media_type = media_ref.media_type.as_str();
+
+        write!(
+            self.output,
+            r#"<a href="/{}/{}" class="{}{}" data-media-type="{}" data-media-id="{}""#,
+            media_type,
+            media_ref.id,
+            class,
+            if resolved.is_some() { "" } else { " unresolved" },
+            media_type,
+            media_ref.id
+        )
+        .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
+
+        if let Some(episode) = media_ref.episode {
+            write!(self.output, r#" data-season="{}" data-episode="{}""#, episode.season, episode.episode)
+                .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
+        }
+        self.output.push('>');
+
+        match resolved {
+            Some(media) => {
+                if let Some(poster) = media.poster_url.as_deref().and_then(|url| self.image_source(url)) {
+                    write!(self.output, r#"<img src="{}" alt="""#, self.escape_attribute(&poster))
+                        .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
+                    if self.options.lazy_load_images {
+                        self.output.push_str(r#" loading="lazy""#);
+                    }
+                    self.output.push('>');
+                }
+
+                // Titles come from the library or TMDB rather than the notes, so they are always escaped
+                write!(self.output, "<span>{}</span>", self.escape_html_text(&media.title))
+                    .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
+                if let Some(episode) = media_ref.episode {
+                    write!(self.output, " <span>{}</span>", episode)
+                        .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
+                }
+            },
+            None => {
+                write!(self.output, "{}", media_ref).map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
+            },
+        }
+
+        self.output.push_str("</a>");
+        Ok(())
+    }
+
+    /// A numbered link to the footnote. Footnotes are numbered in the order they are first referenced,
+    /// and each reference gets its own id for the footnote to link back to.
+    fn render_footnote_reference(&mut self, label: &str) -> Result<(), MarkdownError> {
+        let number = match self.footnotes.iter().position(|(referenced, _)| referenced == label) {
+            Some(index) => index + 1,
+            None => {
+                self.footnotes.push((label.to_string(), 0));
+                self.footnotes.len()
+            },
+        };
+        self.footnotes[number - 1].1 += 1;
+
+        write!(
+            self.output,
+            r##"<sup class="footnote-ref"><a href="#fn-{}" id="{}">{}</a></sup>"##,
+            number,
+            Self::footnote_reference_id(number, self.footnotes[number - 1].1),
+            number
+        )
+        .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
+        Ok(())
+    }
+
+    fn footnote_reference_id(number: usize, reference: usize) -> String {
+        if reference == 1 {
+            format!("fnref-{}", number)
         } else {
-            output.push_str(text);
+            format!("fnref-{}-{}", number, reference)
+        }
+    }
+
+    /// The section listing the referenced footnotes of the document in `children`, each followed by links
+    /// back to where it was referenced. Footnotes nobody references are left out.
+    fn render_footnotes(&mut self, children: &[AstNode]) -> Result<(), MarkdownError> {
+        if self.footnotes.is_empty() {
+            return Ok(());
+        }
+        let mut definitions = FootnoteDefinitions::default();
+        definitions.visit_nodes(children);
+
+        let depth = self.depth;
+        let newline = if self.options.pretty_print { "\n" } else { "" };
+        let pretty_print = self.options.pretty_print;
+        let indent = |depth: usize| if pretty_print { "  ".repeat(depth) } else { String::new() };
+        write!(self.output, r#"{}<section class="footnotes">{}{}<ol>{}"#, indent(depth), newline, indent(depth + 1), newline)
+            .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
+
+        // Footnotes can reference further footnotes, which are added to the list while it is rendered
+        let mut number = 0;
+        while let Some((label, references)) = self.footnotes.get(number).cloned() {
+            number += 1;
+            let Some(content) = definitions.0.get(label.as_str()) else { continue };
+
+            // Rendered on its own first, to put the links back inside its last paragraph
+            let output = std::mem::take(&mut self.output);
+            self.depth = depth + 3;
+            self.visit_nodes(content);
+            self.depth = depth;
+            let item = std::mem::replace(&mut self.output, output);
+
+            let backrefs: Vec<String> = (1..=references)
+                .map(|reference| {
+                    format!(
+                        r##"<a href="#{}" class="footnote-backref" aria-label="Back to reference {}">↩</a>"##,
+                        Self::footnote_reference_id(number, reference),
+                        number
+                    )
+                })
+                .collect();
+            let item = match item.trim_end().strip_suffix("</p>") {
+                Some(paragraph) => format!("{} {}</p>{}", paragraph, backrefs.join(" "), newline),
+                None => format!("{}{}{}", item, indent(depth + 3), backrefs.join(" ")),
+            };
+
+            write!(self.output, r#"{}<li id="fn-{}">{}{}{}</li>{}"#, indent(depth + 2), number, newline, item, indent(depth + 2), newline)
+                .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
         }
+
+        write!(self.output, "{}</ol>{}{}</section>{}", indent(depth + 1), newline, indent(depth), newline)
+            .map_err(|e| MarkdownError::RenderError { message: e.to_string() })?;
+        Ok(())
+    }
+
+    fn render_text(&mut self, text: &str) -> Result<(), MarkdownError> {
+        self.output.push_str(&self.escape_html_text(text));
+        Ok(())
+    }
+
+    fn render_html(&mut self, html: &str) -> Result<(), MarkdownError> {
+        let html = match (self.options.escape_html, &self.options.html_allowlist) {
+            (false, _) => html.to_string(),
+            (true, Some(allowlist)) => {
+                let mut open_tags = self.open_tags.pop().unwrap_or_default();
+                let html = allowlist.sanitize_tag(html, &mut open_tags, &self.options.url_policy, |text| self.escape_html_text(text));
+                self.open_tags.push(open_tags);
+                html
+            },
+            (true, None) => self.escape_html_text(html),
+        };
+        self.output.push_str(&html);
         Ok(())
     }
 
@@ -409,9 +1117,13 @@ impl Renderer for HtmlRenderer {
     type Error = MarkdownError;
 
     fn render(&self, node: &AstNode) -> Result<Self::Output, Self::Error> {
-        let mut output = String::new();
-        self.render_node(node, &mut output, 0)?;
-        Ok(output)
+        let mut writer = HtmlWriter::new(&self.options);
+        writer.visit_node(node);
+        writer.finish();
+        match writer.error {
+            Some(error) => Err(error),
+            None => Ok(writer.output),
+        }
     }
 }
 
@@ -425,16 +1137,19 @@ mod tests {
             children: vec![
                 AstNode::Heading {
                     level: 1,
-                    content: vec![AstNode::Text("Hello World".to_string())],
+                    content: vec![AstNode::Text("Hello World".to_string(), Span::default())],
+                    span: Span::default(),
                 },
                 AstNode::Paragraph {
                     content: vec![
-                        AstNode::Text("This is a ".to_string()),
-                        AstNode::Bold(vec![AstNode::Text("bold".to_string())]),
-                        AstNode::Text(" paragraph.".to_string()),
+                        AstNode::Text("This is a ".to_string(), Span::default()),
+                        AstNode::Bold(vec![AstNode::Text("bold".to_string(), Span::default())], Span::default()),
+                        AstNode::Text(" paragraph.".to_string(), Span::default()),
                     ],
+                    span: Span::default(),
                 },
             ],
+            span: Span::default(),
         }
     }
 
@@ -466,11 +1181,14 @@ mod tests {
     #[test]
     fn test_css_classes() {
         let ast = AstNode::Paragraph {
-            content: vec![AstNode::Text("Test".to_string())],
+            content: vec![AstNode::Text("Test".to_string(), Span::default())],
+            span: Span::default(),
         };
         
-        let mut css_classes = CssClasses::default();
-        css_classes.paragraph = Some("my-paragraph".to_string());
+        let css_classes = CssClasses {
+            paragraph: Some("my-paragraph".to_string()),
+            ..Default::default()
+        };
         
         let options = HtmlOptions {
             css_classes,
@@ -485,7 +1203,7 @@ mod tests {
 
     #[test]
     fn test_html_escaping() {
-        let ast = AstNode::Text("<script>alert('xss')</script>".to_string());
+        let ast = AstNode::Text("<script>alert('xss')</script>".to_string(), Span::default());
         let renderer = HtmlRenderer::new();
         let html = renderer.render(&ast).unwrap();
         
@@ -496,8 +1214,10 @@ mod tests {
     #[test]
     fn test_external_links() {
         let ast = AstNode::Link {
-            text: vec![AstNode::Text("External Link".to_string())],
+            text: vec![AstNode::Text("External Link".to_string(), Span::default())],
             url: "https://example.com".to_string(),
+            title: None,
+            span: Span::default(),
         };
         
         let options = HtmlOptions {
@@ -516,14 +1236,18 @@ mod tests {
     fn test_lists() {
         let ast = AstNode::List {
             ordered: true,
+            start: 1,
             items: vec![
                 AstNode::ListItem {
-                    content: vec![AstNode::Text("First item".to_string())],
+                    content: vec![AstNode::Text("First item".to_string(), Span::default())],
+                    span: Span::default(),
                 },
                 AstNode::ListItem {
-                    content: vec![AstNode::Text("Second item".to_string())],
+                    content: vec![AstNode::Text("Second item".to_string(), Span::default())],
+                    span: Span::default(),
                 },
             ],
+            span: Span::default(),
         };
         
         let renderer = HtmlRenderer::new();
@@ -537,7 +1261,7 @@ mod tests {
 
     #[test]
     fn test_empty_content() {
-        let ast = AstNode::Paragraph { content: vec![] };
+        let ast = AstNode::Paragraph { content: vec![], span: Span::default() };
         let renderer = HtmlRenderer::new();
         let html = renderer.render(&ast).unwrap();
         
@@ -546,10 +1270,149 @@ mod tests {
 
     #[test]
     fn test_line_break() {
-        let ast = AstNode::LineBreak;
+        let ast = AstNode::LineBreak(Span::default());
         let renderer = HtmlRenderer::new();
         let html = renderer.render(&ast).unwrap();
         
         assert_eq!(html, "<br>");
     }
-}
\ No newline at end of file
+
+    fn table_ast() -> AstNode {
+        let cell = |text: &str| AstNode::TableCell { content: vec![AstNode::Text(text.to_string(), Span::default())], span: Span::default() };
+        AstNode::Table {
+            alignments: vec![Alignment::Left, Alignment::None],
+            header: vec![cell("Title"), cell("Year")],
+            rows: vec![vec![cell("Heat"), cell("1995")]],
+            span: Span::default(),
+        }
+    }
+
+    #[test]
+    fn test_table() {
+        let renderer = HtmlRenderer::new();
+        let html = renderer.render(&table_ast()).unwrap();
+
+        assert_eq!(
+            html,
+            r#"<table><thead><tr><th align="left">Title</th><th>Year</th></tr></thead><tbody><tr><td align="left">Heat</td><td>1995</td></tr></tbody></table>"#
+        );
+    }
+
+    #[test]
+    fn test_table_without_body() {
+        let ast = AstNode::Table {
+            alignments: vec![Alignment::Right],
+            header: vec![AstNode::TableCell { content: vec![AstNode::Text("Rating".to_string(), Span::default())], span: Span::default() }],
+            rows: vec![],
+            span: Span::default(),
+        };
+        let options = HtmlOptions {
+            css_classes: CssClasses { table: Some("md-table".to_string()), ..Default::default() },
+            ..Default::default()
+        };
+        let html = HtmlRenderer::with_options(options).render(&ast).unwrap();
+
+        assert_eq!(html, r#"<table class="md-table"><thead><tr><th align="right">Rating</th></tr></thead></table>"#);
+    }
+
+    #[test]
+    fn test_table_pretty_print() {
+        let options = HtmlOptions { pretty_print: true, ..Default::default() };
+        let html = HtmlRenderer::with_options(options).render(&table_ast()).unwrap();
+
+        assert!(html.starts_with("<table>\n  <thead>\n    <tr>\n      <th align=\"left\">Title</th>\n"));
+        assert!(html.ends_with("    </tr>\n  </tbody>\n</table>\n"));
+    }
+
+    #[test]
+    fn test_code_block() {
+        let ast = AstNode::CodeBlock {
+            info: Some("rust ignore".to_string()),
+            content: "let s = \"<b>\";\n".to_string(),
+            span: Span::default(),
+        };
+        let html = HtmlRenderer::new().render(&ast).unwrap();
+
+        assert_eq!(html, "<pre><code class=\"language-rust\">let s = &quot;&lt;b&gt;&quot;;\n</code></pre>");
+    }
+
+    #[test]
+    fn test_code_is_escaped_without_escape_html() {
+        let options = HtmlOptions {
+            escape_html: false,
+            css_classes: CssClasses { code: Some("inline".to_string()), ..Default::default() },
+            ..Default::default()
+        };
+        let ast = AstNode::Paragraph { content: vec![AstNode::Code("a < b && *c*".to_string(), Span::default())], span: Span::default() };
+        let html = HtmlRenderer::with_options(options).render(&ast).unwrap();
+
+        assert_eq!(html, r#"<p><code class="inline">a &lt; b &amp;&amp; *c*</code></p>"#);
+    }
+
+    #[test]
+    fn test_block_quote_and_thematic_break() {
+        let ast = AstNode::Document {
+            children: vec![
+                AstNode::BlockQuote {
+                    children: vec![AstNode::Paragraph { content: vec![AstNode::Text("Quoted".to_string(), Span::default())], span: Span::default() }],
+                    span: Span::default(),
+                },
+                AstNode::ThematicBreak(Span::default()),
+            ],
+            span: Span::default(),
+        };
+
+        let html = HtmlRenderer::new().render(&ast).unwrap();
+        assert_eq!(html, "<blockquote><p>Quoted</p></blockquote><hr>");
+
+        let options = HtmlOptions { pretty_print: true, ..Default::default() };
+        let html = HtmlRenderer::with_options(options).render(&ast).unwrap();
+        assert_eq!(html, "<blockquote>\n  <p>Quoted</p>\n</blockquote>\n<hr>\n");
+    }
+
+    #[test]
+    fn test_render_to() {
+        let ast = crate::parse_markdown("# Heat\n\n- [ ] rewatch[^1]\n\n> Quoted\n\n[^1]: Soon").unwrap();
+        let options = HtmlOptions {
+            pretty_print: true,
+            css_classes: CssClasses { document: Some("notes".to_string()), ..Default::default() },
+            ..Default::default()
+        };
+        let renderer = HtmlRenderer::with_options(options);
+        let html = renderer.render(&ast).unwrap();
+
+        let mut streamed = String::new();
+        renderer.render_to(&ast, &mut streamed).unwrap();
+        assert_eq!(streamed, html);
+
+        let mut bytes = Vec::new();
+        renderer.write_to(&ast, &mut bytes).unwrap();
+        assert_eq!(String::from_utf8(bytes).unwrap(), html);
+    }
+
+    #[test]
+    fn test_write_to_stops_at_io_errors() {
+        struct Full(usize);
+
+        impl io::Write for Full {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0 += 1;
+                match self.0 {
+                    1 => Ok(buf.len()),
+                    _ => Err(io::Error::new(io::ErrorKind::WriteZero, "disk full")),
+                }
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let ast = crate::parse_markdown("One\n\nTwo\n\nThree").unwrap();
+        let mut out = Full(0);
+        let error = HtmlRenderer::new().write_to(&ast, &mut out).unwrap_err();
+
+        assert!(matches!(error, MarkdownError::IoError(ref e) if e.kind() == io::ErrorKind::WriteZero));
+        assert_eq!(out.0, 2);
+    }
+}
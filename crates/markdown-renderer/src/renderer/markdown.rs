@@ -0,0 +1,458 @@
+use std::convert::Infallible;
+
+use crate::parser::{Alignment, AstNode};
+use crate::visit::Visitor;
+use super::{prefix_lines, Renderer};
+
+/// Writes a tree back out as markdown, e.g. to format notes or save them after editing the tree.
+///
+/// The output is normalized rather than a copy of the source: emphasis uses `*` and `**` where it
+/// can't run into other asterisks, bullets `-`, code blocks are fenced, reference links become inline
+/// links and setext headings become ATX ones unless they span several lines. Text is escaped wherever it could be read as markup, so parsing the
+/// output gives back the same tree.
+#[derive(Debug, Default)]
+pub struct MarkdownRenderer;
+
+impl MarkdownRenderer {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+/// Writes the markdown of one tree, each block into its own buffer so containers can prefix its lines
+#[derive(Default)]
+struct MarkdownWriter {
+    output: String,
+    // Whether the list being written follows a list of the same kind, and so needs other markers to
+    // stay a list of its own
+    alternate_list: bool,
+    in_heading: bool,
+    // Whether the node being written is followed by emphasis
+    before_emphasis: bool,
+}
+
+impl<'ast> Visitor<'ast> for MarkdownWriter {
+    fn visit_node(&mut self, node: &'ast AstNode) {
+        self.render_node(node);
+    }
+
+    fn visit_nodes(&mut self, nodes: &'ast [AstNode]) {
+        for (index, node) in nodes.iter().enumerate() {
+            self.before_emphasis = matches!(nodes.get(index + 1), Some(AstNode::Bold(..) | AstNode::Italic(..)));
+            self.render_node(node);
+        }
+    }
+}
+
+impl MarkdownWriter {
+    fn render_node(&mut self, node: &AstNode) {
+        match node {
+            AstNode::Document { children, .. } => {
+                let blocks = self.render_blocks(children, "\n\n");
+                if !blocks.is_empty() {
+                    self.output.push_str(&blocks);
+                    self.output.push('\n');
+                }
+            },
+            AstNode::Heading { level, content, .. } => self.render_heading(*level, content),
+            AstNode::Paragraph { content, .. } | AstNode::TableCell { content, .. } => self.visit_nodes(content),
+            AstNode::List { ordered, start, items, .. } => self.render_list(*ordered, *start, items),
+            // Items are written by their list, which knows their marker
+            AstNode::ListItem { content, .. } => self.render_list_item("-", content, "\n"),
+            AstNode::TaskItem { checked, content, .. } => {
+                self.render_list_item(if *checked { "- [x]" } else { "- [ ]" }, content, "\n")
+            },
+            AstNode::Table { alignments, header, rows, .. } => self.render_table(alignments, header, rows),
+            AstNode::CodeBlock { info, content, .. } => self.render_code_block(info.as_deref(), content),
+            AstNode::BlockQuote { children, .. } => {
+                let blocks = self.render_blocks(children, "\n\n");
+                self.output.push_str(&prefix_lines(&blocks, "> ", "> "));
+            },
+            AstNode::ThematicBreak(_) => self.output.push_str("---"),
+            AstNode::FootnoteDefinition { label, children, .. } => {
+                let blocks = self.render_blocks(children, "\n\n");
+                self.output.push_str(&prefix_lines(&blocks, &format!("[^{}]: ", label), "    "));
+            },
+            AstNode::Text(text, _) => self.render_text(text),
+            AstNode::Html(html, _) => self.output.push_str(html),
+            AstNode::Bold(content, _) => self.render_delimited(if self.underscores() { "__" } else { "**" }, content),
+            AstNode::Italic(content, _) => match content.as_slice() {
+                // `***both***`
+                [AstNode::Bold(content, _)] => self.render_delimited(if self.underscores() { "___" } else { "***" }, content),
+                _ => self.render_delimited(if self.underscores() { "_" } else { "*" }, content),
+            },
+            AstNode::Strikethrough(content, _) => self.render_delimited("~~", content),
+            AstNode::Highlight(content, _) => self.render_delimited("==", content),
+            AstNode::Spoiler(content, _) => self.render_delimited("||", content),
+            AstNode::Link { text, url, title, .. } => self.render_link(text, url, title.as_deref()),
+            AstNode::Image { alt, url, title, .. } => {
+                self.output.push_str("![");
+                self.render_text(alt);
+                self.output.push(']');
+                self.render_destination(url, title.as_deref());
+            },
+            AstNode::MediaRef(media_ref, _) => {
+                self.escape_last('!');
+                self.output.push_str(&format!("[[{}]]", media_ref));
+            },
+            AstNode::Code(code, _) => self.render_code(code),
+            AstNode::FootnoteReference { label, .. } => {
+                self.escape_last('!');
+                self.output.push_str(&format!("[^{}]", label));
+            },
+            AstNode::LineBreak(_) => self.output.push_str("\\\n"),
+        }
+    }
+
+    /// Write each of `nodes` into a buffer of its own, joined by `separator`
+    fn render_blocks(&mut self, nodes: &[AstNode], separator: &str) -> String {
+        let output = std::mem::take(&mut self.output);
+        let mut blocks = String::new();
+        let mut previous_list = None;
+        let mut alternate = false;
+        for (index, node) in nodes.iter().enumerate() {
+            if index > 0 {
+                // Quotes only a line apart are read back as one, even in a tight list
+                let quotes = matches!((&nodes[index - 1], node), (AstNode::BlockQuote { .. }, AstNode::BlockQuote { .. }));
+                blocks.push_str(if quotes { "\n\n" } else { separator });
+            }
+            // Two lists in a row would be read back as one, unless their markers differ. Lists nested in
+            // the previous one set the flag too, so it's kept here.
+            if let AstNode::List { ordered, .. } = node {
+                alternate = previous_list == Some(*ordered) && !alternate;
+                previous_list = Some(*ordered);
+            } else {
+                alternate = false;
+                previous_list = None;
+            }
+            self.alternate_list = alternate;
+            self.visit_node(node);
+            blocks.push_str(&std::mem::take(&mut self.output));
+        }
+        self.output = output;
+        blocks
+    }
+
+    fn render_heading(&mut self, level: u8, content: &[AstNode]) {
+        self.in_heading = true;
+        let output = std::mem::take(&mut self.output);
+        self.visit_nodes(content);
+        let text = std::mem::replace(&mut self.output, output);
+        self.in_heading = false;
+
+        // Only setext headings can span lines, and they only come in two levels
+        if text.contains('\n') && level <= 2 {
+            self.output.push_str(&text);
+            self.output.push_str(if level == 1 { "\n===" } else { "\n---" });
+        } else {
+            self.output.push_str(&"#".repeat(level as usize));
+            if !text.is_empty() {
+                self.output.push(' ');
+                self.output.push_str(&text.replace('\n', " "));
+            }
+        }
+    }
+
+    fn render_list(&mut self, ordered: bool, start: u32, items: &[AstNode]) {
+        // Loose lists wrap the content of their items in paragraphs and separate the items by blank lines
+        let loose = items.iter().any(|item| match item {
+            AstNode::ListItem { content, .. } | AstNode::TaskItem { content, .. } => {
+                content.iter().any(|child| matches!(child, AstNode::Paragraph { .. }))
+            },
+            _ => false,
+        });
+        let separator = if loose { "\n\n" } else { "\n" };
+        let (bullet, delimiter) = if self.alternate_list { ("*", ")") } else { ("-", ".") };
+
+        let mut rendered = Vec::new();
+        for (number, item) in (start..).zip(items) {
+            let marker = if ordered { format!("{}{}", number, delimiter) } else { bullet.to_string() };
+            let output = std::mem::take(&mut self.output);
+            match item {
+                AstNode::ListItem { content, .. } => self.render_list_item(&marker, content, separator),
+                AstNode::TaskItem { checked, content, .. } => {
+                    let marker = format!("{} [{}]", marker, if *checked { "x" } else { " " });
+                    self.render_list_item(&marker, content, separator);
+                },
+                other => self.visit_node(other),
+            }
+            rendered.push(std::mem::replace(&mut self.output, output));
+        }
+        self.output.push_str(&rendered.join(separator));
+    }
+
+    /// Write an item's inline content and blocks after `marker`, lining the lines that follow up with
+    /// the content. Task markers are left out of the alignment, like the parser does.
+    fn render_list_item(&mut self, marker: &str, content: &[AstNode], separator: &str) {
+        let mut parts = Vec::new();
+        // Of the blocks, only paragraphs and the headings of bullet items are read on the marker's line, the
+        // others start below it, as does a hard break that would be a lone backslash there
+        let bullet = !marker.starts_with(|c: char| c.is_ascii_digit());
+        let on_marker_line = |first: &AstNode| match first {
+            AstNode::Paragraph { content, .. } => !matches!(content.first(), Some(AstNode::LineBreak(_))),
+            AstNode::Heading { .. } => bullet,
+            AstNode::LineBreak(_) => false,
+            node => node.is_inline(),
+        };
+        if content.first().is_some_and(|first| !on_marker_line(first)) {
+            parts.push(String::new());
+        }
+        // A tight item holds its paragraphs' content unwrapped, so each run of inline nodes is one of them
+        let mut rest = content;
+        while let Some(first) = rest.first() {
+            let len = rest.iter().take_while(|child| child.is_inline() == first.is_inline()).count();
+            let (run, after) = rest.split_at(len);
+            if first.is_inline() {
+                let output = std::mem::take(&mut self.output);
+                self.visit_nodes(run);
+                parts.push(std::mem::replace(&mut self.output, output));
+            } else {
+                parts.push(self.render_blocks(run, separator));
+            }
+            rest = after;
+        }
+
+        let width = marker.split(' ').next().unwrap_or(marker).len() + 1;
+        let item = prefix_lines(&parts.join("\n"), &format!("{} ", marker), &" ".repeat(width));
+        let mut item = item.trim_end_matches(' ').to_string();
+        // A task marker is only read as one with a space after it, even when nothing follows on its line
+        if marker.ends_with(']') && item.split('\n').next() == Some(marker) {
+            item.insert(marker.len(), ' ');
+        }
+        self.output.push_str(&item);
+    }
+
+    fn render_table(&mut self, alignments: &[Alignment], header: &[AstNode], rows: &[Vec<AstNode>]) {
+        let mut lines = vec![self.render_table_row(header)];
+        let delimiters: Vec<&str> = alignments
+            .iter()
+            .map(|alignment| match alignment {
+                Alignment::None => "---",
+                Alignment::Left => ":---",
+                Alignment::Center => ":---:",
+                Alignment::Right => "---:",
+            })
+            .collect();
+        lines.push(format!("| {} |", delimiters.join(" | ")));
+        for row in rows {
+            lines.push(self.render_table_row(row));
+        }
+        self.output.push_str(&lines.join("\n"));
+    }
+
+    fn render_table_row(&mut self, cells: &[AstNode]) -> String {
+        let cells: Vec<String> = cells
+            .iter()
+            .map(|cell| {
+                let output = std::mem::take(&mut self.output);
+                self.visit_node(cell);
+                std::mem::replace(&mut self.output, output)
+            })
+            .collect();
+        format!("| {} |", cells.join(" | "))
+    }
+
+    fn render_code_block(&mut self, info: Option<&str>, content: &str) {
+        // Only tildes can fence an info string with a backtick in it. The fence has to be longer than any
+        // run of its character starting a line of the code.
+        let marker = if info.is_some_and(|info| info.contains('`')) { '~' } else { '`' };
+        let longest = content
+            .lines()
+            .map(|line| line.trim_start().chars().take_while(|&c| c == marker).count())
+            .max()
+            .unwrap_or(0);
+        let fence = marker.to_string().repeat(longest.max(2) + 1);
+
+        self.output.push_str(&fence);
+        // Escapes and character references in the info string are resolved when parsing, and a space keeps
+        // it apart from the fence
+        let info = info.unwrap_or("");
+        if info.starts_with(marker) {
+            self.output.push(' ');
+        }
+        self.output.push_str(&escape(info, "\\"));
+        self.output.push('\n');
+        self.output.push_str(content);
+        if !content.is_empty() && !content.ends_with('\n') {
+            self.output.push('\n');
+        }
+        self.output.push_str(&fence);
+    }
+
+    fn render_delimited(&mut self, delimiter: &str, content: &[AstNode]) {
+        // Text ending in the delimiter's character would make the run longer. A run of them is the closing
+        // delimiter of the node before.
+        let c = delimiter.chars().next().unwrap_or_default();
+        if !self.output.ends_with(&format!("{}{}", c, c)) {
+            self.escape_last(c);
+        }
+        self.output.push_str(delimiter);
+        self.visit_nodes(content);
+        self.escape_last(c);
+        self.output.push_str(delimiter);
+    }
+
+    fn render_link(&mut self, text: &[AstNode], url: &str, title: Option<&str>) {
+        // Links the parser made from bare URLs are written back as autolinks
+        if let ([AstNode::Text(text, _)], None) = (text, title) {
+            if text == url && (url.starts_with("http://") || url.starts_with("https://")) && !url.contains(['<', '>', ' ']) {
+                self.output.push_str(&format!("<{}>", url));
+                return;
+            }
+        }
+        // A `!` ending the text before the link would make it an image
+        self.escape_last('!');
+        self.output.push('[');
+        self.visit_nodes(text);
+        self.output.push(']');
+        self.render_destination(url, title);
+    }
+
+    fn render_destination(&mut self, url: &str, title: Option<&str>) {
+        // A pipe would end a table cell
+        self.output.push('(');
+        self.output.push_str(&escape(url, "\\()`<>|"));
+        if let Some(title) = title {
+            self.output.push_str(&format!(" \"{}\"", escape(title, "\\\"|")));
+        }
+        self.output.push(')');
+    }
+
+    /// Whether emphasis takes underscores rather than asterisks. Right after an asterisk they would run
+    /// into it, and so would the emphasis after it, which gets asterisks then. Underscores can't open
+    /// inside a word, though.
+    fn underscores(&self) -> bool {
+        let before = self.before_emphasis;
+        self.ends_with_unescaped('*')
+            || before && !self.ends_with_unescaped('_') && !self.output.ends_with(char::is_alphanumeric)
+    }
+
+    /// Whether the output ends with a `c` that isn't escaped
+    fn ends_with_unescaped(&self, c: char) -> bool {
+        self.output.strip_suffix(c).is_some_and(|before| (before.len() - before.trim_end_matches('\\').len()) % 2 == 0)
+    }
+
+    /// Escape the last character written when it's `c`, for markup that would run into it
+    fn escape_last(&mut self, c: char) {
+        if self.ends_with_unescaped(c) {
+            self.output.insert(self.output.len() - c.len_utf8(), '\\');
+        }
+    }
+
+    /// A code span fenced by more backticks than the longest run inside it
+    fn render_code(&mut self, code: &str) {
+        // The lexer reads `\|` in a code span as `|`, for pipes inside table cells
+        let code = &code.replace("\\|", "\\\\|");
+        let mut longest = 0;
+        let mut run = 0;
+        for c in code.chars() {
+            run = if c == '`' { run + 1 } else { 0 };
+            longest = longest.max(run);
+        }
+        let fence = "`".repeat(longest + 1);
+        // One space on both sides is stripped again when parsing, which keeps backticks at the edges apart
+        // from the fence
+        let padded = code.starts_with('`') || code.ends_with('`')
+            || (code.starts_with(' ') && code.ends_with(' ') && !code.trim().is_empty());
+        let padding = if padded { " " } else { "" };
+        self.output.push_str(&format!("{}{}{}{}{}", fence, padding, code, padding, fence));
+    }
+
+    /// Write text, escaping the characters that would otherwise be read as markup where they are
+    fn render_text(&mut self, text: &str) {
+        for (index, c) in text.char_indices() {
+            let line = self.output.rsplit('\n').next().unwrap_or("");
+            let rest = &text[index + c.len_utf8()..];
+            let escaped = match c {
+                '\\' | '`' | '*' | '_' | '[' | ']' | '|' | '~' => true,
+                '=' => line.is_empty() || line.ends_with('=') || rest.starts_with('='),
+                '#' => line.is_empty() || self.in_heading,
+                '-' | '+' | '>' => line.is_empty(),
+                // An ordered list marker
+                '.' | ')' => !line.is_empty() && line.bytes().all(|b| b.is_ascii_digit()),
+                '<' => rest.starts_with(|c: char| c.is_ascii_alphabetic() || c == '/'),
+                '&' => is_character_reference(rest),
+                // The `:` of what would be read as a bare URL, e.g. inside emphasis where URLs are text
+                ':' => rest.starts_with("//") && (line.ends_with("http") || line.ends_with("https")),
+                _ => false,
+            };
+            if escaped {
+                self.output.push('\\');
+            }
+            self.output.push(c);
+        }
+    }
+}
+
+/// Whether `rest`, following an `&`, would make it a character reference such as `&amp;` or `&#39;`
+fn is_character_reference(rest: &str) -> bool {
+    let name = rest.strip_prefix('#').unwrap_or(rest);
+    let len = name.chars().take_while(char::is_ascii_alphanumeric).count();
+    len > 0 && name[len..].starts_with(';')
+}
+
+/// Backslash-escape each of `special` in `text`, and the `&` of what would be read as a character reference
+fn escape(text: &str, special: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for (index, c) in text.char_indices() {
+        if special.contains(c) || (c == '&' && is_character_reference(&text[index + 1..])) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+impl Renderer for MarkdownRenderer {
+    type Output = String;
+    type Error = Infallible;
+
+    fn render(&self, node: &AstNode) -> Result<Self::Output, Self::Error> {
+        let mut writer = MarkdownWriter::default();
+        writer.visit_node(node);
+        Ok(writer.output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_markdown;
+
+    fn format(markdown: &str) -> String {
+        MarkdownRenderer::new().render(&parse_markdown(markdown).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_normalizes_markup() {
+        assert_eq!(
+            format("Title\n=====\n\n**Heat** and *Ronin*\n\n* one\n* two\n\n[Heat][heat]\n\n[heat]: /movie/949 \"Heat\""),
+            "# Title\n\n**Heat** and *Ronin*\n\n- one\n- two\n\n[Heat](/movie/949 \"Heat\")\n"
+        );
+    }
+
+    #[test]
+    fn test_escapes_text() {
+        assert_eq!(format(r"\*not emphasis\* 1 \< 2"), "\\*not emphasis\\* 1 < 2\n");
+        assert_eq!(format("1986\\. A year\n\\- not a list"), "1986\\. A year\n\\- not a list\n");
+        assert_eq!(format(r"\&amp; & \[x\]"), "\\&amp; & \\[x\\]\n");
+    }
+
+    #[test]
+    fn test_nested_blocks() {
+        assert_eq!(
+            format("> - a\n>   - b\n>\n> ```rust\n> let x = 1;\n> ```\n\n1. one\n\n   more\n2. two"),
+            "> - a\n>   - b\n>\n> ```rust\n> let x = 1;\n> ```\n\n1. one\n\n   more\n\n2. two\n"
+        );
+    }
+
+    #[test]
+    fn test_adjacent_lists_keep_apart() {
+        assert_eq!(format("- a\n* b\n+ c"), "- a\n\n* b\n\n- c\n");
+    }
+
+    #[test]
+    fn test_code_span_fences() {
+        assert_eq!(format("`` a`b `` and ` `` `"), "``a`b`` and ``` `` ```\n");
+    }
+}
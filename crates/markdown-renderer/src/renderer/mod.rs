@@ -1,10 +1,22 @@
+#[cfg(feature = "terminal")]
+pub mod ansi;
 #[cfg(feature = "html")]
 pub mod html;
+pub mod markdown;
+pub mod plain_text;
+#[cfg(feature = "html")]
+pub mod sanitize;
 
+#[cfg(feature = "terminal")]
+pub use ansi::AnsiRenderer;
 #[cfg(feature = "html")]
 pub use html::HtmlRenderer;
+pub use markdown::MarkdownRenderer;
+pub use plain_text::PlainTextRenderer;
+
+use std::fmt;
 
-use crate::parser::AstNode;
+use crate::parser::{AstNode, MediaRef};
 
 #[cfg(feature = "html")]
 use crate::error::MarkdownError;
@@ -18,6 +30,56 @@ pub trait Renderer {
     fn render(&self, node: &AstNode) -> Result<Self::Output, Self::Error>;
 }
 
+/// Looks up the titles `[[movie:603]]` references point at, e.g. in the local library or on TMDB
+pub trait MediaResolver: Send + Sync {
+    /// Display details of the referenced title, or `None` when it's unknown
+    fn resolve(&self, media_ref: &MediaRef) -> Option<ResolvedMedia>;
+}
+
+impl fmt::Debug for dyn MediaResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("MediaResolver(..)")
+    }
+}
+
+/// What a reference renders as
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedMedia {
+    pub title: String,
+    pub poster_url: Option<String>,
+}
+
+/// Render AST to text without formatting, see `PlainTextRenderer`
+pub fn render_plain_text(ast: &AstNode) -> String {
+    match PlainTextRenderer::new().render(ast) {
+        Ok(text) => text,
+        Err(never) => match never {},
+    }
+}
+
+/// Render AST back to normalized markdown, see `MarkdownRenderer`
+pub fn render_markdown(ast: &AstNode) -> String {
+    match MarkdownRenderer::new().render(ast) {
+        Ok(markdown) => markdown,
+        Err(never) => match never {},
+    }
+}
+
+/// `text` with `first` before its first line and `rest` before each line after it; blank lines only
+/// get the trimmed `rest`, so they carry no trailing whitespace
+pub(crate) fn prefix_lines(text: &str, first: &str, rest: &str) -> String {
+    let mut prefixed = String::with_capacity(text.len());
+    for (index, line) in text.split('\n').enumerate() {
+        if index > 0 {
+            prefixed.push('\n');
+        }
+        let prefix = if index == 0 { first } else { rest };
+        prefixed.push_str(if line.is_empty() { prefix.trim_end() } else { prefix });
+        prefixed.push_str(line);
+    }
+    prefixed
+}
+
 /// Render AST to HTML (only available with "html" feature)
 #[cfg(feature = "html")]
 pub fn render_html(ast: &AstNode) -> Result<String, MarkdownError> {
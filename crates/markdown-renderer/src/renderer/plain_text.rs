@@ -0,0 +1,219 @@
+use std::convert::Infallible;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::parser::{AstNode, MediaRef};
+use crate::visit::Visitor;
+use super::{prefix_lines, MediaResolver, Renderer};
+
+/// Renders the text of a tree without its formatting, for search indexing, previews and notifications.
+///
+/// Blocks are separated by blank lines and lists keep their markers and nesting, so the text still
+/// reads as it was laid out. Links and images become their text, table cells are separated by ` | `
+/// and spoilers are hidden as `[spoiler]`.
+#[derive(Default)]
+pub struct PlainTextRenderer {
+    media_resolver: Option<Arc<dyn MediaResolver>>,
+}
+
+impl PlainTextRenderer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Render `[[movie:603]]` references as the titles the resolver knows them by, rather than as written
+    pub fn with_media_resolver(media_resolver: Arc<dyn MediaResolver>) -> Self {
+        Self { media_resolver: Some(media_resolver) }
+    }
+}
+
+impl fmt::Debug for PlainTextRenderer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PlainTextRenderer").field("media_resolver", &self.media_resolver).finish()
+    }
+}
+
+/// Writes the text of one tree, each block into its own buffer so lists can indent its lines
+struct PlainTextWriter<'a> {
+    media_resolver: Option<&'a dyn MediaResolver>,
+    output: String,
+}
+
+impl<'ast> Visitor<'ast> for PlainTextWriter<'_> {
+    fn visit_node(&mut self, node: &'ast AstNode) {
+        self.render_node(node);
+    }
+}
+
+impl PlainTextWriter<'_> {
+    fn render_node(&mut self, node: &AstNode) {
+        match node {
+            AstNode::Document { children, .. } | AstNode::BlockQuote { children, .. } => {
+                let blocks = self.render_blocks(children, "\n\n");
+                self.output.push_str(&blocks);
+            },
+            AstNode::Heading { content, .. }
+            | AstNode::Paragraph { content, .. }
+            | AstNode::TableCell { content, .. }
+            | AstNode::Bold(content, _)
+            | AstNode::Italic(content, _)
+            | AstNode::Strikethrough(content, _)
+            | AstNode::Highlight(content, _)
+            | AstNode::Link { text: content, .. } => self.visit_nodes(content),
+            AstNode::List { ordered, start, items, .. } => {
+                let mut rendered = Vec::new();
+                for (number, item) in (*start..).zip(items) {
+                    let output = std::mem::take(&mut self.output);
+                    let marker = if *ordered { format!("{}.", number) } else { "-".to_string() };
+                    self.render_list_item(&marker, item);
+                    rendered.push(std::mem::replace(&mut self.output, output));
+                }
+                self.output.push_str(&rendered.join("\n"));
+            },
+            AstNode::ListItem { .. } | AstNode::TaskItem { .. } => self.render_list_item("-", node),
+            AstNode::Table { header, rows, .. } => {
+                let mut lines = vec![self.render_table_row(header)];
+                for row in rows {
+                    lines.push(self.render_table_row(row));
+                }
+                self.output.push_str(&lines.join("\n"));
+            },
+            AstNode::CodeBlock { content, .. } => self.output.push_str(content.trim_end_matches('\n')),
+            AstNode::ThematicBreak(_) => {},
+            AstNode::FootnoteDefinition { label, children, .. } => {
+                let blocks = self.render_blocks(children, "\n\n");
+                self.output.push_str(&format!("[{}] {}", label, blocks));
+            },
+            AstNode::Text(text, _) | AstNode::Code(text, _) | AstNode::Html(text, _) => self.output.push_str(text),
+            AstNode::Spoiler(..) => self.output.push_str("[spoiler]"),
+            AstNode::Image { alt, .. } => self.output.push_str(alt),
+            AstNode::MediaRef(media_ref, _) => self.render_media_ref(media_ref),
+            AstNode::FootnoteReference { label, .. } => self.output.push_str(&format!("[{}]", label)),
+            AstNode::LineBreak(_) => self.output.push('\n'),
+        }
+    }
+
+    /// Write each of `nodes` into a buffer of its own, joined by `separator` and leaving out those without
+    /// text, such as thematic breaks
+    fn render_blocks(&mut self, nodes: &[AstNode], separator: &str) -> String {
+        let output = std::mem::take(&mut self.output);
+        let mut blocks = Vec::new();
+        for node in nodes {
+            self.visit_node(node);
+            let block = std::mem::take(&mut self.output);
+            if !block.is_empty() {
+                blocks.push(block);
+            }
+        }
+        self.output = output;
+        blocks.join(separator)
+    }
+
+    /// Write an item after its marker, with the lines that follow indented under its text
+    fn render_list_item(&mut self, marker: &str, item: &AstNode) {
+        let (content, marker) = match item {
+            AstNode::ListItem { content, .. } => (content.as_slice(), marker.to_string()),
+            AstNode::TaskItem { checked, content, .. } => {
+                (content.as_slice(), format!("{} [{}]", marker, if *checked { "x" } else { " " }))
+            },
+            other => (std::slice::from_ref(other), marker.to_string()),
+        };
+
+        let mut parts = Vec::new();
+        let inline_len = content.iter().take_while(|child| child.is_inline()).count();
+        if inline_len > 0 {
+            let output = std::mem::take(&mut self.output);
+            self.visit_nodes(&content[..inline_len]);
+            parts.push(std::mem::replace(&mut self.output, output));
+        }
+        if inline_len < content.len() {
+            parts.push(self.render_blocks(&content[inline_len..], "\n"));
+        }
+
+        let width = marker.split(' ').next().unwrap_or(&marker).len() + 1;
+        let item = prefix_lines(&parts.join("\n"), &format!("{} ", marker), &" ".repeat(width));
+        self.output.push_str(item.trim_end_matches(' '));
+    }
+
+    fn render_table_row(&mut self, cells: &[AstNode]) -> String {
+        let cells: Vec<String> = cells
+            .iter()
+            .map(|cell| {
+                let output = std::mem::take(&mut self.output);
+                self.visit_node(cell);
+                std::mem::replace(&mut self.output, output)
+            })
+            .collect();
+        cells.join(" | ")
+    }
+
+    fn render_media_ref(&mut self, media_ref: &MediaRef) {
+        match self.media_resolver.and_then(|resolver| resolver.resolve(media_ref)) {
+            Some(media) => {
+                self.output.push_str(&media.title);
+                if let Some(episode) = media_ref.episode {
+                    self.output.push_str(&format!(" {}", episode));
+                }
+            },
+            None => self.output.push_str(&media_ref.to_string()),
+        }
+    }
+}
+
+impl Renderer for PlainTextRenderer {
+    type Output = String;
+    type Error = Infallible;
+
+    fn render(&self, node: &AstNode) -> Result<Self::Output, Self::Error> {
+        let mut writer = PlainTextWriter { media_resolver: self.media_resolver.as_deref(), output: String::new() };
+        writer.visit_node(node);
+        Ok(writer.output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_markdown;
+    use crate::renderer::ResolvedMedia;
+
+    fn plain_text(markdown: &str) -> String {
+        PlainTextRenderer::new().render(&parse_markdown(markdown).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_strips_formatting() {
+        assert_eq!(
+            plain_text("# *Heat* (1995)\n\nA **great** [heist](https://example.com) film with `code`.\n\n---\n\n> ![Poster](/p.png)"),
+            "Heat (1995)\n\nA great heist film with code.\n\nPoster"
+        );
+    }
+
+    #[test]
+    fn test_keeps_list_structure() {
+        assert_eq!(
+            plain_text("- [x] Season 1\n  - *Pilot*\n- [ ] Season 2\n\n3. three\n4. four"),
+            "- [x] Season 1\n  - Pilot\n- [ ] Season 2\n\n3. three\n4. four"
+        );
+    }
+
+    #[test]
+    fn test_hides_spoilers() {
+        assert_eq!(plain_text("The twist: ||he was dead all along||"), "The twist: [spoiler]");
+    }
+
+    #[test]
+    fn test_media_refs() {
+        struct Library;
+
+        impl MediaResolver for Library {
+            fn resolve(&self, media_ref: &MediaRef) -> Option<ResolvedMedia> {
+                (media_ref.id == 1399).then(|| ResolvedMedia { title: "Game of Thrones".to_string(), poster_url: None })
+            }
+        }
+
+        let ast = parse_markdown("[[tv:1399 S02E03]] and [[movie:603]]").unwrap();
+        let text = PlainTextRenderer::with_media_resolver(Arc::new(Library)).render(&ast).unwrap();
+        assert_eq!(text, "Game of Thrones S02E03 and movie:603");
+    }
+}
@@ -0,0 +1,206 @@
+//! Checks on the URLs and raw HTML in markdown, so rendered notes can't run script in the webview
+
+use crate::lexer::html::Tag;
+
+/// Which URLs links may point at. Links to other URLs render as their text only.
+#[derive(Debug, Clone)]
+pub struct UrlPolicy {
+    /// Schemes links may use, compared case-insensitively
+    pub allowed_schemes: Vec<String>,
+    /// Whether URLs without a scheme, such as `/movie/603` or `#cast`, are allowed, for links and images alike
+    pub allow_relative: bool,
+    /// `rel` attribute of links to `http`/`https` URLs, e.g. `noopener noreferrer nofollow`; when unset,
+    /// links opened in a new tab get `noopener noreferrer`
+    pub rel: Option<String>,
+}
+
+impl Default for UrlPolicy {
+    fn default() -> Self {
+        Self {
+            allowed_schemes: vec!["http".to_string(), "https".to_string(), "mailto".to_string()],
+            allow_relative: true,
+            rel: None,
+        }
+    }
+}
+
+impl UrlPolicy {
+    /// Whether a link may point at `url`
+    pub fn allows(&self, url: &str) -> bool {
+        self.allows_scheme(url, &self.allowed_schemes)
+    }
+
+    /// Whether `url` is relative and those are allowed, or its scheme is one of `schemes`
+    pub(crate) fn allows_scheme(&self, url: &str, schemes: &[String]) -> bool {
+        match url_scheme(url) {
+            Some(scheme) => schemes.iter().any(|allowed| allowed.eq_ignore_ascii_case(&scheme)),
+            None => self.allow_relative,
+        }
+    }
+}
+
+/// The lowercased scheme of `url`, or `None` for relative URLs. Browsers ignore tabs and newlines anywhere
+/// in a URL and control characters and spaces around it, so ` java\tscript:` counts as `javascript`.
+/// A scheme is a letter followed by letters, digits, `+`, `-` or `.` up to a colon, and protocol-relative
+/// `//host/path` URLs count as `https`. Browsers resolve anything else, like `foo):`, as a relative path.
+pub(crate) fn url_scheme(url: &str) -> Option<String> {
+    let url: String = url
+        .trim_matches(|ch: char| ch <= ' ')
+        .chars()
+        .filter(|&ch| !matches!(ch, '\t' | '\n' | '\r'))
+        .collect();
+
+    if url.starts_with("//") || url.starts_with("\\\\") {
+        return Some("https".to_string());
+    }
+    let (scheme, _) = url.split_once(':')?;
+    let valid = scheme.starts_with(|ch: char| ch.is_ascii_alphabetic())
+        && scheme.chars().all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '+' | '-' | '.'));
+    valid.then(|| scheme.to_ascii_lowercase())
+}
+
+/// Raw HTML tags let through when `HtmlOptions::html_allowlist` is set; all other markup stays escaped
+#[derive(Debug, Clone)]
+pub struct HtmlAllowlist {
+    /// Lowercase tag names
+    pub tags: Vec<String>,
+    /// Lowercase attribute names kept on allowed tags. Event handlers and `style` are always dropped,
+    /// and `href`/`src` values must pass the `UrlPolicy`.
+    pub attributes: Vec<String>,
+}
+
+impl Default for HtmlAllowlist {
+    fn default() -> Self {
+        let tags = ["b", "i", "em", "strong", "u", "s", "sub", "sup", "kbd", "mark", "small", "abbr", "br"];
+        Self {
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            attributes: vec!["title".to_string()],
+        }
+    }
+}
+
+/// Tags without content or a closing tag
+const VOID_TAGS: &[&str] = &["br", "wbr", "hr", "img"];
+
+impl HtmlAllowlist {
+    /// The HTML to write for the raw `html` tag: the tag with only its allowed attributes when it's allowed,
+    /// escaped otherwise. `open_tags` holds the allowed tags opened so far in the same block; a closing tag
+    /// only passes when it closes one of them, closing those opened after it as well, so raw HTML can't
+    /// close or break out of the renderer's own elements. What's left open is closed with `close_tags`.
+    pub(crate) fn sanitize_tag(&self, html: &str, open_tags: &mut Vec<String>, policy: &UrlPolicy, escape: impl Fn(&str) -> String) -> String {
+        let Some((tag, _)) = Tag::parse(html).filter(|&(_, len)| len == html.len()) else {
+            return escape(html);
+        };
+        if !self.tags.contains(&tag.name) {
+            return escape(html);
+        }
+
+        if tag.closing {
+            return match open_tags.iter().rposition(|open| *open == tag.name) {
+                Some(position) => close_tags(open_tags, position),
+                None => escape(html),
+            };
+        }
+
+        let mut output = format!("<{}", tag.name);
+        for (name, value) in &tag.attributes {
+            let allowed = self.attributes.contains(name)
+                && !name.starts_with("on")
+                && name != "style"
+                && (!matches!(name.as_str(), "href" | "src") || policy.allows(value));
+            if allowed {
+                output.push_str(&format!(r#" {}="{}""#, name, escape(value)));
+            }
+        }
+        output.push('>');
+
+        if !VOID_TAGS.contains(&tag.name.as_str()) && !tag.self_closing {
+            open_tags.push(tag.name);
+        }
+        output
+    }
+}
+
+/// Closing tags for the `open_tags` from index `from` on, innermost first, which are removed
+pub(crate) fn close_tags(open_tags: &mut Vec<String>, from: usize) -> String {
+    open_tags.drain(from..).rev().map(|open| format!("</{}>", open)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn escape(text: &str) -> String {
+        text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+    }
+
+    /// Sanitize the tags in `text` as the renderer does those of a paragraph, escaping the rest
+    fn sanitize(text: &str) -> String {
+        let allowlist = HtmlAllowlist::default();
+        let mut open_tags = Vec::new();
+        let mut output = String::new();
+        let mut rest = text;
+        while let Some(start) = rest.find('<') {
+            output.push_str(&escape(&rest[..start]));
+            let len = Tag::parse(&rest[start..]).map_or(1, |(_, len)| len);
+            output.push_str(&allowlist.sanitize_tag(&rest[start..start + len], &mut open_tags, &UrlPolicy::default(), escape));
+            rest = &rest[start + len..];
+        }
+        output.push_str(&escape(rest));
+        output + &close_tags(&mut open_tags, 0)
+    }
+
+    #[test]
+    fn test_url_scheme() {
+        assert_eq!(url_scheme("https://example.com"), Some("https".to_string()));
+        assert_eq!(url_scheme(" JaVa\tScRiPt:alert(1)"), Some("javascript".to_string()));
+        assert_eq!(url_scheme("\u{1}javascript:alert(1)"), Some("javascript".to_string()));
+        assert_eq!(url_scheme("//evil.example"), Some("https".to_string()));
+        // Written as `&amp;#106;`, so browsers see a relative URL with a fragment rather than an entity
+        assert_eq!(url_scheme("&#106;avascript:x"), None);
+        assert_eq!(url_scheme("/movie/603"), None);
+        assert_eq!(url_scheme("notes/a:b"), None);
+        assert_eq!(url_scheme("foo):"), None);
+        assert_eq!(url_scheme("web+movie:603"), Some("web+movie".to_string()));
+        assert_eq!(url_scheme("#cast"), None);
+    }
+
+    #[test]
+    fn test_url_policy() {
+        let policy = UrlPolicy::default();
+
+        assert!(policy.allows("mailto:neo@example.com"));
+        assert!(policy.allows("../posters/603.jpg"));
+        assert!(!policy.allows("javascript:alert(1)"));
+        assert!(!policy.allows("data:text/html,<script>alert(1)</script>"));
+        assert!(!policy.allows("vbscript:msgbox"));
+
+        let policy = UrlPolicy { allow_relative: false, ..Default::default() };
+        assert!(!policy.allows("/movie/603"));
+    }
+
+    #[test]
+    fn test_sanitize_keeps_allowed_tags() {
+        assert_eq!(sanitize("Press <kbd>Ctrl</kbd>+<KBD>C</KBD>"), "Press <kbd>Ctrl</kbd>+<kbd>C</kbd>");
+        assert_eq!(
+            sanitize(r#"<abbr title="Hypertext" onclick="x()" style="color:red">HTML</abbr><br/>"#),
+            r#"<abbr title="Hypertext">HTML</abbr><br>"#
+        );
+    }
+
+    #[test]
+    fn test_sanitize_escapes_everything_else() {
+        assert_eq!(sanitize("<script>alert(1)</script>"), "&lt;script&gt;alert(1)&lt;/script&gt;");
+        assert_eq!(sanitize("<img src=x onerror=alert(1)>"), "&lt;img src=x onerror=alert(1)&gt;");
+        assert_eq!(sanitize("a < b > c"), "a &lt; b &gt; c");
+        assert_eq!(sanitize("<b onmouseover=alert(1)>x"), "<b>x</b>");
+    }
+
+    #[test]
+    fn test_sanitize_balances_tags() {
+        // Stray closing tags are escaped and unclosed ones closed, innermost first
+        assert_eq!(sanitize("</b></p>x"), "&lt;/b&gt;&lt;/p&gt;x");
+        assert_eq!(sanitize("<b><i>x</b> y"), "<b><i>x</i></b> y");
+        assert_eq!(sanitize("<sub><sup>x"), "<sub><sup>x</sup></sub>");
+    }
+}
@@ -0,0 +1,80 @@
+//! Source positions of tokens and AST nodes, so rendered output can be mapped back to the markdown
+
+use std::ops::Range;
+
+/// A point in the source: a byte offset plus the 1-based line and column, counting columns in characters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Position {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Self { offset: 0, line: 1, column: 1 }
+    }
+}
+
+/// The source a token or node was read from, from `start` up to but not including `end`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Span {
+    pub fn new(start: Position, end: Position) -> Self {
+        Self { start, end }
+    }
+
+    /// An empty span at `position`
+    pub fn at(position: Position) -> Self {
+        Self { start: position, end: position }
+    }
+
+    /// Byte range of the span, for slicing the source
+    pub fn range(&self) -> Range<usize> {
+        self.start.offset..self.end.offset
+    }
+
+    /// Span from the start of `self` to the end of `other`
+    pub fn to(&self, other: Span) -> Span {
+        Span { start: self.start, end: other.end }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start.offset == self.end.offset
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(offset: usize, line: usize, column: usize) -> Position {
+        Position { offset, line, column }
+    }
+
+    #[test]
+    fn test_spans_compare_by_position() {
+        let first = Span::new(position(0, 1, 1), position(3, 1, 4));
+        let second = Span::new(position(7, 2, 1), position(9, 2, 3));
+
+        assert_ne!(first, second);
+        assert_eq!(first, Span::new(position(0, 1, 1), position(3, 1, 4)));
+    }
+
+    #[test]
+    fn test_range_and_join() {
+        let source = "# Heat\n\nA *crime* film";
+        let first = Span::new(position(8, 3, 1), position(10, 3, 3));
+        let last = Span::new(position(10, 3, 3), position(17, 3, 10));
+
+        assert_eq!(&source[first.range()], "A ");
+        assert_eq!(&source[first.to(last).range()], "A *crime*");
+        assert!(Span::at(first.end).is_empty());
+    }
+}
@@ -0,0 +1,123 @@
+//! Task list items and checking them off in the source, by the offsets the HTML renderer writes to
+//! their checkboxes
+
+use crate::lexer::Lexer;
+use crate::parser::{AstNode, Parser};
+use crate::visit::{walk_node, Visitor};
+
+/// A task list item, as returned by `tasks`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TaskEntry {
+    /// Byte offset of the item in the source, which its rendered checkbox carries as `data-task-offset`
+    /// and `toggle_task` takes
+    pub offset: usize,
+    pub checked: bool,
+    /// The item's first line of text with the formatting dropped
+    pub text: String,
+}
+
+/// Every task list item of a document parsed with spans, in document order, including nested ones and
+/// those inside quotes
+pub fn tasks(node: &AstNode) -> Vec<TaskEntry> {
+    let mut tasks = Tasks::default();
+    tasks.visit_node(node);
+    tasks.0
+}
+
+#[derive(Default)]
+struct Tasks(Vec<TaskEntry>);
+
+impl<'ast> Visitor<'ast> for Tasks {
+    fn visit_node(&mut self, node: &'ast AstNode) {
+        if let AstNode::TaskItem { checked, content, span } = node {
+            // Loose items wrap their text in a paragraph, nested lists have none of their own
+            let inline = match content.first() {
+                Some(AstNode::Paragraph { content, .. }) => content.as_slice(),
+                _ => content.as_slice(),
+            };
+            let text = Parser::plain_text(inline).split_whitespace().collect::<Vec<_>>().join(" ");
+            self.0.push(TaskEntry { offset: span.start.offset, checked: *checked, text });
+        }
+        walk_node(self, node);
+    }
+}
+
+/// Check or uncheck the task list item at byte `offset` of the source, as in the rendered
+/// `data-task-offset` attributes. Only that checkbox changes in the returned source; markdown without a
+/// task item starting there, e.g. because text was added before it since it was rendered, is returned
+/// unchanged.
+pub fn toggle_task(markdown: &str, offset: usize) -> String {
+    let mut lexer = Lexer::new(markdown);
+    let Ok(tokens) = lexer.tokenize_with_spans() else {
+        return markdown.to_string();
+    };
+    let is_task = Parser::with_spans(tokens).parse().is_ok_and(|ast| tasks(&ast).iter().any(|task| task.offset == offset));
+    if !is_task {
+        return markdown.to_string();
+    }
+    // Positions point at the `[` and count characters, the state character follows it. The item's own
+    // checkbox is the first one after its start.
+    let state = lexer
+        .task_positions()
+        .iter()
+        .filter_map(|&position| markdown.char_indices().nth(position + 1))
+        .find(|&(index, _)| index > offset);
+
+    match state {
+        Some((index, state)) => {
+            let mut toggled = markdown.to_string();
+            toggled.replace_range(index..index + state.len_utf8(), if state == ' ' { "x" } else { " " });
+            toggled
+        },
+        None => markdown.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_markdown;
+
+    const NOTES: &str = "- [ ] rewatch S1\n  - [X] buy *popcorn*\n\n```\n- [ ] not a task\n```\n\n3) [ ] ünïcode [ ] item";
+
+    #[test]
+    fn test_tasks() {
+        let task = |offset, checked, text: &str| TaskEntry { offset, checked, text: text.to_string() };
+
+        assert_eq!(
+            tasks(&parse_markdown(NOTES).unwrap()),
+            vec![task(0, false, "rewatch S1"), task(17, true, "buy popcorn"), task(66, false, "ünïcode [ ] item")]
+        );
+    }
+
+    #[test]
+    fn test_toggle_task() {
+        assert_eq!(toggle_task(NOTES, 0), NOTES.replacen("- [ ]", "- [x]", 1));
+        assert_eq!(toggle_task(NOTES, 17), NOTES.replace("[X]", "[ ]"));
+        assert_eq!(toggle_task(NOTES, 66), NOTES.replace("3) [ ]", "3) [x]"));
+        // Offsets that aren't the start of a task item, like the code block's, change nothing
+        assert_eq!(toggle_task(NOTES, 2), NOTES);
+        assert_eq!(toggle_task(NOTES, 44), NOTES);
+        assert_eq!(toggle_task(NOTES, 1000), NOTES);
+    }
+
+    #[test]
+    fn test_toggle_task_after_edit() {
+        let rendered = "- [ ] one\n- [ ] two";
+        let offset = tasks(&parse_markdown(rendered).unwrap())[1].offset;
+
+        // Text added since rendering moves the item, so the stale offset toggles nothing
+        let edited = "- [ ] one more\n- [ ] two";
+        assert_eq!(toggle_task(edited, offset), edited);
+    }
+
+    #[test]
+    fn test_toggle_task_round_trip() {
+        let notes = "> 1. [x] quoted task";
+        let offset = tasks(&parse_markdown(notes).unwrap())[0].offset;
+
+        assert_eq!(toggle_task(&toggle_task(notes, offset), offset), notes);
+        assert!(!tasks(&parse_markdown(&toggle_task(notes, offset)).unwrap())[0].checked);
+    }
+}
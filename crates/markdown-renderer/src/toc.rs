@@ -0,0 +1,121 @@
+//! Tables of contents and the slugs identifying headings, shared with the HTML renderer's heading ids
+
+use std::collections::HashSet;
+
+use crate::parser::{AstNode, Parser};
+use crate::visit::{walk_node, Visitor};
+
+/// A heading of the document, in the outline returned by `toc`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TocEntry {
+    pub level: u8,
+    /// The heading's text with the formatting dropped
+    pub text: String,
+    /// The heading's id when rendered with `HtmlOptions::heading_ids`, so `#slug` links to it
+    pub slug: String,
+}
+
+/// Every heading of the document in order, including those inside quotes and lists. Slugs are unique
+/// within the document and match the ids the HTML renderer gives the headings.
+pub fn toc(node: &AstNode) -> Vec<TocEntry> {
+    let mut headings = Headings::default();
+    headings.visit_node(node);
+    headings.entries
+}
+
+#[derive(Default)]
+struct Headings {
+    slugger: Slugger,
+    entries: Vec<TocEntry>,
+}
+
+impl<'ast> Visitor<'ast> for Headings {
+    fn visit_node(&mut self, node: &'ast AstNode) {
+        match node {
+            AstNode::Heading { level, content, .. } => {
+                let text = heading_text(content);
+                let slug = self.slugger.slug(&text);
+                self.entries.push(TocEntry { level: *level, text, slug });
+            },
+            // Footnotes are rendered after the rest of the document, so their headings are left out to
+            // keep the slugs in step with the renderer's
+            AstNode::FootnoteDefinition { .. } => {},
+            node => walk_node(self, node),
+        }
+    }
+}
+
+/// Plain text of a heading, on one line even when it was a multi-line setext heading
+pub(crate) fn heading_text(content: &[AstNode]) -> String {
+    Parser::plain_text(content).split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Hands out GitHub style slugs, numbering repeats: `cast`, `cast-1`, `cast-2`
+#[derive(Debug, Default)]
+pub(crate) struct Slugger {
+    used: HashSet<String>,
+}
+
+impl Slugger {
+    pub(crate) fn slug(&mut self, text: &str) -> String {
+        let base = slugify(text);
+        let mut slug = base.clone();
+        let mut repeat = 0;
+        while !self.used.insert(slug.clone()) {
+            repeat += 1;
+            slug = format!("{}-{}", base, repeat);
+        }
+        slug
+    }
+}
+
+/// Lowercase the text, drop punctuation and turn spaces into hyphens, e.g. `Heat (1995)!` into
+/// `heat-1995`. Text without a letter or digit becomes `section`.
+fn slugify(text: &str) -> String {
+    let slug: String = text
+        .trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+            _ => None,
+        })
+        .collect();
+    if slug.chars().any(char::is_alphanumeric) {
+        slug
+    } else {
+        "section".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_markdown;
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("Heat (1995)!"), "heat-1995");
+        assert_eq!(slugify("Season 2: The *Good* Part"), "season-2-the-good-part");
+        assert_eq!(slugify("Amélie"), "amélie");
+        assert_eq!(slugify("?!"), "section");
+    }
+
+    #[test]
+    fn test_toc() {
+        let ast = parse_markdown("# Breaking Bad\n\n## Season 1\n\n## Season 1\n\n> ### **Best** episode\n\nText\n---").unwrap();
+
+        let entry = |level, text: &str, slug: &str| TocEntry { level, text: text.to_string(), slug: slug.to_string() };
+        assert_eq!(
+            toc(&ast),
+            vec![
+                entry(1, "Breaking Bad", "breaking-bad"),
+                entry(2, "Season 1", "season-1"),
+                entry(2, "Season 1", "season-1-1"),
+                entry(3, "Best episode", "best-episode"),
+                entry(2, "Text", "text"),
+            ]
+        );
+    }
+}
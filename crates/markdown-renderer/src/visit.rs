@@ -0,0 +1,355 @@
+//! Walking and rewriting syntax trees without matching every `AstNode` variant by hand: implement
+//! `Visitor` to read a tree or `VisitorMut` to change it, overriding the methods for the nodes of
+//! interest. The `walk_*` functions are the default recursion into a node's children, for overrides to
+//! call when they carry on into them.
+
+use crate::parser::{Alignment, AstNode, MediaRef};
+use crate::span::Span;
+
+/// Reads a tree, visiting each node before its children in document order. `'ast` is the lifetime of
+/// the tree, so a visitor can hold on to the nodes it visits.
+///
+/// `visit_node` hands each node to the method for its kind, whose default visits the node's children;
+/// override those to handle some kinds of node, or `visit_node` to see every node whatever its kind.
+pub trait Visitor<'ast> {
+    /// Visit one node, by default with the method for its kind, see `dispatch_node`
+    fn visit_node(&mut self, node: &'ast AstNode) {
+        dispatch_node(self, node);
+    }
+
+    /// Visit a list of child nodes, e.g. a paragraph's content or a table row, by default one by one
+    fn visit_nodes(&mut self, nodes: &'ast [AstNode]) {
+        walk_nodes(self, nodes);
+    }
+
+    fn visit_document(&mut self, children: &'ast [AstNode], _span: Span) {
+        self.visit_nodes(children);
+    }
+
+    fn visit_heading(&mut self, _level: u8, content: &'ast [AstNode], _span: Span) {
+        self.visit_nodes(content);
+    }
+
+    fn visit_paragraph(&mut self, content: &'ast [AstNode], _span: Span) {
+        self.visit_nodes(content);
+    }
+
+    fn visit_list(&mut self, _ordered: bool, _start: u32, items: &'ast [AstNode], _span: Span) {
+        self.visit_nodes(items);
+    }
+
+    fn visit_list_item(&mut self, content: &'ast [AstNode], _span: Span) {
+        self.visit_nodes(content);
+    }
+
+    fn visit_task_item(&mut self, _checked: bool, content: &'ast [AstNode], _span: Span) {
+        self.visit_nodes(content);
+    }
+
+    /// Visit a table, by default its header and then each row as one list of cells
+    fn visit_table(&mut self, _alignments: &'ast [Alignment], header: &'ast [AstNode], rows: &'ast [Vec<AstNode>], _span: Span) {
+        self.visit_nodes(header);
+        for row in rows {
+            self.visit_nodes(row);
+        }
+    }
+
+    fn visit_table_cell(&mut self, content: &'ast [AstNode], _span: Span) {
+        self.visit_nodes(content);
+    }
+
+    fn visit_code_block(&mut self, _info: Option<&'ast str>, _content: &'ast str, _span: Span) {}
+
+    fn visit_block_quote(&mut self, children: &'ast [AstNode], _span: Span) {
+        self.visit_nodes(children);
+    }
+
+    fn visit_thematic_break(&mut self, _span: Span) {}
+
+    fn visit_footnote_definition(&mut self, _label: &'ast str, children: &'ast [AstNode], _span: Span) {
+        self.visit_nodes(children);
+    }
+
+    fn visit_text(&mut self, _text: &'ast str, _span: Span) {}
+
+    fn visit_bold(&mut self, content: &'ast [AstNode], _span: Span) {
+        self.visit_nodes(content);
+    }
+
+    fn visit_italic(&mut self, content: &'ast [AstNode], _span: Span) {
+        self.visit_nodes(content);
+    }
+
+    fn visit_strikethrough(&mut self, content: &'ast [AstNode], _span: Span) {
+        self.visit_nodes(content);
+    }
+
+    fn visit_highlight(&mut self, content: &'ast [AstNode], _span: Span) {
+        self.visit_nodes(content);
+    }
+
+    fn visit_spoiler(&mut self, content: &'ast [AstNode], _span: Span) {
+        self.visit_nodes(content);
+    }
+
+    fn visit_link(&mut self, text: &'ast [AstNode], _url: &'ast str, _title: Option<&'ast str>, _span: Span) {
+        self.visit_nodes(text);
+    }
+
+    fn visit_image(&mut self, _alt: &'ast str, _url: &'ast str, _title: Option<&'ast str>, _span: Span) {}
+
+    fn visit_media_ref(&mut self, _media_ref: &'ast MediaRef, _span: Span) {}
+
+    fn visit_code(&mut self, _code: &'ast str, _span: Span) {}
+
+    fn visit_footnote_reference(&mut self, _label: &'ast str, _span: Span) {}
+
+    fn visit_html(&mut self, _html: &'ast str, _span: Span) {}
+
+    fn visit_line_break(&mut self, _span: Span) {}
+}
+
+/// Changes a tree in place, visiting each node before its children in document order
+pub trait VisitorMut {
+    /// Visit one node, which may be changed but not replaced by several; by default visit its children
+    fn visit_node_mut(&mut self, node: &mut AstNode) {
+        walk_node_mut(self, node);
+    }
+
+    /// Visit a list of child nodes, by default one by one. Override this to add, remove or split nodes,
+    /// e.g. to replace emphasis with its content.
+    fn visit_nodes_mut(&mut self, nodes: &mut Vec<AstNode>) {
+        walk_nodes_mut(self, nodes);
+    }
+}
+
+/// Visit each list of children of `node`; table headers and rows are visited as one list each
+pub fn walk_node<'ast, V: Visitor<'ast> + ?Sized>(visitor: &mut V, node: &'ast AstNode) {
+    match node {
+        AstNode::Document { children, .. }
+        | AstNode::BlockQuote { children, .. }
+        | AstNode::FootnoteDefinition { children, .. } => visitor.visit_nodes(children),
+        AstNode::Heading { content, .. }
+        | AstNode::Paragraph { content, .. }
+        | AstNode::ListItem { content, .. }
+        | AstNode::TaskItem { content, .. }
+        | AstNode::TableCell { content, .. }
+        | AstNode::Bold(content, _)
+        | AstNode::Italic(content, _)
+        | AstNode::Strikethrough(content, _)
+        | AstNode::Highlight(content, _)
+        | AstNode::Spoiler(content, _)
+        | AstNode::Link { text: content, .. } => visitor.visit_nodes(content),
+        AstNode::List { items, .. } => visitor.visit_nodes(items),
+        AstNode::Table { header, rows, .. } => {
+            visitor.visit_nodes(header);
+            for row in rows {
+                visitor.visit_nodes(row);
+            }
+        },
+        AstNode::CodeBlock { .. }
+        | AstNode::ThematicBreak(_)
+        | AstNode::Text(..)
+        | AstNode::Image { .. }
+        | AstNode::MediaRef(..)
+        | AstNode::Code(..)
+        | AstNode::FootnoteReference { .. }
+        | AstNode::Html(..)
+        | AstNode::LineBreak(_) => {},
+    }
+}
+
+/// Hand `node` to the visitor's method for its kind, which is what `Visitor::visit_node` does by default
+pub fn dispatch_node<'ast, V: Visitor<'ast> + ?Sized>(visitor: &mut V, node: &'ast AstNode) {
+    match node {
+        AstNode::Document { children, span } => visitor.visit_document(children, *span),
+        AstNode::Heading { level, content, span } => visitor.visit_heading(*level, content, *span),
+        AstNode::Paragraph { content, span } => visitor.visit_paragraph(content, *span),
+        AstNode::List { ordered, start, items, span } => visitor.visit_list(*ordered, *start, items, *span),
+        AstNode::ListItem { content, span } => visitor.visit_list_item(content, *span),
+        AstNode::TaskItem { checked, content, span } => visitor.visit_task_item(*checked, content, *span),
+        AstNode::Table { alignments, header, rows, span } => visitor.visit_table(alignments, header, rows, *span),
+        AstNode::TableCell { content, span } => visitor.visit_table_cell(content, *span),
+        AstNode::CodeBlock { info, content, span } => visitor.visit_code_block(info.as_deref(), content, *span),
+        AstNode::BlockQuote { children, span } => visitor.visit_block_quote(children, *span),
+        AstNode::ThematicBreak(span) => visitor.visit_thematic_break(*span),
+        AstNode::FootnoteDefinition { label, children, span } => visitor.visit_footnote_definition(label, children, *span),
+        AstNode::Text(text, span) => visitor.visit_text(text, *span),
+        AstNode::Bold(content, span) => visitor.visit_bold(content, *span),
+        AstNode::Italic(content, span) => visitor.visit_italic(content, *span),
+        AstNode::Strikethrough(content, span) => visitor.visit_strikethrough(content, *span),
+        AstNode::Highlight(content, span) => visitor.visit_highlight(content, *span),
+        AstNode::Spoiler(content, span) => visitor.visit_spoiler(content, *span),
+        AstNode::Link { text, url, title, span } => visitor.visit_link(text, url, title.as_deref(), *span),
+        AstNode::Image { alt, url, title, span } => visitor.visit_image(alt, url, title.as_deref(), *span),
+        AstNode::MediaRef(media_ref, span) => visitor.visit_media_ref(media_ref, *span),
+        AstNode::Code(code, span) => visitor.visit_code(code, *span),
+        AstNode::FootnoteReference { label, span } => visitor.visit_footnote_reference(label, *span),
+        AstNode::Html(html, span) => visitor.visit_html(html, *span),
+        AstNode::LineBreak(span) => visitor.visit_line_break(*span),
+    }
+}
+
+/// Visit each node of `nodes`
+pub fn walk_nodes<'ast, V: Visitor<'ast> + ?Sized>(visitor: &mut V, nodes: &'ast [AstNode]) {
+    for node in nodes {
+        visitor.visit_node(node);
+    }
+}
+
+/// Visit each list of children of `node` mutably, like `walk_node`
+pub fn walk_node_mut<V: VisitorMut + ?Sized>(visitor: &mut V, node: &mut AstNode) {
+    match node {
+        AstNode::Document { children, .. }
+        | AstNode::BlockQuote { children, .. }
+        | AstNode::FootnoteDefinition { children, .. } => visitor.visit_nodes_mut(children),
+        AstNode::Heading { content, .. }
+        | AstNode::Paragraph { content, .. }
+        | AstNode::ListItem { content, .. }
+        | AstNode::TaskItem { content, .. }
+        | AstNode::TableCell { content, .. }
+        | AstNode::Bold(content, _)
+        | AstNode::Italic(content, _)
+        | AstNode::Strikethrough(content, _)
+        | AstNode::Highlight(content, _)
+        | AstNode::Spoiler(content, _)
+        | AstNode::Link { text: content, .. } => visitor.visit_nodes_mut(content),
+        AstNode::List { items, .. } => visitor.visit_nodes_mut(items),
+        AstNode::Table { header, rows, .. } => {
+            visitor.visit_nodes_mut(header);
+            for row in rows {
+                visitor.visit_nodes_mut(row);
+            }
+        },
+        AstNode::CodeBlock { .. }
+        | AstNode::ThematicBreak(_)
+        | AstNode::Text(..)
+        | AstNode::Image { .. }
+        | AstNode::MediaRef(..)
+        | AstNode::Code(..)
+        | AstNode::FootnoteReference { .. }
+        | AstNode::Html(..)
+        | AstNode::LineBreak(_) => {},
+    }
+}
+
+/// Visit each node of `nodes` mutably
+pub fn walk_nodes_mut<V: VisitorMut + ?Sized>(visitor: &mut V, nodes: &mut Vec<AstNode>) {
+    for node in nodes {
+        visitor.visit_node_mut(node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_markdown;
+    use crate::span::Span;
+
+    fn text(text: &str) -> AstNode {
+        AstNode::Text(text.to_string(), Span::default())
+    }
+
+    #[test]
+    fn test_visit_in_document_order() {
+        struct Texts<'ast>(Vec<&'ast str>);
+
+        impl<'ast> Visitor<'ast> for Texts<'ast> {
+            fn visit_node(&mut self, node: &'ast AstNode) {
+                if let AstNode::Text(text, _) = node {
+                    self.0.push(text);
+                }
+                walk_node(self, node);
+            }
+        }
+
+        let ast = parse_markdown("# One\n\n- *two*\n\n| three |\n|---|\n| four |\n\n> [five](/5)").unwrap();
+        let mut texts = Texts(Vec::new());
+        texts.visit_node(&ast);
+
+        assert_eq!(texts.0, vec!["One", "two", "three", "four", "five"]);
+    }
+
+    #[test]
+    fn test_visit_one_kind_of_node() {
+        // Only links are handled, the default methods carry on into everything else
+        struct Links<'ast>(Vec<(&'ast str, usize)>);
+
+        impl<'ast> Visitor<'ast> for Links<'ast> {
+            fn visit_link(&mut self, text: &'ast [AstNode], url: &'ast str, _title: Option<&'ast str>, _span: Span) {
+                self.0.push((url, text.len()));
+                self.visit_nodes(text);
+            }
+        }
+
+        let ast = parse_markdown("> - [*Heat*](/movie/949)\n\n| [Ronin](/movie/8195) and more |\n|---|").unwrap();
+        let mut links = Links(Vec::new());
+        links.visit_node(&ast);
+
+        assert_eq!(links.0, vec![("/movie/949", 1), ("/movie/8195", 1)]);
+    }
+
+    #[test]
+    fn test_rewrite_links() {
+        struct Absolute;
+
+        impl VisitorMut for Absolute {
+            fn visit_node_mut(&mut self, node: &mut AstNode) {
+                if let AstNode::Link { url, .. } = node {
+                    if url.starts_with('/') {
+                        url.insert_str(0, "https://example.com");
+                    }
+                }
+                walk_node_mut(self, node);
+            }
+        }
+
+        let mut ast = parse_markdown("> See [Heat](/movie/949)\n\n- and [Ronin](https://ronin.example)").unwrap();
+        Absolute.visit_node_mut(&mut ast);
+
+        let mut urls = Vec::new();
+        struct Urls<'a>(&'a mut Vec<String>);
+        impl<'ast> Visitor<'ast> for Urls<'_> {
+            fn visit_node(&mut self, node: &'ast AstNode) {
+                if let AstNode::Link { url, .. } = node {
+                    self.0.push(url.clone());
+                }
+                walk_node(self, node);
+            }
+        }
+        Urls(&mut urls).visit_node(&ast);
+        assert_eq!(urls, vec!["https://example.com/movie/949", "https://ronin.example"]);
+    }
+
+    #[test]
+    fn test_strip_emphasis() {
+        struct StripEmphasis;
+
+        impl VisitorMut for StripEmphasis {
+            fn visit_nodes_mut(&mut self, nodes: &mut Vec<AstNode>) {
+                *nodes = std::mem::take(nodes)
+                    .into_iter()
+                    .flat_map(|node| match node {
+                        AstNode::Bold(content, _) | AstNode::Italic(content, _) => content,
+                        node => vec![node],
+                    })
+                    .collect();
+                // Emphasis can be nested, and the content moved up is visited here
+                if nodes.iter().any(|node| matches!(node, AstNode::Bold(..) | AstNode::Italic(..))) {
+                    self.visit_nodes_mut(nodes);
+                } else {
+                    walk_nodes_mut(self, nodes);
+                }
+            }
+        }
+
+        let mut ast = parse_markdown("A ***very*** *good* film").unwrap();
+        StripEmphasis.visit_node_mut(&mut ast);
+
+        let AstNode::Document { children, .. } = ast.without_spans() else { panic!("Expected document node") };
+        assert_eq!(children, vec![AstNode::Paragraph {
+            content: vec![text("A "), text("very"), text(" "), text("good"), text(" film")],
+            span: Span::default(),
+        }]);
+    }
+}
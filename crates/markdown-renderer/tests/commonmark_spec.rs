@@ -0,0 +1,291 @@
+//! Conformance tests running the CommonMark spec examples through `markdown_to_html`.
+//!
+//! `commonmark/spec.json` holds the examples of CommonMark 0.31.2 (https://spec.commonmark.org,
+//! CC-BY-SA 4.0). Examples we don't handle yet are listed in `commonmark/expected_failures.txt`: the
+//! test fails when any other example fails, and when a listed example starts passing so the list is
+//! kept up to date as conformance improves. An example may only be listed while its output keeps all of
+//! the source's text, i.e. it renders the text differently but doesn't drop any of it.
+//!
+//! - `cargo test --test commonmark_spec -- --nocapture` prints the pass rate per section
+//! - `UPDATE_EXPECTED_FAILURES=1 cargo test --test commonmark_spec` rewrites the list from the current results
+
+#![cfg(feature = "html")]
+
+use std::collections::BTreeSet;
+use std::panic;
+use std::path::Path;
+
+use markdown_renderer::markdown_to_html;
+use serde_json::Value;
+
+const SPEC: &str = include_str!("commonmark/spec.json");
+const EXPECTED_FAILURES: &str = "tests/commonmark/expected_failures.txt";
+
+struct Example {
+    number: u64,
+    section: String,
+    markdown: String,
+    html: String,
+}
+
+fn examples() -> Vec<Example> {
+    let spec: Vec<Value> = serde_json::from_str(SPEC).expect("spec.json is valid JSON");
+    spec.iter()
+        .map(|example| Example {
+            number: example["example"].as_u64().unwrap(),
+            section: example["section"].as_str().unwrap().to_string(),
+            markdown: example["markdown"].as_str().unwrap().to_string(),
+            html: example["html"].as_str().unwrap().to_string(),
+        })
+        .collect()
+}
+
+/// Example numbers in the expected failures file, ignoring `#` comments
+fn expected_failures() -> BTreeSet<u64> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(EXPECTED_FAILURES);
+    let list = std::fs::read_to_string(path).unwrap_or_default();
+    list.lines()
+        .map(|line| line.split('#').next().unwrap().trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| line.parse().unwrap_or_else(|_| panic!("Invalid example number {:?} in {}", line, EXPECTED_FAILURES)))
+        .collect()
+}
+
+/// Drop the formatting differences the spec doesn't care about: line breaks between tags and the
+/// self-closing slash of void elements
+fn normalize(html: &str) -> String {
+    let html = html.replace(" />", ">");
+    let mut normalized = String::with_capacity(html.len());
+    let mut chars = html.chars().peekable();
+    while let Some(c) = chars.next() {
+        normalized.push(c);
+        if c == '>' {
+            while chars.peek() == Some(&'\n') {
+                chars.next();
+            }
+        }
+    }
+    normalized.trim_end().to_string()
+}
+
+/// Whether the renderer produces the spec's HTML for `example`. Panics count as failures, so one
+/// crash doesn't hide the results of every other example.
+fn passes(example: &Example) -> bool {
+    match panic::catch_unwind(|| markdown_to_html(&example.markdown)) {
+        Ok(Ok(html)) => normalize(&html) == normalize(&example.html),
+        _ => false,
+    }
+}
+
+/// The text of `html` between its tags, and the attributes holding source text as `(name, raw value,
+/// value)`, with entities and percent-encoding decoded
+fn html_text(html: &str) -> (String, Vec<(&'static str, String, String)>) {
+    let mut text = String::new();
+    let mut attributes = Vec::new();
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        let end = rest[start..].find('>').map_or(rest.len(), |end| start + end + 1);
+        let tag = &rest[start..end];
+        for name in ["href", "src", "alt", "title", "class", "start"] {
+            let Some(value) = tag.split(&format!(" {}=\"", name)).nth(1) else { continue };
+            let value = value.split('"').next().unwrap_or_default();
+            attributes.push((name, value.to_string(), decode_entities(&percent_decode(value))));
+        }
+        rest = &rest[end..];
+    }
+    text.push_str(rest);
+    (decode_entities(&text), attributes)
+}
+
+fn percent_decode(url: &str) -> String {
+    let mut bytes = Vec::with_capacity(url.len());
+    let mut rest = url.as_bytes();
+    while let Some((&byte, after)) = rest.split_first() {
+        let encoded = after.get(..2).and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match (byte, encoded) {
+            (b'%', Some(decoded)) => {
+                bytes.push(decoded);
+                rest = &after[2..];
+            },
+            _ => {
+                bytes.push(byte);
+                rest = after;
+            },
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest.find(';').map(|end| &rest[1..end]);
+        let c = match entity {
+            Some("amp") => Some('&'),
+            Some("lt") => Some('<'),
+            Some("gt") => Some('>'),
+            Some("quot") => Some('"'),
+            Some(entity) => entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|decimal| decimal.parse().ok()))
+                .and_then(char::from_u32),
+            None => None,
+        };
+        match (c, entity) {
+            (Some(c), Some(entity)) => {
+                decoded.push(c);
+                rest = &rest[entity.len() + 2..];
+            },
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            },
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+fn alphanumeric(text: &str) -> String {
+    text.chars().filter(|c| c.is_alphanumeric()).collect()
+}
+
+/// The letters and digits of the spec's output for `example` that are missing from ours, and those of
+/// each link target, title and the like. Reading markup differently only moves them around or changes
+/// the punctuation between them, so missing ones are text the renderer lost.
+fn lost_text(example: &Example) -> Vec<String> {
+    let (text, attributes) = match panic::catch_unwind(|| markdown_to_html(&example.markdown)) {
+        Ok(Ok(html)) => html_text(&html),
+        _ => Default::default(),
+    };
+    let values: String = attributes.iter().map(|(_, _, value)| value.as_str()).collect();
+    let actual: Vec<char> = alphanumeric(&(text + &values)).chars().collect();
+    let missing = |expected: &str| {
+        let mut remaining = actual.clone();
+        let missing: String = alphanumeric(expected)
+            .chars()
+            .filter(|c| match remaining.iter().position(|other| other == c) {
+                Some(index) => {
+                    remaining.swap_remove(index);
+                    false
+                },
+                None => true,
+            })
+            .collect();
+        (!missing.is_empty()).then_some(missing)
+    };
+
+    let (expected_text, expected_attributes) = html_text(&example.html);
+    let mut lost: Vec<String> = missing(&expected_text).into_iter().collect();
+    for (name, raw, value) in expected_attributes {
+        // Raw HTML the spec passes through is text for us, and `mailto:` isn't in the source
+        if example.markdown.contains(&format!("{}=\"{}\"", name, raw)) {
+            continue;
+        }
+        if let Some(missing) = missing(value.strip_prefix("mailto:").unwrap_or(&value)) {
+            lost.push(format!("{}: {}", name, missing));
+        }
+    }
+    lost
+}
+
+/// Pass rate per section, in spec order
+fn report(examples: &[Example], failures: &BTreeSet<u64>) -> String {
+    let mut sections: Vec<(&str, usize, usize)> = Vec::new();
+    for example in examples {
+        let passed = usize::from(!failures.contains(&example.number));
+        match sections.last_mut() {
+            Some((section, total, passes)) if *section == example.section => {
+                *total += 1;
+                *passes += passed;
+            },
+            _ => sections.push((&example.section, 1, passed)),
+        }
+    }
+
+    let mut report = String::new();
+    for (section, total, passed) in sections {
+        report.push_str(&format!("{:<45} {:>3}/{:<3} {:>5.1}%\n", section, passed, total, percent(passed, total)));
+    }
+    let passed = examples.len() - failures.len();
+    report.push_str(&format!("{:<45} {:>3}/{:<3} {:>5.1}%", "Total", passed, examples.len(), percent(passed, examples.len())));
+    report
+}
+
+fn percent(passed: usize, total: usize) -> f64 {
+    passed as f64 * 100.0 / total as f64
+}
+
+/// The expected failures file listing `failures`, grouped by section
+fn failures_file(examples: &[Example], failures: &BTreeSet<u64>) -> String {
+    let mut file = String::from("# CommonMark spec examples markdown_to_html doesn't pass yet, see tests/commonmark_spec.rs\n");
+    let mut section = "";
+    for example in examples.iter().filter(|example| failures.contains(&example.number)) {
+        if example.section != section {
+            section = &example.section;
+            file.push_str(&format!("\n# {}\n", section));
+        }
+        file.push_str(&format!("{}\n", example.number));
+    }
+    file
+}
+
+#[test]
+fn test_commonmark_spec() {
+    let examples = examples();
+
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let failures: BTreeSet<u64> = examples.iter().filter(|example| !passes(example)).map(|example| example.number).collect();
+    panic::set_hook(hook);
+
+    println!("{}", report(&examples, &failures));
+
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let losing: Vec<(&Example, Vec<String>)> = examples
+        .iter()
+        .filter(|example| failures.contains(&example.number))
+        .map(|example| (example, lost_text(example)))
+        .filter(|(_, lost)| !lost.is_empty())
+        .collect();
+    panic::set_hook(hook);
+    let mut message = String::new();
+    for (example, lost) in &losing {
+        message.push_str(&format!(
+            "Example {} ({}) loses {:?}\n  markdown: {:?}\n",
+            example.number, example.section, lost, example.markdown,
+        ));
+    }
+
+    if std::env::var_os("UPDATE_EXPECTED_FAILURES").is_some() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(EXPECTED_FAILURES);
+        std::fs::write(path, failures_file(&examples, &failures)).unwrap();
+        return;
+    }
+
+    let expected = expected_failures();
+    let regressions: Vec<&Example> = examples
+        .iter()
+        .filter(|example| failures.contains(&example.number) && !expected.contains(&example.number))
+        .collect();
+    let fixed: Vec<&u64> = expected.difference(&failures).collect();
+
+    for example in &regressions {
+        let actual = panic::catch_unwind(|| markdown_to_html(&example.markdown));
+        message.push_str(&format!(
+            "Example {} ({}) regressed\n  markdown: {:?}\n  expected: {:?}\n  actual:   {:?}\n",
+            example.number, example.section, example.markdown, example.html, actual.ok(),
+        ));
+    }
+    if !fixed.is_empty() {
+        message.push_str(&format!("Examples {:?} pass now, remove them from {}\n", fixed, EXPECTED_FAILURES));
+    }
+    assert!(message.is_empty(), "{}", message);
+}
@@ -1,4 +1,4 @@
-use markdown_renderer::{parse_markdown, AstNode, ParseError, MarkdownError};
+use markdown_renderer::{parse_markdown, Alignment, AstNode, ParseError, MarkdownError, Span};
 
 #[test]
 fn test_deeply_nested_emphasis() {
@@ -7,10 +7,10 @@ fn test_deeply_nested_emphasis() {
     let ast = parse_markdown(markdown).unwrap();
     
     match ast {
-        AstNode::Document { children } => {
+        AstNode::Document { children, .. } => {
             assert_eq!(children.len(), 1);
             match &children[0] {
-                AstNode::Paragraph { content } => {
+                AstNode::Paragraph { content, .. } => {
                     // Should handle nested formatting
                     assert!(!content.is_empty());
                 },
@@ -23,7 +23,7 @@ fn test_deeply_nested_emphasis() {
 
 #[test]
 fn test_malformed_links() {
-    let test_cases = vec![
+    let test_cases = [
         "[text](", // Missing closing paren
         "[text", // Missing closing bracket
         "[]()", // Empty text and URL
@@ -90,7 +90,7 @@ fn test_extreme_heading_levels() {
         let ast = parse_markdown(&markdown).unwrap();
         
         match ast {
-            AstNode::Document { children } => {
+            AstNode::Document { children, .. } => {
                 match &children[0] {
                     AstNode::Heading { level: parsed_level, .. } => {
                         assert_eq!(*parsed_level, level as u8);
@@ -115,7 +115,7 @@ fn test_mixed_line_endings() {
     for markdown in test_cases {
         let ast = parse_markdown(markdown).unwrap();
         match ast {
-            AstNode::Document { children } => {
+            AstNode::Document { children, .. } => {
                 // Should parse into paragraphs regardless of line ending style
                 assert!(!children.is_empty());
             },
@@ -132,14 +132,14 @@ fn test_very_long_text_nodes() {
     let ast = parse_markdown(&markdown).unwrap();
     
     match ast {
-        AstNode::Document { children } => {
+        AstNode::Document { children, .. } => {
             assert_eq!(children.len(), 2); // heading and paragraph
             
             // Check heading
             match &children[0] {
                 AstNode::Heading { content, .. } => {
                     match &content[0] {
-                        AstNode::Text(text) => {
+                        AstNode::Text(text, _) => {
                             assert_eq!(text.len(), 10000);
                         },
                         _ => panic!("Expected text node"),
@@ -179,14 +179,14 @@ fn test_empty_list_items() {
     let ast = parse_markdown(markdown).unwrap();
     
     match ast {
-        AstNode::Document { children } => {
+        AstNode::Document { children, .. } => {
             match &children[0] {
                 AstNode::List { items, .. } => {
                     assert_eq!(items.len(), 5);
                     
                     // Check that empty items exist
                     match &items[0] {
-                        AstNode::ListItem { content } => {
+                        AstNode::ListItem { content, .. } => {
                             assert!(content.is_empty());
                         },
                         _ => panic!("Expected list item"),
@@ -209,9 +209,9 @@ fn test_large_numbers_in_ordered_lists() {
     let ast = parse_markdown(markdown).unwrap();
     
     match ast {
-        AstNode::Document { children } => {
+        AstNode::Document { children, .. } => {
             match &children[0] {
-                AstNode::List { ordered: true, items } => {
+                AstNode::List { ordered: true, items, .. } => {
                     assert_eq!(items.len(), 4);
                 },
                 _ => panic!("Expected ordered list"),
@@ -239,7 +239,7 @@ List with symbols:
     match result {
         Ok(ast) => {
             match ast {
-                AstNode::Document { children } => {
+                AstNode::Document { children, .. } => {
                     assert!(children.len() >= 3); // At least heading, paragraph, list
                 },
                 _ => panic!("Expected document node"),
@@ -272,26 +272,57 @@ fn test_whitespace_variations() {
 }
 
 #[test]
-fn test_nested_lists_simulation() {
-    // Our parser doesn't support nested lists, but should handle
-    // what looks like nested lists as separate lists
+fn test_nested_lists() {
     let markdown = r#"- Outer item 1
 - Outer item 2
-  - What looks like nested
-  - Another nested-looking item
+  - Nested item
+  - Another nested item
 - Outer item 3"#;
     
     let ast = parse_markdown(markdown).unwrap();
     
     match ast {
-        AstNode::Document { children } => {
-            // Should parse as multiple elements
-            assert!(!children.is_empty());
+        AstNode::Document { children, .. } => {
+            assert_eq!(children.len(), 1);
+            let AstNode::List { items, .. } = &children[0] else { panic!("Expected list") };
+            assert_eq!(items.len(), 3);
+            let AstNode::ListItem { content, .. } = &items[1] else { panic!("Expected list item") };
+            assert!(matches!(content.last(), Some(AstNode::List { items, .. }) if items.len() == 2));
         },
         _ => panic!("Expected document node"),
     }
 }
 
+#[test]
+fn test_list_kinds_split_lists() {
+    // Switching bullet character or number delimiter starts a new list
+    for markdown in ["- a\n* b\n+ c", "1. a\n2) b\n- c"] {
+        let AstNode::Document { children, .. } = parse_markdown(markdown).unwrap() else { panic!("Expected document") };
+        assert_eq!(children.len(), 3, "{}", markdown);
+    }
+}
+
+#[test]
+fn test_deeply_nested_lists() {
+    let markdown: String = (0..10).map(|depth| format!("{}- level {}\n", "  ".repeat(depth), depth)).collect();
+
+    let mut node = parse_markdown(&markdown).unwrap();
+    let mut depth = 0;
+    while let Some(list) = match &node {
+        AstNode::Document { children, .. } => children.first().cloned(),
+        AstNode::List { items, .. } => items.first().cloned(),
+        AstNode::ListItem { content, .. } => content.iter().find(|child| matches!(child, AstNode::List { .. })).cloned(),
+        _ => None,
+    } {
+        if matches!(list, AstNode::List { .. }) {
+            depth += 1;
+        }
+        node = list;
+    }
+
+    assert_eq!(depth, 10);
+}
+
 #[test]
 fn test_boundary_conditions() {
     let test_cases = vec![
@@ -332,10 +363,10 @@ fn test_rapid_format_changes() {
     match result {
         Ok(ast) => {
             match ast {
-                AstNode::Document { children } => {
+                AstNode::Document { children, .. } => {
                     assert_eq!(children.len(), 1);
                     match &children[0] {
-                        AstNode::Paragraph { content } => {
+                        AstNode::Paragraph { content, .. } => {
                             // Should handle rapid format changes
                             assert!(!content.is_empty());
                         },
@@ -373,7 +404,7 @@ fn test_stress_many_elements() {
     assert!(duration.as_millis() < 5000, "Parsing took too long: {:?}", duration);
     
     match ast {
-        AstNode::Document { children } => {
+        AstNode::Document { children, .. } => {
             // Should have many children
             assert!(children.len() > 100);
         },
@@ -429,4 +460,227 @@ fn test_memory_usage_large_nested() {
             // Acceptable if parser rejects overly complex input
         }
     }
-}
\ No newline at end of file
+}
+
+fn table_rows(markdown: &str) -> (Vec<AstNode>, Vec<Vec<AstNode>>) {
+    match parse_markdown(markdown).unwrap() {
+        AstNode::Document { children, .. } => match children.into_iter().next() {
+            Some(AstNode::Table { header, rows, .. }) => (header, rows),
+            other => panic!("Expected table, got {:?}", other),
+        },
+        _ => panic!("Expected document node"),
+    }
+}
+
+fn cell_text(cell: &AstNode) -> String {
+    match cell {
+        AstNode::TableCell { content, .. } => content
+            .iter()
+            .map(|node| match node {
+                AstNode::Text(text, _) => text.clone(),
+                AstNode::Code(code, _) => format!("`{}`", code),
+                other => panic!("Expected plain text in cell, got {:?}", other),
+            })
+            .collect(),
+        other => panic!("Expected table cell, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_table_ragged_rows() {
+    let markdown = "| a | b | c |\n|---|---|---|\n| 1 |\n| 1 | 2 | 3 | 4 | 5 |\n|\n";
+    let (header, rows) = table_rows(markdown);
+
+    assert_eq!(header.len(), 3);
+    assert_eq!(rows.len(), 3);
+    // Short rows are padded with empty cells, long rows lose the extra cells
+    for row in &rows {
+        assert_eq!(row.len(), 3);
+    }
+    assert_eq!(rows[0].iter().map(cell_text).collect::<Vec<_>>(), ["1", "", ""]);
+    assert_eq!(rows[1].iter().map(cell_text).collect::<Vec<_>>(), ["1", "2", "3"]);
+    assert_eq!(rows[2].iter().map(cell_text).collect::<Vec<_>>(), ["", "", ""]);
+}
+
+#[test]
+fn test_table_escaped_pipes() {
+    let markdown = "| Command | Meaning |\n| --- | --- |\n| `a \\| b` | pipe a into b |\n| x\\|y\\| | two escapes |";
+    let (header, rows) = table_rows(markdown);
+
+    assert_eq!(header.len(), 2);
+    assert_eq!(rows[0].iter().map(cell_text).collect::<Vec<_>>(), ["`a | b`", "pipe a into b"]);
+    assert_eq!(rows[1].iter().map(cell_text).collect::<Vec<_>>(), ["x|y|", "two escapes"]);
+}
+
+#[test]
+fn test_table_needs_matching_delimiter_row() {
+    let test_cases = [
+        "| a | b |\n| --- |",       // Column count differs
+        "| a | b |\n| --- | x |",   // Not a delimiter row
+        "| a | b |",                // No delimiter row at all
+        "a | b\n\n--- | ---",       // Delimiter row not directly below
+    ];
+
+    for markdown in test_cases {
+        let ast = parse_markdown(markdown).unwrap();
+        match ast {
+            AstNode::Document { children, .. } => {
+                assert!(
+                    !children.iter().any(|child| matches!(child, AstNode::Table { .. })),
+                    "Unexpected table for: {:?}", markdown
+                );
+            },
+            _ => panic!("Expected document node"),
+        }
+    }
+}
+
+#[test]
+fn test_delimiter_row_without_table_keeps_its_text() {
+    for markdown in ["--- | ---", "|:--|  --:|", "| a | b |\n| --- |"] {
+        let ast = parse_markdown(markdown).unwrap();
+        let AstNode::Document { children, .. } = ast else { panic!("Expected document node") };
+        let row = markdown.lines().last().unwrap();
+        let found = children.iter().any(|child| match child {
+            AstNode::Paragraph { content, .. } => content.iter().any(|node| matches!(node, AstNode::Text(text, _) if text == row)),
+            _ => false,
+        });
+        assert!(found, "{:?} lost its delimiter row: {:?}", markdown, children);
+    }
+}
+
+#[test]
+fn test_table_without_outer_pipes() {
+    let markdown = "Title | Year\n:-- | --:\nHeat | 1995";
+    match parse_markdown(markdown).unwrap() {
+        AstNode::Document { children, .. } => match &children[0] {
+            AstNode::Table { alignments, rows, .. } => {
+                assert_eq!(alignments, &vec![Alignment::Left, Alignment::Right]);
+                assert_eq!(rows[0].iter().map(cell_text).collect::<Vec<_>>(), ["Heat", "1995"]);
+            },
+            other => panic!("Expected table, got {:?}", other),
+        },
+        _ => panic!("Expected document node"),
+    }
+}
+
+#[test]
+fn test_markdown_inside_code_is_literal() {
+    let markdown = "Use `*args* and __kwargs__` here\n\n```python\ndef f(*args, **kwargs):\n    # [link](url)\n```";
+    let ast = parse_markdown(markdown).unwrap().without_spans();
+
+    match ast {
+        AstNode::Document { children, .. } => {
+            assert_eq!(children.len(), 2);
+            assert_eq!(children[0], AstNode::Paragraph {
+                content: vec![
+                    AstNode::Text("Use ".to_string(), Span::default()),
+                    AstNode::Code("*args* and __kwargs__".to_string(), Span::default()),
+                    AstNode::Text(" here".to_string(), Span::default()),
+                ],
+                span: Span::default(),
+            });
+            assert_eq!(children[1], AstNode::CodeBlock {
+                info: Some("python".to_string()),
+                content: "def f(*args, **kwargs):\n    # [link](url)\n".to_string(),
+                span: Span::default(),
+            });
+        },
+        _ => panic!("Expected document node"),
+    }
+}
+
+#[test]
+fn test_unclosed_code() {
+    // An unmatched backtick is text, and an unclosed fence runs to the end of the document
+    let ast = parse_markdown("a ` b\n\n```\n*x*\n\n# y").unwrap().without_spans();
+
+    match ast {
+        AstNode::Document { children, .. } => {
+            assert_eq!(children[0], AstNode::Paragraph {
+                content: vec![
+                    AstNode::Text("a ".to_string(), Span::default()),
+                    AstNode::Text("`".to_string(), Span::default()),
+                    AstNode::Text(" b".to_string(), Span::default()),
+                ],
+                span: Span::default(),
+            });
+            assert_eq!(children[1], AstNode::CodeBlock { info: None, content: "*x*\n\n# y\n".to_string(), span: Span::default() });
+        },
+        _ => panic!("Expected document node"),
+    }
+}
+
+#[test]
+fn test_indented_code_block_needs_blank_line() {
+    let ast = parse_markdown("Intro\n\n    let x = 1;\n    let y = 2;\n\nOutro\n    still outro").unwrap().without_spans();
+
+    match ast {
+        AstNode::Document { children, .. } => {
+            assert_eq!(children.len(), 3);
+            assert_eq!(children[1], AstNode::CodeBlock { info: None, content: "let x = 1;\nlet y = 2;\n".to_string(), span: Span::default() });
+            // The indented line continues the paragraph
+            assert_eq!(children[2], AstNode::Paragraph {
+                content: vec![
+                    AstNode::Text("Outro".to_string(), Span::default()),
+                    AstNode::Text("\n".to_string(), Span::default()),
+                    AstNode::Text("still outro".to_string(), Span::default()),
+                ],
+                span: Span::default(),
+            });
+        },
+        _ => panic!("Expected document node"),
+    }
+}
+
+#[test]
+fn test_hyphen_rule_is_not_a_list() {
+    let ast = parse_markdown("Above\n\n---\n\n- item\n- - -").unwrap().without_spans();
+
+    match ast {
+        AstNode::Document { children, .. } => {
+            assert_eq!(children.len(), 4);
+            assert_eq!(children[1], AstNode::ThematicBreak(Span::default()));
+            assert!(matches!(children[2], AstNode::List { .. }));
+            assert_eq!(children[3], AstNode::ThematicBreak(Span::default()));
+        },
+        _ => panic!("Expected document node"),
+    }
+}
+
+#[test]
+fn test_block_quote_holds_any_block() {
+    let markdown = "> # Notes\n> 1. first\n> 2. second\n>\n> > nested *quote*\n> ```\n> > not a quote\n> ```\n\nafter";
+    let ast = parse_markdown(markdown).unwrap().without_spans();
+
+    match ast {
+        AstNode::Document { children, .. } => {
+            assert_eq!(children.len(), 2);
+            match &children[0] {
+                AstNode::BlockQuote { children, .. } => {
+                    assert_eq!(children.len(), 4);
+                    assert!(matches!(children[0], AstNode::Heading { level: 1, .. }));
+                    assert!(matches!(children[1], AstNode::List { ordered: true, .. }));
+                    assert!(matches!(&children[2], AstNode::BlockQuote { children, .. } if children.len() == 1));
+                    assert_eq!(children[3], AstNode::CodeBlock { info: None, content: "> not a quote\n".to_string(), span: Span::default() });
+                },
+                other => panic!("Expected block quote, got {:?}", other),
+            }
+            assert!(matches!(children[1], AstNode::Paragraph { .. }));
+        },
+        _ => panic!("Expected document node"),
+    }
+}
+
+#[test]
+fn test_blank_line_ends_block_quote() {
+    let ast = parse_markdown("> one\n\n> two").unwrap();
+
+    match ast {
+        AstNode::Document { children, .. } => {
+            assert_eq!(children.len(), 2);
+            assert!(children.iter().all(|child| matches!(child, AstNode::BlockQuote { .. })));
+        },
+        _ => panic!("Expected document node"),
+    }
+}
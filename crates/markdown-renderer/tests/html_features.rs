@@ -1,7 +1,8 @@
 #[cfg(feature = "html")]
 use markdown_renderer::{
     markdown_to_html, markdown_to_html_with_options, HtmlRenderer, HtmlOptions, CssClasses, 
-    CustomAttributes, Renderer, AstNode
+    CustomAttributes, Renderer, AstNode, UrlRewriter, MediaRef, MediaResolver, MediaType, ResolvedMedia,
+    HtmlAllowlist, UrlPolicy, Span
 };
 
 #[cfg(feature = "html")]
@@ -14,7 +15,7 @@ mod html_tests {
         let html = markdown_to_html(markdown).unwrap();
         
         assert!(html.contains("<h1>Hello World</h1>"));
-        assert!(html.contains("<p>This is a<strong>bold</strong>paragraph.</p>"));
+        assert!(html.contains("<p>This is a <strong>bold</strong> paragraph.</p>"));
     }
 
     #[test]
@@ -97,16 +98,27 @@ mod html_tests {
 
     #[test]
     fn test_no_html_escaping() {
-        let ast = AstNode::Text("<b>bold</b>".to_string());
+        let ast = AstNode::Paragraph {
+            content: vec![
+                AstNode::Html("<b>".to_string(), Span::default()),
+                AstNode::Text("bold".to_string(), Span::default()),
+                AstNode::Html("</b>".to_string(), Span::default()),
+            ],
+            span: Span::default(),
+        };
         let options = HtmlOptions {
             escape_html: false,
             ..Default::default()
         };
-        let renderer = HtmlRenderer::with_options(options);
+        let renderer = HtmlRenderer::with_options(options.clone());
         let html = renderer.render(&ast).unwrap();
         
         assert!(html.contains("<b>bold</b>"));
         assert!(!html.contains("&lt;"));
+
+        // Only raw HTML passes, markup in text was escaped in the markdown
+        let html = markdown_to_html_with_options(r"<i>a</i> \<b>b\</b> &lt;u&gt;c", options).unwrap();
+        assert_eq!(html, "<p><i>a</i> &lt;b&gt;b&lt;/b&gt; &lt;u&gt;c</p>");
     }
 
     #[test]
@@ -138,10 +150,12 @@ mod html_tests {
     fn test_css_classes() {
         let markdown = "# Title\n\nParagraph with **bold** text.";
         
-        let mut css_classes = CssClasses::default();
-        css_classes.heading = Some("custom-heading".to_string());
-        css_classes.paragraph = Some("custom-paragraph".to_string());
-        css_classes.bold = Some("custom-bold".to_string());
+        let css_classes = CssClasses {
+            heading: Some("custom-heading".to_string()),
+            paragraph: Some("custom-paragraph".to_string()),
+            bold: Some("custom-bold".to_string()),
+            ..Default::default()
+        };
         
         let options = HtmlOptions {
             css_classes,
@@ -159,8 +173,10 @@ mod html_tests {
     fn test_document_wrapper_class() {
         let markdown = "# Title";
         
-        let mut css_classes = CssClasses::default();
-        css_classes.document = Some("markdown-content".to_string());
+        let css_classes = CssClasses {
+            document: Some("markdown-content".to_string()),
+            ..Default::default()
+        };
         
         let options = HtmlOptions {
             css_classes,
@@ -178,10 +194,12 @@ mod html_tests {
     fn test_list_css_classes() {
         let markdown = "- Unordered\n\n1. Ordered";
         
-        let mut css_classes = CssClasses::default();
-        css_classes.list_unordered = Some("ul-class".to_string());
-        css_classes.list_ordered = Some("ol-class".to_string());
-        css_classes.list_item = Some("li-class".to_string());
+        let css_classes = CssClasses {
+            list_unordered: Some("ul-class".to_string()),
+            list_ordered: Some("ol-class".to_string()),
+            list_item: Some("li-class".to_string()),
+            ..Default::default()
+        };
         
         let options = HtmlOptions {
             css_classes,
@@ -218,14 +236,16 @@ mod html_tests {
     fn test_custom_attributes() {
         let markdown = "# Title\n\n[Link](https://example.com)";
         
-        let mut custom_attributes = CustomAttributes::default();
-        custom_attributes.heading = vec![
-            ("data-section".to_string(), "main".to_string()),
-            ("id".to_string(), "main-title".to_string()),
-        ];
-        custom_attributes.link = vec![
-            ("data-track".to_string(), "click".to_string()),
-        ];
+        let custom_attributes = CustomAttributes {
+            heading: vec![
+                ("data-section".to_string(), "main".to_string()),
+                ("id".to_string(), "main-title".to_string()),
+            ],
+            link: vec![
+                ("data-track".to_string(), "click".to_string()),
+            ],
+            ..Default::default()
+        };
         
         let options = HtmlOptions {
             custom_attributes,
@@ -241,7 +261,7 @@ mod html_tests {
 
     #[test]
     fn test_line_break_rendering() {
-        let ast = AstNode::LineBreak;
+        let ast = AstNode::LineBreak(Span::default());
         let renderer = HtmlRenderer::new();
         let html = renderer.render(&ast).unwrap();
         
@@ -250,7 +270,7 @@ mod html_tests {
 
     #[test]
     fn test_line_break_with_pretty_print() {
-        let ast = AstNode::LineBreak;
+        let ast = AstNode::LineBreak(Span::default());
         let options = HtmlOptions {
             pretty_print: true,
             ..Default::default()
@@ -265,11 +285,12 @@ mod html_tests {
     fn test_empty_elements() {
         let ast = AstNode::Document {
             children: vec![
-                AstNode::Heading { level: 1, content: vec![] },
-                AstNode::Paragraph { content: vec![] },
-                AstNode::Bold(vec![]),
-                AstNode::Italic(vec![]),
+                AstNode::Heading { level: 1, content: vec![], span: Span::default() },
+                AstNode::Paragraph { content: vec![], span: Span::default() },
+                AstNode::Bold(vec![], Span::default()),
+                AstNode::Italic(vec![], Span::default()),
             ],
+            span: Span::default(),
         };
         
         let renderer = HtmlRenderer::new();
@@ -335,20 +356,57 @@ That's all for now!"#;
     #[test]
     fn test_attribute_escaping() {
         let ast = AstNode::Link {
-            text: vec![AstNode::Text("Test".to_string())],
-            url: r#"javascript:alert("xss")"#.to_string(),
+            text: vec![AstNode::Text("Test".to_string(), Span::default())],
+            url: r#"https://example.com/"onmouseover="alert('xss')"#.to_string(),
+            title: None,
+            span: Span::default(),
         };
         
         let renderer = HtmlRenderer::new();
         let html = renderer.render(&ast).unwrap();
         
         assert!(html.contains("&quot;"));
-        assert!(!html.contains(r#"alert("xss")"#));
+        assert!(!html.contains(r#""onmouseover=""#));
+    }
+
+    #[test]
+    fn test_unsafe_link_keeps_only_text() {
+        let ast = AstNode::Link {
+            text: vec![AstNode::Text("Test".to_string(), Span::default())],
+            url: r#"javascript:alert("xss")"#.to_string(),
+            title: None,
+            span: Span::default(),
+        };
+
+        assert_eq!(HtmlRenderer::new().render(&ast).unwrap(), "Test");
+    }
+
+    #[test]
+    fn test_url_policy() {
+        let options = HtmlOptions {
+            url_policy: UrlPolicy {
+                allowed_schemes: vec!["https".to_string()],
+                allow_relative: false,
+                rel: Some("noopener noreferrer nofollow".to_string()),
+            },
+            ..Default::default()
+        };
+        let html = markdown_to_html_with_options("[a](https://a.org) [b](mailto:b@c.org) [c](/movie/603) ![d](e.jpg)", options).unwrap();
+
+        assert_eq!(html, r#"<p><a href="https://a.org" rel="noopener noreferrer nofollow">a</a> b c d</p>"#);
+    }
+
+    #[test]
+    fn test_html_allowlist() {
+        let options = HtmlOptions { html_allowlist: Some(HtmlAllowlist::default()), ..Default::default() };
+        let html = markdown_to_html_with_options("Press <kbd>Ctrl</kbd> <script>x</script> <b onclick=x>y", options).unwrap();
+
+        assert_eq!(html, "<p>Press <kbd>Ctrl</kbd> &lt;script&gt;x&lt;/script&gt; <b>y</b></p>");
     }
 
     #[test]
     fn test_renderer_trait_implementation() {
-        let ast = AstNode::Text("Hello".to_string());
+        let ast = AstNode::Text("Hello".to_string(), Span::default());
         let renderer = HtmlRenderer::new();
         
         // Test that it implements the Renderer trait
@@ -403,11 +461,15 @@ That's all for now!"#;
     fn test_combined_css_and_attributes() {
         let markdown = "# Test";
         
-        let mut css_classes = CssClasses::default();
-        css_classes.heading = Some("heading-class".to_string());
+        let css_classes = CssClasses {
+            heading: Some("heading-class".to_string()),
+            ..Default::default()
+        };
         
-        let mut custom_attributes = CustomAttributes::default();
-        custom_attributes.heading = vec![("id".to_string(), "test-heading".to_string())];
+        let custom_attributes = CustomAttributes {
+            heading: vec![("id".to_string(), "test-heading".to_string())],
+            ..Default::default()
+        };
         
         let options = HtmlOptions {
             css_classes,
@@ -424,7 +486,7 @@ That's all for now!"#;
     #[test]
     fn test_error_handling() {
         // This test ensures that the renderer handles edge cases gracefully
-        let ast = AstNode::Document { children: vec![] };
+        let ast = AstNode::Document { children: vec![], span: Span::default() };
         let renderer = HtmlRenderer::new();
         let result = renderer.render(&ast);
         
@@ -432,6 +494,328 @@ That's all for now!"#;
         let html = result.unwrap();
         assert!(html.is_empty() || html.trim().is_empty());
     }
+
+    #[test]
+    fn test_table_rendering() {
+        let markdown = "| Title | *Year* |\n|:--|--:|\n| Heat | 1995 |\n| a \\| b |";
+        let html = markdown_to_html(markdown).unwrap();
+
+        assert_eq!(
+            html,
+            concat!(
+                r#"<table><thead><tr><th align="left">Title</th><th align="right"><em>Year</em></th></tr></thead>"#,
+                r#"<tbody><tr><td align="left">Heat</td><td align="right">1995</td></tr>"#,
+                r#"<tr><td align="left">a | b</td><td align="right"></td></tr></tbody></table>"#,
+            )
+        );
+    }
+
+    #[test]
+    fn test_table_css_class() {
+        let options = HtmlOptions {
+            css_classes: CssClasses { table: Some("watch-table".to_string()), ..Default::default() },
+            ..Default::default()
+        };
+        let html = markdown_to_html_with_options("a | b\n--- | ---", options).unwrap();
+
+        assert_eq!(html, r#"<table class="watch-table"><thead><tr><th>a</th><th>b</th></tr></thead></table>"#);
+    }
+
+    #[test]
+    fn test_code_rendering() {
+        let markdown = "Call `<init>()` first\n\n```html\n<p>&nbsp;</p>\n```";
+        let html = markdown_to_html(markdown).unwrap();
+
+        assert_eq!(
+            html,
+            concat!(
+                "<p>Call <code>&lt;init&gt;()</code> first</p>",
+                "<pre><code class=\"language-html\">&lt;p&gt;&amp;nbsp;&lt;/p&gt;\n</code></pre>",
+            )
+        );
+    }
+
+    #[test]
+    fn test_block_quote_rendering() {
+        let html = markdown_to_html("> **Rewatch** soon\n> - with friends\n\n***").unwrap();
+
+        assert_eq!(
+            html,
+            "<blockquote><p><strong>Rewatch</strong> soon</p><ul><li>with friends</li></ul></blockquote><hr>"
+        );
+    }
+
+    #[test]
+    fn test_nested_list_rendering() {
+        let html = markdown_to_html("3. first\n   - inner\n4. second").unwrap();
+
+        assert_eq!(html, r#"<ol start="3"><li>first<ul><li>inner</li></ul></li><li>second</li></ol>"#);
+    }
+
+    #[test]
+    fn test_loose_list_rendering() {
+        let html = markdown_to_html("* one\n\n  more\n* two").unwrap();
+
+        assert_eq!(html, "<ul><li><p>one</p><p>more</p></li><li><p>two</p></li></ul>");
+    }
+
+    #[test]
+    fn test_nested_list_pretty_print() {
+        let options = HtmlOptions { pretty_print: true, ..Default::default() };
+        let html = markdown_to_html_with_options("- a\n  - b", options).unwrap();
+
+        assert_eq!(html, "<ul>\n  <li>a\n    <ul>\n      <li>b</li>\n    </ul>\n  </li>\n</ul>\n");
+    }
+
+    #[test]
+    fn test_task_list_rendering() {
+        let html = markdown_to_html("- [ ] rewatch S1\n- [x] buy popcorn\n- plain").unwrap();
+
+        assert_eq!(
+            html,
+            r#"<ul><li><input type="checkbox" data-task-offset="0"> rewatch S1</li><li><input type="checkbox" data-task-offset="17" checked> buy popcorn</li><li>plain</li></ul>"#
+        );
+    }
+
+    #[test]
+    fn test_task_offset_toggles_that_task() {
+        let notes = "Intro\n\n> 1. [ ] quoted\n>    - [x] nested";
+        let html = markdown_to_html(notes).unwrap();
+
+        let offsets: Vec<usize> = html.split(r#"data-task-offset=""#).skip(1).map(|rest| rest[..rest.find('"').unwrap()].parse().unwrap()).collect();
+        assert_eq!(offsets.len(), 2);
+        assert_eq!(markdown_renderer::toggle_task(notes, offsets[0]), notes.replace("[ ]", "[x]"));
+        assert_eq!(markdown_renderer::toggle_task(notes, offsets[1]), notes.replace("[x]", "[ ]"));
+    }
+
+    #[test]
+    fn test_strikethrough_highlight_spoiler_rendering() {
+        let html = markdown_to_html("~~bad~~ ==good== ||Rosebud was the sled||").unwrap();
+
+        assert_eq!(
+            html,
+            r#"<p><del>bad</del> <mark>good</mark> <span class="spoiler" tabindex="0">Rosebud was the sled</span></p>"#
+        );
+    }
+
+    #[test]
+    fn test_spoiler_css_class() {
+        let options = HtmlOptions {
+            css_classes: CssClasses { spoiler: Some("hidden".to_string()), highlight: Some("hl".to_string()), ..Default::default() },
+            ..Default::default()
+        };
+        let html = markdown_to_html_with_options("||x|| ==y==", options).unwrap();
+
+        assert_eq!(html, r#"<p><span class="hidden" tabindex="0">x</span> <mark class="hl">y</mark></p>"#);
+    }
+
+    #[test]
+    fn test_double_pipe_in_table_is_empty_cell() {
+        let html = markdown_to_html("| a | b | c |\n|---|---|---|\n| 1 || 3 |").unwrap();
+
+        assert!(html.contains("<tr><td>1</td><td></td><td>3</td></tr>"), "{}", html);
+    }
+
+    #[test]
+    fn test_image_rendering() {
+        let html = markdown_to_html(r#"![Neo & Trinity](https://image.tmdb.org/p.jpg "The Matrix")"#).unwrap();
+
+        assert_eq!(
+            html,
+            r#"<p><img src="https://image.tmdb.org/p.jpg" alt="Neo &amp; Trinity" title="The Matrix" loading="lazy"></p>"#
+        );
+    }
+
+    #[test]
+    fn test_image_schemes() {
+        // Relative URLs are always allowed, other schemes fall back to the alt text
+        let html = markdown_to_html("![poster](posters/603.jpg) ![<x>](javascript:alert(1)) ![y](data:image/png;base64,AAAA)").unwrap();
+        assert_eq!(html, r#"<p><img src="posters/603.jpg" alt="poster" loading="lazy"> &lt;x&gt; y</p>"#);
+
+        let options = HtmlOptions {
+            image_schemes: vec!["data".to_string()],
+            lazy_load_images: false,
+            ..Default::default()
+        };
+        let html = markdown_to_html_with_options("![y](data:image/png;base64,AAAA) ![z](https://a.org/z.png)", options).unwrap();
+        assert_eq!(html, r#"<p><img src="data:image/png;base64,AAAA" alt="y"> z</p>"#);
+    }
+
+    #[test]
+    fn test_rewrite_image_url() {
+        let options = HtmlOptions {
+            rewrite_image_url: Some(UrlRewriter::new(|url| format!("/cache?src={}", url))),
+            css_classes: CssClasses { image: Some("poster".to_string()), ..Default::default() },
+            ..Default::default()
+        };
+        let html = markdown_to_html_with_options("![p](https://a.org/p.jpg) ![q](ftp://a.org/q.jpg)", options).unwrap();
+
+        assert_eq!(html, r#"<p><img src="/cache?src=https://a.org/p.jpg" alt="p" class="poster" loading="lazy"> q</p>"#);
+    }
+
+    #[test]
+    fn test_autolinks() {
+        let html = markdown_to_html("Data from https://www.themoviedb.org. Mail <mailto:a@b.org>").unwrap();
+
+        assert_eq!(
+            html,
+            r#"<p>Data from <a href="https://www.themoviedb.org">https://www.themoviedb.org</a>. Mail <a href="mailto:a@b.org">mailto:a@b.org</a></p>"#
+        );
+    }
+
+    struct Titles;
+
+    impl MediaResolver for Titles {
+        fn resolve(&self, media_ref: &MediaRef) -> Option<ResolvedMedia> {
+            match (media_ref.media_type, media_ref.id) {
+                (MediaType::Movie, 603) => Some(ResolvedMedia {
+                    title: "The <Matrix>".to_string(),
+                    poster_url: Some("https://image.tmdb.org/t/p/w92/matrix.jpg".to_string()),
+                }),
+                (MediaType::Tv, 1399) => Some(ResolvedMedia { title: "Game of Thrones".to_string(), poster_url: None }),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn test_media_ref_without_resolver() {
+        let html = markdown_to_html("See [[tv:1399 S02E03]]").unwrap();
+
+        assert_eq!(
+            html,
+            r#"<p>See <a href="/tv/1399" class="media-ref unresolved" data-media-type="tv" data-media-id="1399" data-season="2" data-episode="3">tv:1399 S02E03</a></p>"#
+        );
+    }
+
+    #[test]
+    fn test_media_ref_chips() {
+        let options = HtmlOptions {
+            media_resolver: Some(std::sync::Arc::new(Titles)),
+            ..Default::default()
+        };
+        let html = markdown_to_html_with_options("[[movie:603]] [[tv:1399 S02E03]] [[movie:1]]", options).unwrap();
+
+        assert_eq!(
+            html,
+            concat!(
+                r#"<p><a href="/movie/603" class="media-ref" data-media-type="movie" data-media-id="603">"#,
+                r#"<img src="https://image.tmdb.org/t/p/w92/matrix.jpg" alt="" loading="lazy"><span>The &lt;Matrix&gt;</span></a> "#,
+                r#"<a href="/tv/1399" class="media-ref" data-media-type="tv" data-media-id="1399" data-season="2" data-episode="3">"#,
+                r#"<span>Game of Thrones</span> <span>S02E03</span></a> "#,
+                r#"<a href="/movie/1" class="media-ref unresolved" data-media-type="movie" data-media-id="1">movie:1</a></p>"#,
+            )
+        );
+    }
+
+    #[test]
+    fn test_source_positions() {
+        let options = HtmlOptions { source_positions: true, ..Default::default() };
+        let html = markdown_to_html_with_options("# Heat\n\n- one\n- **two**\n\n> quote", options).unwrap();
+
+        assert_eq!(
+            html,
+            concat!(
+                r#"<h1 data-sourcepos="1:1-1:6">Heat</h1>"#,
+                r#"<ul data-sourcepos="3:1-4:9"><li data-sourcepos="3:1-3:5">one</li><li data-sourcepos="4:1-4:9"><strong>two</strong></li></ul>"#,
+                r#"<blockquote data-sourcepos="6:1-6:7"><p data-sourcepos="6:3-6:7">quote</p></blockquote>"#,
+            )
+        );
+        // Off by default
+        assert_eq!(markdown_to_html("# Heat").unwrap(), "<h1>Heat</h1>");
+    }
+
+    #[test]
+    fn test_escapes_entities_and_breaks() {
+        let html = markdown_to_html("Tom &amp; Jerry \\*1940\\*  \nby Hanna&ndash;Barbera\nand MGM\n\nCartoons\n===").unwrap();
+
+        assert_eq!(html, "<p>Tom &amp; Jerry *1940*<br>by Hanna\u{2013}Barbera\nand MGM</p><h1>Cartoons</h1>");
+    }
+
+    #[test]
+    fn test_reference_links_and_footnotes() {
+        let html = markdown_to_html("See [Heat] twice[^1], really[^1].\n\n[heat]: https://example.com \"Heat (1995)\"\n[^1]: Or *three* times.").unwrap();
+
+        assert_eq!(
+            html,
+            concat!(
+                r#"<p>See <a href="https://example.com" title="Heat (1995)">Heat</a> twice"#,
+                r##"<sup class="footnote-ref"><a href="#fn-1" id="fnref-1">1</a></sup>, really"##,
+                r##"<sup class="footnote-ref"><a href="#fn-1" id="fnref-1-2">1</a></sup>.</p>"##,
+                r#"<section class="footnotes"><ol><li id="fn-1"><p>Or <em>three</em> times. "#,
+                r##"<a href="#fnref-1" class="footnote-backref" aria-label="Back to reference 1">↩</a> "##,
+                r##"<a href="#fnref-1-2" class="footnote-backref" aria-label="Back to reference 1">↩</a></p></li></ol></section>"##,
+            )
+        );
+    }
+
+    #[test]
+    fn test_footnotes_numbered_by_first_reference() {
+        let html = markdown_to_html("A[^b] B[^a]\n\n[^a]: First defined.\n[^b]: Second defined.\n[^unused]: Never referenced.").unwrap();
+
+        assert!(html.contains(r##"A<sup class="footnote-ref"><a href="#fn-1" id="fnref-1">1</a></sup>"##));
+        assert!(html.find(r#"<li id="fn-1"><p>Second defined."#).unwrap() < html.find(r#"<li id="fn-2"><p>First defined."#).unwrap());
+        assert!(!html.contains("Never referenced"));
+    }
+
+    #[test]
+    fn test_heading_ids() {
+        let options = HtmlOptions { heading_ids: true, ..Default::default() };
+        let html = markdown_to_html_with_options("# The Wire
+
+## Season 1
+
+## Season 1
+
+See [season 1](#season-1).", options).unwrap();
+
+        assert_eq!(
+            html,
+            concat!(
+                r#"<h1 id="the-wire">The Wire</h1><h2 id="season-1">Season 1</h2><h2 id="season-1-1">Season 1</h2>"#,
+                r##"<p>See <a href="#season-1">season 1</a>.</p>"##,
+            )
+        );
+        // Off by default
+        assert_eq!(markdown_to_html("# The Wire").unwrap(), "<h1>The Wire</h1>");
+    }
+
+    #[test]
+    fn test_sentences_starting_with_numbers() {
+        let cases = [
+            ("3 stars", "<p>3 stars</p>"),
+            ("2024 was great", "<p>2024 was great</p>"),
+            ("1.5 stars", "<p>1.5 stars</p>"),
+            ("I give it\n4 stars", "<p>I give it\n4 stars</p>"),
+            // Only a list starting at 1 interrupts a paragraph
+            ("Top picks\n2. Heat", "<p>Top picks\n2. Heat</p>"),
+            ("Top picks\n1. Heat", "<p>Top picks</p><ol><li>Heat</li></ol>"),
+            ("3) Heat\n4) Ronin", r#"<ol start="3"><li>Heat</li><li>Ronin</li></ol>"#),
+        ];
+
+        for (markdown, expected) in cases {
+            assert_eq!(markdown_to_html(markdown).unwrap(), expected, "markdown: {:?}", markdown);
+        }
+    }
+
+    #[test]
+    fn test_underscores() {
+        let cases = [
+            ("_italic_ and __bold__", "<p><em>italic</em> and <strong>bold</strong></p>"),
+            ("snake_case", "<p>snake_case</p>"),
+            ("foo_bar_baz", "<p>foo_bar_baz</p>"),
+            ("_foo_bar_", "<p><em>foo_bar</em></p>"),
+            // Unclosed underscores stay as typed
+            ("a _b", "<p>a _b</p>"),
+            ("foo_bar_", "<p>foo_bar_</p>"),
+            ("__bold", "<p>__bold</p>"),
+            ("*a_b*", "<p><em>a_b</em></p>"),
+        ];
+
+        for (markdown, expected) in cases {
+            assert_eq!(markdown_to_html(markdown).unwrap(), expected, "markdown: {:?}", markdown);
+        }
+    }
 }
 
 #[cfg(not(feature = "html"))]
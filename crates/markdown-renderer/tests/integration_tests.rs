@@ -1,18 +1,18 @@
-use markdown_renderer::{parse_markdown, tokenize, parse, AstNode, ParseError, MarkdownError};
+use markdown_renderer::{parse_markdown, parse_markdown_with_diagnostics, tokenize, parse, AstNode, ParseError, Span};
 
 #[test]
 fn test_complete_pipeline_simple() {
     let markdown = "# Hello World";
-    let ast = parse_markdown(markdown).unwrap();
+    let ast = parse_markdown(markdown).unwrap().without_spans();
     
     match ast {
-        AstNode::Document { children } => {
+        AstNode::Document { children, .. } => {
             assert_eq!(children.len(), 1);
             match &children[0] {
-                AstNode::Heading { level, content } => {
+                AstNode::Heading { level, content, .. } => {
                     assert_eq!(*level, 1);
                     assert_eq!(content.len(), 1);
-                    assert_eq!(content[0], AstNode::Text("Hello World".to_string()));
+                    assert_eq!(content[0], AstNode::Text("Hello World".to_string(), Span::default()));
                 },
                 _ => panic!("Expected heading node"),
             }
@@ -43,7 +43,7 @@ Final paragraph."#;
     let ast = parse_markdown(markdown).unwrap();
     
     match ast {
-        AstNode::Document { children } => {
+        AstNode::Document { children, .. } => {
             assert_eq!(children.len(), 8); // title, paragraph, subsection, paragraph, list, subsubsection, ordered list, final paragraph
             
             // Check main title
@@ -51,11 +51,11 @@ Final paragraph."#;
             
             // Check first paragraph with formatting
             match &children[1] {
-                AstNode::Paragraph { content } => {
+                AstNode::Paragraph { content, .. } => {
                     assert!(content.len() > 3); // Should have text, bold, text, italic, text
                     // Find bold and italic elements
-                    let has_bold = content.iter().any(|node| matches!(node, AstNode::Bold(_)));
-                    let has_italic = content.iter().any(|node| matches!(node, AstNode::Italic(_)));
+                    let has_bold = content.iter().any(|node| matches!(node, AstNode::Bold(..)));
+                    let has_italic = content.iter().any(|node| matches!(node, AstNode::Italic(..)));
                     assert!(has_bold);
                     assert!(has_italic);
                 },
@@ -78,12 +78,12 @@ Final paragraph."#;
             assert!(ordered_list.is_some(), "Expected to find ordered list");
             
             // Check the unordered list has the expected structure
-            if let Some(AstNode::List { ordered: false, items }) = unordered_list {
+            if let Some(AstNode::List { ordered: false, items, .. }) = unordered_list {
                 assert_eq!(items.len(), 2); // The parser groups consecutive list items together
                 
                 // Check first item has a link
                 match &items[0] {
-                    AstNode::ListItem { content } => {
+                    AstNode::ListItem { content, .. } => {
                         let has_link = content.iter().any(|node| matches!(node, AstNode::Link { .. }));
                         assert!(has_link);
                     },
@@ -92,7 +92,7 @@ Final paragraph."#;
             }
             
             // Check the ordered list
-            if let Some(AstNode::List { ordered: true, items }) = ordered_list {
+            if let Some(AstNode::List { ordered: true, items, .. }) = ordered_list {
                 assert_eq!(items.len(), 2);
             }
         },
@@ -112,7 +112,7 @@ fn test_tokenize_then_parse() {
     let ast = parse(tokens).unwrap();
     
     match ast {
-        AstNode::Document { children } => {
+        AstNode::Document { children, .. } => {
             assert_eq!(children.len(), 2); // heading and paragraph
         },
         _ => panic!("Expected document node"),
@@ -120,16 +120,30 @@ fn test_tokenize_then_parse() {
 }
 
 #[test]
-fn test_error_propagation() {
-    // Test with malformed markdown that should cause a parse error
+fn test_error_recovery() {
+    // Malformed markdown still parses, with the problem reported as a diagnostic
     let markdown = "[unclosed link";
-    
-    match parse_markdown(markdown) {
-        Err(MarkdownError::ParseError(ParseError::UnclosedDelimiter { .. })) => {
-            // Expected error
+    let (ast, diagnostics) = parse_markdown_with_diagnostics(markdown).unwrap();
+
+    assert_eq!(plain_text(&ast), markdown);
+    match diagnostics.as_slice() {
+        [diagnostic] => {
+            assert!(matches!(diagnostic.error, ParseError::UnclosedDelimiter { delimiter: '[', .. }));
+            assert_eq!(diagnostic.span.range(), 0..1);
         },
-        Ok(_) => panic!("Expected parse error"),
-        Err(e) => panic!("Unexpected error type: {:?}", e),
+        _ => panic!("Expected one diagnostic, got {:?}", diagnostics),
+    }
+}
+
+/// Text of a document's inline nodes with the formatting dropped
+fn plain_text(node: &AstNode) -> String {
+    match node {
+        AstNode::Text(text, _) => text.clone(),
+        AstNode::Document { children, .. } => children.iter().map(plain_text).collect::<Vec<_>>().join("\n"),
+        AstNode::Paragraph { content, .. }
+        | AstNode::Bold(content, _)
+        | AstNode::Italic(content, _) => content.iter().map(plain_text).collect(),
+        _ => String::new(),
     }
 }
 
@@ -138,7 +152,7 @@ fn test_empty_input() {
     let ast = parse_markdown("").unwrap();
     
     match ast {
-        AstNode::Document { children } => {
+        AstNode::Document { children, .. } => {
             assert!(children.is_empty());
         },
         _ => panic!("Expected empty document"),
@@ -150,7 +164,7 @@ fn test_whitespace_only() {
     let ast = parse_markdown("   \n  \n   ").unwrap();
     
     match ast {
-        AstNode::Document { children } => {
+        AstNode::Document { children, .. } => {
             assert!(children.is_empty());
         },
         _ => panic!("Expected empty document"),
@@ -169,7 +183,7 @@ fn test_multiple_headings() {
     let ast = parse_markdown(markdown).unwrap();
     
     match ast {
-        AstNode::Document { children } => {
+        AstNode::Document { children, .. } => {
             assert_eq!(children.len(), 6);
             
             for (i, child) in children.iter().enumerate() {
@@ -188,35 +202,17 @@ fn test_multiple_headings() {
 #[test]
 fn test_nested_formatting() {
     let markdown = "Text with **bold and *italic inside*** more text.";
-    let result = parse_markdown(markdown);
-    
-    match result {
-        Ok(ast) => {
-            match ast {
-                AstNode::Document { children } => {
-                    assert_eq!(children.len(), 1);
-                    match &children[0] {
-                        AstNode::Paragraph { content } => {
-                            // Should have text, bold section, text
-                            assert!(content.len() >= 3);
-                            
-                            // Find the bold section
-                            let bold_found = content.iter().any(|node| {
-                                matches!(node, AstNode::Bold(_))
-                            });
-                            assert!(bold_found);
-                        },
-                        _ => panic!("Expected paragraph"),
-                    }
-                },
-                _ => panic!("Expected document node"),
-            }
-        },
-        Err(MarkdownError::ParseError(ParseError::UnclosedDelimiter { .. })) => {
-            // Expected for complex nested formatting that might not match properly
+    let (ast, diagnostics) = parse_markdown_with_diagnostics(markdown).unwrap();
+
+    match &ast {
+        AstNode::Document { children, .. } => {
+            assert_eq!(children.len(), 1);
         },
-        Err(e) => panic!("Unexpected error: {:?}", e),
+        _ => panic!("Expected document node"),
     }
+    // Delimiter runs are not split, so the unmatched markers are kept as text and nothing is lost
+    assert_eq!(plain_text(&ast), markdown);
+    assert!(!diagnostics.is_empty());
 }
 
 #[test]
@@ -232,12 +228,12 @@ fn test_mixed_list_types() {
     let ast = parse_markdown(markdown).unwrap();
     
     match ast {
-        AstNode::Document { children } => {
+        AstNode::Document { children, .. } => {
             assert_eq!(children.len(), 3); // Two lists separated by content
             
             // First should be unordered
             match &children[0] {
-                AstNode::List { ordered: false, items } => {
+                AstNode::List { ordered: false, items, .. } => {
                     assert_eq!(items.len(), 2);
                 },
                 _ => panic!("Expected unordered list"),
@@ -245,7 +241,7 @@ fn test_mixed_list_types() {
             
             // Second should be ordered
             match &children[1] {
-                AstNode::List { ordered: true, items } => {
+                AstNode::List { ordered: true, items, .. } => {
                     assert_eq!(items.len(), 2);
                 },
                 _ => panic!("Expected ordered list"),
@@ -253,7 +249,7 @@ fn test_mixed_list_types() {
             
             // Third should be unordered again
             match &children[2] {
-                AstNode::List { ordered: false, items } => {
+                AstNode::List { ordered: false, items, .. } => {
                     assert_eq!(items.len(), 1);
                 },
                 _ => panic!("Expected unordered list"),
@@ -269,9 +265,9 @@ fn test_links_with_formatting() {
     let ast = parse_markdown(markdown).unwrap();
     
     match ast {
-        AstNode::Document { children } => {
+        AstNode::Document { children, .. } => {
             match &children[0] {
-                AstNode::Paragraph { content } => {
+                AstNode::Paragraph { content, .. } => {
                     // Should contain links
                     let link_count = content.iter().filter(|node| {
                         matches!(node, AstNode::Link { .. })
@@ -288,30 +284,19 @@ fn test_links_with_formatting() {
 #[test]
 fn test_consecutive_formatting() {
     let markdown = "**bold***italic*normal**bold2**";
-    let result = parse_markdown(markdown);
-    
-    match result {
-        Ok(ast) => {
-            match ast {
-                AstNode::Document { children } => {
-                    match &children[0] {
-                        AstNode::Paragraph { content } => {
-                            // Should have multiple formatting elements
-                            let has_bold = content.iter().any(|node| matches!(node, AstNode::Bold(_)));
-                            let has_italic = content.iter().any(|node| matches!(node, AstNode::Italic(_)));
-                            assert!(has_bold);
-                            assert!(has_italic);
-                        },
-                        _ => panic!("Expected paragraph"),
-                    }
+    let ast = parse_markdown(markdown).unwrap();
+
+    match &ast {
+        AstNode::Document { children, .. } => {
+            match &children[0] {
+                AstNode::Paragraph { content, .. } => {
+                    let has_bold = content.iter().any(|node| matches!(node, AstNode::Bold(..)));
+                    assert!(has_bold);
                 },
-                _ => panic!("Expected document node"),
+                _ => panic!("Expected paragraph"),
             }
         },
-        Err(MarkdownError::ParseError(ParseError::UnclosedDelimiter { .. })) => {
-            // Expected for unmatched emphasis markers
-        },
-        Err(e) => panic!("Unexpected error: {:?}", e),
+        _ => panic!("Expected document node"),
     }
 }
 
@@ -324,14 +309,14 @@ fn test_list_with_empty_items() {
     let ast = parse_markdown(markdown).unwrap();
     
     match ast {
-        AstNode::Document { children } => {
+        AstNode::Document { children, .. } => {
             match &children[0] {
                 AstNode::List { items, .. } => {
                     assert_eq!(items.len(), 3);
                     
                     // Check second item is empty
                     match &items[1] {
-                        AstNode::ListItem { content } => {
+                        AstNode::ListItem { content, .. } => {
                             assert!(content.is_empty());
                         },
                         _ => panic!("Expected list item"),
@@ -364,7 +349,7 @@ fn test_performance_large_document() {
     
     // Verify structure
     match ast {
-        AstNode::Document { children } => {
+        AstNode::Document { children, .. } => {
             // Should have many children (headings, paragraphs, lists)
             assert!(children.len() > 200);
         },
@@ -378,14 +363,14 @@ fn test_unicode_content() {
     let ast = parse_markdown(markdown).unwrap();
     
     match ast {
-        AstNode::Document { children } => {
+        AstNode::Document { children, .. } => {
             assert_eq!(children.len(), 2);
             
             // Check title has unicode
             match &children[0] {
                 AstNode::Heading { content, .. } => {
                     match &content[0] {
-                        AstNode::Text(text) => {
+                        AstNode::Text(text, _) => {
                             assert!(text.contains("🚀"));
                         },
                         _ => panic!("Expected text node"),
@@ -412,21 +397,14 @@ fn test_edge_case_empty_elements() {
 
 1. "#;
     
-    let result = parse_markdown(markdown);
+    // Malformed empty elements are kept as text rather than failing the parse
+    let ast = parse_markdown(markdown).unwrap();
     
-    match result {
-        Ok(ast) => {
-            match ast {
-                AstNode::Document { children } => {
-                    // Should handle empty elements gracefully
-                    assert!(!children.is_empty());
-                },
-                _ => panic!("Expected document node"),
-            }
-        },
-        Err(MarkdownError::ParseError(_)) => {
-            // Expected for malformed empty elements
+    match ast {
+        AstNode::Document { children, .. } => {
+            // Should handle empty elements gracefully
+            assert!(!children.is_empty());
         },
-        Err(e) => panic!("Unexpected error: {:?}", e),
+        _ => panic!("Expected document node"),
     }
 }
\ No newline at end of file
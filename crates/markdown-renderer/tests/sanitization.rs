@@ -0,0 +1,293 @@
+// Script injection corpus: every payload is rendered on its own and inside each markdown construct,
+// and the HTML is checked tag by tag for anything that could run script in the webview.
+
+#[cfg(feature = "html")]
+use markdown_renderer::{markdown_to_html_with_options, HtmlAllowlist, HtmlOptions, UrlPolicy};
+
+#[cfg(feature = "html")]
+mod sanitization_tests {
+    use super::*;
+
+    const PAYLOADS: &[&str] = &[
+        "<script>alert(1)</script>",
+        "<SCRIPT SRC=//evil.example/x.js></SCRIPT>",
+        "<scr<script>ipt>alert(1)</script>",
+        "<img src=x onerror=alert(1)>",
+        "<img src=\"x\" onerror=\"alert(1)\"/>",
+        "<svg onload=alert(1)>",
+        "<svg><script>alert(1)</script></svg>",
+        "<iframe src=\"javascript:alert(1)\"></iframe>",
+        "<iframe srcdoc=\"<script>alert(1)</script>\">",
+        "<a href=\"javascript:alert(1)\">x</a>",
+        "<b onmouseover=alert(1)>hover</b>",
+        "<b/onmouseover=alert(1)>hover</b>",
+        "<abbr title=\"x\" onclick=\"alert(1)\">y</abbr>",
+        "<abbr title='\"><script>alert(1)</script>'>y</abbr>",
+        "<style>body{background:url(javascript:alert(1))}</style>",
+        "<math><mtext><img src=x onerror=alert(1)></mtext></math>",
+        "<details open ontoggle=alert(1)>",
+        "<body onload=alert(1)>",
+        "<object data=\"data:text/html,<script>alert(1)</script>\">",
+        "<embed src=javascript:alert(1)>",
+        "<form action=javascript:alert(1)><button>x</button></form>",
+        "<meta http-equiv=\"refresh\" content=\"0;url=javascript:alert(1)\">",
+        "<link rel=stylesheet href=javascript:alert(1)>",
+        "<!--<script>alert(1)</script>-->",
+        "</p><script>alert(1)</script><p>",
+        "</li></ul><script>alert(1)</script>",
+        "</span><script>alert(1)</script>",
+        "\"><script>alert(1)</script>",
+        "'><img src=x onerror=alert(1)>",
+        "[x](javascript:alert(1))",
+        "[x](JaVaScRiPt:alert(1))",
+        "[x]( javascript:alert(1))",
+        "[x](java\tscript:alert(1))",
+        "[x](javascript&colon;alert(1))",
+        "[x](&#106;avascript:alert(1))",
+        "[x](data:text/html;base64,PHNjcmlwdD5hbGVydCgxKTwvc2NyaXB0Pg==)",
+        "[x](vbscript:msgbox(1))",
+        "[x](https://a.org\" onmouseover=\"alert(1))",
+        "[x](https://a.org 'onmouseover=alert(1)')",
+        "[x](//evil.example)",
+        "![x](javascript:alert(1))",
+        "![x\" onerror=\"alert(1)](https://a.org/p.jpg)",
+        "![x](https://a.org/p.jpg \"t\\\" onerror=\\\"alert(1)\")",
+        "![x](data:image/svg+xml,<svg onload=alert(1)>)",
+        "<javascript:alert(1)>",
+        "<data:text/html,<script>alert(1)</script>>",
+        "https://a.org/<script>alert(1)</script>",
+        "`<script>alert(1)</script>`",
+        "```html\n<script>alert(1)</script>\n```",
+        "[[movie:603\"><script>alert(1)</script>]]",
+        "||<img src=x onerror=alert(1)>||",
+        "~~<svg onload=alert(1)>~~",
+        "==<b onclick=alert(1)>x</b>==",
+        "<kbd><script>alert(1)</script></kbd>",
+        "<sub><sup><b><i>",
+        "<br/><br onclick=alert(1)>",
+        "<<script>script>alert(1)<</script>/script>",
+        "&lt;script&gt;alert(1)&lt;/script&gt;",
+        "&lt;b onclick=alert(1)&gt;x&lt;/b&gt;",
+        "&#60;img src=x onerror=alert(1)&#62;",
+        "\\<script>alert(1)\\</script>",
+        "\\<b onclick=alert(1)>x\\</b>",
+        "<b>\\</b>&lt;/b&gt;</b>",
+    ];
+
+    /// Places a payload can end up in
+    const WRAPPERS: &[&str] = &[
+        "{}",
+        "# {}",
+        "**{}**",
+        "*{}*",
+        "[{}](https://a.org)",
+        "- {}",
+        "1. [ ] {}",
+        "> {}",
+        "| a |\n|---|\n| {} |",
+        "text {} text\nnext line {}",
+        "{}\n\n{}",
+    ];
+
+    /// Elements the renderer writes itself plus the default raw HTML allowlist
+    const SAFE_TAGS: &[&str] = &[
+        "p", "h1", "h2", "h3", "h4", "h5", "h6", "ul", "ol", "li", "input", "strong", "em", "del", "mark", "span",
+        "a", "img", "code", "pre", "blockquote", "hr", "table", "thead", "tbody", "tr", "th", "td", "div", "b",
+        "i", "u", "s", "sub", "sup", "kbd", "small", "abbr", "br",
+    ];
+
+    const SAFE_SCHEMES: &[&str] = &["http", "https", "mailto"];
+
+    fn option_sets() -> Vec<HtmlOptions> {
+        vec![
+            HtmlOptions::default(),
+            HtmlOptions { html_allowlist: Some(HtmlAllowlist::default()), ..Default::default() },
+            HtmlOptions {
+                html_allowlist: Some(HtmlAllowlist::default()),
+                external_links_new_tab: true,
+                url_policy: UrlPolicy { rel: Some("noopener noreferrer nofollow".to_string()), ..Default::default() },
+                ..Default::default()
+            },
+        ]
+    }
+
+    fn unescape(value: &str) -> String {
+        value
+            .replace("&quot;", "\"")
+            .replace("&#x27;", "'")
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&amp;", "&")
+    }
+
+    /// Scheme of a URL the way a browser reads it
+    fn scheme(url: &str) -> Option<String> {
+        let url: String = url.trim_matches(|ch: char| ch <= ' ').chars().filter(|ch| !matches!(ch, '\t' | '\n' | '\r')).collect();
+        if url.starts_with("//") {
+            return Some("https".to_string());
+        }
+        let end = url.find([':', '/', '?', '#'])?;
+        let scheme = &url[..end];
+        // Anything but a letter followed by letters, digits, `+`, `-` and `.` makes the URL relative
+        let valid = scheme.starts_with(|ch: char| ch.is_ascii_alphabetic())
+            && scheme.chars().all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '+' | '-' | '.'));
+        (url[end..].starts_with(':') && valid).then(|| scheme.to_ascii_lowercase())
+    }
+
+    /// Attributes of the tag starting at `tag` (just after its name) up to the closing `>`, assuming
+    /// values are always double quoted as the renderer writes them
+    fn attributes(tag: &str) -> Vec<(String, String)> {
+        let mut attributes = Vec::new();
+        let mut rest = tag;
+        loop {
+            rest = rest.trim_start();
+            if rest.is_empty() || rest.starts_with('>') {
+                return attributes;
+            }
+            let name_end = rest.find(|ch: char| ch == '=' || ch == '>' || ch.is_whitespace()).unwrap_or(rest.len());
+            let name = rest[..name_end].to_ascii_lowercase();
+            rest = &rest[name_end..];
+            let value = match rest.strip_prefix("=\"") {
+                Some(quoted) => {
+                    let end = quoted.find('"').expect("unterminated attribute value");
+                    rest = &quoted[end + 1..];
+                    quoted[..end].to_string()
+                },
+                None => {
+                    assert!(!rest.starts_with('='), "unquoted attribute value in {:?}", tag);
+                    String::new()
+                },
+            };
+            attributes.push((name, unescape(&value)));
+        }
+    }
+
+    fn assert_safe(markdown: &str, html: &str) {
+        let context = || format!("markdown {:?} rendered as {:?}", markdown, html);
+
+        for (start, _) in html.match_indices('<') {
+            let tag = &html[start + 1..];
+            let tag = tag.strip_prefix('/').unwrap_or(tag);
+            let end = tag.find('>').unwrap_or_else(|| panic!("unterminated tag: {}", context()));
+            let tag = &tag[..end];
+
+            let name_end = tag.find(|ch: char| ch.is_whitespace()).unwrap_or(tag.len());
+            let name = tag[..name_end].to_ascii_lowercase();
+            assert!(SAFE_TAGS.contains(&name.as_str()), "unexpected <{}>: {}", name, context());
+
+            for (attribute, value) in attributes(&tag[name_end..]) {
+                assert!(!attribute.starts_with("on"), "event handler {}: {}", attribute, context());
+                assert!(!matches!(attribute.as_str(), "style" | "srcdoc" | "formaction"), "{}: {}", attribute, context());
+                if matches!(attribute.as_str(), "href" | "src") {
+                    if let Some(scheme) = scheme(&value) {
+                        assert!(SAFE_SCHEMES.contains(&scheme.as_str()), "{} scheme {:?}: {}", attribute, scheme, context());
+                    }
+                }
+                if name == "input" && attribute == "type" {
+                    assert_eq!(value, "checkbox", "{}", context());
+                }
+            }
+        }
+    }
+
+    fn render(markdown: &str, options: &HtmlOptions) -> Option<String> {
+        // Markdown the parser rejects never reaches the webview
+        markdown_to_html_with_options(markdown, options.clone()).ok()
+    }
+
+    #[test]
+    fn test_payloads_are_neutralized() {
+        for options in option_sets() {
+            for payload in PAYLOADS {
+                for wrapper in WRAPPERS {
+                    let markdown = wrapper.replace("{}", payload);
+                    if let Some(html) = render(&markdown, &options) {
+                        assert_safe(&markdown, &html);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_payload_pairs_are_neutralized() {
+        // Pairs catch payloads that only work when an earlier one leaves something open
+        for options in option_sets() {
+            for first in PAYLOADS {
+                for second in PAYLOADS {
+                    let markdown = format!("{} {}\n{}", first, second, first);
+                    if let Some(html) = render(&markdown, &options) {
+                        assert_safe(&markdown, &html);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_generated_inputs_are_neutralized() {
+        // A fixed-seed generator splicing payload fragments with markdown syntax, so failures reproduce
+        const FRAGMENTS: &[&str] = &[
+            "<", ">", "</", "/>", "\"", "'", "=", "script", "img", "b", "kbd", "abbr", " onerror=", " title=",
+            "alert(1)", "javascript:", "data:", "[", "](", ")", "![", "*", "**", "_", "~~", "==", "||", "`", "\n",
+            "- ", "> ", "| ", "[[movie:603]]", "https://a.org", " ", "&", "#", "\t",
+        ];
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        for options in option_sets() {
+            for _ in 0..2000 {
+                let len = 1 + next() % 24;
+                let markdown: String = (0..len).map(|_| FRAGMENTS[(next() % FRAGMENTS.len() as u64) as usize]).collect();
+                if let Some(html) = render(&markdown, &options) {
+                    assert_safe(&markdown, &html);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_safe_content_survives() {
+        let options = HtmlOptions { html_allowlist: Some(HtmlAllowlist::default()), ..Default::default() };
+        let html = markdown_to_html_with_options("[TMDB](https://www.themoviedb.org) <kbd>Space</kbd> a < b", options).unwrap();
+
+        assert_eq!(html, r#"<p><a href="https://www.themoviedb.org">TMDB</a> <kbd>Space</kbd> a &lt; b</p>"#);
+    }
+
+    #[test]
+    fn test_escaped_tags_stay_text() {
+        // Only tags written as HTML go through the allowlist, escaped and encoded ones are text
+        let options = HtmlOptions { html_allowlist: Some(HtmlAllowlist::default()), ..Default::default() };
+        let render = |markdown| markdown_to_html_with_options(markdown, options.clone()).unwrap();
+
+        assert_eq!(render("&lt;b&gt;x&lt;/b&gt;"), "<p>&lt;b&gt;x&lt;/b&gt;</p>");
+        assert_eq!(render(r"\<b>x\</b>"), "<p>&lt;b&gt;x&lt;/b&gt;</p>");
+        assert_eq!(render("&#60;b&#62;x&#60;/b&#62;"), "<p>&lt;b&gt;x&lt;/b&gt;</p>");
+        assert_eq!(render(r"<b>x\</b>"), "<p><b>x&lt;/b&gt;</b></p>");
+    }
+
+    #[test]
+    fn test_tags_span_inline_children() {
+        // A tag pair wraps the markup between them, but never reaches outside the span or block it opened in
+        let options = HtmlOptions { html_allowlist: Some(HtmlAllowlist::default()), ..Default::default() };
+        let render = |markdown| markdown_to_html_with_options(markdown, options.clone()).unwrap();
+
+        assert_eq!(render("<b>**x**</b>"), "<p><b><strong>x</strong></b></p>");
+        assert_eq!(
+            render("<kbd>a *b* [c](/d)</kbd> e"),
+            "<p><kbd>a <em>b</em> <a href=\"/d\">c</a></kbd> e</p>"
+        );
+        assert_eq!(render("<b>x\ny</b>"), "<p><b>x\ny</b></p>");
+        assert_eq!(render("**<b>x**</b>"), "<p><strong><b>x</b></strong>&lt;/b&gt;</p>");
+        assert_eq!(render("<b>x\n\ny</b>"), "<p><b>x</b></p><p>y&lt;/b&gt;</p>");
+        assert_eq!(
+            render("- <i>a\n  - b</i>\n- c</i>"),
+            "<ul><li><i>a<ul><li>b&lt;/i&gt;</li></ul></i></li><li>c&lt;/i&gt;</li></ul>"
+        );
+    }
+}
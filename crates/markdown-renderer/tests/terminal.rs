@@ -0,0 +1,93 @@
+//! Snapshot tests of `AnsiRenderer`.
+//!
+//! Each test renders some markdown and compares it with `snapshots/terminal/<name>.txt`, where the
+//! escape character is written as `\e` to keep the files readable. `cat` a snapshot through
+//! `sed 's/\\e/\x1b/g'` to see it the way a terminal shows it.
+//!
+//! - `UPDATE_SNAPSHOTS=1 cargo test --features terminal --test terminal` rewrites the snapshots from the
+//!   current output, review the diff before committing it
+
+#![cfg(feature = "terminal")]
+
+use std::path::Path;
+use std::sync::Arc;
+
+use markdown_renderer::{parse_markdown, AnsiOptions, AnsiRenderer, MediaRef, MediaResolver, Renderer, ResolvedMedia};
+
+const SNAPSHOTS: &str = "tests/snapshots/terminal";
+
+fn render(markdown: &str, options: AnsiOptions) -> String {
+    let ast = parse_markdown(markdown).unwrap();
+    match AnsiRenderer::with_options(options).render(&ast) {
+        Ok(output) => output,
+        Err(never) => match never {},
+    }
+}
+
+fn assert_snapshot(name: &str, output: &str) {
+    let output = output.replace('\x1b', "\\e");
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(SNAPSHOTS).join(format!("{}.txt", name));
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, &output).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("No snapshot at {}, run with UPDATE_SNAPSHOTS=1 to create it", path.display()));
+    assert_eq!(output, expected, "Output differs from the snapshot {}", path.display());
+}
+
+#[test]
+fn test_headings_and_emphasis() {
+    let markdown = "# Heat\n\n## Cast\n\nA **great** *heist* film, ***really***. ~~Boring~~ ==Must see== `code`\n\n### Spoilers\n\nThe ending: ||everyone dies||";
+    assert_snapshot("headings_and_emphasis", &render(markdown, AnsiOptions::default()));
+}
+
+#[test]
+fn test_lists() {
+    let markdown = "- Season 1\n  - Pilot\n    - Cold open\n- Season 2\n\n3. three\n4. four\n\n- [x] Heat\n- [ ] Ronin\n\n1. Loose\n\n   With a second paragraph\n2. Item";
+    assert_snapshot("lists", &render(markdown, AnsiOptions::default()));
+}
+
+#[test]
+fn test_wrapping() {
+    let markdown = "A long paragraph about **a heist that goes wrong** in the middle of Los Angeles, told over three hours.\nA soft break reflows.\\\nA hard one doesn't.\n\n> Quoted text wraps inside the quote, with the bar on every line.\n\n- List items wrap under their text, not under the bullet\n  - Nested ones too, one level further in\n\nhttps://example.com/a/very/long/url/that/does/not/fit/on/one/line";
+    let options = AnsiOptions { width: Some(30), ..Default::default() };
+    assert_snapshot("wrapping", &render(markdown, options));
+}
+
+#[test]
+fn test_no_wrapping() {
+    let markdown = "Lines stay\nas they were written, however long they get when there is no width to wrap at.";
+    let options = AnsiOptions { width: None, ..Default::default() };
+    assert_snapshot("no_wrapping", &render(markdown, options));
+}
+
+#[test]
+fn test_links() {
+    let markdown = "See [the review](https://example.com/heat \"Review\"), <https://example.com> and ![Poster](https://example.com/p.png).";
+    assert_snapshot("links", &render(markdown, AnsiOptions::default()));
+    let options = AnsiOptions { hyperlinks: false, ..Default::default() };
+    assert_snapshot("links_without_hyperlinks", &render(markdown, options));
+}
+
+#[test]
+fn test_blocks() {
+    let markdown = "| Title | Year |\n|:--|--:|\n| *Heat* | 1995 |\n| Ronin | 1998 |\n\n```rust\nfn main() {}\n```\n\n---\n\nFootnote[^1].\n\n[^1]: The note.";
+    let options = AnsiOptions { width: Some(40), ..Default::default() };
+    assert_snapshot("blocks", &render(markdown, options));
+}
+
+#[test]
+fn test_media_refs() {
+    struct Library;
+
+    impl MediaResolver for Library {
+        fn resolve(&self, media_ref: &MediaRef) -> Option<ResolvedMedia> {
+            (media_ref.id == 1399).then(|| ResolvedMedia { title: "Game of Thrones".to_string(), poster_url: None })
+        }
+    }
+
+    let options = AnsiOptions { media_resolver: Some(Arc::new(Library)), ..Default::default() };
+    assert_snapshot("media_refs", &render("Like [[tv:1399 S02E03]], unlike [[movie:603]].", options));
+}
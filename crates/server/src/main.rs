@@ -0,0 +1,28 @@
+mod auth;
+mod error;
+mod openapi;
+mod routes;
+mod state;
+
+use anyhow::{Context, Result};
+use logic::config::ServerConfig;
+use logic::service::Library;
+use std::net::{Ipv4Addr, SocketAddr};
+
+use crate::state::AppState;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let config = ServerConfig::initialize()?;
+    let state = AppState::new(Library::open()?, &config.token());
+
+    // Only listen on loopback: the API is meant for scripts and home automation on this machine
+    let address = SocketAddr::from((Ipv4Addr::LOCALHOST, config.port()));
+    let listener = tokio::net::TcpListener::bind(address)
+        .await
+        .with_context(|| format!("Failed to bind {}", address))?;
+
+    eprintln!("Listening on http://{} (OpenAPI document at /api/openapi.json)", address);
+    axum::serve(listener, routes::router(state)).await?;
+    Ok(())
+}
@@ -0,0 +1,75 @@
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+
+use crate::routes::{markdown, notes, tmdb, watched, watchlist};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "TV Tracker API", description = "Local HTTP API over the TV tracker library"),
+    paths(
+        tmdb::trending_movies,
+        tmdb::trending_tv,
+        tmdb::search_movies,
+        tmdb::search_tv,
+        tmdb::movie_details,
+        tmdb::tv_details,
+        watchlist::list_movies,
+        watchlist::add_movie,
+        watchlist::remove_movie,
+        watchlist::list_tv_shows,
+        watchlist::add_tv_show,
+        watchlist::remove_tv_show,
+        watched::list_movies,
+        watched::get_movie,
+        watched::rate_movie,
+        watched::remove_movie,
+        watched::list_tv_shows,
+        watched::get_tv_show,
+        watched::rate_tv_show,
+        watched::remove_tv_show,
+        notes::get_movie_notes,
+        notes::update_movie_notes,
+        notes::get_tv_show_notes,
+        notes::update_tv_show_notes,
+        notes::get_watched_movie_notes,
+        notes::update_watched_movie_notes,
+        notes::get_watched_tv_show_notes,
+        notes::update_watched_tv_show_notes,
+        notes::toggle_movie_task,
+        notes::toggle_tv_show_task,
+        notes::toggle_watched_movie_task,
+        notes::toggle_watched_tv_show_task,
+        notes::movie_backlinks,
+        notes::tv_show_backlinks,
+        markdown::render_html,
+        markdown::parse_ast,
+    ),
+    modifiers(&BearerToken),
+    tags(
+        (name = "tmdb", description = "Trending titles, search and details from TMDB"),
+        (name = "watchlist", description = "Movies and TV shows to watch"),
+        (name = "watched", description = "Rated movies and TV shows"),
+        (name = "notes", description = "Markdown notes on library entries"),
+        (name = "markdown", description = "Markdown rendering"),
+    )
+)]
+pub struct ApiDoc;
+
+impl ApiDoc {
+    pub fn document() -> utoipa::openapi::OpenApi {
+        ApiDoc::openapi()
+    }
+}
+
+struct BearerToken;
+
+impl Modify for BearerToken {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        if let Some(components) = openapi.components.as_mut() {
+            components.add_security_scheme(
+                "token",
+                SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+            );
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use axum::extract::State;
+use axum::Json;
+use markdown_renderer::AstNode;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::error::{ApiResult, ErrorBody};
+use crate::state::AppState;
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RenderRequest {
+    pub markdown: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RenderedHtml {
+    pub html: String,
+}
+
+#[utoipa::path(post, path = "/api/markdown/html", tag = "markdown", security(("token" = [])), request_body = RenderRequest,
+    responses((status = 200, body = RenderedHtml), (status = 400, body = ErrorBody)))]
+pub async fn render_html(State(state): State<AppState>, Json(request): Json<RenderRequest>) -> ApiResult<Json<RenderedHtml>> {
+    let html = state.library.render_notes(&request.markdown).await?;
+    Ok(Json(RenderedHtml { html }))
+}
+
+#[utoipa::path(post, path = "/api/markdown/ast", tag = "markdown", security(("token" = [])), request_body = RenderRequest,
+    responses(
+        (status = 200, body = Object, description = "The syntax tree, each node tagged with its `type` and carrying its source `span`"),
+        (status = 400, body = ErrorBody),
+    ))]
+pub async fn parse_ast(State(state): State<AppState>, Json(request): Json<RenderRequest>) -> ApiResult<Json<AstNode>> {
+    Ok(Json(state.library.parse_notes(&request.markdown)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::routes::tests::{send, test_router};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_render_html() {
+        let router = test_router();
+
+        let (_, body) = send(&router, "POST", "/api/markdown/html", Some(json!({ "markdown": "# Notes" }))).await;
+
+        assert_eq!(body["html"], r#"<h1 id="notes" data-sourcepos="1:1-1:7">Notes</h1>"#);
+    }
+
+    #[tokio::test]
+    async fn test_parse_ast() {
+        let router = test_router();
+
+        let (_, body) = send(&router, "POST", "/api/markdown/ast", Some(json!({ "markdown": "# Notes" }))).await;
+
+        assert_eq!(body["type"], "document");
+        assert_eq!(body["children"][0]["type"], "heading");
+        assert_eq!(body["children"][0]["content"][0]["type"], "text");
+    }
+
+    #[tokio::test]
+    async fn test_render_html_resolves_title_references() {
+        let router = test_router();
+        send(&router, "POST", "/api/watchlist/movies", Some(json!({ "id": 603, "title": "The Matrix" }))).await;
+
+        let (_, body) = send(&router, "POST", "/api/markdown/html", Some(json!({ "markdown": "[[movie:603]]" }))).await;
+
+        assert!(body["html"].as_str().unwrap().contains("<span>The Matrix</span>"), "{}", body["html"]);
+    }
+}
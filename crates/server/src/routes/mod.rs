@@ -0,0 +1,128 @@
+pub mod markdown;
+pub mod notes;
+pub mod tmdb;
+pub mod watched;
+pub mod watchlist;
+
+use axum::routing::{get, post};
+use axum::{middleware, Json, Router};
+
+use crate::auth::require_token;
+use crate::openapi::ApiDoc;
+use crate::state::AppState;
+
+pub fn router(state: AppState) -> Router {
+    Router::new()
+        .route("/api/trending/movies", get(tmdb::trending_movies))
+        .route("/api/trending/tv", get(tmdb::trending_tv))
+        .route("/api/search/movies", get(tmdb::search_movies))
+        .route("/api/search/tv", get(tmdb::search_tv))
+        .route("/api/movies/{id}", get(tmdb::movie_details))
+        .route("/api/tv/{id}", get(tmdb::tv_details))
+        .route("/api/watchlist/movies", get(watchlist::list_movies).post(watchlist::add_movie))
+        .route("/api/watchlist/movies/{id}", axum::routing::delete(watchlist::remove_movie))
+        .route("/api/watchlist/tv", get(watchlist::list_tv_shows).post(watchlist::add_tv_show))
+        .route("/api/watchlist/tv/{id}", axum::routing::delete(watchlist::remove_tv_show))
+        .route("/api/watched/movies", get(watched::list_movies))
+        .route(
+            "/api/watched/movies/{id}",
+            get(watched::get_movie).put(watched::rate_movie).delete(watched::remove_movie),
+        )
+        .route("/api/watched/tv", get(watched::list_tv_shows))
+        .route(
+            "/api/watched/tv/{id}",
+            get(watched::get_tv_show).put(watched::rate_tv_show).delete(watched::remove_tv_show),
+        )
+        .route("/api/watchlist/movies/{id}/notes", get(notes::get_movie_notes).put(notes::update_movie_notes))
+        .route("/api/watchlist/tv/{id}/notes", get(notes::get_tv_show_notes).put(notes::update_tv_show_notes))
+        .route(
+            "/api/watched/movies/{id}/notes",
+            get(notes::get_watched_movie_notes).put(notes::update_watched_movie_notes),
+        )
+        .route(
+            "/api/watched/tv/{id}/notes",
+            get(notes::get_watched_tv_show_notes).put(notes::update_watched_tv_show_notes),
+        )
+        .route("/api/watchlist/movies/{id}/notes/tasks/{offset}", post(notes::toggle_movie_task))
+        .route("/api/watchlist/tv/{id}/notes/tasks/{offset}", post(notes::toggle_tv_show_task))
+        .route("/api/watched/movies/{id}/notes/tasks/{offset}", post(notes::toggle_watched_movie_task))
+        .route("/api/watched/tv/{id}/notes/tasks/{offset}", post(notes::toggle_watched_tv_show_task))
+        .route("/api/movies/{id}/backlinks", get(notes::movie_backlinks))
+        .route("/api/tv/{id}/backlinks", get(notes::tv_show_backlinks))
+        .route("/api/markdown/html", post(markdown::render_html))
+        .route("/api/markdown/ast", post(markdown::parse_ast))
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_token))
+        // Added after the auth layer so clients can discover the API without a token
+        .route("/api/openapi.json", get(|| async { Json(ApiDoc::document()) }))
+        .with_state(state)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use http_body_util::BodyExt;
+    use logic::service::Library;
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    pub const TOKEN: &str = "test-token";
+
+    pub fn test_router() -> Router {
+        router(AppState::new(Library::open_in_memory().unwrap(), TOKEN))
+    }
+
+    pub async fn request(router: &Router, method: &str, uri: &str, body: Option<Value>, token: Option<&str>) -> (StatusCode, Value) {
+        let mut builder = Request::builder().method(method).uri(uri);
+        if let Some(token) = token {
+            builder = builder.header("Authorization", format!("Bearer {}", token));
+        }
+        let body = match body {
+            Some(json) => {
+                builder = builder.header("Content-Type", "application/json");
+                Body::from(json.to_string())
+            }
+            None => Body::empty(),
+        };
+
+        let response = router.clone().oneshot(builder.body(body).unwrap()).await.unwrap();
+        let status = response.status();
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+    }
+
+    /// Send an authenticated request and decode the JSON response
+    pub async fn send(router: &Router, method: &str, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+        request(router, method, uri, body, Some(TOKEN)).await
+    }
+
+    #[tokio::test]
+    async fn test_requires_token() {
+        let router = test_router();
+
+        let (status, body) = request(&router, "GET", "/api/watchlist/movies", None, None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["error"], "Missing or invalid bearer token");
+
+        let (status, _) = request(&router, "GET", "/api/watchlist/movies", None, Some("wrong")).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let (status, _) = send(&router, "GET", "/api/watchlist/movies", None).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_openapi_is_public() {
+        let router = test_router();
+
+        let (status, body) = request(&router, "GET", "/api/openapi.json", None, None).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert!(body["paths"]["/api/watchlist/movies"]["post"].is_object());
+        assert!(body["paths"]["/api/watched/tv/{id}/notes/tasks/{offset}"]["post"].is_object());
+        assert!(body["paths"]["/api/markdown/ast"]["post"].is_object());
+        assert!(body["components"]["schemas"]["MovieToWatch"].is_object());
+        assert!(body["components"]["securitySchemes"]["token"].is_object());
+    }
+}
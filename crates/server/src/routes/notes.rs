@@ -0,0 +1,193 @@
+use axum::extract::{Path, State};
+use axum::Json;
+use logic::service::{Backlink, MediaKind, NotesLocation};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::error::{ApiError, ApiResult, ErrorBody};
+use crate::state::AppState;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct Notes {
+    /// Markdown notes
+    pub notes: String,
+}
+
+fn get_notes(state: &AppState, location: NotesLocation, kind: MediaKind, id: i32) -> ApiResult<Json<Notes>> {
+    let notes = state.library.notes(location, kind, id)?;
+    notes
+        .map(|notes| Json(Notes { notes }))
+        .ok_or_else(|| ApiError::not_found(format!("No entry with id {}", id)))
+}
+
+fn set_notes(state: &AppState, location: NotesLocation, kind: MediaKind, id: i32, notes: Notes) -> ApiResult<Json<Notes>> {
+    state.library.update_notes(location, kind, id, &notes.notes)?;
+    Ok(Json(notes))
+}
+
+const TASK_OFFSET: &str = "Byte offset of the task list item in the notes, the `data-task-offset` of its rendered checkbox";
+
+/// Check or uncheck a task list item and return the new notes, which stay as they are when no item
+/// starts at the offset
+fn toggle_task(state: &AppState, location: NotesLocation, kind: MediaKind, id: i32, offset: usize) -> ApiResult<Json<Notes>> {
+    let notes = state.library.toggle_notes_task(location, kind, id, offset)?;
+    Ok(Json(Notes { notes }))
+}
+
+#[utoipa::path(get, path = "/api/watchlist/movies/{id}/notes", tag = "notes", security(("token" = [])),
+    params(("id" = i32, Path, description = "TMDB movie id")),
+    responses((status = 200, body = Notes), (status = 404, body = ErrorBody)))]
+pub async fn get_movie_notes(State(state): State<AppState>, Path(id): Path<i32>) -> ApiResult<Json<Notes>> {
+    get_notes(&state, NotesLocation::Watchlist, MediaKind::Movie, id)
+}
+
+#[utoipa::path(put, path = "/api/watchlist/movies/{id}/notes", tag = "notes", security(("token" = [])),
+    params(("id" = i32, Path, description = "TMDB movie id")), request_body = Notes,
+    responses((status = 200, body = Notes), (status = 404, body = ErrorBody)))]
+pub async fn update_movie_notes(State(state): State<AppState>, Path(id): Path<i32>, Json(notes): Json<Notes>) -> ApiResult<Json<Notes>> {
+    set_notes(&state, NotesLocation::Watchlist, MediaKind::Movie, id, notes)
+}
+
+#[utoipa::path(get, path = "/api/watchlist/tv/{id}/notes", tag = "notes", security(("token" = [])),
+    params(("id" = i32, Path, description = "TMDB TV show id")),
+    responses((status = 200, body = Notes), (status = 404, body = ErrorBody)))]
+pub async fn get_tv_show_notes(State(state): State<AppState>, Path(id): Path<i32>) -> ApiResult<Json<Notes>> {
+    get_notes(&state, NotesLocation::Watchlist, MediaKind::Tv, id)
+}
+
+#[utoipa::path(put, path = "/api/watchlist/tv/{id}/notes", tag = "notes", security(("token" = [])),
+    params(("id" = i32, Path, description = "TMDB TV show id")), request_body = Notes,
+    responses((status = 200, body = Notes), (status = 404, body = ErrorBody)))]
+pub async fn update_tv_show_notes(State(state): State<AppState>, Path(id): Path<i32>, Json(notes): Json<Notes>) -> ApiResult<Json<Notes>> {
+    set_notes(&state, NotesLocation::Watchlist, MediaKind::Tv, id, notes)
+}
+
+#[utoipa::path(get, path = "/api/watched/movies/{id}/notes", tag = "notes", security(("token" = [])),
+    params(("id" = i32, Path, description = "TMDB movie id")),
+    responses((status = 200, body = Notes), (status = 404, body = ErrorBody)))]
+pub async fn get_watched_movie_notes(State(state): State<AppState>, Path(id): Path<i32>) -> ApiResult<Json<Notes>> {
+    get_notes(&state, NotesLocation::Watched, MediaKind::Movie, id)
+}
+
+#[utoipa::path(put, path = "/api/watched/movies/{id}/notes", tag = "notes", security(("token" = [])),
+    params(("id" = i32, Path, description = "TMDB movie id")), request_body = Notes,
+    responses((status = 200, body = Notes), (status = 404, body = ErrorBody)))]
+pub async fn update_watched_movie_notes(State(state): State<AppState>, Path(id): Path<i32>, Json(notes): Json<Notes>) -> ApiResult<Json<Notes>> {
+    set_notes(&state, NotesLocation::Watched, MediaKind::Movie, id, notes)
+}
+
+#[utoipa::path(get, path = "/api/watched/tv/{id}/notes", tag = "notes", security(("token" = [])),
+    params(("id" = i32, Path, description = "TMDB TV show id")),
+    responses((status = 200, body = Notes), (status = 404, body = ErrorBody)))]
+pub async fn get_watched_tv_show_notes(State(state): State<AppState>, Path(id): Path<i32>) -> ApiResult<Json<Notes>> {
+    get_notes(&state, NotesLocation::Watched, MediaKind::Tv, id)
+}
+
+#[utoipa::path(put, path = "/api/watched/tv/{id}/notes", tag = "notes", security(("token" = [])),
+    params(("id" = i32, Path, description = "TMDB TV show id")), request_body = Notes,
+    responses((status = 200, body = Notes), (status = 404, body = ErrorBody)))]
+pub async fn update_watched_tv_show_notes(State(state): State<AppState>, Path(id): Path<i32>, Json(notes): Json<Notes>) -> ApiResult<Json<Notes>> {
+    set_notes(&state, NotesLocation::Watched, MediaKind::Tv, id, notes)
+}
+
+#[utoipa::path(post, path = "/api/watchlist/movies/{id}/notes/tasks/{offset}", tag = "notes", security(("token" = [])),
+    params(("id" = i32, Path, description = "TMDB movie id"), ("offset" = usize, Path, description = TASK_OFFSET)),
+    responses((status = 200, body = Notes), (status = 404, body = ErrorBody)))]
+pub async fn toggle_movie_task(State(state): State<AppState>, Path((id, offset)): Path<(i32, usize)>) -> ApiResult<Json<Notes>> {
+    toggle_task(&state, NotesLocation::Watchlist, MediaKind::Movie, id, offset)
+}
+
+#[utoipa::path(post, path = "/api/watchlist/tv/{id}/notes/tasks/{offset}", tag = "notes", security(("token" = [])),
+    params(("id" = i32, Path, description = "TMDB TV show id"), ("offset" = usize, Path, description = TASK_OFFSET)),
+    responses((status = 200, body = Notes), (status = 404, body = ErrorBody)))]
+pub async fn toggle_tv_show_task(State(state): State<AppState>, Path((id, offset)): Path<(i32, usize)>) -> ApiResult<Json<Notes>> {
+    toggle_task(&state, NotesLocation::Watchlist, MediaKind::Tv, id, offset)
+}
+
+#[utoipa::path(post, path = "/api/watched/movies/{id}/notes/tasks/{offset}", tag = "notes", security(("token" = [])),
+    params(("id" = i32, Path, description = "TMDB movie id"), ("offset" = usize, Path, description = TASK_OFFSET)),
+    responses((status = 200, body = Notes), (status = 404, body = ErrorBody)))]
+pub async fn toggle_watched_movie_task(State(state): State<AppState>, Path((id, offset)): Path<(i32, usize)>) -> ApiResult<Json<Notes>> {
+    toggle_task(&state, NotesLocation::Watched, MediaKind::Movie, id, offset)
+}
+
+#[utoipa::path(post, path = "/api/watched/tv/{id}/notes/tasks/{offset}", tag = "notes", security(("token" = [])),
+    params(("id" = i32, Path, description = "TMDB TV show id"), ("offset" = usize, Path, description = TASK_OFFSET)),
+    responses((status = 200, body = Notes), (status = 404, body = ErrorBody)))]
+pub async fn toggle_watched_tv_show_task(State(state): State<AppState>, Path((id, offset)): Path<(i32, usize)>) -> ApiResult<Json<Notes>> {
+    toggle_task(&state, NotesLocation::Watched, MediaKind::Tv, id, offset)
+}
+
+#[utoipa::path(get, path = "/api/movies/{id}/backlinks", tag = "notes", security(("token" = [])),
+    params(("id" = i32, Path, description = "TMDB movie id")),
+    responses((status = 200, body = Vec<Backlink>), (status = 400, body = ErrorBody)))]
+pub async fn movie_backlinks(State(state): State<AppState>, Path(id): Path<i32>) -> ApiResult<Json<Vec<Backlink>>> {
+    Ok(Json(state.library.backlinks(MediaKind::Movie, id)?))
+}
+
+#[utoipa::path(get, path = "/api/tv/{id}/backlinks", tag = "notes", security(("token" = [])),
+    params(("id" = i32, Path, description = "TMDB TV show id")),
+    responses((status = 200, body = Vec<Backlink>), (status = 400, body = ErrorBody)))]
+pub async fn tv_show_backlinks(State(state): State<AppState>, Path(id): Path<i32>) -> ApiResult<Json<Vec<Backlink>>> {
+    Ok(Json(state.library.backlinks(MediaKind::Tv, id)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::routes::tests::{send, test_router};
+    use axum::http::StatusCode;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_update_and_get_notes() {
+        let router = test_router();
+        send(&router, "POST", "/api/watchlist/movies", Some(json!({ "id": 603, "title": "The Matrix" }))).await;
+
+        let (status, _) = send(&router, "PUT", "/api/watchlist/movies/603/notes", Some(json!({ "notes": "# Rewatch" }))).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (_, body) = send(&router, "GET", "/api/watchlist/movies/603/notes", None).await;
+        assert_eq!(body["notes"], "# Rewatch");
+    }
+
+    #[tokio::test]
+    async fn test_notes_need_an_entry() {
+        let router = test_router();
+
+        let (status, _) = send(&router, "PUT", "/api/watched/tv/1399/notes", Some(json!({ "notes": "x" }))).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, _) = send(&router, "GET", "/api/watchlist/tv/1399/notes", None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_toggle_task() {
+        let router = test_router();
+        send(&router, "POST", "/api/watchlist/tv", Some(json!({ "id": 1399, "name": "Game of Thrones" }))).await;
+        send(&router, "PUT", "/api/watchlist/tv/1399/notes", Some(json!({ "notes": "- [ ] S1\n- [ ] S2" }))).await;
+
+        let (status, body) = send(&router, "POST", "/api/watchlist/tv/1399/notes/tasks/9", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["notes"], "- [ ] S1\n- [x] S2");
+
+        // An offset where no item starts leaves the notes alone
+        let (_, body) = send(&router, "POST", "/api/watchlist/tv/1399/notes/tasks/3", None).await;
+        assert_eq!(body["notes"], "- [ ] S1\n- [x] S2");
+
+        let (status, _) = send(&router, "POST", "/api/watched/tv/1399/notes/tasks/0", None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_backlinks() {
+        let router = test_router();
+        send(&router, "POST", "/api/watchlist/movies", Some(json!({ "id": 604, "title": "The Matrix Reloaded" }))).await;
+        send(&router, "PUT", "/api/watchlist/movies/604/notes", Some(json!({ "notes": "After [[movie:603]]" }))).await;
+
+        let (status, body) = send(&router, "GET", "/api/movies/603/backlinks", None).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, json!([{ "location": "watchlist", "kind": "movie", "id": 604, "title": "The Matrix Reloaded" }]));
+    }
+}
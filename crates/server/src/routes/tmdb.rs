@@ -0,0 +1,52 @@
+use axum::extract::{Path, Query, State};
+use axum::Json;
+use logic::api::{Movie, MovieDetail, Tv, TvDetail};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::error::{ApiResult, ErrorBody};
+use crate::state::AppState;
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct SearchQuery {
+    /// Title to search TMDB for
+    pub query: String,
+}
+
+#[utoipa::path(get, path = "/api/trending/movies", tag = "tmdb", security(("token" = [])),
+    responses((status = 200, body = [Movie]), (status = 502, body = ErrorBody)))]
+pub async fn trending_movies(State(state): State<AppState>) -> ApiResult<Json<Vec<Movie>>> {
+    Ok(Json(state.library.trending_movies().await?))
+}
+
+#[utoipa::path(get, path = "/api/trending/tv", tag = "tmdb", security(("token" = [])),
+    responses((status = 200, body = [Tv]), (status = 502, body = ErrorBody)))]
+pub async fn trending_tv(State(state): State<AppState>) -> ApiResult<Json<Vec<Tv>>> {
+    Ok(Json(state.library.trending_tv().await?))
+}
+
+#[utoipa::path(get, path = "/api/search/movies", tag = "tmdb", security(("token" = [])), params(SearchQuery),
+    responses((status = 200, body = [Movie]), (status = 502, body = ErrorBody)))]
+pub async fn search_movies(State(state): State<AppState>, Query(search): Query<SearchQuery>) -> ApiResult<Json<Vec<Movie>>> {
+    Ok(Json(state.library.search_movies(&search.query).await?))
+}
+
+#[utoipa::path(get, path = "/api/search/tv", tag = "tmdb", security(("token" = [])), params(SearchQuery),
+    responses((status = 200, body = [Tv]), (status = 502, body = ErrorBody)))]
+pub async fn search_tv(State(state): State<AppState>, Query(search): Query<SearchQuery>) -> ApiResult<Json<Vec<Tv>>> {
+    Ok(Json(state.library.search_tv(&search.query).await?))
+}
+
+#[utoipa::path(get, path = "/api/movies/{id}", tag = "tmdb", security(("token" = [])),
+    params(("id" = u32, Path, description = "TMDB movie id")),
+    responses((status = 200, body = MovieDetail), (status = 502, body = ErrorBody)))]
+pub async fn movie_details(State(state): State<AppState>, Path(id): Path<u32>) -> ApiResult<Json<MovieDetail>> {
+    Ok(Json(state.library.movie_details(id).await?))
+}
+
+#[utoipa::path(get, path = "/api/tv/{id}", tag = "tmdb", security(("token" = [])),
+    params(("id" = u32, Path, description = "TMDB TV show id")),
+    responses((status = 200, body = TvDetail), (status = 502, body = ErrorBody)))]
+pub async fn tv_details(State(state): State<AppState>, Path(id): Path<u32>) -> ApiResult<Json<TvDetail>> {
+    Ok(Json(state.library.tv_details(id).await?))
+}
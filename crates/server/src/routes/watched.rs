@@ -0,0 +1,176 @@
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use logic::database::entities::{WatchedMovie, WatchedTvShow};
+use logic::service::{self, MediaKind};
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+use crate::error::{ApiError, ApiResult, ErrorBody};
+use crate::state::AppState;
+
+/// Rate a movie. The title fields are only needed the first time a movie is rated.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RateMovie {
+    pub rating: f32,
+    /// RFC 3339 timestamp; defaults to now for new entries and is kept otherwise
+    pub watched_at: Option<String>,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub poster_path: String,
+}
+
+/// Rate a TV show. The show fields are only needed the first time a show is rated.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RateTvShow {
+    pub rating: f32,
+    /// RFC 3339 timestamp; defaults to now for new entries and is kept otherwise
+    pub watched_at: Option<String>,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub poster_path: String,
+    #[serde(default)]
+    pub first_air_date: String,
+    #[serde(default)]
+    pub vote_average: f32,
+    #[serde(default)]
+    pub overview: String,
+}
+
+#[utoipa::path(get, path = "/api/watched/movies", tag = "watched", security(("token" = [])),
+    responses((status = 200, body = [WatchedMovie])))]
+pub async fn list_movies(State(state): State<AppState>) -> ApiResult<Json<Vec<WatchedMovie>>> {
+    Ok(Json(state.library.watched_movies()?))
+}
+
+#[utoipa::path(get, path = "/api/watched/movies/{id}", tag = "watched", security(("token" = [])),
+    params(("id" = i32, Path, description = "TMDB movie id")),
+    responses((status = 200, body = WatchedMovie), (status = 404, body = ErrorBody)))]
+pub async fn get_movie(State(state): State<AppState>, Path(id): Path<i32>) -> ApiResult<Json<WatchedMovie>> {
+    let movie = state.library.watched_movie(id)?;
+    movie.map(Json).ok_or_else(|| ApiError::not_found(format!("Movie {} has not been rated", id)))
+}
+
+/// Rate a movie, keeping its notes and watch date when it was rated before
+#[utoipa::path(put, path = "/api/watched/movies/{id}", tag = "watched", security(("token" = [])),
+    params(("id" = i32, Path, description = "TMDB movie id")), request_body = RateMovie,
+    responses((status = 200, body = WatchedMovie), (status = 400, body = ErrorBody)))]
+pub async fn rate_movie(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    Json(request): Json<RateMovie>,
+) -> ApiResult<Json<WatchedMovie>> {
+    let movie = state.library.rate_movie(service::RateMovie {
+        id,
+        rating: request.rating,
+        watched_at: request.watched_at,
+        title: request.title,
+        poster_path: request.poster_path,
+    })?;
+    Ok(Json(movie))
+}
+
+#[utoipa::path(delete, path = "/api/watched/movies/{id}", tag = "watched", security(("token" = [])),
+    params(("id" = i32, Path, description = "TMDB movie id")),
+    responses((status = 204)))]
+pub async fn remove_movie(State(state): State<AppState>, Path(id): Path<i32>) -> ApiResult<StatusCode> {
+    state.library.remove_rating(MediaKind::Movie, id)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(get, path = "/api/watched/tv", tag = "watched", security(("token" = [])),
+    responses((status = 200, body = [WatchedTvShow])))]
+pub async fn list_tv_shows(State(state): State<AppState>) -> ApiResult<Json<Vec<WatchedTvShow>>> {
+    Ok(Json(state.library.watched_tv_shows()?))
+}
+
+#[utoipa::path(get, path = "/api/watched/tv/{id}", tag = "watched", security(("token" = [])),
+    params(("id" = i32, Path, description = "TMDB TV show id")),
+    responses((status = 200, body = WatchedTvShow), (status = 404, body = ErrorBody)))]
+pub async fn get_tv_show(State(state): State<AppState>, Path(id): Path<i32>) -> ApiResult<Json<WatchedTvShow>> {
+    let show = state.library.watched_tv_show(id)?;
+    show.map(Json).ok_or_else(|| ApiError::not_found(format!("TV show {} has not been rated", id)))
+}
+
+/// Rate a TV show, keeping its notes and watch date when it was rated before
+#[utoipa::path(put, path = "/api/watched/tv/{id}", tag = "watched", security(("token" = [])),
+    params(("id" = i32, Path, description = "TMDB TV show id")), request_body = RateTvShow,
+    responses((status = 200, body = WatchedTvShow), (status = 400, body = ErrorBody)))]
+pub async fn rate_tv_show(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    Json(request): Json<RateTvShow>,
+) -> ApiResult<Json<WatchedTvShow>> {
+    let show = state.library.rate_tv_show(service::RateTvShow {
+        id,
+        rating: request.rating,
+        watched_at: request.watched_at,
+        name: request.name,
+        poster_path: request.poster_path,
+        first_air_date: request.first_air_date,
+        vote_average: request.vote_average,
+        overview: request.overview,
+    })?;
+    Ok(Json(show))
+}
+
+#[utoipa::path(delete, path = "/api/watched/tv/{id}", tag = "watched", security(("token" = [])),
+    params(("id" = i32, Path, description = "TMDB TV show id")),
+    responses((status = 204)))]
+pub async fn remove_tv_show(State(state): State<AppState>, Path(id): Path<i32>) -> ApiResult<StatusCode> {
+    state.library.remove_rating(MediaKind::Tv, id)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::routes::tests::{send, test_router};
+    use axum::http::StatusCode;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_rate_movie_then_update_rating() {
+        let router = test_router();
+        let first = json!({ "rating": 3.5, "title": "The Matrix", "watched_at": "2024-01-01T20:00:00Z" });
+
+        let (status, _) = send(&router, "PUT", "/api/watched/movies/603", Some(first)).await;
+        assert_eq!(status, StatusCode::OK);
+        send(&router, "PUT", "/api/watched/movies/603/notes", Some(json!({ "notes": "Red pill" }))).await;
+
+        let (_, body) = send(&router, "PUT", "/api/watched/movies/603", Some(json!({ "rating": 5.0 }))).await;
+        assert_eq!(body["rating"], 5.0);
+        assert_eq!(body["watched_at"], "2024-01-01T20:00:00Z");
+        assert_eq!(body["notes"], "Red pill");
+    }
+
+    #[tokio::test]
+    async fn test_rating_validation() {
+        let router = test_router();
+
+        let (status, _) = send(&router, "PUT", "/api/watched/tv/1399", Some(json!({ "rating": 4.25, "name": "GoT" }))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, _) = send(&router, "PUT", "/api/watched/tv/1399", Some(json!({ "rating": 4.0, "name": "GoT", "watched_at": "yesterday" }))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, body) = send(&router, "PUT", "/api/watched/tv/1399", Some(json!({ "rating": 4.0 }))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "Title is required");
+    }
+
+    #[tokio::test]
+    async fn test_get_and_remove_rating() {
+        let router = test_router();
+        send(&router, "PUT", "/api/watched/tv/1399", Some(json!({ "rating": 4.5, "name": "Game of Thrones" }))).await;
+
+        let (status, body) = send(&router, "GET", "/api/watched/tv/1399", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["rating"], 4.5);
+
+        send(&router, "DELETE", "/api/watched/tv/1399", None).await;
+        let (status, _) = send(&router, "GET", "/api/watched/tv/1399", None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
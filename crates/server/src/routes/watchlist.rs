@@ -0,0 +1,107 @@
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use logic::database::entities::{MovieToWatch, TvShowToWatch};
+use logic::service::{MediaKind, NewMovie, NewTvShow};
+
+use crate::error::{ApiResult, ErrorBody};
+use crate::state::AppState;
+
+#[utoipa::path(get, path = "/api/watchlist/movies", tag = "watchlist", security(("token" = [])),
+    responses((status = 200, body = [MovieToWatch])))]
+pub async fn list_movies(State(state): State<AppState>) -> ApiResult<Json<Vec<MovieToWatch>>> {
+    Ok(Json(state.library.watchlist_movies()?))
+}
+
+/// Add a movie, keeping any notes already stored for it
+#[utoipa::path(post, path = "/api/watchlist/movies", tag = "watchlist", security(("token" = [])), request_body = NewMovie,
+    responses((status = 201, body = MovieToWatch), (status = 400, body = ErrorBody)))]
+pub async fn add_movie(
+    State(state): State<AppState>,
+    Json(movie): Json<NewMovie>,
+) -> ApiResult<(StatusCode, Json<MovieToWatch>)> {
+    Ok((StatusCode::CREATED, Json(state.library.add_movie_to_watchlist(movie)?)))
+}
+
+#[utoipa::path(delete, path = "/api/watchlist/movies/{id}", tag = "watchlist", security(("token" = [])),
+    params(("id" = i32, Path, description = "TMDB movie id")),
+    responses((status = 204)))]
+pub async fn remove_movie(State(state): State<AppState>, Path(id): Path<i32>) -> ApiResult<StatusCode> {
+    state.library.remove_from_watchlist(MediaKind::Movie, id)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(get, path = "/api/watchlist/tv", tag = "watchlist", security(("token" = [])),
+    responses((status = 200, body = [TvShowToWatch])))]
+pub async fn list_tv_shows(State(state): State<AppState>) -> ApiResult<Json<Vec<TvShowToWatch>>> {
+    Ok(Json(state.library.watchlist_tv_shows()?))
+}
+
+/// Add a TV show, keeping any notes already stored for it
+#[utoipa::path(post, path = "/api/watchlist/tv", tag = "watchlist", security(("token" = [])), request_body = NewTvShow,
+    responses((status = 201, body = TvShowToWatch), (status = 400, body = ErrorBody)))]
+pub async fn add_tv_show(
+    State(state): State<AppState>,
+    Json(show): Json<NewTvShow>,
+) -> ApiResult<(StatusCode, Json<TvShowToWatch>)> {
+    Ok((StatusCode::CREATED, Json(state.library.add_tv_show_to_watchlist(show)?)))
+}
+
+#[utoipa::path(delete, path = "/api/watchlist/tv/{id}", tag = "watchlist", security(("token" = [])),
+    params(("id" = i32, Path, description = "TMDB TV show id")),
+    responses((status = 204)))]
+pub async fn remove_tv_show(State(state): State<AppState>, Path(id): Path<i32>) -> ApiResult<StatusCode> {
+    state.library.remove_from_watchlist(MediaKind::Tv, id)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::routes::tests::{send, test_router};
+    use axum::http::StatusCode;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_add_list_remove_movie() {
+        let router = test_router();
+
+        let (status, body) = send(&router, "POST", "/api/watchlist/movies", Some(json!({ "id": 603, "title": "The Matrix" }))).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body["notes"], "");
+
+        let (_, body) = send(&router, "GET", "/api/watchlist/movies", None).await;
+        assert_eq!(body[0]["title"], "The Matrix");
+
+        let (status, _) = send(&router, "DELETE", "/api/watchlist/movies/603", None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (_, body) = send(&router, "GET", "/api/watchlist/movies", None).await;
+        assert_eq!(body, json!([]));
+    }
+
+    #[tokio::test]
+    async fn test_readding_keeps_notes() {
+        let router = test_router();
+        let show = json!({ "id": 1399, "name": "Game of Thrones" });
+        send(&router, "POST", "/api/watchlist/tv", Some(show.clone())).await;
+        send(&router, "PUT", "/api/watchlist/tv/1399/notes", Some(json!({ "notes": "S01 only" }))).await;
+
+        let (_, body) = send(&router, "POST", "/api/watchlist/tv", Some(show)).await;
+
+        assert_eq!(body["notes"], "S01 only");
+    }
+
+    #[tokio::test]
+    async fn test_rejects_invalid_entries() {
+        let router = test_router();
+
+        let (status, body) = send(&router, "POST", "/api/watchlist/movies", Some(json!({ "id": 0, "title": "x" }))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "Invalid id");
+        assert_eq!(body["fields"], json!([{ "field": "id", "message": "Invalid id" }]));
+
+        let (status, body) = send(&router, "POST", "/api/watchlist/tv", Some(json!({ "id": -1, "name": " " }))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["fields"][0]["field"], "id");
+        assert_eq!(body["fields"][1]["field"], "name");
+    }
+}
@@ -0,0 +1,15 @@
+use logic::service::Library;
+use std::sync::Arc;
+
+/// Shared by every handler; cheap to clone
+#[derive(Clone)]
+pub struct AppState {
+    pub library: Arc<Library>,
+    pub token: Arc<str>,
+}
+
+impl AppState {
+    pub fn new(library: Library, token: &str) -> Self {
+        AppState { library: Arc::new(library), token: Arc::from(token) }
+    }
+}
@@ -1,370 +1,231 @@
-use logic::{api, database};
-use chrono;
-use markdown_renderer;
+use logic::api;
+use logic::database::entities::{MovieToWatch, TvShowToWatch, WatchedMovie, WatchedTvShow};
+use logic::service::{Backlink, EntryId, Library, MediaKind, NewMovie, NewTvShow, NotesLocation, RateMovie, RateTvShow};
+use markdown_renderer::AstNode;
+use tauri::State;
+
+use crate::error::CommandResult;
 
 // API Commands
 
 #[tauri::command]
-pub async fn get_trending_movies() -> Result<Vec<api::Movie>, String> {
-    let tmdb = api::Tmdb::new(logic::TmdbConfig::default());
-    match tmdb.trending_movies().await {
-        Some(movies) => Ok(movies),
-        None => Err("Failed to fetch trending movies".into()),
-    }
+pub async fn get_trending_movies(library: State<'_, Library>) -> CommandResult<Vec<api::Movie>> {
+    Ok(library.trending_movies().await?)
 }
 
 #[tauri::command]
-pub async fn get_trending_tv() -> Result<Vec<api::Tv>, String> {
-    let tmdb = api::Tmdb::new(logic::TmdbConfig::default());
-    match tmdb.trending_tv().await {
-        Some(shows) => Ok(shows),
-        None => Err("Failed to fetch trending TV shows".into()),
-    }
+pub async fn get_trending_tv(library: State<'_, Library>) -> CommandResult<Vec<api::Tv>> {
+    Ok(library.trending_tv().await?)
 }
 
 #[tauri::command]
-pub async fn valid_key() -> Result<bool, String> {
-    let tmdb = api::Tmdb::new(logic::TmdbConfig::default());
-    tmdb.valid_key().await.map_err(|e| e.to_string())
+pub async fn valid_key(library: State<'_, Library>) -> CommandResult<bool> {
+    Ok(library.valid_key().await?)
 }
 
 #[tauri::command]
-pub async fn add_api_key(key: String) -> Result<(), String> {
-    logic::config::write_api_key_to_config(&key).map_err(|e| e.to_string())?;
-    Ok(())
+pub async fn add_api_key(library: State<'_, Library>, key: String) -> CommandResult<()> {
+    Ok(library.set_api_key(&key)?)
 }
 
 #[tauri::command]
-pub async fn search_movies(query: String) -> Result<Vec<api::Movie>, String> {
-    let tmdb = api::Tmdb::new(logic::TmdbConfig::default());
-    match tmdb.find_movies(&query).await {
-        Some(movies) => Ok(movies),
-        None => Err("Failed to fetch movies".into()),
-    }
+pub async fn search_movies(library: State<'_, Library>, query: String) -> CommandResult<Vec<api::Movie>> {
+    Ok(library.search_movies(&query).await?)
 }
 
 #[tauri::command]
-pub async fn search_tv(query: String) -> Result<Vec<api::Tv>, String> {
-    let tmdb = api::Tmdb::new(logic::TmdbConfig::default());
-    match tmdb.find_tv(&query).await {
-        Some(shows) => Ok(shows),
-        None => Err("Failed to fetch TV shows".into()),
-    }
+pub async fn search_tv(library: State<'_, Library>, query: String) -> CommandResult<Vec<api::Tv>> {
+    Ok(library.search_tv(&query).await?)
 }
 
 #[tauri::command]
-pub async fn get_movie_details(id: u32) -> Result<api::MovieDetail, String> {
-    let tmdb = api::Tmdb::new(logic::TmdbConfig::default());
-    match tmdb.get_movie_details(id).await {
-        Ok(movie) => Ok(movie),
-        Err(_) => Err("Failed to fetch movie details".into()),
-    }
+pub async fn get_movie_details(library: State<'_, Library>, id: u32) -> CommandResult<api::MovieDetail> {
+    Ok(library.movie_details(id).await?)
 }
 
 #[tauri::command]
-pub async fn get_tv_show_details(id: u32) -> Result<api::TvDetail, String> {
-    let tmdb = api::Tmdb::new(logic::TmdbConfig::default());
-    match tmdb.get_tv_show_details(id).await {
-        Ok(show) => Ok(show),
-        Err(_) => Err("Failed to fetch TV show details".into()),
-    }
+pub async fn get_tv_show_details(library: State<'_, Library>, id: u32) -> CommandResult<api::TvDetail> {
+    Ok(library.tv_details(id).await?)
 }
 
 // Movie Watchlist Commands
 
 #[tauri::command]
-pub async fn add_movie_to_watchlist(movie: serde_json::Value) -> Result<(), String> {
-    let conn = database::Sqlight::get_connection().map_err(|e| e.to_string())?;
-    let db = conn.lock().expect("Failed to lock the mutex");
-
-    // Extract the required fields and convert types
-    let movie_to_watch = database::entities::MovieToWatch {
-        id: movie["id"].as_i64().unwrap_or(0) as i32,
-        title: movie["title"].as_str().unwrap_or("").to_string(),
-        poster_path: movie["poster_path"].as_str().unwrap_or("").to_string(),
-        notes: String::new(),
-    };
-
-    db.insert_movie_to_watch(&movie_to_watch).map_err(|e| e.to_string())?;
+pub async fn add_movie_to_watchlist(library: State<'_, Library>, movie: NewMovie) -> CommandResult<()> {
+    library.add_movie_to_watchlist(movie)?;
     Ok(())
 }
 
 #[tauri::command]
-pub async fn get_watchlist_movies() -> Result<Vec<database::entities::MovieToWatch>, String> {
-    let conn = database::Sqlight::get_connection().map_err(|e| e.to_string())?;
-    let db = conn.lock().expect("Failed to lock the mutex");
-    db.get_all_movies_to_watch().map_err(|e| e.to_string())
+pub async fn get_watchlist_movies(library: State<'_, Library>) -> CommandResult<Vec<MovieToWatch>> {
+    Ok(library.watchlist_movies()?)
 }
 
 #[tauri::command]
-pub async fn remove_movie_from_watchlist(movie: serde_json::Value) -> Result<(), String> {
-    let conn = database::Sqlight::get_connection().map_err(|e| e.to_string())?;
-    let db = conn.lock().expect("Failed to lock the mutex");
-
-    let movie_id = movie["id"].as_i64().unwrap_or(0) as i32;
-    db.delete_movie_to_watch(movie_id)
-        .map_err(|e| e.to_string())
+pub async fn remove_movie_from_watchlist(library: State<'_, Library>, movie: EntryId) -> CommandResult<()> {
+    Ok(library.remove_from_watchlist(MediaKind::Movie, movie.id)?)
 }
 
 // TV Show Watchlist Commands
 
 #[tauri::command]
-pub async fn add_show_to_watchlist(show: serde_json::Value) -> Result<(), String> {
-
-    let conn = database::Sqlight::get_connection().map_err(|e| e.to_string())?;
-    let db = conn.lock().expect("Failed to lock the mutex");
-
-    // Extract the required fields and convert types
-    let show_id = show["id"].as_i64().unwrap_or(0) as i32;
-    let show_name = show["name"].as_str().unwrap_or("").to_string();
-    let show_poster_path = show["poster_path"].as_str().unwrap_or("").to_string();
-    let show_first_air_date = show["first_air_date"].as_str().unwrap_or("").to_string();
-    let show_vote_average = show["vote_average"].as_f64().unwrap_or(0.0) as f32;
-    let show_overview = show["overview"].as_str().unwrap_or("").to_string();
-
-    if show_id == 0 {
-        return Err("Invalid show ID".to_string());
-    }
-
-    if show_name.is_empty() {
-        return Err("Show name is required".to_string());
-    }
-
-    let tv_show = database::entities::TvShowToWatch {
-        id: show_id,
-        name: show_name,
-        poster_path: show_poster_path,
-        first_air_date: show_first_air_date,
-        vote_average: show_vote_average,
-        overview: show_overview,
-        notes: String::new(),
-    };
-
-    db.insert_tv_show_to_watch(&tv_show).map_err(|e| {
-        println!("Database error inserting TV show: {}", e);
-        e.to_string()
-    })?;
-
-    println!("Successfully added TV show to database");
+pub async fn add_show_to_watchlist(library: State<'_, Library>, show: NewTvShow) -> CommandResult<()> {
+    library.add_tv_show_to_watchlist(show)?;
     Ok(())
 }
 
 #[tauri::command]
-pub async fn get_watchlist_shows() -> Result<Vec<database::entities::TvShowToWatch>, String> {
-    let conn = database::Sqlight::get_connection().map_err(|e| e.to_string())?;
-    let db = conn.lock().expect("Failed to lock the mutex");
-
-    let result = db.get_all_tv_shows_to_watch().map_err(|e| {
-        println!("Database error getting TV shows: {}", e);
-        e.to_string()
-    })?;
-
-
-    Ok(result)
+pub async fn get_watchlist_shows(library: State<'_, Library>) -> CommandResult<Vec<TvShowToWatch>> {
+    Ok(library.watchlist_tv_shows()?)
 }
 
 #[tauri::command]
-pub async fn remove_show_from_watchlist(show: serde_json::Value) -> Result<(), String> {
-    let conn = database::Sqlight::get_connection().map_err(|e| e.to_string())?;
-    let db = conn.lock().expect("Failed to lock the mutex");
-
-    let show_id = show["id"].as_i64().unwrap_or(0) as i32;
-
-    if show_id == 0 {
-        return Err("Invalid show ID".to_string());
-    }
-
-    db.delete_tv_show_to_watch(show_id).map_err(|e| {
-        println!("Database error deleting TV show: {}", e);
-        e.to_string()
-    })?;
-
-    Ok(())
+pub async fn remove_show_from_watchlist(library: State<'_, Library>, show: EntryId) -> CommandResult<()> {
+    Ok(library.remove_from_watchlist(MediaKind::Tv, show.id)?)
 }
 
 // Rating Commands
 
 #[tauri::command]
-pub async fn rate_movie(movie: serde_json::Value, rating: f32, watched_at: Option<String>) -> Result<(), String> {
-    if rating < 0.5 || rating > 5.0 || (rating * 2.0).fract() != 0.0 {
-        return Err("Rating must be between 0.5 and 5.0 in 0.5 increments".to_string());
-    }
-
-    let conn = database::Sqlight::get_connection().map_err(|e| e.to_string())?;
-    let db = conn.lock().expect("Failed to lock the mutex");
-
-    let watched_movie = database::entities::WatchedMovie {
-        id: movie["id"].as_i64().unwrap_or(0) as i32,
-        title: movie["title"].as_str().unwrap_or("").to_string(),
-        poster_path: movie["poster_path"].as_str().unwrap_or("").to_string(),
+pub async fn rate_movie(
+    library: State<'_, Library>,
+    movie: NewMovie,
+    rating: f32,
+    watched_at: Option<String>,
+) -> CommandResult<()> {
+    let input = RateMovie {
+        id: movie.id,
         rating,
-        watched_at: watched_at.unwrap_or_else(|| chrono::Utc::now().to_rfc3339()),
-        notes: String::new(),
+        watched_at,
+        title: movie.title,
+        poster_path: movie.poster_path,
     };
-
-    db.rate_movie(&watched_movie).map_err(|e| e.to_string())?;
+    library.rate_movie(input)?;
     Ok(())
 }
 
 #[tauri::command]
-pub async fn rate_tv_show(show: serde_json::Value, rating: f32, watched_at: Option<String>) -> Result<(), String> {
-    if rating < 0.5 || rating > 5.0 || (rating * 2.0).fract() != 0.0 {
-        return Err("Rating must be between 0.5 and 5.0 in 0.5 increments".to_string());
-    }
-
-    let conn = database::Sqlight::get_connection().map_err(|e| e.to_string())?;
-    let db = conn.lock().expect("Failed to lock the mutex");
-
-    let watched_tv_show = database::entities::WatchedTvShow {
-        id: show["id"].as_i64().unwrap_or(0) as i32,
-        name: show["name"].as_str().unwrap_or("").to_string(),
-        poster_path: show["poster_path"].as_str().unwrap_or("").to_string(),
-        first_air_date: show["first_air_date"].as_str().unwrap_or("").to_string(),
-        vote_average: show["vote_average"].as_f64().unwrap_or(0.0) as f32,
-        overview: show["overview"].as_str().unwrap_or("").to_string(),
+pub async fn rate_tv_show(
+    library: State<'_, Library>,
+    show: NewTvShow,
+    rating: f32,
+    watched_at: Option<String>,
+) -> CommandResult<()> {
+    let input = RateTvShow {
+        id: show.id,
         rating,
-        watched_at: watched_at.unwrap_or_else(|| chrono::Utc::now().to_rfc3339()),
-        notes: String::new(),
+        watched_at,
+        name: show.name,
+        poster_path: show.poster_path,
+        first_air_date: show.first_air_date,
+        vote_average: show.vote_average,
+        overview: show.overview,
     };
-
-    db.rate_tv_show(&watched_tv_show).map_err(|e| e.to_string())?;
+    library.rate_tv_show(input)?;
     Ok(())
 }
 
 #[tauri::command]
-pub async fn get_movie_rating(movie_id: i32) -> Result<Option<f32>, String> {
-    let conn = database::Sqlight::get_connection().map_err(|e| e.to_string())?;
-    let db = conn.lock().expect("Failed to lock the mutex");
-
-    db.get_movie_rating(movie_id).map_err(|e| e.to_string())
+pub async fn get_movie_rating(library: State<'_, Library>, movie_id: i32) -> CommandResult<Option<f32>> {
+    Ok(library.rating(MediaKind::Movie, movie_id)?)
 }
 
 #[tauri::command]
-pub async fn get_tv_show_rating(show_id: i32) -> Result<Option<f32>, String> {
-    let conn = database::Sqlight::get_connection().map_err(|e| e.to_string())?;
-    let db = conn.lock().expect("Failed to lock the mutex");
-
-    db.get_tv_show_rating(show_id).map_err(|e| e.to_string())
+pub async fn get_tv_show_rating(library: State<'_, Library>, show_id: i32) -> CommandResult<Option<f32>> {
+    Ok(library.rating(MediaKind::Tv, show_id)?)
 }
 
 #[tauri::command]
-pub async fn remove_movie_rating(movie_id: i32) -> Result<(), String> {
-    let conn = database::Sqlight::get_connection().map_err(|e| e.to_string())?;
-    let db = conn.lock().expect("Failed to lock the mutex");
-
-    db.remove_movie_rating(movie_id).map_err(|e| e.to_string())
+pub async fn remove_movie_rating(library: State<'_, Library>, movie_id: i32) -> CommandResult<()> {
+    Ok(library.remove_rating(MediaKind::Movie, movie_id)?)
 }
 
 #[tauri::command]
-pub async fn remove_tv_show_rating(show_id: i32) -> Result<(), String> {
-    let conn = database::Sqlight::get_connection().map_err(|e| e.to_string())?;
-    let db = conn.lock().expect("Failed to lock the mutex");
-
-    db.remove_tv_show_rating(show_id).map_err(|e| e.to_string())
+pub async fn remove_tv_show_rating(library: State<'_, Library>, show_id: i32) -> CommandResult<()> {
+    Ok(library.remove_rating(MediaKind::Tv, show_id)?)
 }
 
 #[tauri::command]
-pub async fn get_all_rated_movies() -> Result<Vec<database::entities::WatchedMovie>, String> {
-    let conn = database::Sqlight::get_connection().map_err(|e| e.to_string())?;
-    let db = conn.lock().expect("Failed to lock the mutex");
-
-    db.get_all_rated_movies().map_err(|e| e.to_string())
+pub async fn get_all_rated_movies(library: State<'_, Library>) -> CommandResult<Vec<WatchedMovie>> {
+    Ok(library.watched_movies()?)
 }
 
 #[tauri::command]
-pub async fn get_all_rated_tv_shows() -> Result<Vec<database::entities::WatchedTvShow>, String> {
-    let conn = database::Sqlight::get_connection().map_err(|e| e.to_string())?;
-    let db = conn.lock().expect("Failed to lock the mutex");
-
-    db.get_all_rated_tv_shows().map_err(|e| e.to_string())
+pub async fn get_all_rated_tv_shows(library: State<'_, Library>) -> CommandResult<Vec<WatchedTvShow>> {
+    Ok(library.watched_tv_shows()?)
 }
 
 // Notes Commands
 
+// Saving notes from the details page puts the title on the watchlist if it isn't there yet
 #[tauri::command]
-pub async fn update_movie_notes(movie: serde_json::Value, notes: String) -> Result<(), String> {
-    let conn = database::Sqlight::get_connection().map_err(|e| e.to_string())?;
-    let db = conn.lock().expect("Failed to lock the mutex");
-
-    // Create or update movie with notes using UPSERT
-    let movie_to_watch = database::entities::MovieToWatch {
-        id: movie["id"].as_i64().unwrap_or(0) as i32,
-        title: movie["title"].as_str().unwrap_or("").to_string(),
-        poster_path: movie["poster_path"].as_str().unwrap_or("").to_string(),
-        notes: notes,
-    };
-
-    db.insert_movie_to_watch(&movie_to_watch).map_err(|e| e.to_string())
+pub async fn update_movie_notes(library: State<'_, Library>, movie: NewMovie, notes: String) -> CommandResult<()> {
+    let movie = library.add_movie_to_watchlist(movie)?;
+    Ok(library.update_notes(NotesLocation::Watchlist, MediaKind::Movie, movie.id, &notes)?)
 }
 
 #[tauri::command]
-pub async fn update_tv_show_notes(tv_show: serde_json::Value, notes: String) -> Result<(), String> {
-    let conn = database::Sqlight::get_connection().map_err(|e| e.to_string())?;
-    let db = conn.lock().expect("Failed to lock the mutex");
-
-    // Create or update TV show with notes using UPSERT
-    let tv_show_to_watch = database::entities::TvShowToWatch {
-        id: tv_show["id"].as_i64().unwrap_or(0) as i32,
-        name: tv_show["name"].as_str().unwrap_or("").to_string(),
-        poster_path: tv_show["poster_path"].as_str().unwrap_or("").to_string(),
-        first_air_date: tv_show["first_air_date"].as_str().unwrap_or("").to_string(),
-        vote_average: tv_show["vote_average"].as_f64().unwrap_or(0.0) as f32,
-        overview: tv_show["overview"].as_str().unwrap_or("").to_string(),
-        notes: notes,
-    };
-
-    db.insert_tv_show_to_watch(&tv_show_to_watch).map_err(|e| e.to_string())
+pub async fn update_tv_show_notes(library: State<'_, Library>, tv_show: NewTvShow, notes: String) -> CommandResult<()> {
+    let show = library.add_tv_show_to_watchlist(tv_show)?;
+    Ok(library.update_notes(NotesLocation::Watchlist, MediaKind::Tv, show.id, &notes)?)
 }
 
 #[tauri::command]
-pub async fn update_watched_movie_notes(movie_id: i32, notes: String) -> Result<(), String> {
-    let conn = database::Sqlight::get_connection().map_err(|e| e.to_string())?;
-    let db = conn.lock().expect("Failed to lock the mutex");
-
-    db.update_watched_movie_notes(movie_id, &notes).map_err(|e| e.to_string())
+pub async fn update_watched_movie_notes(library: State<'_, Library>, movie_id: i32, notes: String) -> CommandResult<()> {
+    Ok(library.update_notes(NotesLocation::Watched, MediaKind::Movie, movie_id, &notes)?)
 }
 
 #[tauri::command]
-pub async fn update_watched_tv_show_notes(tv_show_id: i32, notes: String) -> Result<(), String> {
-    let conn = database::Sqlight::get_connection().map_err(|e| e.to_string())?;
-    let db = conn.lock().expect("Failed to lock the mutex");
-
-    db.update_watched_tv_show_notes(tv_show_id, &notes).map_err(|e| e.to_string())
+pub async fn update_watched_tv_show_notes(library: State<'_, Library>, tv_show_id: i32, notes: String) -> CommandResult<()> {
+    Ok(library.update_notes(NotesLocation::Watched, MediaKind::Tv, tv_show_id, &notes)?)
 }
 
 #[tauri::command]
-pub async fn get_movie_notes(movie_id: i32) -> Result<Option<String>, String> {
-    let conn = database::Sqlight::get_connection().map_err(|e| e.to_string())?;
-    let db = conn.lock().expect("Failed to lock the mutex");
-
-    db.get_movie_notes(movie_id).map_err(|e| e.to_string())
+pub async fn get_movie_notes(library: State<'_, Library>, movie_id: i32) -> CommandResult<Option<String>> {
+    Ok(library.notes(NotesLocation::Watchlist, MediaKind::Movie, movie_id)?)
 }
 
 #[tauri::command]
-pub async fn get_tv_show_notes(tv_show_id: i32) -> Result<Option<String>, String> {
-    let conn = database::Sqlight::get_connection().map_err(|e| e.to_string())?;
-    let db = conn.lock().expect("Failed to lock the mutex");
+pub async fn get_tv_show_notes(library: State<'_, Library>, tv_show_id: i32) -> CommandResult<Option<String>> {
+    Ok(library.notes(NotesLocation::Watchlist, MediaKind::Tv, tv_show_id)?)
+}
 
-    db.get_tv_show_notes(tv_show_id).map_err(|e| e.to_string())
+#[tauri::command]
+pub async fn get_watched_movie_notes(library: State<'_, Library>, movie_id: i32) -> CommandResult<Option<String>> {
+    Ok(library.notes(NotesLocation::Watched, MediaKind::Movie, movie_id)?)
 }
 
 #[tauri::command]
-pub async fn get_watched_movie_notes(movie_id: i32) -> Result<Option<String>, String> {
-    let conn = database::Sqlight::get_connection().map_err(|e| e.to_string())?;
-    let db = conn.lock().expect("Failed to lock the mutex");
+pub async fn get_watched_tv_show_notes(library: State<'_, Library>, tv_show_id: i32) -> CommandResult<Option<String>> {
+    Ok(library.notes(NotesLocation::Watched, MediaKind::Tv, tv_show_id)?)
+}
 
-    db.get_watched_movie_notes(movie_id).map_err(|e| e.to_string())
+// Clicking a checkbox in rendered notes flips the task at its `data-task-offset` in the stored markdown
+// and returns the new notes
+#[tauri::command]
+pub async fn toggle_notes_task(
+    library: State<'_, Library>,
+    location: NotesLocation,
+    kind: MediaKind,
+    id: i32,
+    offset: usize,
+) -> CommandResult<String> {
+    Ok(library.toggle_notes_task(location, kind, id, offset)?)
 }
 
+// Entries whose notes link to this title with `[[movie:603]]` style references
 #[tauri::command]
-pub async fn get_watched_tv_show_notes(tv_show_id: i32) -> Result<Option<String>, String> {
-    let conn = database::Sqlight::get_connection().map_err(|e| e.to_string())?;
-    let db = conn.lock().expect("Failed to lock the mutex");
+pub async fn get_backlinks(library: State<'_, Library>, kind: MediaKind, id: i32) -> CommandResult<Vec<Backlink>> {
+    Ok(library.backlinks(kind, id)?)
+}
 
-    db.get_watched_tv_show_notes(tv_show_id).map_err(|e| e.to_string())
+#[tauri::command]
+pub async fn render_markdown_to_html(library: State<'_, Library>, markdown: String) -> CommandResult<String> {
+    Ok(library.render_notes(&markdown).await?)
 }
 
+// The notes as a syntax tree, in the tagged JSON shape of the `serde` feature of markdown-renderer
 #[tauri::command]
-pub async fn render_markdown_to_html(markdown: String) -> Result<String, String> {
-    markdown_renderer::markdown_to_html(&markdown).map_err(|e| e.to_string())
+pub async fn parse_markdown_to_ast(library: State<'_, Library>, markdown: String) -> CommandResult<AstNode> {
+    Ok(library.parse_notes(&markdown)?)
 }
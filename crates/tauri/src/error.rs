@@ -0,0 +1,22 @@
+use logic::service::{FieldError, LibraryError};
+use serde::Serialize;
+
+/// Error payload of every library command: `{ message, fields }`, where `fields`
+/// lists the rejected input fields when the payload failed validation
+#[derive(Debug, Serialize)]
+pub struct CommandError {
+    pub message: String,
+    pub fields: Vec<FieldError>,
+}
+
+impl From<LibraryError> for CommandError {
+    fn from(error: LibraryError) -> Self {
+        let fields = match &error {
+            LibraryError::Invalid(errors) => errors.fields().to_vec(),
+            _ => Vec::new(),
+        };
+        CommandError { message: error.to_string(), fields }
+    }
+}
+
+pub type CommandResult<T> = Result<T, CommandError>;
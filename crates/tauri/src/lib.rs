@@ -1,11 +1,14 @@
 mod commands;
+mod error;
 
 use commands::*;
+use logic::service::Library;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
+        .manage(Library::open().expect("Failed to open the library database"))
         .invoke_handler(tauri::generate_handler![
             // API Commands
             get_trending_movies,
@@ -42,7 +45,10 @@ pub fn run() {
             get_tv_show_notes,
             get_watched_movie_notes,
             get_watched_tv_show_notes,
+            toggle_notes_task,
+            get_backlinks,
             render_markdown_to_html,
+            parse_markdown_to_ast,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,776 @@
+use anyhow::{Context, Result};
+use logic::api::{Movie, MovieDetail, Tv, TvDetail};
+use logic::database::entities::{MovieToWatch, TvShowToWatch, WatchedMovie, WatchedTvShow};
+use logic::service::{self, Library, NewMovie, NewTvShow, NotesLocation, RateMovie, RateTvShow};
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::catalog::Catalog;
+use crate::editor::Editor;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tab {
+    Popular,
+    Watchlist,
+    Watched,
+    Details,
+}
+
+impl Tab {
+    pub const ALL: [Tab; 4] = [Tab::Popular, Tab::Watchlist, Tab::Watched, Tab::Details];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Tab::Popular => "Popular",
+            Tab::Watchlist => "Watchlist",
+            Tab::Watched => "Watched",
+            Tab::Details => "Details",
+        }
+    }
+
+    pub fn index(self) -> usize {
+        Tab::ALL.iter().position(|tab| *tab == self).unwrap_or(0)
+    }
+
+    fn next(self) -> Tab {
+        Tab::ALL[(self.index() + 1) % Tab::ALL.len()]
+    }
+
+    fn previous(self) -> Tab {
+        Tab::ALL[(self.index() + Tab::ALL.len() - 1) % Tab::ALL.len()]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaKind {
+    Movie,
+    Tv,
+}
+
+impl MediaKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            MediaKind::Movie => "Movies",
+            MediaKind::Tv => "TV shows",
+        }
+    }
+}
+
+impl From<MediaKind> for service::MediaKind {
+    fn from(kind: MediaKind) -> Self {
+        match kind {
+            MediaKind::Movie => service::MediaKind::Movie,
+            MediaKind::Tv => service::MediaKind::Tv,
+        }
+    }
+}
+
+/// One line in any of the list tabs
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub kind: MediaKind,
+    pub id: u32,
+    pub title: String,
+    pub rating: Option<f32>,
+    pub notes: String,
+}
+
+impl Entry {
+    fn new(kind: MediaKind, id: u32, title: &str) -> Self {
+        Entry { kind, id, title: title.to_string(), rating: None, notes: String::new() }
+    }
+}
+
+#[derive(Debug)]
+pub enum Details {
+    Movie(MovieDetail),
+    Tv(TvDetail),
+}
+
+#[derive(Debug)]
+pub enum Mode {
+    Normal,
+    Search(String),
+    Rating { entry: Entry, rating: f32 },
+    Notes { entry: Entry, location: NotesLocation, editor: Editor },
+}
+
+const NO_TMDB: &str = "No TMDB API key found, add one to ~/.config/tv/config.toml";
+
+pub struct App<C: Catalog> {
+    catalog: Option<C>,
+    library: Library,
+    pub tab: Tab,
+    pub kind: MediaKind,
+    pub mode: Mode,
+    pub popular: Vec<Entry>,
+    pub watchlist: Vec<Entry>,
+    pub watched: Vec<Entry>,
+    pub query: Option<String>,
+    pub details: Option<(Entry, Details)>,
+    pub status: Option<String>,
+    pub should_quit: bool,
+    selected: [usize; 3],
+}
+
+impl<C: Catalog> App<C> {
+    /// Without a catalog the app only works on the library, with search disabled
+    pub fn new(catalog: Option<C>, library: Library) -> Self {
+        let status = catalog.is_none().then(|| format!("Offline, search is disabled: {}", NO_TMDB));
+        App {
+            catalog,
+            library,
+            tab: Tab::Popular,
+            kind: MediaKind::Movie,
+            mode: Mode::Normal,
+            popular: Vec::new(),
+            watchlist: Vec::new(),
+            watched: Vec::new(),
+            query: None,
+            details: None,
+            status,
+            should_quit: false,
+            selected: [0; 3],
+        }
+    }
+
+    /// Reload both the TMDB list and the library lists
+    pub fn refresh(&mut self) {
+        self.report(|app| {
+            app.load_popular()?;
+            app.load_library()
+        });
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        match self.tab {
+            Tab::Popular => &self.popular,
+            Tab::Watchlist => &self.watchlist,
+            Tab::Watched => &self.watched,
+            Tab::Details => &[],
+        }
+    }
+
+    pub fn selected(&self) -> Option<usize> {
+        let index = *self.selected.get(self.tab.index())?;
+        (index < self.entries().len()).then_some(index)
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) {
+        match std::mem::replace(&mut self.mode, Mode::Normal) {
+            Mode::Normal => self.handle_normal_key(key),
+            Mode::Search(query) => self.handle_search_key(key, query),
+            Mode::Rating { entry, rating } => self.handle_rating_key(key, entry, rating),
+            Mode::Notes { entry, location, editor } => self.handle_notes_key(key, entry, location, editor),
+        }
+    }
+
+    fn handle_normal_key(&mut self, key: KeyEvent) {
+        self.status = None;
+
+        match key.code {
+            KeyCode::Char('q') => self.should_quit = true,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => self.should_quit = true,
+            KeyCode::Esc if self.query.is_some() => {
+                self.query = None;
+                self.report(|app| app.load_popular());
+            }
+            KeyCode::Tab | KeyCode::Right => self.tab = self.tab.next(),
+            KeyCode::BackTab | KeyCode::Left => self.tab = self.tab.previous(),
+            KeyCode::Char(ch @ '1'..='4') => self.tab = Tab::ALL[ch as usize - '1' as usize],
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+            KeyCode::Char('m') => {
+                self.kind = match self.kind {
+                    MediaKind::Movie => MediaKind::Tv,
+                    MediaKind::Tv => MediaKind::Movie,
+                };
+                self.selected = [0; 3];
+                self.refresh();
+            }
+            KeyCode::Char('/') if self.catalog.is_none() => {
+                self.status = Some(format!("Search is disabled: {}", NO_TMDB));
+            }
+            KeyCode::Char('/') => {
+                self.tab = Tab::Popular;
+                self.mode = Mode::Search(String::new());
+            }
+            KeyCode::Enter => {
+                if let Some(entry) = self.current_entry() {
+                    self.report(|app| app.open_details(entry));
+                }
+            }
+            KeyCode::Char('a') => {
+                if let Some(entry) = self.current_entry() {
+                    self.report(|app| app.add_to_watchlist(&entry));
+                }
+            }
+            KeyCode::Char('d') => self.report(|app| app.remove_selected()),
+            KeyCode::Char('r') => {
+                if let Some(entry) = self.current_entry() {
+                    let rating = self.library_entry(&entry).and_then(|entry| entry.rating).unwrap_or(3.0);
+                    self.mode = Mode::Rating { entry, rating };
+                }
+            }
+            KeyCode::Char('n') => {
+                if let Some(entry) = self.current_entry() {
+                    self.open_notes(entry);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_search_key(&mut self, key: KeyEvent, mut query: String) {
+        match key.code {
+            KeyCode::Esc => {}
+            KeyCode::Enter if !query.trim().is_empty() => {
+                self.query = Some(query.trim().to_string());
+                self.selected[Tab::Popular.index()] = 0;
+                self.report(|app| app.load_popular());
+            }
+            KeyCode::Backspace => {
+                query.pop();
+                self.mode = Mode::Search(query);
+            }
+            KeyCode::Char(ch) => {
+                query.push(ch);
+                self.mode = Mode::Search(query);
+            }
+            _ => self.mode = Mode::Search(query),
+        }
+    }
+
+    fn handle_rating_key(&mut self, key: KeyEvent, entry: Entry, rating: f32) {
+        let rating = match key.code {
+            KeyCode::Esc => return,
+            KeyCode::Enter => {
+                self.report(|app| app.save_rating(&entry, rating));
+                return;
+            }
+            KeyCode::Left | KeyCode::Char('h') | KeyCode::Char('-') => rating - 0.5,
+            KeyCode::Right | KeyCode::Char('l') | KeyCode::Char('+') => rating + 0.5,
+            KeyCode::Char(ch @ '1'..='5') => (ch as u8 - b'0') as f32,
+            _ => rating,
+        };
+        self.mode = Mode::Rating { entry, rating: rating.clamp(0.5, 5.0) };
+    }
+
+    fn handle_notes_key(&mut self, key: KeyEvent, entry: Entry, location: NotesLocation, mut editor: Editor) {
+        match key.code {
+            KeyCode::Esc => {}
+            KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.report(|app| app.save_notes(&entry, location, &editor.text()));
+            }
+            _ => {
+                editor.handle_key(key);
+                self.mode = Mode::Notes { entry, location, editor };
+            }
+        }
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        let len = self.entries().len();
+        if let Some(selected) = self.selected.get_mut(self.tab.index()) {
+            if len > 0 {
+                *selected = selected.saturating_add_signed(delta).min(len - 1);
+            }
+        }
+    }
+
+    /// The entry the next action applies to: the highlighted row, or the open title on the details tab
+    fn current_entry(&self) -> Option<Entry> {
+        match self.tab {
+            Tab::Details => self.details.as_ref().map(|(entry, _)| entry.clone()),
+            _ => self.selected().map(|index| self.entries()[index].clone()),
+        }
+    }
+
+    /// Find the watched entry of a title, falling back to its watchlist entry
+    fn library_entry(&self, entry: &Entry) -> Option<&Entry> {
+        let same = |other: &&Entry| other.kind == entry.kind && other.id == entry.id;
+        self.watched.iter().find(same).or_else(|| self.watchlist.iter().find(same))
+    }
+
+    fn open_notes(&mut self, entry: Entry) {
+        let same = |other: &&Entry| other.kind == entry.kind && other.id == entry.id;
+        let location = if let Some(watched) = self.watched.iter().find(same) {
+            Some((NotesLocation::Watched, watched.notes.clone()))
+        } else {
+            self.watchlist.iter().find(same).map(|listed| (NotesLocation::Watchlist, listed.notes.clone()))
+        };
+
+        match location {
+            Some((location, notes)) => {
+                self.mode = Mode::Notes { entry, location, editor: Editor::new(&notes) };
+            }
+            None => self.status = Some(format!("Add {} to the watchlist before writing notes", entry.title)),
+        }
+    }
+
+    fn report(&mut self, action: impl FnOnce(&mut Self) -> Result<()>) {
+        if let Err(error) = action(self) {
+            self.status = Some(format!("Error: {:#}", error));
+        }
+    }
+
+    /// TMDB, or an error explaining that the app runs without it
+    fn catalog(&self) -> Result<&C> {
+        self.catalog.as_ref().context(NO_TMDB)
+    }
+
+    fn load_popular(&mut self) -> Result<()> {
+        let Some(catalog) = &self.catalog else {
+            self.popular.clear();
+            return Ok(());
+        };
+        self.popular = match (self.kind, self.query.as_deref()) {
+            (MediaKind::Movie, None) => movie_entries(catalog.trending_movies()?),
+            (MediaKind::Movie, Some(query)) => movie_entries(catalog.search_movies(query)?),
+            (MediaKind::Tv, None) => tv_entries(catalog.trending_tv()?),
+            (MediaKind::Tv, Some(query)) => tv_entries(catalog.search_tv(query)?),
+        };
+        Ok(())
+    }
+
+    fn load_library(&mut self) -> Result<()> {
+        match self.kind {
+            MediaKind::Movie => {
+                self.watchlist = self.library.watchlist_movies()?.into_iter().map(Entry::from).collect();
+                self.watched = self.library.watched_movies()?.into_iter().map(Entry::from).collect();
+            }
+            MediaKind::Tv => {
+                self.watchlist = self.library.watchlist_tv_shows()?.into_iter().map(Entry::from).collect();
+                self.watched = self.library.watched_tv_shows()?.into_iter().map(Entry::from).collect();
+            }
+        }
+        Ok(())
+    }
+
+    fn open_details(&mut self, entry: Entry) -> Result<()> {
+        let details = match entry.kind {
+            MediaKind::Movie => Details::Movie(self.catalog()?.movie_details(entry.id)?),
+            MediaKind::Tv => Details::Tv(self.catalog()?.tv_details(entry.id)?),
+        };
+        self.details = Some((entry, details));
+        self.tab = Tab::Details;
+        Ok(())
+    }
+
+    fn add_to_watchlist(&mut self, entry: &Entry) -> Result<()> {
+        let id = entry.id as i32;
+        match entry.kind {
+            MediaKind::Movie => {
+                let movie = self.catalog()?.movie_details(entry.id)?;
+                self.library.add_movie_to_watchlist(NewMovie {
+                    id,
+                    title: movie.title,
+                    poster_path: movie.poster_path,
+                })?;
+            }
+            MediaKind::Tv => {
+                let show = self.catalog()?.tv_details(entry.id)?;
+                self.library.add_tv_show_to_watchlist(NewTvShow {
+                    id,
+                    name: show.name,
+                    poster_path: show.poster_path,
+                    first_air_date: show.first_air_date,
+                    vote_average: show.vote_average,
+                    overview: show.overview,
+                })?;
+            }
+        }
+        self.load_library()?;
+        self.status = Some(format!("Added {} to the watchlist", entry.title));
+        Ok(())
+    }
+
+    fn remove_selected(&mut self) -> Result<()> {
+        let Some(entry) = self.current_entry() else {
+            return Ok(());
+        };
+        let id = entry.id as i32;
+
+        match self.tab {
+            Tab::Watchlist => self.library.remove_from_watchlist(entry.kind.into(), id)?,
+            Tab::Watched => self.library.remove_rating(entry.kind.into(), id)?,
+            _ => return Ok(()),
+        }
+
+        self.load_library()?;
+        self.move_selection(0);
+        self.status = Some(format!("Removed {}", entry.title));
+        Ok(())
+    }
+
+    fn save_rating(&mut self, entry: &Entry, rating: f32) -> Result<()> {
+        let id = entry.id as i32;
+        // Titles are only needed the first time, re-rating keeps the stored entry
+        let rated = self.library.rating(entry.kind.into(), id)?.is_some();
+        match entry.kind {
+            MediaKind::Movie => {
+                let mut input = RateMovie { id, rating, watched_at: None, title: String::new(), poster_path: String::new() };
+                if !rated {
+                    let details = self.catalog()?.movie_details(entry.id)?;
+                    input.title = details.title;
+                    input.poster_path = details.poster_path;
+                }
+                self.library.rate_movie(input)?;
+            }
+            MediaKind::Tv => {
+                let mut input = RateTvShow {
+                    id,
+                    rating,
+                    watched_at: None,
+                    name: String::new(),
+                    poster_path: String::new(),
+                    first_air_date: String::new(),
+                    vote_average: 0.0,
+                    overview: String::new(),
+                };
+                if !rated {
+                    let details = self.catalog()?.tv_details(entry.id)?;
+                    input.name = details.name;
+                    input.poster_path = details.poster_path;
+                    input.first_air_date = details.first_air_date;
+                    input.vote_average = details.vote_average;
+                    input.overview = details.overview;
+                }
+                self.library.rate_tv_show(input)?;
+            }
+        }
+        self.load_library()?;
+        self.status = Some(format!("Rated {} {}", entry.title, stars(rating)));
+        Ok(())
+    }
+
+    fn save_notes(&mut self, entry: &Entry, location: NotesLocation, notes: &str) -> Result<()> {
+        self.library.update_notes(location, entry.kind.into(), entry.id as i32, notes)?;
+        self.load_library()?;
+        self.status = Some(format!("Saved notes for {}", entry.title));
+        Ok(())
+    }
+}
+
+/// Render a rating as five stars with half-star precision, e.g. 3.5 -> "★★★½☆"
+pub fn stars(rating: f32) -> String {
+    let halves = (rating * 2.0).round().clamp(0.0, 10.0) as usize;
+    let mut stars = "★".repeat(halves / 2);
+    if halves % 2 == 1 {
+        stars.push('½');
+    }
+    let empty = 5 - halves.div_ceil(2);
+    stars.push_str(&"☆".repeat(empty));
+    stars
+}
+
+fn movie_entries(movies: Vec<Movie>) -> Vec<Entry> {
+    movies.iter().map(|movie| Entry::new(MediaKind::Movie, movie.id, &movie.title)).collect()
+}
+
+fn tv_entries(shows: Vec<Tv>) -> Vec<Entry> {
+    shows.iter().map(|show| Entry::new(MediaKind::Tv, show.id, &show.name)).collect()
+}
+
+impl From<MovieToWatch> for Entry {
+    fn from(movie: MovieToWatch) -> Self {
+        Entry { notes: movie.notes, ..Entry::new(MediaKind::Movie, movie.id as u32, &movie.title) }
+    }
+}
+
+impl From<TvShowToWatch> for Entry {
+    fn from(show: TvShowToWatch) -> Self {
+        Entry { notes: show.notes, ..Entry::new(MediaKind::Tv, show.id as u32, &show.name) }
+    }
+}
+
+impl From<WatchedMovie> for Entry {
+    fn from(movie: WatchedMovie) -> Self {
+        Entry {
+            rating: Some(movie.rating),
+            notes: movie.notes,
+            ..Entry::new(MediaKind::Movie, movie.id as u32, &movie.title)
+        }
+    }
+}
+
+impl From<WatchedTvShow> for Entry {
+    fn from(show: WatchedTvShow) -> Self {
+        Entry {
+            rating: Some(show.rating),
+            notes: show.notes,
+            ..Entry::new(MediaKind::Tv, show.id as u32, &show.name)
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    /// In-memory stand-in for TMDB
+    pub struct MockCatalog;
+
+    impl Catalog for MockCatalog {
+        fn trending_movies(&self) -> Result<Vec<Movie>> {
+            Ok(vec![
+                Movie { id: 603, title: "The Matrix".to_string(), poster_path: "/matrix.jpg".to_string() },
+                Movie { id: 27205, title: "Inception".to_string(), poster_path: "/inception.jpg".to_string() },
+            ])
+        }
+
+        fn trending_tv(&self) -> Result<Vec<Tv>> {
+            Ok(vec![Tv { id: 1399, name: "Game of Thrones".to_string(), poster_path: "/got.jpg".to_string() }])
+        }
+
+        fn search_movies(&self, query: &str) -> Result<Vec<Movie>> {
+            Ok(self
+                .trending_movies()?
+                .into_iter()
+                .filter(|movie| movie.title.to_lowercase().contains(&query.to_lowercase()))
+                .collect())
+        }
+
+        fn search_tv(&self, _query: &str) -> Result<Vec<Tv>> {
+            self.trending_tv()
+        }
+
+        fn movie_details(&self, id: u32) -> Result<MovieDetail> {
+            let movie = self.trending_movies()?.into_iter().find(|movie| movie.id == id);
+            let movie = movie.ok_or_else(|| anyhow!("Movie {} not found", id))?;
+            Ok(MovieDetail {
+                id,
+                title: movie.title,
+                poster_path: movie.poster_path,
+                runtime: 120,
+                overview: "An overview".to_string(),
+                genres: vec!["Science Fiction".to_string()],
+                vote_average: 8.0,
+                release_date: "1999-03-31".to_string(),
+            })
+        }
+
+        fn tv_details(&self, id: u32) -> Result<TvDetail> {
+            Ok(TvDetail {
+                id,
+                name: "Game of Thrones".to_string(),
+                poster_path: "/got.jpg".to_string(),
+                episode_run_time: vec![60],
+                overview: "Winter is coming".to_string(),
+                genres: vec!["Drama".to_string()],
+                vote_average: 8.4,
+                first_air_date: "2011-04-17".to_string(),
+                number_of_seasons: 8,
+                number_of_episodes: 73,
+                status: "Ended".to_string(),
+            })
+        }
+    }
+
+    pub fn test_app() -> App<MockCatalog> {
+        let mut app = App::new(Some(MockCatalog), Library::open_in_memory().unwrap());
+        app.refresh();
+        app
+    }
+
+    fn press(app: &mut App<MockCatalog>, code: KeyCode) {
+        app.handle_key(KeyEvent::new(code, KeyModifiers::NONE));
+    }
+
+    fn type_str(app: &mut App<MockCatalog>, text: &str) {
+        for ch in text.chars() {
+            press(app, KeyCode::Char(ch));
+        }
+    }
+
+    #[test]
+    fn test_refresh_loads_trending() {
+        let app = test_app();
+
+        assert_eq!(app.popular.len(), 2);
+        assert_eq!(app.popular[0].title, "The Matrix");
+        assert_eq!(app.selected(), Some(0));
+    }
+
+    #[test]
+    fn test_switch_tabs() {
+        let mut app = test_app();
+
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(app.tab, Tab::Watchlist);
+        press(&mut app, KeyCode::Char('4'));
+        assert_eq!(app.tab, Tab::Details);
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(app.tab, Tab::Popular);
+        press(&mut app, KeyCode::BackTab);
+        assert_eq!(app.tab, Tab::Details);
+    }
+
+    #[test]
+    fn test_search_and_clear() {
+        let mut app = test_app();
+
+        press(&mut app, KeyCode::Char('/'));
+        type_str(&mut app, "incep");
+        press(&mut app, KeyCode::Enter);
+
+        assert_eq!(app.query.as_deref(), Some("incep"));
+        assert_eq!(app.popular.len(), 1);
+        assert_eq!(app.popular[0].id, 27205);
+
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.query, None);
+        assert_eq!(app.popular.len(), 2);
+    }
+
+    #[test]
+    fn test_add_selected_to_watchlist() {
+        let mut app = test_app();
+
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Char('a'));
+
+        assert_eq!(app.watchlist.len(), 1);
+        assert_eq!(app.watchlist[0].title, "Inception");
+        assert_eq!(app.library.watchlist_movies().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_rate_with_half_stars() {
+        let mut app = test_app();
+
+        press(&mut app, KeyCode::Char('r'));
+        press(&mut app, KeyCode::Right);
+        press(&mut app, KeyCode::Right);
+        press(&mut app, KeyCode::Right);
+        assert!(matches!(app.mode, Mode::Rating { rating, .. } if rating == 4.5));
+        press(&mut app, KeyCode::Enter);
+
+        let movie = app.library.watched_movie(603).unwrap().unwrap();
+        assert_eq!(movie.rating, 4.5);
+        assert_eq!(app.watched[0].rating, Some(4.5));
+    }
+
+    #[test]
+    fn test_rating_is_clamped() {
+        let mut app = test_app();
+
+        press(&mut app, KeyCode::Char('r'));
+        press(&mut app, KeyCode::Char('5'));
+        press(&mut app, KeyCode::Right);
+        assert!(matches!(app.mode, Mode::Rating { rating, .. } if rating == 5.0));
+
+        for _ in 0..12 {
+            press(&mut app, KeyCode::Left);
+        }
+        assert!(matches!(app.mode, Mode::Rating { rating, .. } if rating == 0.5));
+    }
+
+    #[test]
+    fn test_edit_notes_on_watchlist_entry() {
+        let mut app = test_app();
+        press(&mut app, KeyCode::Char('a'));
+        press(&mut app, KeyCode::Char('2'));
+
+        press(&mut app, KeyCode::Char('n'));
+        type_str(&mut app, "- [ ] rewatch");
+        app.handle_key(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL));
+
+        assert!(matches!(app.mode, Mode::Normal));
+        assert_eq!(
+            app.library.notes(NotesLocation::Watchlist, service::MediaKind::Movie, 603).unwrap().as_deref(),
+            Some("- [ ] rewatch")
+        );
+        assert_eq!(app.watchlist[0].notes, "- [ ] rewatch");
+    }
+
+    #[test]
+    fn test_notes_require_library_entry() {
+        let mut app = test_app();
+
+        press(&mut app, KeyCode::Char('n'));
+
+        assert!(matches!(app.mode, Mode::Normal));
+        assert!(app.status.as_deref().unwrap().contains("Add The Matrix"));
+    }
+
+    #[test]
+    fn test_open_details() {
+        let mut app = test_app();
+
+        press(&mut app, KeyCode::Enter);
+
+        assert_eq!(app.tab, Tab::Details);
+        assert!(matches!(app.details, Some((_, Details::Movie(ref movie))) if movie.runtime == 120));
+    }
+
+    #[test]
+    fn test_remove_from_watchlist() {
+        let mut app = test_app();
+        press(&mut app, KeyCode::Char('a'));
+        press(&mut app, KeyCode::Char('2'));
+
+        press(&mut app, KeyCode::Char('d'));
+
+        assert!(app.watchlist.is_empty());
+        assert_eq!(app.selected(), None);
+    }
+
+    #[test]
+    fn test_toggle_media_kind() {
+        let mut app = test_app();
+
+        press(&mut app, KeyCode::Char('m'));
+
+        assert_eq!(app.kind, MediaKind::Tv);
+        assert_eq!(app.popular[0].title, "Game of Thrones");
+    }
+
+    #[test]
+    fn test_offline_disables_search() {
+        let library = Library::open_in_memory().unwrap();
+        library
+            .add_movie_to_watchlist(NewMovie { id: 603, title: "The Matrix".to_string(), poster_path: String::new() })
+            .unwrap();
+        let mut app = App::<MockCatalog>::new(None, library);
+        app.refresh();
+
+        assert!(app.popular.is_empty());
+        assert_eq!(app.watchlist.len(), 1);
+        assert!(app.status.as_deref().unwrap().contains("search is disabled"));
+
+        press(&mut app, KeyCode::Char('/'));
+        assert!(matches!(app.mode, Mode::Normal));
+        assert!(app.status.as_deref().unwrap().starts_with("Search is disabled"));
+
+        press(&mut app, KeyCode::Char('2'));
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.tab, Tab::Watchlist);
+        assert!(app.status.as_deref().unwrap().starts_with("Error: No TMDB API key"));
+
+        press(&mut app, KeyCode::Char('n'));
+        assert!(matches!(app.mode, Mode::Notes { .. }));
+    }
+
+    #[test]
+    fn test_catalog_errors_show_in_status() {
+        let mut app = test_app();
+        app.popular.push(Entry::new(MediaKind::Movie, 1, "Unknown"));
+
+        press(&mut app, KeyCode::Up);
+        for _ in 0..2 {
+            press(&mut app, KeyCode::Down);
+        }
+        press(&mut app, KeyCode::Enter);
+
+        assert_eq!(app.tab, Tab::Popular);
+        assert_eq!(app.status.as_deref(), Some("Error: Movie 1 not found"));
+    }
+
+    #[test]
+    fn test_stars() {
+        assert_eq!(stars(0.5), "½☆☆☆☆");
+        assert_eq!(stars(3.5), "★★★½☆");
+        assert_eq!(stars(5.0), "★★★★★");
+    }
+}
@@ -0,0 +1,51 @@
+use anyhow::{Context, Result};
+use logic::api::{Movie, MovieDetail, Tmdb, Tv, TvDetail};
+use tokio::runtime::Runtime;
+
+/// The TMDB lookups the TUI needs, kept behind a trait so tests can swap in a fake catalog
+pub trait Catalog {
+    fn trending_movies(&self) -> Result<Vec<Movie>>;
+    fn trending_tv(&self) -> Result<Vec<Tv>>;
+    fn search_movies(&self, query: &str) -> Result<Vec<Movie>>;
+    fn search_tv(&self, query: &str) -> Result<Vec<Tv>>;
+    fn movie_details(&self, id: u32) -> Result<MovieDetail>;
+    fn tv_details(&self, id: u32) -> Result<TvDetail>;
+}
+
+/// Blocking adapter over the async `Tmdb` client
+pub struct TmdbCatalog {
+    tmdb: Tmdb,
+    runtime: Runtime,
+}
+
+impl TmdbCatalog {
+    pub fn new(tmdb: Tmdb) -> Result<Self> {
+        Ok(TmdbCatalog { tmdb, runtime: Runtime::new()? })
+    }
+}
+
+impl Catalog for TmdbCatalog {
+    fn trending_movies(&self) -> Result<Vec<Movie>> {
+        self.runtime.block_on(self.tmdb.trending_movies()).context("Failed to fetch trending movies from TMDB")
+    }
+
+    fn trending_tv(&self) -> Result<Vec<Tv>> {
+        self.runtime.block_on(self.tmdb.trending_tv()).context("Failed to fetch trending TV shows from TMDB")
+    }
+
+    fn search_movies(&self, query: &str) -> Result<Vec<Movie>> {
+        self.runtime.block_on(self.tmdb.find_movies(query)).context("Failed to search TMDB for movies")
+    }
+
+    fn search_tv(&self, query: &str) -> Result<Vec<Tv>> {
+        self.runtime.block_on(self.tmdb.find_tv(query)).context("Failed to search TMDB for TV shows")
+    }
+
+    fn movie_details(&self, id: u32) -> Result<MovieDetail> {
+        self.runtime.block_on(self.tmdb.get_movie_details(id))
+    }
+
+    fn tv_details(&self, id: u32) -> Result<TvDetail> {
+        self.runtime.block_on(self.tmdb.get_tv_show_details(id))
+    }
+}
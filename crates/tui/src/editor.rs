@@ -0,0 +1,219 @@
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// A small multi-line text editor used for notes
+#[derive(Debug, Clone, PartialEq)]
+pub struct Editor {
+    lines: Vec<String>,
+    row: usize,
+    // Cursor column counted in chars, not bytes
+    col: usize,
+}
+
+impl Editor {
+    pub fn new(text: &str) -> Self {
+        let mut lines: Vec<String> = text.split('\n').map(|line| line.to_string()).collect();
+        if lines.is_empty() {
+            lines.push(String::new());
+        }
+
+        let row = lines.len() - 1;
+        let col = lines[row].chars().count();
+        Editor { lines, row, col }
+    }
+
+    pub fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    /// Cursor position as (row, column)
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.row, self.col)
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) {
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            return;
+        }
+
+        match key.code {
+            KeyCode::Char(ch) => self.insert(ch),
+            KeyCode::Tab => {
+                for _ in 0..4 {
+                    self.insert(' ');
+                }
+            }
+            KeyCode::Enter => self.newline(),
+            KeyCode::Backspace => self.backspace(),
+            KeyCode::Delete => self.delete(),
+            KeyCode::Left => self.move_left(),
+            KeyCode::Right => self.move_right(),
+            KeyCode::Up if self.row > 0 => {
+                self.row -= 1;
+                self.clamp_col();
+            }
+            KeyCode::Down if self.row + 1 < self.lines.len() => {
+                self.row += 1;
+                self.clamp_col();
+            }
+            KeyCode::Home => self.col = 0,
+            KeyCode::End => self.col = self.line_len(),
+            _ => {}
+        }
+    }
+
+    fn insert(&mut self, ch: char) {
+        let index = self.byte_index();
+        self.lines[self.row].insert(index, ch);
+        self.col += 1;
+    }
+
+    fn newline(&mut self) {
+        let index = self.byte_index();
+        let rest = self.lines[self.row].split_off(index);
+        self.row += 1;
+        self.col = 0;
+        self.lines.insert(self.row, rest);
+    }
+
+    fn backspace(&mut self) {
+        if self.col > 0 {
+            self.col -= 1;
+            let index = self.byte_index();
+            self.lines[self.row].remove(index);
+        } else if self.row > 0 {
+            let line = self.lines.remove(self.row);
+            self.row -= 1;
+            self.col = self.line_len();
+            self.lines[self.row].push_str(&line);
+        }
+    }
+
+    fn delete(&mut self) {
+        if self.col < self.line_len() {
+            let index = self.byte_index();
+            self.lines[self.row].remove(index);
+        } else if self.row + 1 < self.lines.len() {
+            let line = self.lines.remove(self.row + 1);
+            self.lines[self.row].push_str(&line);
+        }
+    }
+
+    fn move_left(&mut self) {
+        if self.col > 0 {
+            self.col -= 1;
+        } else if self.row > 0 {
+            self.row -= 1;
+            self.col = self.line_len();
+        }
+    }
+
+    fn move_right(&mut self) {
+        if self.col < self.line_len() {
+            self.col += 1;
+        } else if self.row + 1 < self.lines.len() {
+            self.row += 1;
+            self.col = 0;
+        }
+    }
+
+    fn clamp_col(&mut self) {
+        self.col = self.col.min(self.line_len());
+    }
+
+    fn line_len(&self) -> usize {
+        self.lines[self.row].chars().count()
+    }
+
+    fn byte_index(&self) -> usize {
+        let line = &self.lines[self.row];
+        line.char_indices().nth(self.col).map(|(index, _)| index).unwrap_or(line.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(editor: &mut Editor, code: KeyCode) {
+        editor.handle_key(KeyEvent::new(code, KeyModifiers::NONE));
+    }
+
+    fn type_str(editor: &mut Editor, text: &str) {
+        for ch in text.chars() {
+            press(editor, KeyCode::Char(ch));
+        }
+    }
+
+    #[test]
+    fn test_new_places_cursor_at_end() {
+        let editor = Editor::new("first\nsecond");
+
+        assert_eq!(editor.cursor(), (1, 6));
+        assert_eq!(editor.lines().len(), 2);
+    }
+
+    #[test]
+    fn test_typing_and_newlines() {
+        let mut editor = Editor::new("");
+        type_str(&mut editor, "# Notes");
+        press(&mut editor, KeyCode::Enter);
+        type_str(&mut editor, "- [ ] rewatch");
+
+        assert_eq!(editor.text(), "# Notes\n- [ ] rewatch");
+    }
+
+    #[test]
+    fn test_backspace_joins_lines() {
+        let mut editor = Editor::new("ab\ncd");
+        press(&mut editor, KeyCode::Home);
+        press(&mut editor, KeyCode::Backspace);
+
+        assert_eq!(editor.text(), "abcd");
+        assert_eq!(editor.cursor(), (0, 2));
+    }
+
+    #[test]
+    fn test_split_line_in_the_middle() {
+        let mut editor = Editor::new("hello world");
+        for _ in 0..6 {
+            press(&mut editor, KeyCode::Left);
+        }
+        press(&mut editor, KeyCode::Enter);
+
+        assert_eq!(editor.text(), "hello\n world");
+    }
+
+    #[test]
+    fn test_multibyte_characters() {
+        let mut editor = Editor::new("Amélie");
+        for _ in 0..3 {
+            press(&mut editor, KeyCode::Left);
+        }
+        press(&mut editor, KeyCode::Backspace);
+        press(&mut editor, KeyCode::Char('e'));
+
+        assert_eq!(editor.text(), "Amelie");
+    }
+
+    #[test]
+    fn test_delete_at_line_end() {
+        let mut editor = Editor::new("one\ntwo");
+        press(&mut editor, KeyCode::Up);
+        press(&mut editor, KeyCode::End);
+        press(&mut editor, KeyCode::Delete);
+
+        assert_eq!(editor.text(), "onetwo");
+    }
+
+    #[test]
+    fn test_ignores_control_keys() {
+        let mut editor = Editor::new("text");
+        editor.handle_key(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL));
+
+        assert_eq!(editor.text(), "text");
+    }
+}
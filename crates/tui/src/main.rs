@@ -0,0 +1,40 @@
+mod app;
+mod catalog;
+mod editor;
+mod markdown;
+mod ui;
+
+use anyhow::Result;
+use logic::api::Tmdb;
+use logic::service::Library;
+use logic::TmdbConfig;
+use ratatui::crossterm::event::{self, Event, KeyEventKind};
+use ratatui::DefaultTerminal;
+
+use crate::app::App;
+use crate::catalog::{Catalog, TmdbCatalog};
+
+fn main() -> Result<()> {
+    // The library works offline, so without an API key only the TMDB lists and lookups are disabled
+    let tmdb = TmdbConfig::initialize().ok().filter(|config| !config.api_key().is_empty());
+    let catalog = tmdb.map(|config| TmdbCatalog::new(Tmdb::new(config))).transpose()?;
+    let mut app = App::new(catalog, Library::open()?);
+    app.refresh();
+
+    let mut terminal = ratatui::init();
+    let result = run(&mut terminal, &mut app);
+    ratatui::restore();
+    result
+}
+
+fn run<C: Catalog>(terminal: &mut DefaultTerminal, app: &mut App<C>) -> Result<()> {
+    while !app.should_quit {
+        terminal.draw(|frame| ui::draw(frame, app))?;
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press {
+                app.handle_key(key);
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,326 @@
+use markdown_renderer::{parse_markdown, AstNode};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span, Text};
+
+/// Render markdown notes into styled terminal text. Notes that fail to parse are shown verbatim.
+pub fn render_notes(markdown: &str) -> Text<'static> {
+    match parse_markdown(markdown) {
+        Ok(AstNode::Document { children, .. }) => {
+            let mut lines = Vec::new();
+            for (index, block) in children.iter().enumerate() {
+                if index > 0 {
+                    lines.push(Line::default());
+                }
+                render_block(block, &mut lines);
+            }
+            Text::from(lines)
+        }
+        Ok(node) => {
+            let mut lines = Vec::new();
+            render_block(&node, &mut lines);
+            Text::from(lines)
+        }
+        Err(_) => Text::from(markdown.to_string()),
+    }
+}
+
+fn render_block(node: &AstNode, lines: &mut Vec<Line<'static>>) {
+    match node {
+        AstNode::Heading { level, content, .. } => {
+            let style = Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD);
+            let mut spans = vec![Span::styled(format!("{} ", "#".repeat(*level as usize)), style)];
+            render_inline(content, style, &mut spans);
+            lines.push(Line::from(spans));
+        }
+        AstNode::Paragraph { content, .. } => {
+            // Hard line breaks start a new terminal line
+            for line in content.split(|node| matches!(node, AstNode::LineBreak(_))) {
+                let mut spans = Vec::new();
+                render_inline(line, Style::default(), &mut spans);
+                lines.push(Line::from(spans));
+            }
+        }
+        AstNode::List { ordered, start, items, .. } => {
+            for (index, item) in items.iter().enumerate() {
+                let marker = if *ordered { format!("{}. ", *start as usize + index) } else { "• ".to_string() };
+                let mut item_lines = match item {
+                    AstNode::ListItem { content, .. } | AstNode::TaskItem { content, .. } => render_list_item(content),
+                    _ => Vec::new(),
+                };
+                if item_lines.is_empty() {
+                    item_lines.push(Line::default());
+                }
+                if let AstNode::TaskItem { checked, .. } = item {
+                    let checkbox = if *checked { "☑ " } else { "☐ " };
+                    item_lines[0].spans.insert(0, Span::styled(checkbox, Style::default().fg(Color::Cyan)));
+                }
+                // Nested blocks line up under the item's text
+                let padding = " ".repeat(marker.chars().count());
+                for (line_index, line) in item_lines.into_iter().enumerate() {
+                    let prefix = if line_index == 0 {
+                        Span::styled(marker.clone(), Style::default().fg(Color::Cyan))
+                    } else {
+                        Span::raw(padding.clone())
+                    };
+                    let mut spans = vec![prefix];
+                    spans.extend(line.spans);
+                    lines.push(Line::from(spans));
+                }
+            }
+        }
+        AstNode::Table { header, rows, .. } => render_table(header, rows, lines),
+        AstNode::CodeBlock { content, .. } => {
+            for line in content.lines() {
+                lines.push(Line::from(Span::styled(format!("  {}", line), Style::default().fg(Color::Green))));
+            }
+        }
+        AstNode::BlockQuote { children, .. } => {
+            let mut quoted = Vec::new();
+            for child in children {
+                render_block(child, &mut quoted);
+            }
+            for line in quoted {
+                let mut spans = vec![Span::styled("│ ", Style::default().fg(Color::DarkGray))];
+                spans.extend(line.spans);
+                lines.push(Line::from(spans));
+            }
+        }
+        AstNode::ThematicBreak(_) => lines.push(Line::from(Span::styled("─".repeat(20), Style::default().fg(Color::DarkGray)))),
+        // Footnotes stay where they were written, marked with their label
+        AstNode::FootnoteDefinition { label, children, .. } => {
+            let mut note = Vec::new();
+            for child in children {
+                render_block(child, &mut note);
+            }
+            let mut note = note.into_iter();
+            let mut spans = vec![Span::styled(format!("[^{}] ", label), Style::default().fg(Color::DarkGray))];
+            spans.extend(note.next().map(|line| line.spans).unwrap_or_default());
+            lines.push(Line::from(spans));
+            lines.extend(note);
+        }
+        AstNode::Document { children, .. } => {
+            for child in children {
+                render_block(child, lines);
+            }
+        }
+        inline => {
+            let mut spans = Vec::new();
+            render_inline(std::slice::from_ref(inline), Style::default(), &mut spans);
+            lines.push(Line::from(spans));
+        }
+    }
+}
+
+/// Tables are drawn with columns padded to their widest cell and the header in bold
+fn render_table(header: &[AstNode], rows: &[Vec<AstNode>], lines: &mut Vec<Line<'static>>) {
+    let render_row = |cells: &[AstNode], style: Style| -> Vec<Vec<Span<'static>>> {
+        cells
+            .iter()
+            .map(|cell| {
+                let mut spans = Vec::new();
+                if let AstNode::TableCell { content, .. } = cell {
+                    render_inline(content, style, &mut spans);
+                }
+                spans
+            })
+            .collect()
+    };
+    let header_style = Style::default().add_modifier(Modifier::BOLD);
+    let table: Vec<Vec<Vec<Span<'static>>>> = std::iter::once(render_row(header, header_style))
+        .chain(rows.iter().map(|row| render_row(row, Style::default())))
+        .collect();
+
+    let width = |spans: &[Span]| spans.iter().map(|span| span.content.chars().count()).sum::<usize>();
+    let mut widths = vec![0; header.len()];
+    for row in &table {
+        for (column, cell) in row.iter().enumerate() {
+            widths[column] = widths[column].max(width(cell));
+        }
+    }
+
+    let border = Style::default().fg(Color::DarkGray);
+    for (index, row) in table.into_iter().enumerate() {
+        let mut spans = Vec::new();
+        for (column, cell) in row.into_iter().enumerate() {
+            if column > 0 {
+                spans.push(Span::styled(" │ ", border));
+            }
+            let padding = widths[column] - width(&cell);
+            spans.extend(cell);
+            spans.push(Span::raw(" ".repeat(padding)));
+        }
+        lines.push(Line::from(spans));
+        if index == 0 {
+            let rule: Vec<String> = widths.iter().map(|width| "─".repeat(*width)).collect();
+            lines.push(Line::from(Span::styled(rule.join("─┼─"), border)));
+        }
+    }
+}
+
+/// Tight items hold inline nodes directly, loose ones paragraphs; both may contain nested lists
+fn render_list_item(content: &[AstNode]) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    let mut spans = Vec::new();
+    for node in content {
+        if let AstNode::LineBreak(_) = node {
+            lines.push(Line::from(std::mem::take(&mut spans)));
+        } else if node.is_inline() {
+            render_inline(std::slice::from_ref(node), Style::default(), &mut spans);
+        } else {
+            if !spans.is_empty() {
+                lines.push(Line::from(std::mem::take(&mut spans)));
+            }
+            render_block(node, &mut lines);
+        }
+    }
+    if !spans.is_empty() {
+        lines.push(Line::from(spans));
+    }
+    lines
+}
+
+fn render_inline(nodes: &[AstNode], style: Style, spans: &mut Vec<Span<'static>>) {
+    for node in nodes {
+        match node {
+            AstNode::Text(text, _) => spans.push(Span::styled(text.replace('\n', " "), style)),
+            AstNode::Html(html, _) => spans.push(Span::styled(html.clone(), style)),
+            AstNode::Bold(content, _) => render_inline(content, style.add_modifier(Modifier::BOLD), spans),
+            AstNode::Italic(content, _) => render_inline(content, style.add_modifier(Modifier::ITALIC), spans),
+            AstNode::Strikethrough(content, _) => render_inline(content, style.add_modifier(Modifier::CROSSED_OUT), spans),
+            AstNode::Highlight(content, _) => render_inline(content, style.fg(Color::Black).bg(Color::Yellow), spans),
+            // Spoilers stay unreadable until the notes are opened in the editor
+            AstNode::Spoiler(content, _) => render_inline(content, style.fg(Color::DarkGray).bg(Color::DarkGray), spans),
+            AstNode::Link { text, .. } => {
+                render_inline(text, style.fg(Color::Cyan).add_modifier(Modifier::UNDERLINED), spans)
+            }
+            AstNode::Image { alt, .. } => {
+                spans.push(Span::styled(format!("[image: {}]", alt), style.fg(Color::Cyan).add_modifier(Modifier::ITALIC)))
+            }
+            AstNode::MediaRef(media_ref, _) => {
+                spans.push(Span::styled(format!("[[{}]]", media_ref), style.fg(Color::Magenta)))
+            }
+            AstNode::Code(code, _) => spans.push(Span::styled(code.clone(), style.fg(Color::Green))),
+            AstNode::FootnoteReference { label, .. } => {
+                spans.push(Span::styled(format!("[^{}]", label), style.fg(Color::DarkGray)))
+            }
+            // Only reached inside headings and table cells, where lines can't break
+            AstNode::LineBreak(_) => spans.push(Span::raw(" ")),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain(text: &Text) -> Vec<String> {
+        text.lines
+            .iter()
+            .map(|line| line.spans.iter().map(|span| span.content.as_ref()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_render_heading_and_paragraph() {
+        let text = render_notes("# Season 1\n\nGreat start");
+
+        assert_eq!(plain(&text), vec!["# Season 1", "", "Great start"]);
+        assert!(text.lines[0].spans[1].style.add_modifier.contains(Modifier::BOLD));
+    }
+
+    #[test]
+    fn test_render_lists() {
+        let text = render_notes("- one\n- two\n\n1. first\n2. second");
+
+        assert_eq!(plain(&text), vec!["• one", "• two", "", "1. first", "2. second"]);
+    }
+
+    #[test]
+    fn test_render_nested_lists() {
+        let text = render_notes("3. first\n   - inner\n4. second\nlazy");
+
+        assert_eq!(plain(&text), vec!["3. first", "   • inner", "4. second lazy"]);
+    }
+
+    #[test]
+    fn test_render_task_list() {
+        let text = render_notes("- [ ] rewatch S1\n- [x] buy popcorn");
+
+        assert_eq!(plain(&text), vec!["• ☐ rewatch S1", "• ☑ buy popcorn"]);
+    }
+
+    #[test]
+    fn test_render_emphasis_styles() {
+        let text = render_notes("*so* **good**");
+        let spans = &text.lines[0].spans;
+
+        assert_eq!(plain(&text), vec!["so good"]);
+        assert!(spans[0].style.add_modifier.contains(Modifier::ITALIC));
+        assert!(spans[2].style.add_modifier.contains(Modifier::BOLD));
+    }
+
+    #[test]
+    fn test_render_keeps_spaces_around_markup() {
+        // The preview joins the text of each line as parsed, so the spaces between words and markup
+        // have to come from the renderer's text nodes
+        let text = render_notes("Watch **this**, then *that* with [friends](/x) and `popcorn` [[movie:603]] too");
+
+        assert_eq!(plain(&text), vec!["Watch this, then that with friends and popcorn [[movie:603]] too"]);
+    }
+
+    #[test]
+    fn test_render_strike_highlight_spoiler() {
+        let text = render_notes("~~meh~~ ==great== ||he dies||");
+        let spans = &text.lines[0].spans;
+
+        assert_eq!(plain(&text), vec!["meh great he dies"]);
+        assert!(spans[0].style.add_modifier.contains(Modifier::CROSSED_OUT));
+        assert_eq!(spans[2].style.bg, Some(Color::Yellow));
+        assert_eq!(spans[4].style.fg, spans[4].style.bg);
+    }
+
+    #[test]
+    fn test_render_table() {
+        let text = render_notes("| Episode | Rating |\n|---|--:|\n| Pilot | 4.5 |");
+
+        assert_eq!(plain(&text), vec!["Episode │ Rating", "────────┼───────", "Pilot   │ 4.5   "]);
+        assert!(text.lines[0].spans[0].style.add_modifier.contains(Modifier::BOLD));
+    }
+
+    #[test]
+    fn test_render_code() {
+        let text = render_notes("Try `*this*`\n\n```\nfn main() {}\n```");
+
+        assert_eq!(plain(&text), vec!["Try *this*", "", "  fn main() {}"]);
+        assert_eq!(text.lines[0].spans[1].style.fg, Some(Color::Green));
+    }
+
+    #[test]
+    fn test_render_quote_and_rule() {
+        let text = render_notes("> # Quote\n> > inner\n\n---");
+
+        assert_eq!(plain(&text), vec!["│ # Quote", "│ │ inner", "", "─".repeat(20).as_str()]);
+    }
+
+    #[test]
+    fn test_render_line_breaks() {
+        let text = render_notes("Seen it twice  \nstill great\nno break");
+
+        assert_eq!(plain(&text), vec!["Seen it twice", "still great no break"]);
+    }
+
+    #[test]
+    fn test_render_footnotes() {
+        let text = render_notes("Slow burn[^1].\n\n[^1]: Almost three hours.");
+
+        assert_eq!(plain(&text), vec!["Slow burn[^1].", "", "[^1] Almost three hours."]);
+    }
+
+    #[test]
+    fn test_unparsable_notes_are_shown_verbatim() {
+        let text = render_notes("[half typed link");
+
+        assert_eq!(plain(&text), vec!["[half typed link"]);
+    }
+}
@@ -0,0 +1,231 @@
+use ratatui::layout::{Constraint, Layout, Position, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Tabs, Wrap};
+use ratatui::Frame;
+
+use crate::app::{stars, App, Details, Entry, Mode, Tab};
+use crate::catalog::Catalog;
+use crate::markdown::render_notes;
+
+pub fn draw<C: Catalog>(frame: &mut Frame, app: &App<C>) {
+    let [tabs_area, body_area, status_area] =
+        Layout::vertical([Constraint::Length(3), Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+
+    draw_tabs(frame, app, tabs_area);
+    match app.tab {
+        Tab::Details => draw_details(frame, app, body_area),
+        _ => draw_list(frame, app, body_area),
+    }
+    draw_status(frame, app, status_area);
+
+    match &app.mode {
+        Mode::Rating { entry, rating } => draw_rating(frame, entry, *rating),
+        Mode::Notes { entry, editor, .. } => draw_notes_editor(frame, entry, editor),
+        _ => {}
+    }
+}
+
+fn draw_tabs<C: Catalog>(frame: &mut Frame, app: &App<C>, area: Rect) {
+    let titles = Tab::ALL.iter().enumerate().map(|(index, tab)| format!("{} {}", index + 1, tab.title()));
+    let tabs = Tabs::new(titles)
+        .select(app.tab.index())
+        .block(Block::default().borders(Borders::ALL).title(format!(" TV Tracker · {} ", app.kind.label())))
+        .highlight_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD));
+    frame.render_widget(tabs, area);
+}
+
+fn draw_list<C: Catalog>(frame: &mut Frame, app: &App<C>, area: Rect) {
+    let title = match (app.tab, app.query.as_deref()) {
+        (Tab::Popular, Some(query)) => format!(" Search: {} ", query),
+        (tab, _) => format!(" {} ", tab.title()),
+    };
+
+    let items: Vec<ListItem> = app.entries().iter().map(list_item).collect();
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .highlight_style(Style::default().bg(Color::DarkGray).add_modifier(Modifier::BOLD))
+        .highlight_symbol("> ");
+
+    let mut state = ListState::default().with_selected(app.selected());
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
+fn list_item(entry: &Entry) -> ListItem<'static> {
+    let mut spans = vec![Span::raw(entry.title.clone())];
+    if let Some(rating) = entry.rating {
+        spans.push(Span::raw("  "));
+        spans.push(Span::styled(stars(rating), Style::default().fg(Color::Yellow)));
+    }
+    if !entry.notes.trim().is_empty() {
+        spans.push(Span::styled("  ✎", Style::default().fg(Color::DarkGray)));
+    }
+    ListItem::new(Line::from(spans))
+}
+
+fn draw_details<C: Catalog>(frame: &mut Frame, app: &App<C>, area: Rect) {
+    let Some((entry, details)) = &app.details else {
+        let hint = Paragraph::new("Select a title and press Enter to see its details")
+            .block(Block::default().borders(Borders::ALL).title(" Details "));
+        frame.render_widget(hint, area);
+        return;
+    };
+
+    let [info_area, notes_area] = Layout::horizontal([Constraint::Percentage(55), Constraint::Percentage(45)]).areas(area);
+
+    let label = Style::default().fg(Color::Cyan);
+    let field = |name: &str, value: String| Line::from(vec![Span::styled(format!("{:<10}", name), label), Span::raw(value)]);
+    let (title, mut lines, overview) = match details {
+        Details::Movie(movie) => (
+            movie.title.clone(),
+            vec![
+                field("Released", movie.release_date.clone()),
+                field("Runtime", format!("{} min", movie.runtime)),
+                field("Genres", movie.genres.join(", ")),
+                field("TMDB", format!("{:.1}/10", movie.vote_average)),
+            ],
+            movie.overview.clone(),
+        ),
+        Details::Tv(show) => (
+            show.name.clone(),
+            vec![
+                field("First aired", show.first_air_date.clone()),
+                field("Seasons", format!("{} ({} episodes)", show.number_of_seasons, show.number_of_episodes)),
+                field("Status", show.status.clone()),
+                field("Genres", show.genres.join(", ")),
+                field("TMDB", format!("{:.1}/10", show.vote_average)),
+            ],
+            show.overview.clone(),
+        ),
+    };
+
+    let library = app.watched.iter().chain(&app.watchlist).find(|other| other.kind == entry.kind && other.id == entry.id);
+    if let Some(rating) = library.and_then(|entry| entry.rating) {
+        lines.push(field("Rating", stars(rating)));
+    }
+    lines.push(Line::default());
+    lines.push(Line::raw(overview));
+
+    let info = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .block(Block::default().borders(Borders::ALL).title(format!(" {} ", title)));
+    frame.render_widget(info, info_area);
+
+    let notes = library.map(|entry| render_notes(&entry.notes)).unwrap_or_else(|| Text::raw("Not in your library yet"));
+    let notes = Paragraph::new(notes).wrap(Wrap { trim: false }).block(Block::default().borders(Borders::ALL).title(" Notes "));
+    frame.render_widget(notes, notes_area);
+}
+
+fn draw_status<C: Catalog>(frame: &mut Frame, app: &App<C>, area: Rect) {
+    let line = match (&app.mode, &app.status) {
+        (Mode::Search(query), _) => {
+            frame.set_cursor_position(Position::new(area.x + 8 + query.chars().count() as u16, area.y));
+            Line::from(vec![Span::styled("Search: ", Style::default().fg(Color::Yellow)), Span::raw(query.clone())])
+        }
+        (_, Some(status)) if status.starts_with("Error") => Line::styled(status.clone(), Style::default().fg(Color::Red)),
+        (_, Some(status)) => Line::styled(status.clone(), Style::default().fg(Color::Green)),
+        _ => Line::styled(
+            "q quit · ←/→ tabs · j/k move · / search · m movies/tv · enter details · a add · r rate · n notes · d remove",
+            Style::default().fg(Color::DarkGray),
+        ),
+    };
+    frame.render_widget(Paragraph::new(line), area);
+}
+
+fn draw_rating(frame: &mut Frame, entry: &Entry, rating: f32) {
+    let area = centered(frame.area(), 40, 5);
+    let text = vec![
+        Line::styled(stars(rating), Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+        Line::styled("←/→ half a star · 1-5 · enter save", Style::default().fg(Color::DarkGray)),
+    ];
+    let popup = Paragraph::new(text)
+        .centered()
+        .block(Block::default().borders(Borders::ALL).title(format!(" Rate {} ", entry.title)));
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup, area);
+}
+
+fn draw_notes_editor(frame: &mut Frame, entry: &Entry, editor: &crate::editor::Editor) {
+    let area = centered(frame.area(), frame.area().width.saturating_sub(4), frame.area().height.saturating_sub(4));
+    let [editor_area, preview_area] = Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(area);
+
+    let (row, col) = editor.cursor();
+    let inner_height = editor_area.height.saturating_sub(2) as usize;
+    let scroll = (row + 1).saturating_sub(inner_height) as u16;
+    let source = Paragraph::new(editor.lines().iter().map(|line| Line::raw(line.clone())).collect::<Vec<_>>())
+        .scroll((scroll, 0))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(" Notes: {} ", entry.title))
+                .title_bottom(" ctrl-s save · esc cancel "),
+        );
+    let preview = Paragraph::new(render_notes(&editor.text()))
+        .wrap(Wrap { trim: false })
+        .block(Block::default().borders(Borders::ALL).title(" Preview "));
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(source, editor_area);
+    frame.render_widget(preview, preview_area);
+    frame.set_cursor_position(Position::new(
+        editor_area.x + 1 + col as u16,
+        editor_area.y + 1 + row as u16 - scroll,
+    ));
+}
+
+fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect::new(area.x + (area.width - width) / 2, area.y + (area.height - height) / 2, width, height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::tests::test_app;
+    use ratatui::backend::TestBackend;
+    use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use ratatui::Terminal;
+
+    fn screen<C: Catalog>(app: &App<C>) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(100, 20)).unwrap();
+        terminal.draw(|frame| draw(frame, app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        buffer
+            .content()
+            .chunks(buffer.area.width as usize)
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_draw_popular_list() {
+        let app = test_app();
+        let screen = screen(&app);
+
+        assert!(screen.contains("1 Popular"));
+        assert!(screen.contains("> The Matrix"));
+        assert!(screen.contains("Inception"));
+    }
+
+    #[test]
+    fn test_draw_rating_popup() {
+        let mut app = test_app();
+        app.handle_key(KeyEvent::new(KeyCode::Char('r'), KeyModifiers::NONE));
+        app.handle_key(KeyEvent::new(KeyCode::Left, KeyModifiers::NONE));
+
+        assert!(screen(&app).contains("★★½☆☆"));
+    }
+
+    #[test]
+    fn test_draw_details_with_notes() {
+        let mut app = test_app();
+        app.handle_key(KeyEvent::new(KeyCode::Char('a'), KeyModifiers::NONE));
+        app.handle_key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        let screen = screen(&app);
+
+        assert!(screen.contains("Runtime   120 min"));
+        assert!(screen.contains("Notes"));
+    }
+}